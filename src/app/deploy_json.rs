@@ -32,13 +32,78 @@ pub(crate) fn deploy_json_data_applied(
     targets: Vec<String>,
     plan: crate::deploy::PlanResult,
     snapshot_id: String,
+    hooks: Vec<crate::hooks::HookResult>,
 ) -> serde_json::Value {
-    serde_json::json!({
+    let mut data = serde_json::json!({
         "applied": true,
         "snapshot_id": snapshot_id,
         "profile": profile,
         "targets": targets,
         "changes": plan.changes,
         "summary": plan.summary,
-    })
+    });
+    if !hooks.is_empty()
+        && let Some(obj) = data.as_object_mut()
+    {
+        obj.insert("hooks".to_string(), serde_json::json!(hooks));
+    }
+    data
+}
+
+/// Per-project entry for `deploy --all-projects`: the single-project `data` object plus the
+/// registered project's identity.
+pub(crate) fn deploy_json_project(
+    project: &crate::project_registry::RegisteredProject,
+    mut data: serde_json::Value,
+) -> serde_json::Value {
+    if let Some(obj) = data.as_object_mut() {
+        obj.remove("profile");
+        obj.insert(
+            "project_id".to_string(),
+            serde_json::json!(project.project_id),
+        );
+        obj.insert(
+            "project_root".to_string(),
+            serde_json::json!(project.project_root),
+        );
+        obj.insert(
+            "project_root_posix".to_string(),
+            serde_json::json!(crate::paths::path_to_posix_string(&project.root())),
+        );
+    }
+    data
+}
+
+/// A project whose apply failed under `deploy --all-projects`; the other projects still ran.
+pub(crate) fn deploy_json_project_failed(
+    project: &crate::project_registry::RegisteredProject,
+    err: &anyhow::Error,
+) -> serde_json::Value {
+    let (code, message, details) = crate::user_error::anyhow_error_parts_for_envelope(err);
+    deploy_json_project(
+        project,
+        serde_json::json!({
+            "applied": false,
+            "failed": true,
+            "error": {
+                "code": code,
+                "message": message,
+                "details": details,
+            },
+        }),
+    )
+}
+
+pub(crate) fn deploy_json_project_skipped(
+    project: &crate::project_registry::RegisteredProject,
+    reason: &str,
+) -> serde_json::Value {
+    deploy_json_project(
+        project,
+        serde_json::json!({
+            "applied": false,
+            "skipped": true,
+            "reason": reason,
+        }),
+    )
 }
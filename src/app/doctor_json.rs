@@ -6,12 +6,14 @@ pub(crate) fn doctor_json_data(
     machine_id: String,
     roots: Vec<crate::handlers::doctor::DoctorRootCheck>,
     gitignore_fixes: Vec<crate::handlers::doctor::DoctorGitignoreFix>,
+    interrupted_applies: Vec<crate::handlers::doctor::DoctorInterruptedApply>,
     next_actions: &std::collections::BTreeSet<String>,
 ) -> anyhow::Result<serde_json::Value> {
     let mut data = serde_json::json!({
         "machine_id": machine_id,
         "roots": roots,
         "gitignore_fixes": gitignore_fixes,
+        "interrupted_applies": interrupted_applies,
     });
 
     if !next_actions.is_empty() {
@@ -11,6 +11,7 @@ pub(crate) struct DoctorNextActions {
 pub(crate) fn doctor_next_actions(
     roots: &[DoctorRootCheck],
     needs_gitignore_fix: bool,
+    needs_restore: bool,
     fix: bool,
     prefix: &str,
 ) -> DoctorNextActions {
@@ -28,7 +29,7 @@ pub(crate) fn doctor_next_actions(
         }
     }
 
-    if needs_gitignore_fix && !fix {
+    if (needs_gitignore_fix || needs_restore) && !fix {
         out.human.insert(format!("{prefix} doctor --fix"));
         out.json
             .insert(format!("{prefix} doctor --fix --yes --json"));
@@ -41,3 +41,15 @@ pub(crate) fn evolve_propose_json_data_created(
         "committed": committed,
     })
 }
+
+pub(crate) fn evolve_propose_json_data_upstream(
+    proposals: Vec<crate::handlers::evolve::EvolveProposeUpstreamItem>,
+    skipped: Vec<crate::handlers::evolve::EvolveProposeSkippedItem>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "created": true,
+        "upstream": true,
+        "proposals": proposals,
+        "skipped": skipped,
+    })
+}
@@ -3,6 +3,8 @@ pub(crate) struct ExplainedModule {
     pub(crate) module_id: String,
     pub(crate) module_type: Option<String>,
     pub(crate) layer: Option<String>,
+    /// Every layer contributing the file, low → high precedence (`layer` is the last one).
+    pub(crate) layers: Vec<String>,
     pub(crate) module_path: Option<String>,
 }
 
@@ -28,11 +30,13 @@ pub(crate) struct ExplainedDrift {
 
 pub(crate) fn explain_plan_json_data(
     profile: &str,
+    machine_group: Option<&str>,
     targets: Vec<String>,
     changes: Vec<ExplainedChange>,
 ) -> serde_json::Value {
     serde_json::json!({
         "profile": profile,
+        "machine_group": machine_group,
         "targets": targets,
         "changes": changes,
     })
@@ -10,3 +10,12 @@ pub(crate) fn plan_json_data(
         "summary": plan.summary,
     })
 }
+
+/// `data.saved_plan` for `plan --out` and `deploy --plan`.
+pub(crate) fn saved_plan_json(path: &std::path::Path, plan_hash: &str) -> serde_json::Value {
+    serde_json::json!({
+        "path": path.display().to_string(),
+        "path_posix": crate::paths::path_to_posix_string(path),
+        "plan_hash": plan_hash,
+    })
+}
@@ -57,7 +57,7 @@ pub(crate) fn preview_diff_files(
             if let Some(df) = desired.get(&tp) {
                 match (
                     std::str::from_utf8(&before_bytes).ok(),
-                    std::str::from_utf8(&df.bytes).ok(),
+                    std::str::from_utf8(df.deployed_bytes()).ok(),
                 ) {
                     (Some(from), Some(to)) => {
                         let from_name = format!("a/{rel_path}");
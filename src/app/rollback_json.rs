@@ -1,9 +1,46 @@
 pub(crate) fn rollback_json_data(
     rolled_back_to: &str,
     event_snapshot_id: &str,
+    hooks: &[crate::hooks::HookResult],
 ) -> serde_json::Value {
-    serde_json::json!({
+    let mut data = serde_json::json!({
         "rolled_back_to": rolled_back_to,
         "event_snapshot_id": event_snapshot_id,
-    })
+    });
+    if !hooks.is_empty()
+        && let Some(obj) = data.as_object_mut()
+    {
+        obj.insert("hooks".to_string(), serde_json::json!(hooks));
+    }
+    data
+}
+
+/// `rollback --dry-run`: the rollback as a `preview --diff` style plan and diff.
+pub(crate) fn rollback_json_data_dry_run(
+    plan: crate::rollback_plan::RollbackPlan,
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<serde_json::Value> {
+    let files = crate::app::preview_diff::preview_diff_files(
+        &plan.plan,
+        &plan.desired,
+        &plan.roots,
+        warnings,
+    )?;
+
+    Ok(serde_json::json!({
+        "snapshot_id": plan.snapshot_id,
+        "current_snapshot_id": plan.current_head,
+        "targets": plan.targets,
+        "partial": plan.partial,
+        "plan": {
+            "changes": plan.plan.changes,
+            "summary": plan.plan.summary,
+        },
+        "diff": {
+            "changes": plan.plan.changes,
+            "summary": plan.plan.summary,
+            "files": files,
+        },
+        "local_edits": plan.local_edits,
+    }))
 }
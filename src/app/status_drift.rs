@@ -15,6 +15,7 @@ pub(crate) fn drift_summary(
             "modified" => summary.modified += 1,
             "missing" => summary.missing += 1,
             "extra" => summary.extra += 1,
+            "permissions" => summary.permissions += 1,
             _ => {}
         }
     }
@@ -41,6 +42,7 @@ pub(crate) fn drift_summary_by_root(
             "modified" => entry.summary.modified += 1,
             "missing" => entry.summary.missing += 1,
             "extra" => entry.summary.extra += 1,
+            "permissions" => entry.summary.permissions += 1,
             _ => {}
         }
     }
@@ -35,6 +35,7 @@ pub(crate) fn status_next_actions(
     summary_modified: u64,
     summary_missing: u64,
     summary_extra: u64,
+    summary_permissions: u64,
     any_manifest: bool,
     needs_deploy_apply: bool,
 ) -> std::collections::BTreeSet<StatusNextAction> {
@@ -52,6 +53,8 @@ pub(crate) fn status_next_actions(
         if any_manifest {
             out.insert(StatusNextAction::EvolvePropose);
         }
+    } else if summary_permissions > 0 {
+        out.insert(StatusNextAction::DeployApply);
     } else if summary_extra > 0 {
         out.insert(StatusNextAction::PreviewDiff);
     }
@@ -3,10 +3,13 @@ use std::path::{Path, PathBuf};
 use anyhow::Context as _;
 
 use crate::deploy::{DesiredState, Op, PlanResult, TargetPath};
-use crate::fs::write_atomic;
+use crate::fs::{write_atomic, write_atomic_with_mode, write_symlink};
 use crate::hash::sha256_hex;
+use crate::journal::{ApplyJournal, JournalChange, restore_all_interrupted, restore_interrupted};
 use crate::paths::AgentpackHome;
-use crate::state::{AppliedChange, DeploymentSnapshot, ManagedFile, list_snapshots};
+use crate::rollback_plan::{RollbackPlan, RollbackSelection, local_edits_error, plan_rollback};
+use crate::state::{AppliedChange, DeploymentSnapshot, ManagedFile};
+use crate::state_lock::StateLock;
 use crate::store::sanitize_module_id;
 use crate::target_manifest::{ManagedManifestFile, TargetManifest, manifest_path_for_target};
 use crate::targets::{TargetRoot, best_root_for};
@@ -19,7 +22,10 @@ pub fn apply_plan(
     lockfile_path: Option<&Path>,
     roots: &[TargetRoot],
 ) -> anyhow::Result<DeploymentSnapshot> {
+    let _lock = StateLock::acquire(home, kind)?;
     std::fs::create_dir_all(&home.snapshots_dir).context("create snapshots dir")?;
+    // A previous apply that crashed midway is undone before anything new is written.
+    restore_all_interrupted(home)?;
 
     let now = time::OffsetDateTime::now_utc();
     let id = now.unix_timestamp_nanos().to_string();
@@ -27,6 +33,36 @@ pub fn apply_plan(
         .format(&time::format_description::well_known::Rfc3339)
         .context("format timestamp")?;
 
+    let mut journal = ApplyJournal::begin(home, &id, kind, &created_at)?;
+    let result = apply_journaled(home, &mut journal, plan, desired, lockfile_path, roots);
+    match result {
+        Ok(snapshot) => {
+            journal.commit()?;
+            Ok(snapshot)
+        }
+        Err(err) => {
+            drop(journal);
+            match restore_interrupted(home, &id) {
+                Ok(_) => Err(err),
+                Err(restore_err) => Err(err.context(format!(
+                    "apply failed and restoring the previous files also failed ({restore_err:#}); run `agentpack doctor --fix` to retry the restore"
+                ))),
+            }
+        }
+    }
+}
+
+fn apply_journaled(
+    home: &AgentpackHome,
+    journal: &mut ApplyJournal,
+    plan: &PlanResult,
+    desired: &DesiredState,
+    lockfile_path: Option<&Path>,
+    roots: &[TargetRoot],
+) -> anyhow::Result<DeploymentSnapshot> {
+    let id = journal.snapshot_id.clone();
+    let created_at = journal.created_at.clone();
+    let kind = journal.kind.clone();
     let backup_root = DeploymentSnapshot::backup_root(home, &id);
     std::fs::create_dir_all(&backup_root).context("create backup root")?;
     let state_root = DeploymentSnapshot::state_root(home, &id);
@@ -49,6 +85,22 @@ pub fn apply_plan(
                 }
             }
         };
+        let before_link_target = if crate::fs::is_symlink(&path) {
+            std::fs::read_link(&path)
+                .ok()
+                .map(|p| p.to_string_lossy().to_string())
+        } else {
+            None
+        };
+        journal.record(JournalChange {
+            target: c.target.clone(),
+            path: c.path.clone(),
+            existed: crate::fs::path_present(&path),
+            backup_path: backup_path
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            before_link_target: before_link_target.clone(),
+        })?;
 
         match c.op {
             Op::Create | Op::Update => {
@@ -60,7 +112,34 @@ pub fn apply_plan(
                     .get(&key)
                     .with_context(|| format!("missing desired bytes for {}", c.path))?;
 
-                write_atomic(&path, &desired_file.bytes)?;
+                if let Some(link) = &desired_file.link_target {
+                    write_symlink(&path, link)?;
+                    let actual_link = std::fs::read_link(&path)
+                        .with_context(|| format!("read link {}", path.display()))?;
+                    if &actual_link != link {
+                        anyhow::bail!(
+                            "symlink verification failed for {}: expected {}, got {}",
+                            path.display(),
+                            link.display(),
+                            actual_link.display()
+                        );
+                    }
+                    // Link targets are live files; their content may legitimately change.
+                    applied.push(AppliedChange {
+                        target: c.target.clone(),
+                        op: op_name(&c.op).to_string(),
+                        path: c.path.clone(),
+                        backup_path: backup_path
+                            .as_ref()
+                            .map(|p| p.to_string_lossy().to_string()),
+                        before_sha256: c.before_sha256.clone(),
+                        after_sha256: c.after_sha256.clone(),
+                        before_link_target,
+                    });
+                    continue;
+                }
+
+                write_atomic_with_mode(&path, desired_file.deployed_bytes(), desired_file.mode)?;
 
                 let actual = std::fs::read(&path)?;
                 let actual_sha = sha256_hex(&actual);
@@ -76,7 +155,7 @@ pub fn apply_plan(
                 }
             }
             Op::Delete => {
-                if path.exists() {
+                if crate::fs::path_present(&path) {
                     std::fs::remove_file(&path)
                         .with_context(|| format!("remove {}", path.display()))?;
                 }
@@ -85,41 +164,45 @@ pub fn apply_plan(
 
         applied.push(AppliedChange {
             target: c.target.clone(),
-            op: match c.op {
-                Op::Create => "create",
-                Op::Update => "update",
-                Op::Delete => "delete",
-            }
-            .to_string(),
+            op: op_name(&c.op).to_string(),
             path: c.path.clone(),
             backup_path: backup_path
                 .as_ref()
                 .map(|p| p.to_string_lossy().to_string()),
             before_sha256: c.before_sha256.clone(),
             after_sha256: c.after_sha256.clone(),
+            before_link_target,
         });
     }
 
     if kind == "deploy" || kind == "bootstrap" {
         applied.extend(write_target_manifests(
+            journal,
             &backup_root,
             &state_root,
-            &created_at,
-            &id,
             plan,
             desired,
             roots,
         )?);
     }
 
-    store_snapshot_state_files(&state_root, desired)?;
+    store_snapshot_state_files(
+        &state_root,
+        &DeploymentSnapshot::merge_base_root(home, &id),
+        desired,
+    )?;
 
     let mut managed_files: Vec<ManagedFile> = desired
         .iter()
         .map(|(tp, desired_file)| ManagedFile {
             target: tp.target.clone(),
             path: tp.path.to_string_lossy().to_string(),
-            sha256: sha256_hex(&desired_file.bytes),
+            sha256: sha256_hex(desired_file.deployed_bytes()),
+            link_target: desired_file
+                .link_target
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            mode: desired_file.mode,
         })
         .collect();
     managed_files.sort_by(|a, b| {
@@ -135,7 +218,7 @@ pub fn apply_plan(
     };
 
     let snapshot = DeploymentSnapshot {
-        kind: kind.to_string(),
+        kind,
         id: id.clone(),
         created_at,
         targets,
@@ -144,6 +227,7 @@ pub fn apply_plan(
         rolled_back_to: None,
         lockfile_sha256,
         backup_root: backup_root.to_string_lossy().to_string(),
+        hooks: Vec::new(),
     };
 
     let snapshot_path = DeploymentSnapshot::path(home, &id);
@@ -153,10 +237,9 @@ pub fn apply_plan(
 }
 
 fn write_target_manifests(
+    journal: &mut ApplyJournal,
     backup_root: &Path,
     state_root: &Path,
-    created_at: &str,
-    snapshot_id: &str,
     plan: &PlanResult,
     desired: &DesiredState,
     roots: &[TargetRoot],
@@ -177,8 +260,17 @@ fn write_target_manifests(
         let rel = rel.to_string_lossy().replace('\\', "/");
         per_root[idx].push(ManagedManifestFile {
             path: rel,
-            sha256: sha256_hex(&desired_file.bytes),
+            sha256: sha256_hex(desired_file.deployed_bytes()),
+            merge_base_sha256: desired_file
+                .merged
+                .as_ref()
+                .map(|_| sha256_hex(&desired_file.bytes)),
             module_ids: desired_file.module_ids.clone(),
+            link_target: desired_file
+                .link_target
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            mode: desired_file.mode,
         });
     }
 
@@ -209,8 +301,8 @@ fn write_target_manifests(
 
         let mut manifest = TargetManifest::new(
             root.target.clone(),
-            created_at.to_string(),
-            Some(snapshot_id.to_string()),
+            journal.created_at.clone(),
+            Some(journal.snapshot_id.clone()),
         );
         per_root[idx].sort_by(|a, b| a.path.cmp(&b.path));
         manifest.managed_files = per_root[idx].clone();
@@ -231,6 +323,15 @@ fn write_target_manifests(
         } else {
             None
         };
+        journal.record(JournalChange {
+            target: root.target.clone(),
+            path: manifest_path.to_string_lossy().to_string(),
+            existed,
+            backup_path: backup_path
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            before_link_target: None,
+        })?;
 
         write_atomic(&manifest_path, content.as_bytes())?;
 
@@ -244,6 +345,7 @@ fn write_target_manifests(
             backup_path: backup_path.map(|p| p.to_string_lossy().to_string()),
             before_sha256,
             after_sha256,
+            before_link_target: None,
         });
     }
 
@@ -259,213 +361,162 @@ fn best_root_index<'a>(
     roots.iter().enumerate().find(|(_, r)| *r == best)
 }
 
-pub fn rollback(home: &AgentpackHome, snapshot_id: &str) -> anyhow::Result<DeploymentSnapshot> {
-    let target_path = DeploymentSnapshot::path(home, snapshot_id);
-    let target_snapshot = DeploymentSnapshot::load(&target_path)
-        .with_context(|| format!("load snapshot {}", target_path.display()))?;
-    if target_snapshot.kind == "rollback" {
-        if let Some(to) = &target_snapshot.rolled_back_to {
-            anyhow::bail!("snapshot {snapshot_id} is a rollback event; use --to {to} instead");
-        }
-        anyhow::bail!(
-            "snapshot {snapshot_id} is a rollback event and cannot be used as a rollback target"
-        );
+/// Rolls back to a snapshot id or tag, limited to `selection`.
+///
+/// Refuses with `E_ROLLBACK_LOCAL_EDITS` when a file to restore or delete was edited after the
+/// current deployment, unless `discard_local_edits` is set.
+pub fn rollback(
+    home: &AgentpackHome,
+    snapshot_ref: &str,
+    selection: &RollbackSelection,
+    discard_local_edits: bool,
+) -> anyhow::Result<(RollbackPlan, DeploymentSnapshot)> {
+    let _lock = StateLock::acquire(home, "rollback")?;
+    restore_all_interrupted(home)?;
+    let plan = plan_rollback(home, snapshot_ref, selection)?;
+    if !plan.local_edits.is_empty() && !discard_local_edits {
+        return Err(local_edits_error(&plan));
     }
 
-    let snapshots = list_snapshots(home)?;
-    if snapshots.is_empty() {
-        anyhow::bail!("no deployment snapshots found");
-    }
+    std::fs::create_dir_all(&home.snapshots_dir).context("create snapshots dir")?;
+    let now = time::OffsetDateTime::now_utc();
+    let id = now.unix_timestamp_nanos().to_string();
+    let created_at = now
+        .format(&time::format_description::well_known::Rfc3339)
+        .context("format timestamp")?;
 
-    let mut parents: std::collections::HashMap<String, Option<String>> =
-        std::collections::HashMap::new();
-    let mut head: Option<String> = None;
-    for s in &snapshots {
-        match s.kind.as_str() {
-            "deploy" | "bootstrap" => {
-                parents.insert(s.id.clone(), head.clone());
-                head = Some(s.id.clone());
-            }
-            "rollback" => {
-                if let Some(to) = &s.rolled_back_to {
-                    head = Some(to.clone());
-                }
+    // Journaled like an apply, so a crash mid-rollback is undone by `doctor --fix`.
+    let mut journal = ApplyJournal::begin(home, &id, "rollback", &created_at)?;
+    match rollback_journaled(home, &mut journal, &plan) {
+        Ok(event) => {
+            journal.commit()?;
+            Ok((plan, event))
+        }
+        Err(err) => {
+            drop(journal);
+            match restore_interrupted(home, &id) {
+                Ok(_) => Err(err),
+                Err(restore_err) => Err(err.context(format!(
+                    "rollback failed and restoring the previous files also failed ({restore_err:#}); run `agentpack doctor --fix` to retry the restore"
+                ))),
             }
-            _ => {}
         }
     }
+}
 
-    let Some(current_head) = head else {
-        anyhow::bail!("no deployment snapshots found");
-    };
+fn rollback_journaled(
+    home: &AgentpackHome,
+    journal: &mut ApplyJournal,
+    plan: &RollbackPlan,
+) -> anyhow::Result<DeploymentSnapshot> {
+    let id = journal.snapshot_id.clone();
+    let created_at = journal.created_at.clone();
+    let backup_root = DeploymentSnapshot::backup_root(home, &id);
 
     let mut applied = Vec::new();
-    let target_state_root = DeploymentSnapshot::state_root(home, snapshot_id);
-    if target_state_root.exists() {
-        let current_snapshot_path = DeploymentSnapshot::path(home, &current_head);
-        let current_snapshot =
-            DeploymentSnapshot::load(&current_snapshot_path).with_context(|| {
-                format!("load current snapshot {}", current_snapshot_path.display())
-            })?;
-
-        let mut desired_set = std::collections::HashSet::new();
-        for f in &target_snapshot.managed_files {
-            desired_set.insert((f.target.clone(), f.path.clone()));
-        }
-
-        for f in &target_snapshot.managed_files {
-            let abs = PathBuf::from(&f.path);
-            let state_path = snapshot_state_path(&target_state_root, &f.target, &abs)?;
-            let bytes = std::fs::read(&state_path).with_context(|| {
-                format!(
-                    "read snapshot state {} for {}",
-                    state_path.display(),
-                    abs.display()
-                )
-            })?;
-            let actual_sha = sha256_hex(&bytes);
-            if actual_sha != f.sha256 {
-                anyhow::bail!(
-                    "snapshot state hash mismatch for {}: expected {}, got {}",
-                    abs.display(),
-                    f.sha256,
-                    actual_sha
-                );
-            }
-
-            let before_sha256 = std::fs::read(&abs).ok().map(|b| sha256_hex(&b));
-            write_atomic(&abs, &bytes)?;
-            applied.push(AppliedChange {
-                target: f.target.clone(),
-                op: "rollback_restore".to_string(),
-                path: f.path.clone(),
-                backup_path: Some(state_path.to_string_lossy().to_string()),
-                before_sha256,
-                after_sha256: Some(actual_sha),
-            });
-        }
-
-        for c in &target_snapshot.changes {
-            let abs = PathBuf::from(&c.path);
-            if !crate::target_manifest::is_target_manifest_path(&abs) {
-                continue;
-            }
-            if c.op != "create" && c.op != "update" {
-                continue;
-            }
-            let state_path = snapshot_state_path(&target_state_root, &c.target, &abs)?;
-            let bytes = std::fs::read(&state_path).with_context(|| {
-                format!(
-                    "read snapshot state {} for {}",
-                    state_path.display(),
-                    abs.display()
-                )
-            })?;
-            let before_sha256 = std::fs::read(&abs).ok().map(|b| sha256_hex(&b));
-            let after_sha256 = Some(sha256_hex(&bytes));
-            write_atomic(&abs, &bytes)?;
-            applied.push(AppliedChange {
-                target: c.target.clone(),
-                op: "rollback_restore".to_string(),
-                path: c.path.clone(),
-                backup_path: Some(state_path.to_string_lossy().to_string()),
-                before_sha256,
-                after_sha256,
-            });
-        }
-
-        for f in &current_snapshot.managed_files {
-            if desired_set.contains(&(f.target.clone(), f.path.clone())) {
-                continue;
+    for c in &plan.plan.changes {
+        let tp = TargetPath {
+            target: c.target.clone(),
+            path: PathBuf::from(&c.path),
+        };
+        match (&c.op, plan.desired.get(&tp)) {
+            (Op::Create | Op::Update, Some(file)) => {
+                record_pre_image(journal, &backup_root, &tp.target, &tp.path)?;
+                match &file.link_target {
+                    Some(link) => write_symlink(&tp.path, link)?,
+                    None => {
+                        // Never write through a managed symlink into its target.
+                        if crate::fs::is_symlink(&tp.path) {
+                            std::fs::remove_file(&tp.path).ok();
+                        }
+                        write_atomic_with_mode(&tp.path, &file.bytes, file.mode)?;
+                    }
+                }
+                applied.push(AppliedChange {
+                    target: c.target.clone(),
+                    op: "rollback_restore".to_string(),
+                    path: c.path.clone(),
+                    backup_path: plan.sources.get(&tp).cloned(),
+                    before_sha256: c.before_sha256.clone(),
+                    after_sha256: c.after_sha256.clone(),
+                    before_link_target: None,
+                });
             }
-            let abs = PathBuf::from(&f.path);
-            let before_sha256 = std::fs::read(&abs).ok().map(|b| sha256_hex(&b));
-            if abs.exists() {
-                std::fs::remove_file(&abs).ok();
+            (Op::Delete, _) => {
+                record_pre_image(journal, &backup_root, &tp.target, &tp.path)?;
+                std::fs::remove_file(&tp.path).ok();
+                applied.push(AppliedChange {
+                    target: c.target.clone(),
+                    op: "rollback_delete".to_string(),
+                    path: c.path.clone(),
+                    backup_path: None,
+                    before_sha256: c.before_sha256.clone(),
+                    after_sha256: None,
+                    before_link_target: None,
+                });
             }
-            applied.push(AppliedChange {
-                target: f.target.clone(),
-                op: "rollback_delete".to_string(),
-                path: f.path.clone(),
-                backup_path: None,
-                before_sha256,
-                after_sha256: None,
-            });
+            _ => {}
         }
-    } else if current_head != snapshot_id {
-        let mut cursor = current_head.clone();
-        loop {
-            if cursor == snapshot_id {
-                break;
-            }
-
-            let snapshot_path = DeploymentSnapshot::path(home, &cursor);
-            let snapshot = DeploymentSnapshot::load(&snapshot_path)
-                .with_context(|| format!("load snapshot {}", snapshot_path.display()))?;
+    }
 
-            for c in &snapshot.changes {
-                let path = PathBuf::from(&c.path);
-                match (&c.op[..], &c.backup_path) {
-                    ("create", None) => {
-                        if path.exists() {
-                            std::fs::remove_file(&path).ok();
-                        }
-                        applied.push(AppliedChange {
-                            target: c.target.clone(),
-                            op: "rollback_delete".to_string(),
-                            path: c.path.clone(),
-                            backup_path: None,
-                            before_sha256: None,
-                            after_sha256: None,
-                        });
-                    }
-                    ("update" | "delete", Some(backup)) => {
-                        let backup_path = PathBuf::from(backup);
-                        if let Some(parent) = path.parent() {
-                            std::fs::create_dir_all(parent).ok();
-                        }
-                        std::fs::copy(&backup_path, &path).with_context(|| {
-                            format!("restore {} -> {}", backup_path.display(), path.display())
-                        })?;
-                        applied.push(AppliedChange {
-                            target: c.target.clone(),
-                            op: "rollback_restore".to_string(),
-                            path: c.path.clone(),
-                            backup_path: c.backup_path.clone(),
-                            before_sha256: None,
-                            after_sha256: None,
-                        });
-                    }
-                    _ => {}
+    if plan.partial {
+        applied.extend(patch_target_manifests(journal, &backup_root, plan)?);
+    } else {
+        for m in &plan.manifests {
+            let before_sha256 = std::fs::read(&m.path).ok().map(|b| sha256_hex(&b));
+            match &m.bytes {
+                Some(bytes) => {
+                    record_pre_image(journal, &backup_root, &m.target, &m.path)?;
+                    write_atomic(&m.path, bytes)?;
+                    applied.push(AppliedChange {
+                        target: m.target.clone(),
+                        op: "rollback_restore".to_string(),
+                        path: m.path.to_string_lossy().to_string(),
+                        backup_path: m.source.clone(),
+                        before_sha256,
+                        after_sha256: Some(sha256_hex(bytes)),
+                        before_link_target: None,
+                    });
+                }
+                None if crate::fs::path_present(&m.path) => {
+                    record_pre_image(journal, &backup_root, &m.target, &m.path)?;
+                    std::fs::remove_file(&m.path).ok();
+                    applied.push(AppliedChange {
+                        target: m.target.clone(),
+                        op: "rollback_delete".to_string(),
+                        path: m.path.to_string_lossy().to_string(),
+                        backup_path: None,
+                        before_sha256,
+                        after_sha256: None,
+                        before_link_target: None,
+                    });
                 }
+                None => {}
             }
-
-            cursor = parents.get(&cursor).cloned().flatten().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "snapshot {snapshot_id} is not reachable from current deployment state {current_head}"
-                )
-            })?;
         }
     }
 
-    std::fs::create_dir_all(&home.snapshots_dir).context("create snapshots dir")?;
-
-    let now = time::OffsetDateTime::now_utc();
-    let id = now.unix_timestamp_nanos().to_string();
-    let created_at = now
-        .format(&time::format_description::well_known::Rfc3339)
-        .context("format timestamp")?;
-
+    // A partial rollback leaves the current deployment in place (it does not move the head
+    // that later rollbacks start from); it only records the files it touched.
+    let (managed_files, rolled_back_to) = if plan.partial {
+        (partially_rolled_back_files(plan), None)
+    } else {
+        let target_path = DeploymentSnapshot::path(home, &plan.snapshot_id);
+        let target = DeploymentSnapshot::load(&target_path)
+            .with_context(|| format!("load snapshot {}", target_path.display()))?;
+        (target.managed_files, Some(plan.snapshot_id.clone()))
+    };
     let event = DeploymentSnapshot {
         kind: "rollback".to_string(),
         id: id.clone(),
         created_at,
-        targets: target_snapshot.targets.clone(),
-        managed_files: target_snapshot.managed_files.clone(),
+        targets: plan.targets.clone(),
+        managed_files,
         changes: applied,
-        rolled_back_to: Some(snapshot_id.to_string()),
-        lockfile_sha256: target_snapshot.lockfile_sha256.clone(),
-        backup_root: String::new(),
+        rolled_back_to,
+        lockfile_sha256: plan.lockfile_sha256.clone(),
+        backup_root: backup_root.to_string_lossy().to_string(),
+        hooks: Vec::new(),
     };
 
     let event_path = DeploymentSnapshot::path(home, &id);
@@ -474,7 +525,154 @@ pub fn rollback(home: &AgentpackHome, snapshot_id: &str) -> anyhow::Result<Deplo
     Ok(event)
 }
 
-fn snapshot_state_path(state_root: &Path, target: &str, path: &Path) -> anyhow::Result<PathBuf> {
+/// Journals what `path` looks like (backing up a regular file) before a rollback touches it.
+fn record_pre_image(
+    journal: &mut ApplyJournal,
+    backup_root: &Path,
+    target: &str,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let before_link_target = if crate::fs::is_symlink(path) {
+        std::fs::read_link(path)
+            .ok()
+            .map(|p| p.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    let backup_path = if before_link_target.is_none() && path.is_file() {
+        Some(backup_file(backup_root, target, path)?)
+    } else {
+        None
+    };
+    journal.record(JournalChange {
+        target: target.to_string(),
+        path: path.to_string_lossy().to_string(),
+        existed: crate::fs::path_present(path),
+        backup_path: backup_path.map(|p| p.to_string_lossy().to_string()),
+        before_link_target,
+    })
+}
+
+/// The current deployment's managed files with a partial rollback's changes applied.
+fn partially_rolled_back_files(plan: &RollbackPlan) -> Vec<ManagedFile> {
+    let mut files: std::collections::BTreeMap<(String, String), ManagedFile> = plan
+        .current_files
+        .iter()
+        .map(|f| ((f.target.clone(), f.path.clone()), f.clone()))
+        .collect();
+    for c in &plan.plan.changes {
+        let key = (c.target.clone(), c.path.clone());
+        let tp = TargetPath {
+            target: c.target.clone(),
+            path: PathBuf::from(&c.path),
+        };
+        match plan.desired.get(&tp) {
+            Some(file) if !matches!(c.op, Op::Delete) => {
+                files.insert(
+                    key,
+                    ManagedFile {
+                        target: c.target.clone(),
+                        path: c.path.clone(),
+                        sha256: sha256_hex(&file.bytes),
+                        link_target: file
+                            .link_target
+                            .as_ref()
+                            .map(|p| p.to_string_lossy().to_string()),
+                        mode: file.mode,
+                    },
+                );
+            }
+            _ => {
+                files.remove(&key);
+            }
+        }
+    }
+    files.into_values().collect()
+}
+
+/// Updates the target manifest entries of paths a partial rollback restored or deleted, so
+/// later plans treat them as managed with the restored content.
+fn patch_target_manifests(
+    journal: &mut ApplyJournal,
+    backup_root: &Path,
+    plan: &RollbackPlan,
+) -> anyhow::Result<Vec<AppliedChange>> {
+    let mut by_manifest: std::collections::BTreeMap<
+        (String, PathBuf),
+        Vec<&crate::deploy::PlanChange>,
+    > = std::collections::BTreeMap::new();
+    for c in &plan.plan.changes {
+        let tp = TargetPath {
+            target: c.target.clone(),
+            path: PathBuf::from(&c.path),
+        };
+        if let Some(owner) = plan.owners.get(&tp) {
+            by_manifest
+                .entry((c.target.clone(), owner.root.clone()))
+                .or_default()
+                .push(c);
+        }
+    }
+
+    let mut out = Vec::new();
+    for ((target, root), changes) in by_manifest {
+        let manifest_path = manifest_path_for_target(&root, &target);
+        let Ok(mut manifest) = TargetManifest::load(&manifest_path) else {
+            continue;
+        };
+        let before_sha256 = std::fs::read(&manifest_path).ok().map(|b| sha256_hex(&b));
+        for c in changes {
+            let tp = TargetPath {
+                target: c.target.clone(),
+                path: PathBuf::from(&c.path),
+            };
+            let rel =
+                crate::paths::path_to_posix_string(tp.path.strip_prefix(&root).unwrap_or(&tp.path));
+            manifest.managed_files.retain(|f| f.path != rel);
+            if let (Some(file), false) = (plan.desired.get(&tp), matches!(c.op, Op::Delete)) {
+                manifest.managed_files.push(ManagedManifestFile {
+                    path: rel,
+                    sha256: sha256_hex(file.deployed_bytes()),
+                    merge_base_sha256: file.merged.as_ref().map(|_| sha256_hex(&file.bytes)),
+                    module_ids: file.module_ids.clone(),
+                    link_target: file
+                        .link_target
+                        .as_ref()
+                        .map(|p| p.to_string_lossy().to_string()),
+                    mode: file.mode,
+                });
+            }
+        }
+        manifest.managed_files.sort_by(|a, b| a.path.cmp(&b.path));
+        record_pre_image(journal, backup_root, &target, &manifest_path)?;
+        manifest.save(&manifest_path)?;
+
+        out.push(AppliedChange {
+            target,
+            op: "rollback_restore".to_string(),
+            path: manifest_path.to_string_lossy().to_string(),
+            backup_path: None,
+            before_sha256,
+            after_sha256: std::fs::read(&manifest_path).ok().map(|b| sha256_hex(&b)),
+            before_link_target: None,
+        });
+    }
+    Ok(out)
+}
+
+fn op_name(op: &Op) -> &'static str {
+    match op {
+        Op::Create => "create",
+        Op::Update => "update",
+        Op::Delete => "delete",
+    }
+}
+
+pub(crate) fn snapshot_state_path(
+    state_root: &Path,
+    target: &str,
+    path: &Path,
+) -> anyhow::Result<PathBuf> {
     let target_dir = state_root.join(sanitize_module_id(target));
     let mut normalized = path.to_string_lossy().to_string();
     normalized = normalized.replace('\\', "/");
@@ -482,10 +680,22 @@ fn snapshot_state_path(state_root: &Path, target: &str, path: &Path) -> anyhow::
     Ok(target_dir.join(key.chars().take(16).collect::<String>()))
 }
 
-fn store_snapshot_state_files(state_root: &Path, desired: &DesiredState) -> anyhow::Result<()> {
+fn store_snapshot_state_files(
+    state_root: &Path,
+    merge_base_root: &Path,
+    desired: &DesiredState,
+) -> anyhow::Result<()> {
     for (tp, desired_file) in desired {
         let state_path = snapshot_state_path(state_root, &tp.target, &tp.path)?;
-        write_atomic(&state_path, &desired_file.bytes)?;
+        write_atomic_with_mode(
+            &state_path,
+            desired_file.deployed_bytes(),
+            desired_file.mode,
+        )?;
+        if desired_file.merged.is_some() {
+            let base_path = snapshot_state_path(merge_base_root, &tp.target, &tp.path)?;
+            write_atomic(&base_path, &desired_file.bytes)?;
+        }
     }
     Ok(())
 }
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 use crate::config::ModuleType;
 
@@ -13,7 +13,7 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub(crate) repo: Option<PathBuf>,
 
-    /// Profile name (default: "default")
+    /// Profile name (default: the machine group's default_profile, else "default")
     #[arg(long, default_value = "default", global = true)]
     pub(crate) profile: String,
 
@@ -133,10 +133,20 @@ pub enum Commands {
         /// Include diffs (human: unified diff; json: diff summary)
         #[arg(long)]
         diff: bool,
+
+        #[command(flatten)]
+        filter: PlanFilterArgs,
     },
 
     /// Show planned changes without applying
-    Plan,
+    Plan {
+        /// Also save the plan (with its desired outputs) to a file for `deploy --apply --plan`
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: PlanFilterArgs,
+    },
 
     /// Show diffs for planned changes
     Diff,
@@ -150,6 +160,32 @@ pub enum Commands {
         /// Allow overwriting existing unmanaged files (adopt updates)
         #[arg(long)]
         adopt: bool,
+
+        /// Deploy to every registered project (see `agentpack project add`) instead of the cwd
+        #[arg(long)]
+        all_projects: bool,
+
+        /// Use a plan saved by `plan --out` instead of re-planning (fails if targets changed since)
+        #[arg(long, conflicts_with_all = ["all_projects", "modules", "paths"])]
+        plan: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: PlanFilterArgs,
+    },
+
+    /// Poll sources, overlays and deployed files; re-plan (and optionally apply) on every change
+    Watch {
+        /// Apply the plan after edits to overlays or local_path sources (not after config changes, nor to adopt files or overwrite local edits)
+        #[arg(long)]
+        apply: bool,
+
+        /// Polling interval in milliseconds
+        #[arg(long, default_value_t = crate::watch::DEFAULT_INTERVAL_MS)]
+        interval_ms: u64,
+
+        /// Re-render only after changes have settled for this many milliseconds
+        #[arg(long, default_value_t = crate::watch::DEFAULT_DEBOUNCE_MS)]
+        debounce_ms: u64,
     },
 
     /// Check drift between expected and deployed outputs
@@ -186,6 +222,12 @@ pub enum Commands {
         fix: bool,
     },
 
+    /// Manage the registry of projects used by `deploy --all-projects`
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommands,
+    },
+
     /// Configure git remotes for the agentpack config repo
     Remote {
         #[command(subcommand)]
@@ -229,9 +271,22 @@ pub enum Commands {
 
     /// Rollback to a deployment snapshot
     Rollback {
-        /// Snapshot id to rollback to
+        /// Snapshot id or tag (see `snapshot tag`) to rollback to
         #[arg(long)]
         to: String,
+
+        /// Overwrite files that were edited after the current deployment
+        #[arg(long)]
+        discard_local_edits: bool,
+
+        #[command(flatten)]
+        filter: PlanFilterArgs,
+    },
+
+    /// List, inspect, compare, tag, export and import deployment snapshots
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
     },
 
     /// Install operator assets for AI self-serve
@@ -259,6 +314,8 @@ pub enum BootstrapScope {
 #[serde(rename_all = "snake_case")]
 pub enum OverlayScope {
     Global,
+    Profile,
+    Group,
     Machine,
     Project,
 }
@@ -270,6 +327,12 @@ pub enum OverlayEditKind {
     Patch,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayCaptureKind {
+    Patch,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum OverlayCommands {
     /// Create an overlay skeleton and open an editor
@@ -318,6 +381,32 @@ pub enum OverlayCommands {
         #[arg(long, value_enum, default_value = "global")]
         scope: OverlayScope,
     },
+
+    /// List existing overlays for all modules (kind, file counts, baseline and drift status)
+    List,
+
+    /// Capture edited files as minimal per-file patches in a patch overlay
+    Capture {
+        /// Module id to capture edits for
+        #[arg(long = "module")]
+        module_id: String,
+
+        /// Overlay kind to write (default: patch)
+        #[arg(long, value_enum, default_value = "patch")]
+        kind: OverlayCaptureKind,
+
+        /// Overlay scope to write into (default: global)
+        #[arg(long, value_enum, default_value = "global")]
+        scope: OverlayScope,
+
+        /// Edited module tree or single file (default: convert the scope's directory overlay)
+        #[arg(long)]
+        from: Option<PathBuf>,
+
+        /// Module-relative path of the --from file (default: the upstream file with the same name)
+        #[arg(long, requires = "from")]
+        rel: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -332,6 +421,65 @@ pub enum RemoteCommands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub enum ProjectCommands {
+    /// Register a project root (default: the project containing the cwd)
+    Add {
+        /// Path inside the project (resolved to its git root when available)
+        path: Option<PathBuf>,
+    },
+
+    /// List registered projects
+    List,
+
+    /// Unregister a project by project id or root path
+    Remove { project: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommands {
+    /// List deployment snapshots (oldest first)
+    List,
+
+    /// Show a snapshot and the changes it recorded
+    Show {
+        /// Snapshot id or tag
+        snapshot: String,
+    },
+
+    /// Compare the managed files of two snapshots (content diffs when available)
+    Diff {
+        /// Snapshot id or tag to compare from
+        from: String,
+        /// Snapshot id or tag to compare to
+        to: String,
+    },
+
+    /// Name a snapshot so `rollback --to <name>` (and other snapshot commands) accept it
+    Tag {
+        /// Snapshot id or tag
+        snapshot: String,
+        /// Tag name (letters, digits, '.', '_', '-'; re-tagging moves the name)
+        name: String,
+    },
+
+    /// Write a snapshot with its backups and state files to a portable archive
+    Export {
+        /// Snapshot id or tag
+        snapshot: String,
+
+        /// Archive file to write
+        #[arg(long)]
+        out: PathBuf,
+    },
+
+    /// Import a snapshot archive written by `snapshot export`
+    Import {
+        /// Archive file to read
+        file: PathBuf,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum McpCommands {
     /// Run the Agentpack MCP server over stdio
@@ -360,6 +508,33 @@ pub enum ExplainCommands {
 
     /// Explain current drift/status (module provenance and overlay layers)
     Status,
+
+    /// Annotate each line of a deployed file with the layer that produced it (blame over overlays)
+    File {
+        /// Deployed file path (relative paths resolve against the current directory)
+        path: PathBuf,
+    },
+}
+
+/// Narrows `preview`/`plan`/`deploy`/`rollback` to some of the planned changes.
+#[derive(Args, Debug, Clone, Default)]
+pub struct PlanFilterArgs {
+    /// Only include changes to files of this module (repeatable)
+    #[arg(long = "module", value_name = "MODULE_ID")]
+    pub modules: Vec<String>,
+
+    /// Only include changes whose path relative to the target root matches this glob (repeatable)
+    #[arg(long = "path", value_name = "GLOB")]
+    pub paths: Vec<String>,
+}
+
+impl PlanFilterArgs {
+    pub fn to_filter(&self) -> crate::plan_filter::PlanFilter {
+        crate::plan_filter::PlanFilter {
+            modules: self.modules.clone(),
+            paths: self.paths.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -367,12 +542,14 @@ pub enum StatusOnly {
     Missing,
     Modified,
     Extra,
+    Permissions,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EvolveScope {
     Global,
+    Profile,
     Machine,
     Project,
 }
@@ -392,6 +569,19 @@ pub enum EvolveCommands {
         /// Branch name to create (default: evolve/propose-<scope>-<module>-<timestamp>)
         #[arg(long)]
         branch: Option<String>,
+
+        /// When a module file was edited differently in several targets, propose this target's variant
+        #[arg(long, conflicts_with = "merge")]
+        prefer_target: Option<String>,
+
+        /// When a module file was edited differently in several targets, three-way merge the variants
+        #[arg(long)]
+        merge: bool,
+
+        /// Commit the edits onto the locked commit of each git-sourced module in a local clone and
+        /// write a `git format-patch` series (nothing is pushed; --scope is ignored)
+        #[arg(long)]
+        upstream: bool,
     },
 
     /// Restore missing desired outputs on disk (create-only; no updates/deletes)
@@ -421,7 +611,7 @@ impl Cli {
             Commands::Lock => vec!["lock".to_string()],
             Commands::Fetch => vec!["fetch".to_string()],
             Commands::Preview { .. } => vec!["preview".to_string()],
-            Commands::Plan => vec!["plan".to_string()],
+            Commands::Plan { .. } => vec!["plan".to_string()],
             Commands::Diff => vec!["diff".to_string()],
             Commands::Deploy { apply, .. } => {
                 let mut out = vec!["deploy".to_string()];
@@ -430,6 +620,13 @@ impl Cli {
                 }
                 out
             }
+            Commands::Watch { apply, .. } => {
+                let mut out = vec!["watch".to_string()];
+                if *apply && !self.dry_run {
+                    out.push("--apply".to_string());
+                }
+                out
+            }
             Commands::Status { .. } => vec!["status".to_string()],
             #[cfg(feature = "tui")]
             Commands::Tui { .. } => vec!["tui".to_string()],
@@ -448,6 +645,13 @@ impl Cli {
                 }
                 out
             }
+            Commands::Project { command } => match command {
+                ProjectCommands::Add { .. } => vec!["project".to_string(), "add".to_string()],
+                ProjectCommands::List => vec!["project".to_string(), "list".to_string()],
+                ProjectCommands::Remove { .. } => {
+                    vec!["project".to_string(), "remove".to_string()]
+                }
+            },
             Commands::Remote { command } => match command {
                 RemoteCommands::Set { .. } => vec!["remote".to_string(), "set".to_string()],
             },
@@ -458,6 +662,7 @@ impl Cli {
                 ExplainCommands::Plan => vec!["explain".to_string(), "plan".to_string()],
                 ExplainCommands::Diff => vec!["explain".to_string(), "diff".to_string()],
                 ExplainCommands::Status => vec!["explain".to_string(), "status".to_string()],
+                ExplainCommands::File { .. } => vec!["explain".to_string(), "file".to_string()],
             },
             Commands::Evolve { command } => match command {
                 EvolveCommands::Propose { .. } => vec!["evolve".to_string(), "propose".to_string()],
@@ -465,11 +670,27 @@ impl Cli {
             },
             Commands::Completions { .. } => vec!["completions".to_string()],
             Commands::Rollback { .. } => vec!["rollback".to_string()],
+            Commands::Snapshot { command } => match command {
+                SnapshotCommands::List => vec!["snapshot".to_string(), "list".to_string()],
+                SnapshotCommands::Show { .. } => vec!["snapshot".to_string(), "show".to_string()],
+                SnapshotCommands::Diff { .. } => vec!["snapshot".to_string(), "diff".to_string()],
+                SnapshotCommands::Tag { .. } => vec!["snapshot".to_string(), "tag".to_string()],
+                SnapshotCommands::Export { .. } => {
+                    vec!["snapshot".to_string(), "export".to_string()]
+                }
+                SnapshotCommands::Import { .. } => {
+                    vec!["snapshot".to_string(), "import".to_string()]
+                }
+            },
             Commands::Bootstrap { .. } => vec!["bootstrap".to_string()],
             Commands::Overlay { command } => match command {
                 OverlayCommands::Edit { .. } => vec!["overlay".to_string(), "edit".to_string()],
                 OverlayCommands::Rebase { .. } => vec!["overlay".to_string(), "rebase".to_string()],
                 OverlayCommands::Path { .. } => vec!["overlay".to_string(), "path".to_string()],
+                OverlayCommands::List => vec!["overlay".to_string(), "list".to_string()],
+                OverlayCommands::Capture { .. } => {
+                    vec!["overlay".to_string(), "capture".to_string()]
+                }
             },
         }
     }
@@ -490,15 +711,17 @@ impl Cli {
             Commands::Update { .. } => "update",
             Commands::Fetch => "fetch",
             Commands::Preview { .. } => "preview",
-            Commands::Plan => "plan",
+            Commands::Plan { .. } => "plan",
             Commands::Diff => "diff",
             Commands::Deploy { .. } => "deploy",
+            Commands::Watch { .. } => "watch",
             Commands::Status { .. } => "status",
             #[cfg(feature = "tui")]
             Commands::Tui { .. } => "tui",
             Commands::Mcp { .. } => "mcp",
             Commands::Policy { .. } => "policy",
             Commands::Doctor { .. } => "doctor",
+            Commands::Project { .. } => "project",
             Commands::Remote { .. } => "remote",
             Commands::Sync { .. } => "sync",
             Commands::Record => "record",
@@ -507,6 +730,7 @@ impl Cli {
             Commands::Evolve { .. } => "evolve",
             Commands::Completions { .. } => "completions",
             Commands::Rollback { .. } => "rollback",
+            Commands::Snapshot { .. } => "snapshot",
             Commands::Bootstrap { .. } => "bootstrap",
             Commands::Overlay { .. } => "overlay",
         }
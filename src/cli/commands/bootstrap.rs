@@ -80,6 +80,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes.clone(),
                     module_ids: vec!["skill:agentpack-operator".to_string()],
+                    ..Default::default()
                 },
             );
             roots.push(crate::targets::TargetRoot {
@@ -100,6 +101,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes.clone(),
                     module_ids: vec!["skill:agentpack-operator".to_string()],
+                    ..Default::default()
                 },
             );
             roots.push(crate::targets::TargetRoot {
@@ -144,6 +146,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_doctor.clone(),
                     module_ids: vec!["command:ap-doctor".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -154,6 +157,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_update.clone(),
                     module_ids: vec!["command:ap-update".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -164,6 +168,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_preview.clone(),
                     module_ids: vec!["command:ap-preview".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -174,6 +179,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_plan.clone(),
                     module_ids: vec!["command:ap-plan".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -184,6 +190,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_deploy.clone(),
                     module_ids: vec!["command:ap-deploy".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -194,6 +201,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_status.clone(),
                     module_ids: vec!["command:ap-status".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -204,6 +212,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_diff.clone(),
                     module_ids: vec!["command:ap-diff".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -214,6 +223,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_explain.clone(),
                     module_ids: vec!["command:ap-explain".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -224,6 +234,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_evolve.clone(),
                     module_ids: vec!["command:ap-evolve".to_string()],
+                    ..Default::default()
                 },
             );
         }
@@ -243,6 +254,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_skill.clone(),
                     module_ids: vec!["skill:agentpack-operator".to_string()],
+                    ..Default::default()
                 },
             );
         }
@@ -263,6 +275,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_doctor,
                     module_ids: vec!["command:ap-doctor".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -273,6 +286,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_update,
                     module_ids: vec!["command:ap-update".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -283,6 +297,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_preview,
                     module_ids: vec!["command:ap-preview".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -293,6 +308,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_plan,
                     module_ids: vec!["command:ap-plan".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -303,6 +319,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_deploy,
                     module_ids: vec!["command:ap-deploy".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -313,6 +330,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_status,
                     module_ids: vec!["command:ap-status".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -323,6 +341,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_diff,
                     module_ids: vec!["command:ap-diff".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -333,6 +352,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_explain,
                     module_ids: vec!["command:ap-explain".to_string()],
+                    ..Default::default()
                 },
             );
             desired.insert(
@@ -343,6 +363,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_evolve,
                     module_ids: vec!["command:ap-evolve".to_string()],
+                    ..Default::default()
                 },
             );
         }
@@ -362,6 +383,7 @@ pub(crate) fn build_desired_and_roots(
                 crate::deploy::DesiredFile {
                     bytes: bytes_skill,
                     module_ids: vec!["skill:agentpack-operator".to_string()],
+                    ..Default::default()
                 },
             );
         }
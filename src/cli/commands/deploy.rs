@@ -1,14 +1,33 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
 use crate::app::deploy_json::{
     deploy_json_data_applied, deploy_json_data_dry_run, deploy_json_data_no_changes,
+    deploy_json_project, deploy_json_project_failed, deploy_json_project_skipped,
 };
+use crate::app::plan_json::saved_plan_json;
 use crate::engine::Engine;
-use crate::handlers::deploy::{ConfirmationStyle, DeployApplyOutcome, deploy_apply_in};
-use crate::handlers::read_only::read_only_context_in;
+use crate::handlers::deploy::{
+    ConfirmationStyle, DeployApplyOutcome, deploy_apply_in, ensure_adopt_ok,
+};
+use crate::handlers::read_only::{
+    ReadOnlyContext, read_only_context_in, registered_project_context_in,
+};
 use crate::output::{JsonEnvelope, print_json};
+use crate::plan_filter::PlanFilter;
+use crate::project_registry::{ProjectRegistry, RegisteredProject};
+use crate::saved_plan::SavedPlan;
+use crate::state_lock::StateLock;
+use crate::user_error::UserError;
 
 use super::Ctx;
 
-pub(crate) fn run(ctx: &Ctx<'_>, apply: bool, adopt: bool) -> anyhow::Result<()> {
+pub(crate) fn run(
+    ctx: &Ctx<'_>,
+    apply: bool,
+    adopt: bool,
+    filter: &PlanFilter,
+) -> anyhow::Result<()> {
     let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
     let crate::handlers::read_only::ReadOnlyContext {
         targets,
@@ -16,7 +35,8 @@ pub(crate) fn run(ctx: &Ctx<'_>, apply: bool, adopt: bool) -> anyhow::Result<()>
         plan,
         warnings,
         roots,
-    } = read_only_context_in(&engine, &ctx.cli.profile, &ctx.cli.target)?;
+    } = read_only_context_in(&engine, &ctx.cli.profile, &ctx.cli.target)?
+        .filtered(&engine.home, filter)?;
 
     let will_apply = apply && !ctx.cli.dry_run;
 
@@ -42,12 +62,113 @@ pub(crate) fn run(ctx: &Ctx<'_>, apply: bool, adopt: bool) -> anyhow::Result<()>
         return Ok(());
     }
 
-    let mut outcome = deploy_apply_in(
-        &engine,
-        &plan,
-        &desired,
-        &roots,
-        adopt,
+    let apply = |confirmed: bool, style: ConfirmationStyle| {
+        deploy_apply_in(&engine, &plan, &desired, &roots, adopt, confirmed, style)
+    };
+    let Some(outcome) = apply_with_confirmation(ctx, apply)? else {
+        return Ok(());
+    };
+    print_outcome(
+        ctx,
+        outcome,
+        ctx.cli.profile.as_str(),
+        targets,
+        plan,
+        warnings,
+        None,
+    )
+}
+
+/// `deploy --plan <file>`: apply exactly what `plan --out` saved, refusing if any planned path
+/// changed on disk since.
+pub(crate) fn run_saved_plan(
+    ctx: &Ctx<'_>,
+    plan_file: &Path,
+    apply: bool,
+    adopt: bool,
+) -> anyhow::Result<()> {
+    let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
+    let saved = SavedPlan::load(plan_file)?;
+    let desired = saved.desired_state()?;
+    let roots = saved.target_roots();
+    let plan = saved.plan.clone();
+    let targets: Vec<String> = roots
+        .iter()
+        .map(|r| r.target.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let saved_plan = saved_plan_json(plan_file, &saved.plan_hash);
+
+    let mut warnings = Vec::new();
+    if saved.machine_id != engine.machine_id {
+        warnings.push(format!(
+            "plan was saved on machine {}; applying on {}",
+            saved.machine_id, engine.machine_id
+        ));
+    }
+
+    saved.ensure_fresh(plan_file)?;
+
+    let will_apply = apply && !ctx.cli.dry_run;
+
+    if !ctx.cli.json {
+        for w in &warnings {
+            eprintln!("Warning: {w}");
+        }
+        println!(
+            "Saved plan {} (profile {}, plan_hash {}): +{} ~{} -{}",
+            plan_file.display(),
+            saved.profile,
+            saved.plan_hash,
+            plan.summary.create,
+            plan.summary.update,
+            plan.summary.delete
+        );
+        super::super::util::print_diff(&plan, &desired)?;
+    }
+
+    if !will_apply {
+        if ctx.cli.json {
+            let mut data = deploy_json_data_dry_run(saved.profile.as_str(), targets, plan);
+            if let Some(obj) = data.as_object_mut() {
+                obj.insert("saved_plan".to_string(), saved_plan);
+            }
+            let mut envelope = JsonEnvelope::ok("deploy", data)
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+            envelope.warnings = warnings;
+            print_json(&envelope)?;
+        }
+        return Ok(());
+    }
+
+    // Hold the state lock from the freshness check through the apply so nothing can change
+    // the targets in between.
+    let apply = |confirmed: bool, style: ConfirmationStyle| {
+        let _lock = StateLock::acquire(ctx.home, "deploy")?;
+        saved.ensure_fresh(plan_file)?;
+        deploy_apply_in(&engine, &plan, &desired, &roots, adopt, confirmed, style)
+    };
+    let Some(outcome) = apply_with_confirmation(ctx, apply)? else {
+        return Ok(());
+    };
+    print_outcome(
+        ctx,
+        outcome,
+        saved.profile.as_str(),
+        targets,
+        plan,
+        warnings,
+        Some(saved_plan),
+    )
+}
+
+/// Runs `apply`, prompting once if it needs interactive confirmation; `None` if aborted.
+fn apply_with_confirmation(
+    ctx: &Ctx<'_>,
+    apply: impl Fn(bool, ConfirmationStyle) -> anyhow::Result<DeployApplyOutcome>,
+) -> anyhow::Result<Option<DeployApplyOutcome>> {
+    let mut outcome = apply(
         ctx.cli.yes,
         if ctx.cli.json {
             ConfirmationStyle::JsonYes {
@@ -61,47 +182,336 @@ pub(crate) fn run(ctx: &Ctx<'_>, apply: bool, adopt: bool) -> anyhow::Result<()>
     if matches!(outcome, DeployApplyOutcome::NeedsConfirmation) {
         if !super::super::util::confirm("Apply changes?")? {
             println!("Aborted");
-            return Ok(());
+            return Ok(None);
         }
-        outcome = deploy_apply_in(
-            &engine,
-            &plan,
-            &desired,
-            &roots,
-            adopt,
-            true,
-            ConfirmationStyle::Interactive,
-        )?;
+        outcome = apply(true, ConfirmationStyle::Interactive)?;
     }
 
-    match outcome {
+    Ok(Some(outcome))
+}
+
+fn print_outcome(
+    ctx: &Ctx<'_>,
+    outcome: DeployApplyOutcome,
+    profile: &str,
+    targets: Vec<String>,
+    plan: crate::deploy::PlanResult,
+    mut warnings: Vec<String>,
+    saved_plan: Option<serde_json::Value>,
+) -> anyhow::Result<()> {
+    let mut data = match outcome {
         DeployApplyOutcome::NoChanges => {
-            if ctx.cli.json {
-                let data = deploy_json_data_no_changes(ctx.cli.profile.as_str(), targets, plan);
-                let mut envelope = JsonEnvelope::ok("deploy", data)
-                    .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
-                envelope.warnings = warnings;
-                print_json(&envelope)?;
-            } else {
+            if !ctx.cli.json {
                 println!("No changes");
+                return Ok(());
             }
-            Ok(())
+            deploy_json_data_no_changes(profile, targets, plan)
         }
-        DeployApplyOutcome::Applied { snapshot_id } => {
-            if ctx.cli.json {
-                let data =
-                    deploy_json_data_applied(ctx.cli.profile.as_str(), targets, plan, snapshot_id);
-                let mut envelope = JsonEnvelope::ok("deploy", data)
-                    .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
-                envelope.warnings = warnings;
-                print_json(&envelope)?;
-            } else {
+        DeployApplyOutcome::Applied { snapshot_id, hooks } => {
+            let hook_warnings = crate::hooks::failure_warnings(&hooks);
+            if !ctx.cli.json {
+                for w in &hook_warnings {
+                    eprintln!("Warning: {w}");
+                }
                 println!("Applied. Snapshot: {snapshot_id}");
+                return Ok(());
             }
-            Ok(())
+            warnings.extend(hook_warnings);
+            deploy_json_data_applied(profile, targets, plan, snapshot_id, hooks)
         }
         DeployApplyOutcome::NeedsConfirmation => {
             anyhow::bail!("deploy apply requires confirmation, but confirmation was not provided")
         }
+    };
+
+    if let (Some(saved_plan), Some(obj)) = (saved_plan, data.as_object_mut()) {
+        obj.insert("saved_plan".to_string(), saved_plan);
+    }
+    let mut envelope = JsonEnvelope::ok("deploy", data)
+        .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+    envelope.warnings = warnings;
+    print_json(&envelope)
+}
+
+struct ProjectDeploy {
+    project: RegisteredProject,
+    engine: Engine,
+    context: ReadOnlyContext,
+}
+
+/// `deploy --all-projects`: plan every registered project, preview them together, then apply
+/// each project separately (one snapshot per project).
+pub(crate) fn run_all_projects(
+    ctx: &Ctx<'_>,
+    apply: bool,
+    adopt: bool,
+    filter: &PlanFilter,
+) -> anyhow::Result<()> {
+    let registry = ProjectRegistry::load(ctx.home)?;
+    let mut warnings = Vec::new();
+    let mut skipped = Vec::new();
+    let mut deploys = Vec::new();
+    let mut claimed_roots = BTreeSet::new();
+
+    if registry.projects.is_empty() {
+        warnings
+            .push("no registered projects; register one with `agentpack project add`".to_string());
+    }
+
+    for project in registry.projects {
+        let root = project.root();
+        if !root.is_dir() {
+            warnings.push(format!(
+                "project {}: directory not found; skipped",
+                project.project_root
+            ));
+            skipped.push(project);
+            continue;
+        }
+
+        let engine = Engine::load_in(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref(), &root)?;
+        let mut context =
+            registered_project_context_in(&engine, &ctx.cli.profile, &ctx.cli.target)?
+                .filtered(&engine.home, filter)?
+                .without_roots(&claimed_roots);
+        claimed_roots.extend(
+            context
+                .roots
+                .iter()
+                .map(|r| (r.target.clone(), r.root.clone())),
+        );
+        warnings.extend(
+            context
+                .warnings
+                .drain(..)
+                .map(|w| format!("project {}: {w}", project.project_root)),
+        );
+        deploys.push(ProjectDeploy {
+            project,
+            engine,
+            context,
+        });
+    }
+
+    let mut summary = crate::deploy::PlanSummary::default();
+    for d in &deploys {
+        summary.create += d.context.plan.summary.create;
+        summary.update += d.context.plan.summary.update;
+        summary.delete += d.context.plan.summary.delete;
+    }
+
+    if !ctx.cli.json {
+        for w in &warnings {
+            eprintln!("Warning: {w}");
+        }
+        for d in &deploys {
+            println!(
+                "Project {} ({})",
+                d.project.project_root, d.project.project_id
+            );
+            println!(
+                "Plan: +{} ~{} -{}",
+                d.context.plan.summary.create,
+                d.context.plan.summary.update,
+                d.context.plan.summary.delete
+            );
+            super::super::util::print_diff(&d.context.plan, &d.context.desired)?;
+        }
+        println!(
+            "Total ({} projects): +{} ~{} -{}",
+            deploys.len(),
+            summary.create,
+            summary.update,
+            summary.delete
+        );
+    }
+
+    let will_apply = apply && !ctx.cli.dry_run;
+    if !will_apply {
+        if ctx.cli.json {
+            let projects = deploys
+                .into_iter()
+                .map(|d| {
+                    deploy_json_project(
+                        &d.project,
+                        deploy_json_data_dry_run(
+                            ctx.cli.profile.as_str(),
+                            d.context.targets,
+                            d.context.plan,
+                        ),
+                    )
+                })
+                .collect();
+            print_all_projects_json(ctx, false, summary, projects, &skipped, warnings)?;
+        }
+        return Ok(());
+    }
+
+    if ctx.cli.json && !ctx.cli.yes {
+        return Err(UserError::confirm_required("deploy --apply"));
+    }
+
+    // Refuse before touching any project, so a refusal never leaves a partial rollout.
+    for d in &deploys {
+        ensure_adopt_ok(&d.context.plan, adopt)?;
+    }
+
+    let has_work = deploys.iter().any(|d| {
+        !d.context.plan.changes.is_empty()
+            || crate::target_manifest::manifests_missing_for_desired(
+                &d.context.roots,
+                &d.context.desired,
+            )
+    });
+    if has_work
+        && !ctx.cli.yes
+        && !super::super::util::confirm(&format!("Apply changes to {} projects?", deploys.len()))?
+    {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    // A failing project does not stop the rollout: the others are still applied, and the
+    // failures are reported together at the end.
+    let attempted = deploys.len();
+    let mut any_applied = false;
+    let mut failed = Vec::new();
+    let mut projects = Vec::new();
+    for d in deploys {
+        let outcome = match deploy_apply_in(
+            &d.engine,
+            &d.context.plan,
+            &d.context.desired,
+            &d.context.roots,
+            adopt,
+            true,
+            ConfirmationStyle::Interactive,
+        ) {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                if !ctx.cli.json {
+                    eprintln!("{}: failed: {err:#}", d.project.project_root);
+                }
+                projects.push(deploy_json_project_failed(&d.project, &err));
+                failed.push(d.project.project_root.clone());
+                continue;
+            }
+        };
+        match outcome {
+            DeployApplyOutcome::NoChanges => {
+                if !ctx.cli.json {
+                    println!("{}: no changes", d.project.project_root);
+                }
+                projects.push(deploy_json_project(
+                    &d.project,
+                    deploy_json_data_no_changes(
+                        ctx.cli.profile.as_str(),
+                        d.context.targets,
+                        d.context.plan,
+                    ),
+                ));
+            }
+            DeployApplyOutcome::Applied { snapshot_id, hooks } => {
+                any_applied = true;
+                let hook_warnings = crate::hooks::failure_warnings(&hooks);
+                if !ctx.cli.json {
+                    for w in &hook_warnings {
+                        eprintln!("Warning: {}: {w}", d.project.project_root);
+                    }
+                    println!(
+                        "{}: applied. Snapshot: {snapshot_id}",
+                        d.project.project_root
+                    );
+                }
+                projects.push(deploy_json_project(
+                    &d.project,
+                    deploy_json_data_applied(
+                        ctx.cli.profile.as_str(),
+                        d.context.targets,
+                        d.context.plan,
+                        snapshot_id,
+                        hooks,
+                    ),
+                ));
+                warnings.extend(
+                    hook_warnings
+                        .into_iter()
+                        .map(|w| format!("{}: {w}", d.project.project_root)),
+                );
+            }
+            DeployApplyOutcome::NeedsConfirmation => {
+                anyhow::bail!(
+                    "deploy apply requires confirmation, but confirmation was not provided"
+                )
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        let mut details = all_projects_json_data(ctx, any_applied, summary, projects, &skipped);
+        if let Some(obj) = details.as_object_mut() {
+            obj.insert("failed".to_string(), serde_json::json!(failed));
+            obj.insert("warnings".to_string(), serde_json::json!(warnings));
+            obj.insert(
+                "reason_code".to_string(),
+                serde_json::json!("deploy_projects_failed"),
+            );
+            obj.insert(
+                "next_actions".to_string(),
+                serde_json::json!(["inspect_failed_projects", "retry_command"]),
+            );
+        }
+        return Err(anyhow::Error::new(
+            UserError::new(
+                "E_DEPLOY_PROJECTS_FAILED",
+                format!(
+                    "deploy --all-projects failed for {} of {attempted} projects: {}",
+                    failed.len(),
+                    failed.join(", ")
+                ),
+            )
+            .with_details(details),
+        ));
     }
+
+    if ctx.cli.json {
+        print_all_projects_json(ctx, any_applied, summary, projects, &skipped, warnings)?;
+    }
+    Ok(())
+}
+
+fn all_projects_json_data(
+    ctx: &Ctx<'_>,
+    applied: bool,
+    summary: crate::deploy::PlanSummary,
+    mut projects: Vec<serde_json::Value>,
+    skipped: &[RegisteredProject],
+) -> serde_json::Value {
+    projects.extend(
+        skipped
+            .iter()
+            .map(|p| deploy_json_project_skipped(p, "project_root_missing")),
+    );
+    serde_json::json!({
+        "applied": applied,
+        "all_projects": true,
+        "profile": ctx.cli.profile,
+        "projects": projects,
+        "summary": summary,
+    })
+}
+
+fn print_all_projects_json(
+    ctx: &Ctx<'_>,
+    applied: bool,
+    summary: crate::deploy::PlanSummary,
+    projects: Vec<serde_json::Value>,
+    skipped: &[RegisteredProject],
+    warnings: Vec<String>,
+) -> anyhow::Result<()> {
+    let data = all_projects_json_data(ctx, applied, summary, projects, skipped);
+    let mut envelope = JsonEnvelope::ok("deploy", data)
+        .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+    envelope.warnings = warnings;
+    print_json(&envelope)
 }
@@ -16,18 +16,31 @@ pub(crate) fn run(ctx: &Ctx<'_>, fix: bool) -> anyhow::Result<()> {
 
     let report =
         crate::handlers::doctor::doctor_report_in(&engine, &ctx.cli.profile, &ctx.cli.target, fix)?;
-    let next_actions = doctor_next_actions(&report.roots, report.needs_gitignore_fix, fix, &prefix);
+    let next_actions = doctor_next_actions(
+        &report.roots,
+        report.needs_gitignore_fix,
+        report.needs_restore,
+        fix,
+        &prefix,
+    );
 
     let crate::handlers::doctor::DoctorReport {
         machine_id,
         roots: checks,
         gitignore_fixes,
+        interrupted_applies,
         mut warnings,
         ..
     } = report;
 
     if ctx.cli.json {
-        let data = doctor_json_data(machine_id, checks, gitignore_fixes, &next_actions.json)?;
+        let data = doctor_json_data(
+            machine_id,
+            checks,
+            gitignore_fixes,
+            interrupted_applies,
+            &next_actions.json,
+        )?;
         let mut envelope = JsonEnvelope::ok("doctor", data)
             .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
         envelope.warnings = warnings;
@@ -46,6 +59,12 @@ pub(crate) fn run(ctx: &Ctx<'_>, fix: bool) -> anyhow::Result<()> {
                     );
                 }
             }
+            for a in &interrupted_applies {
+                println!(
+                    "Restored {} path(s) from interrupted apply {}",
+                    a.apply.changes, a.apply.snapshot_id
+                );
+            }
         }
         for c in checks {
             let status = if c.issues.is_empty() { "ok" } else { "issues" };
@@ -1,6 +1,6 @@
 use crate::app::evolve_propose_json::{
     evolve_propose_json_data_created, evolve_propose_json_data_dry_run,
-    evolve_propose_json_data_noop,
+    evolve_propose_json_data_noop, evolve_propose_json_data_upstream,
 };
 use crate::app::evolve_restore_json::evolve_restore_json_data;
 use crate::engine::Engine;
@@ -16,13 +16,27 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &EvolveCommands) -> anyhow::Result<()>
             module_id,
             scope,
             branch,
-        } => evolve_propose(
-            ctx.cli,
-            &engine,
-            module_id.as_deref(),
-            *scope,
-            branch.as_deref(),
-        ),
+            prefer_target,
+            merge,
+            upstream,
+        } => {
+            let divergent = match (prefer_target.as_deref(), *merge) {
+                (Some(target), _) => {
+                    crate::handlers::evolve::EvolveDivergentResolution::PreferTarget(target)
+                }
+                (None, true) => crate::handlers::evolve::EvolveDivergentResolution::Merge,
+                (None, false) => crate::handlers::evolve::EvolveDivergentResolution::Skip,
+            };
+            evolve_propose(
+                ctx.cli,
+                &engine,
+                module_id.as_deref(),
+                *scope,
+                branch.as_deref(),
+                divergent,
+                *upstream,
+            )
+        }
         EvolveCommands::Restore { module_id } => {
             evolve_restore(ctx.cli, &engine, module_id.as_deref())
         }
@@ -35,10 +49,13 @@ fn evolve_propose(
     module_filter: Option<&str>,
     scope: EvolveScope,
     branch_override: Option<&str>,
+    divergent: crate::handlers::evolve::EvolveDivergentResolution<'_>,
+    upstream: bool,
 ) -> anyhow::Result<()> {
     let prefix = action_prefix(cli);
     let handler_scope = match scope {
         EvolveScope::Global => crate::handlers::evolve::EvolveScope::Global,
+        EvolveScope::Profile => crate::handlers::evolve::EvolveScope::Profile,
         EvolveScope::Machine => crate::handlers::evolve::EvolveScope::Machine,
         EvolveScope::Project => crate::handlers::evolve::EvolveScope::Project,
     };
@@ -52,6 +69,8 @@ fn evolve_propose(
             module_filter,
             scope: handler_scope,
             branch_override,
+            divergent,
+            upstream,
             dry_run: cli.dry_run,
             confirmed: cli.yes,
             json: cli.json,
@@ -75,6 +94,8 @@ fn evolve_propose(
                 module_filter,
                 scope: handler_scope,
                 branch_override,
+                divergent,
+                upstream,
                 dry_run: cli.dry_run,
                 confirmed: true,
                 json: false,
@@ -114,6 +135,7 @@ fn evolve_propose(
                                     }
                                 });
                             println!("- {} {} {} modules={who}", s.reason, s.target, s.path);
+                            print_divergent_variants(&s);
                             match s.reason.as_str() {
                                 "missing" => {
                                     println!(
@@ -125,6 +147,16 @@ fn evolve_propose(
                                         "  hint: add per-module markers to aggregated outputs or split outputs so each file maps to one module"
                                     );
                                 }
+                                "synthesized_text_edited" => {
+                                    println!(
+                                        "  hint: keep edits inside module content; generated frontmatter, separators and markers cannot be proposed"
+                                    );
+                                }
+                                "divergent_edits" => {
+                                    println!(
+                                        "  hint: rerun with --prefer-target <target> to pick a variant, or --merge to combine them"
+                                    );
+                                }
                                 _ => {}
                             }
                         }
@@ -150,7 +182,12 @@ fn evolve_propose(
                 }
                 println!("Candidates (dry-run):");
                 for i in report.candidates {
-                    println!("- {} {} {}", i.module_id, i.target, i.path);
+                    match i.resolution.as_deref() {
+                        Some(resolution) => {
+                            println!("- {} {} {} ({resolution})", i.module_id, i.target, i.path)
+                        }
+                        None => println!("- {} {} {}", i.module_id, i.target, i.path),
+                    }
                 }
                 if !report.skipped.is_empty() {
                     println!("Skipped drift (not proposeable):");
@@ -167,6 +204,7 @@ fn evolve_propose(
                                 }
                             });
                         println!("- {} {} {} modules={who}", s.reason, s.target, s.path);
+                        print_divergent_variants(&s);
                         match s.reason.as_str() {
                             "missing" => {
                                 println!(
@@ -178,6 +216,16 @@ fn evolve_propose(
                                     "  hint: add per-module markers to aggregated outputs or split outputs so each file maps to one module"
                                 );
                             }
+                            "synthesized_text_edited" => {
+                                println!(
+                                    "  hint: keep edits inside module content; generated frontmatter, separators and markers cannot be proposed"
+                                );
+                            }
+                            "divergent_edits" => {
+                                println!(
+                                    "  hint: rerun with --prefer-target <target> to pick a variant, or --merge to combine them"
+                                );
+                            }
                             _ => {}
                         }
                     }
@@ -212,12 +260,57 @@ fn evolve_propose(
             }
             Ok(())
         }
+        crate::handlers::evolve::EvolveProposeOutcome::Upstream(report) => {
+            if cli.json {
+                let data = evolve_propose_json_data_upstream(report.proposals, report.skipped);
+                let mut envelope = JsonEnvelope::ok("evolve.propose", data)
+                    .with_command_meta(cli.command_id(), cli.command_path());
+                envelope.warnings = report.warnings;
+                print_json(&envelope)?;
+            } else {
+                for w in report.warnings {
+                    eprintln!("Warning: {w}");
+                }
+                for p in report.proposals {
+                    println!(
+                        "Created upstream proposal for {} ({} @ {})",
+                        p.module_id, p.url, p.base_commit
+                    );
+                    println!("  branch: {} ({})", p.branch, p.clone_dir);
+                    for f in &p.files {
+                        println!("  - {f}");
+                    }
+                    for patch in &p.patches {
+                        println!("  patch: {patch}");
+                    }
+                    println!(
+                        "  hint: push with `git -C {} push <remote> {}` or send the patches",
+                        p.clone_dir, p.branch
+                    );
+                }
+                for s in report.skipped {
+                    println!("- skipped {} {} {}", s.reason, s.target, s.path);
+                }
+            }
+            Ok(())
+        }
         crate::handlers::evolve::EvolveProposeOutcome::NeedsConfirmation => {
             anyhow::bail!("evolve propose requires confirmation, but confirmation was not provided")
         }
     }
 }
 
+fn print_divergent_variants(item: &crate::handlers::evolve::EvolveProposeSkippedItem) {
+    for v in &item.variants {
+        println!("  variant {} {} sha256={}", v.target, v.path, v.sha256);
+    }
+    if let Some(diff) = item.diff.as_deref() {
+        for line in diff.lines() {
+            println!("    {line}");
+        }
+    }
+}
+
 fn evolve_restore(
     cli: &super::super::args::Cli,
     engine: &Engine,
@@ -10,6 +10,7 @@ use crate::deploy::TargetPath;
 use crate::deploy::load_managed_paths_from_snapshot;
 use crate::deploy::plan as compute_plan;
 use crate::engine::Engine;
+use crate::handlers::explain::{explain_file_json_data, explain_file_report};
 use crate::hash::sha256_hex;
 use crate::output::{JsonEnvelope, print_json};
 use crate::state::latest_snapshot;
@@ -23,9 +24,62 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &ExplainCommands) -> anyhow::Result<()
         ExplainCommands::Plan => explain_plan(ctx.cli, &engine),
         ExplainCommands::Diff => explain_plan(ctx.cli, &engine),
         ExplainCommands::Status => explain_status(ctx.cli, &engine),
+        ExplainCommands::File { path } => explain_file(ctx.cli, &engine, path),
     }
 }
 
+fn explain_file(
+    cli: &super::super::args::Cli,
+    engine: &Engine,
+    path: &std::path::Path,
+) -> anyhow::Result<()> {
+    let report = explain_file_report(engine, &cli.profile, &cli.target, path)?;
+
+    if cli.json {
+        let data = explain_file_json_data(&cli.profile, &report);
+        let mut envelope = JsonEnvelope::ok("explain.file", data)
+            .with_command_meta(cli.command_id(), cli.command_path());
+        envelope.warnings = report.warnings;
+        print_json(&envelope)?;
+    } else {
+        for w in &report.warnings {
+            eprintln!("Warning: {w}");
+        }
+        println!(
+            "Explain file {} (target={}):",
+            report.path.display(),
+            report.target
+        );
+        for m in &report.modules {
+            println!(
+                "- module={} type={} path={}",
+                m.module_id,
+                m.module_type.as_deref().unwrap_or("-"),
+                m.module_path.as_deref().unwrap_or("-")
+            );
+        }
+        if !report.deployed {
+            println!("Note: not deployed yet; showing the desired content");
+        } else if report.modified {
+            println!("Note: modified on disk since deploy; edited lines are marked `local`");
+        }
+        let labels: Vec<String> = report
+            .lines
+            .iter()
+            .map(|(_, origin)| match &origin.module_id {
+                Some(module_id) => format!("{} {module_id}", origin.label()),
+                None => origin.label(),
+            })
+            .collect();
+        let width = labels.iter().map(String::len).max().unwrap_or(0);
+        for (idx, ((text, _), label)) in report.lines.iter().zip(&labels).enumerate() {
+            println!("{label:<width$} {:>4}| {text}", idx + 1);
+        }
+    }
+
+    Ok(())
+}
+
 fn explain_plan(cli: &super::super::args::Cli, engine: &Engine) -> anyhow::Result<()> {
     let targets = super::super::util::selected_targets(&engine.manifest, &cli.target)?;
     let render = engine.desired_state(&cli.profile, &cli.target)?;
@@ -77,16 +131,23 @@ fn explain_plan(cli: &super::super::args::Cli, engine: &Engine) -> anyhow::Resul
             let module_path = module.and_then(|m| {
                 super::super::util::module_rel_path_for_output(m, &module_id, &tp, &roots)
             });
-            let layer = match (module, module_path.as_deref()) {
-                (Some(m), Some(rel)) => Some(super::super::util::source_layer_for_module_file(
-                    engine, m, rel,
+            let layers = match (module, module_path.as_deref()) {
+                (Some(m), Some(rel)) => Some(super::super::util::source_layers_for_module_file(
+                    engine,
+                    m,
+                    &cli.profile,
+                    rel,
                 )?),
                 _ => None,
             };
+            let layer = layers
+                .as_ref()
+                .map(|l| l.last().cloned().unwrap_or_else(|| "missing".to_string()));
             modules.push(ExplainedModule {
                 module_id,
                 module_type,
                 layer,
+                layers: layers.unwrap_or_default(),
                 module_path,
             });
         }
@@ -101,7 +162,8 @@ fn explain_plan(cli: &super::super::args::Cli, engine: &Engine) -> anyhow::Resul
     }
 
     if cli.json {
-        let data = explain_plan_json_data(&cli.profile, targets, explained);
+        let data =
+            explain_plan_json_data(&cli.profile, engine.machine_group()?, targets, explained);
         let mut envelope = JsonEnvelope::ok("explain.plan", data)
             .with_command_meta(cli.command_id(), cli.command_path());
         envelope.warnings = warnings;
@@ -110,12 +172,23 @@ fn explain_plan(cli: &super::super::args::Cli, engine: &Engine) -> anyhow::Resul
         for w in warnings.drain(..) {
             eprintln!("Warning: {w}");
         }
-        println!("Explain plan (machine_id={}):", engine.machine_id);
+        match engine.machine_group()? {
+            Some(group) => println!(
+                "Explain plan (machine_id={} group={group}):",
+                engine.machine_id
+            ),
+            None => println!("Explain plan (machine_id={}):", engine.machine_id),
+        }
         for c in explained {
             println!("- {} {} {}", c.op, c.target, c.path);
             for m in c.modules {
+                let layers = if m.layers.len() > 1 {
+                    format!(" layers={}", m.layers.join(","))
+                } else {
+                    String::new()
+                };
                 println!(
-                    "  - module={} type={} layer={} path={}",
+                    "  - module={} type={} layer={}{layers} path={}",
                     m.module_id,
                     m.module_type.as_deref().unwrap_or("-"),
                     m.layer.as_deref().unwrap_or("-"),
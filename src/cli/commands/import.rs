@@ -3,9 +3,10 @@ use std::path::{Path, PathBuf};
 use anyhow::Context as _;
 
 use crate::config::{LocalPathSource, Manifest, Module, ModuleType, Source};
-use crate::fs::{copy_tree, write_atomic};
+use crate::fs::{copy_tree, write_atomic, write_atomic_with_mode};
 use crate::output::{JsonEnvelope, print_json};
 use crate::project::ProjectContext;
+use crate::state_lock::StateLock;
 use crate::user_error::UserError;
 use crate::validate::validate_materialized_module;
 
@@ -939,6 +940,7 @@ fn apply_imports(
     project_tag: &str,
     plan: &[PlannedImport],
 ) -> anyhow::Result<()> {
+    let _lock = StateLock::acquire(ctx.home, "import --apply")?;
     let mut conflicts = Vec::new();
     for p in plan.iter().filter(|p| p.op == PlanOp::Create) {
         if p.dst.exists() {
@@ -981,7 +983,8 @@ fn apply_imports(
         } else {
             let bytes =
                 std::fs::read(&p.src).with_context(|| format!("read {}", p.src.display()))?;
-            write_atomic(&p.dst, &bytes).with_context(|| format!("write {}", p.dst.display()))?;
+            write_atomic_with_mode(&p.dst, &bytes, crate::fs::file_mode(&p.src))
+                .with_context(|| format!("write {}", p.dst.display()))?;
         }
     }
 
@@ -1005,6 +1008,7 @@ fn apply_imports(
                 include_tags: vec!["base".to_string(), project_tag.to_string()],
                 include_modules: Vec::new(),
                 exclude_modules: Vec::new(),
+                instructions_subpaths: Default::default(),
             });
         if !profile.include_tags.iter().any(|t| t == "base") {
             profile.include_tags.push("base".to_string());
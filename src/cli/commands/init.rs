@@ -140,6 +140,7 @@ fn guided_manifest(targets: &[String], scope: TargetScope) -> (Manifest, Vec<Str
             include_tags: vec!["base".to_string()],
             include_modules: Vec::new(),
             exclude_modules: Vec::new(),
+            instructions_subpaths: Default::default(),
         },
     );
 
@@ -301,6 +302,10 @@ fn guided_manifest(targets: &[String], scope: TargetScope) -> (Manifest, Vec<Str
             version: 1,
             profiles,
             targets: out_targets,
+            custom_targets: Default::default(),
+            plugin_targets: Default::default(),
+            hooks: Default::default(),
+            machines: Default::default(),
             modules: Vec::new(),
         },
         warnings,
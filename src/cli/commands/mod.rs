@@ -20,17 +20,20 @@ pub(crate) mod overlay;
 pub(crate) mod plan;
 pub(crate) mod policy;
 pub(crate) mod preview;
+pub(crate) mod project;
 pub(crate) mod record;
 pub(crate) mod remote;
 pub(crate) mod remove;
 pub(crate) mod rollback;
 pub(crate) mod schema;
 pub(crate) mod score;
+pub(crate) mod snapshot;
 pub(crate) mod status;
 pub(crate) mod sync;
 #[cfg(feature = "tui")]
 pub(crate) mod tui;
 pub(crate) mod update;
+pub(crate) mod watch;
 
 pub(crate) struct Ctx<'a> {
     pub(crate) cli: &'a Cli,
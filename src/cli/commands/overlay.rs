@@ -1,10 +1,12 @@
 use anyhow::Context as _;
 
 use crate::engine::Engine;
+use crate::handlers::overlay::{overlay_list_json_data, overlay_list_report};
 use crate::output::{JsonEnvelope, print_json};
 use crate::overlay::{
-    OverlayRebaseOptions, ensure_overlay_skeleton, ensure_overlay_skeleton_sparse,
-    ensure_patch_overlay_layout, materialize_overlay_from_upstream, rebase_overlay,
+    OverlayCaptureSource, OverlayRebaseOptions, capture_patch_overlay, ensure_overlay_skeleton,
+    ensure_overlay_skeleton_sparse, ensure_patch_overlay_layout, materialize_overlay_from_upstream,
+    rebase_overlay,
 };
 use crate::user_error::UserError;
 
@@ -37,8 +39,12 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
                 effective_scope = OverlayScope::Project;
             }
 
-            let overlay_dir =
-                super::super::util::overlay_dir_for_scope(&engine, module_id_str, effective_scope);
+            let overlay_dir = super::super::util::overlay_dir_for_scope(
+                &engine,
+                &ctx.cli.profile,
+                module_id_str,
+                effective_scope,
+            )?;
 
             let skeleton = match kind {
                 super::super::args::OverlayEditKind::Patch => {
@@ -136,6 +142,8 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
                         "patches_dir": patches_dir,
                         "project": effective_scope == OverlayScope::Project,
                         "machine_id": if matches!(effective_scope, OverlayScope::Machine) { Some(engine.machine_id.clone()) } else { None },
+                        "profile": if matches!(effective_scope, OverlayScope::Profile) { Some(ctx.cli.profile.as_str()) } else { None },
+                        "group": if matches!(effective_scope, OverlayScope::Group) { engine.machine_group()? } else { None },
                         "project_id": if matches!(effective_scope, OverlayScope::Project) { Some(engine.project.project_id.clone()) } else { None },
                     }),
                 )
@@ -178,10 +186,14 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
             let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
             let module_id_str = module_id.as_str();
 
-            let overlay_dir =
-                super::super::util::overlay_dir_for_scope(&engine, module_id_str, *scope);
+            let overlay_dir = super::super::util::overlay_dir_for_scope(
+                &engine,
+                &ctx.cli.profile,
+                module_id_str,
+                *scope,
+            )?;
 
-            let report = rebase_overlay(
+            let mut report = rebase_overlay(
                 &engine.home,
                 &engine.repo,
                 &engine.manifest,
@@ -211,6 +223,7 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
                         "dry_run": ctx.cli.dry_run,
                         "sparsify": sparsify,
                         "conflicts": report.conflicts,
+                        "conflict_hunks": report.conflict_hunks,
                         "summary": report.summary,
                         "reason_code": "overlay_rebase_conflict",
                         "next_actions": ["resolve_overlay_conflicts", "retry_overlay_rebase"],
@@ -218,8 +231,9 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
                 ));
             }
 
+            let warnings = std::mem::take(&mut report.warnings);
             if ctx.cli.json {
-                let envelope = JsonEnvelope::ok(
+                let mut envelope = JsonEnvelope::ok(
                     "overlay.rebase",
                     serde_json::json!({
                         "module_id": module_id,
@@ -232,8 +246,12 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
                     }),
                 )
                 .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                envelope.warnings = warnings;
                 print_json(&envelope)?;
             } else {
+                for w in &warnings {
+                    eprintln!("Warning: {w}");
+                }
                 let verb = if ctx.cli.dry_run {
                     "Would rebase"
                 } else {
@@ -253,8 +271,12 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
             let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
             let module_id_str = module_id.as_str();
 
-            let overlay_dir =
-                super::super::util::overlay_dir_for_scope(&engine, module_id_str, *scope);
+            let overlay_dir = super::super::util::overlay_dir_for_scope(
+                &engine,
+                &ctx.cli.profile,
+                module_id_str,
+                *scope,
+            )?;
 
             if ctx.cli.json {
                 let envelope = JsonEnvelope::ok(
@@ -272,6 +294,133 @@ pub(crate) fn run(ctx: &Ctx<'_>, command: &OverlayCommands) -> anyhow::Result<()
                 println!("{}", overlay_dir.display());
             }
         }
+        OverlayCommands::Capture {
+            module_id,
+            kind,
+            scope,
+            from,
+            rel,
+        } => {
+            if ctx.cli.json && !ctx.cli.yes && !ctx.cli.dry_run {
+                return Err(UserError::confirm_required("overlay capture"));
+            }
+            let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
+            let overlay_dir = super::super::util::overlay_dir_for_scope(
+                &engine,
+                &ctx.cli.profile,
+                module_id,
+                *scope,
+            )?;
+
+            let source = match from {
+                None => OverlayCaptureSource::Overlay,
+                Some(path) if path.is_dir() => OverlayCaptureSource::Dir(path),
+                Some(path) => OverlayCaptureSource::File {
+                    path,
+                    relpath: rel.as_deref(),
+                },
+            };
+            let report = capture_patch_overlay(
+                &engine.home,
+                &engine.repo,
+                &engine.manifest,
+                module_id,
+                &overlay_dir,
+                source,
+                ctx.cli.dry_run,
+            )
+            .context("capture overlay")?;
+
+            if ctx.cli.json {
+                let envelope = JsonEnvelope::ok(
+                    "overlay.capture",
+                    serde_json::json!({
+                        "module_id": module_id,
+                        "scope": scope,
+                        "kind": kind,
+                        "overlay_dir": overlay_dir,
+                        "overlay_dir_posix": crate::paths::path_to_posix_string(&overlay_dir),
+                        "from": from,
+                        "dry_run": ctx.cli.dry_run,
+                        "report": report,
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else {
+                let verb = if ctx.cli.dry_run {
+                    "Would capture"
+                } else {
+                    "Captured"
+                };
+                println!(
+                    "{verb} {} patch(es) for {module_id} into {}",
+                    report.patched.len(),
+                    overlay_dir.display()
+                );
+                for p in &report.patched {
+                    println!("  patched {p}");
+                }
+                for p in &report.removed {
+                    println!("  removed {p} (matches upstream)");
+                }
+                for s in &report.skipped {
+                    println!("  skipped {} ({})", s.path, s.reason);
+                }
+            }
+        }
+        OverlayCommands::List => {
+            let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
+            let report = overlay_list_report(&engine)?;
+
+            if ctx.cli.json {
+                let mut envelope =
+                    JsonEnvelope::ok("overlay.list", overlay_list_json_data(&report))
+                        .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                envelope.warnings = report.warnings;
+                print_json(&envelope)?;
+            } else {
+                for w in &report.warnings {
+                    eprintln!("Warning: {w}");
+                }
+                if report.overlays.is_empty() {
+                    println!("No overlays");
+                    return Ok(());
+                }
+                for o in &report.overlays {
+                    let counts = match o.kind.as_str() {
+                        "patch" => format!("{} patches", o.patches),
+                        _ => format!("{} files", o.files),
+                    };
+                    let deletes = if o.deletes > 0 {
+                        format!(", {} deletes", o.deletes)
+                    } else {
+                        String::new()
+                    };
+                    let scope = match &o.scope_id {
+                        Some(id) => format!("{}:{id}", o.scope),
+                        None => o.scope.clone(),
+                    };
+                    println!(
+                        "{} [{scope}] {} ({counts}{deletes}): {}",
+                        o.module_id,
+                        o.kind,
+                        o.status.as_str()
+                    );
+                    for d in &o.drift {
+                        println!("  - {d}");
+                    }
+                }
+                let s = &report.summary;
+                println!(
+                    "Summary: total={} clean={} drifted={} no_baseline={} upstream_unavailable={}",
+                    s.total, s.clean, s.drifted, s.no_baseline, s.upstream_unavailable
+                );
+                if s.drifted > 0 {
+                    println!("Next: agentpack overlay rebase <module_id> --scope <scope>");
+                }
+            }
+        }
     }
 
     Ok(())
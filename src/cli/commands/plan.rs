@@ -1,24 +1,47 @@
-use crate::app::plan_json::plan_json_data;
-use crate::handlers::read_only::read_only_context;
+use std::path::Path;
+
+use crate::app::plan_json::{plan_json_data, saved_plan_json};
+use crate::engine::Engine;
+use crate::handlers::read_only::read_only_context_in;
 use crate::output::{JsonEnvelope, print_json};
+use crate::plan_filter::PlanFilter;
+use crate::saved_plan::SavedPlan;
 
 use super::Ctx;
 
-pub(crate) fn run(ctx: &Ctx<'_>) -> anyhow::Result<()> {
+pub(crate) fn run(ctx: &Ctx<'_>, out: Option<&Path>, filter: &PlanFilter) -> anyhow::Result<()> {
+    let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
     let crate::handlers::read_only::ReadOnlyContext {
         targets,
+        desired,
         plan,
         warnings,
-        ..
-    } = read_only_context(
-        ctx.cli.repo.as_deref(),
-        ctx.cli.machine.as_deref(),
-        &ctx.cli.profile,
-        &ctx.cli.target,
-    )?;
+        roots,
+    } = read_only_context_in(&engine, &ctx.cli.profile, &ctx.cli.target)?
+        .filtered(&engine.home, filter)?;
+
+    let saved = match out {
+        Some(out) => {
+            let saved = SavedPlan::new(
+                &ctx.cli.profile,
+                &ctx.cli.target,
+                &engine.machine_id,
+                &engine.project.project_root,
+                &plan,
+                &desired,
+                &roots,
+            )?;
+            saved.save(out)?;
+            Some((out, saved.plan_hash))
+        }
+        None => None,
+    };
 
     if ctx.cli.json {
-        let data = plan_json_data(ctx.cli.profile.as_str(), targets, plan);
+        let mut data = plan_json_data(ctx.cli.profile.as_str(), targets, plan);
+        if let (Some((out, plan_hash)), Some(obj)) = (&saved, data.as_object_mut()) {
+            obj.insert("saved_plan".to_string(), saved_plan_json(out, plan_hash));
+        }
         let mut envelope = JsonEnvelope::ok("plan", data)
             .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
         envelope.warnings = warnings;
@@ -34,6 +57,9 @@ pub(crate) fn run(ctx: &Ctx<'_>) -> anyhow::Result<()> {
         for c in &plan.changes {
             println!("{:?} {} {}", c.op, c.target, c.path);
         }
+        if let Some((out, plan_hash)) = &saved {
+            println!("Saved plan to {} (plan_hash {plan_hash})", out.display());
+        }
     }
 
     Ok(())
@@ -1,22 +1,21 @@
 use crate::app::preview_json::preview_json_data;
-use crate::handlers::read_only::read_only_context;
+use crate::engine::Engine;
+use crate::handlers::read_only::read_only_context_in;
 use crate::output::{JsonEnvelope, print_json};
+use crate::plan_filter::PlanFilter;
 
 use super::Ctx;
 
-pub(crate) fn run(ctx: &Ctx<'_>, diff: bool) -> anyhow::Result<()> {
+pub(crate) fn run(ctx: &Ctx<'_>, diff: bool, filter: &PlanFilter) -> anyhow::Result<()> {
+    let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
     let crate::handlers::read_only::ReadOnlyContext {
         targets,
         desired,
         plan,
         mut warnings,
         roots,
-    } = read_only_context(
-        ctx.cli.repo.as_deref(),
-        ctx.cli.machine.as_deref(),
-        &ctx.cli.profile,
-        &ctx.cli.target,
-    )?;
+    } = read_only_context_in(&engine, &ctx.cli.profile, &ctx.cli.target)?
+        .filtered(&engine.home, filter)?;
 
     if ctx.cli.json {
         let data = preview_json_data(
@@ -0,0 +1,108 @@
+use crate::output::{JsonEnvelope, print_json};
+use crate::project_registry::{ProjectRegistry, RegisteredProject};
+
+use super::super::args::ProjectCommands;
+use super::Ctx;
+
+pub(crate) fn run(ctx: &Ctx<'_>, command: &ProjectCommands) -> anyhow::Result<()> {
+    match command {
+        ProjectCommands::Add { path } => {
+            super::super::util::require_yes_for_json_mutation(ctx.cli, "project add")?;
+            let path = match path {
+                Some(p) => p.clone(),
+                None => std::env::current_dir()?,
+            };
+
+            let mut registry = ProjectRegistry::load(ctx.home)?;
+            let (project, added) = registry.add(&path)?;
+            registry.save(ctx.home)?;
+
+            if ctx.cli.json {
+                let envelope = JsonEnvelope::ok(
+                    "project.add",
+                    serde_json::json!({
+                        "added": added,
+                        "project": project_json(&project),
+                        "registry": ProjectRegistry::path(ctx.home).display().to_string(),
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else if added {
+                println!(
+                    "Registered project {} ({})",
+                    project.project_root, project.project_id
+                );
+            } else {
+                println!(
+                    "Project already registered: {} ({})",
+                    project.project_root, project.project_id
+                );
+            }
+        }
+        ProjectCommands::List => {
+            let registry = ProjectRegistry::load(ctx.home)?;
+            if ctx.cli.json {
+                let projects: Vec<serde_json::Value> =
+                    registry.projects.iter().map(project_json).collect();
+                let envelope = JsonEnvelope::ok(
+                    "project.list",
+                    serde_json::json!({
+                        "projects": projects,
+                        "registry": ProjectRegistry::path(ctx.home).display().to_string(),
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else if registry.projects.is_empty() {
+                println!("No registered projects (add one with `agentpack project add`)");
+            } else {
+                for project in &registry.projects {
+                    let missing = if project.root().is_dir() {
+                        ""
+                    } else {
+                        " (missing)"
+                    };
+                    println!("{}  {}{missing}", project.project_id, project.project_root);
+                }
+            }
+        }
+        ProjectCommands::Remove { project } => {
+            super::super::util::require_yes_for_json_mutation(ctx.cli, "project remove")?;
+            let mut registry = ProjectRegistry::load(ctx.home)?;
+            let removed = registry.remove(project)?;
+            registry.save(ctx.home)?;
+
+            if ctx.cli.json {
+                let envelope = JsonEnvelope::ok(
+                    "project.remove",
+                    serde_json::json!({
+                        "removed": true,
+                        "project": project_json(&removed),
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else {
+                println!(
+                    "Unregistered project {} ({})",
+                    removed.project_root, removed.project_id
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn project_json(project: &RegisteredProject) -> serde_json::Value {
+    let root = project.root();
+    serde_json::json!({
+        "project_id": project.project_id,
+        "project_root": project.project_root,
+        "project_root_posix": crate::paths::path_to_posix_string(&root),
+        "origin_url": project.origin_url,
+        "added_at": project.added_at,
+        "exists": root.is_dir(),
+    })
+}
@@ -1,19 +1,104 @@
-use crate::app::rollback_json::rollback_json_data;
-use crate::handlers::rollback::rollback;
+use std::io::IsTerminal as _;
+
+use crate::app::rollback_json::{rollback_json_data, rollback_json_data_dry_run};
+use crate::handlers::rollback::{rollback, rollback_dry_run};
 use crate::output::{JsonEnvelope, print_json};
+use crate::plan_filter::PlanFilter;
+use crate::rollback_plan::RollbackSelection;
 
 use super::Ctx;
 
-pub(crate) fn run(ctx: &Ctx<'_>, snapshot_id: &str) -> anyhow::Result<()> {
-    let event = rollback(ctx.home, snapshot_id, ctx.cli.json, ctx.cli.yes)?;
+pub(crate) fn run(
+    ctx: &Ctx<'_>,
+    snapshot_id: &str,
+    discard_local_edits: bool,
+    filter: &PlanFilter,
+) -> anyhow::Result<()> {
+    let selection = RollbackSelection {
+        target: (ctx.cli.target != "all").then(|| ctx.cli.target.clone()),
+        filter: filter.clone(),
+    };
+
+    if ctx.cli.dry_run {
+        let plan = rollback_dry_run(ctx.home, snapshot_id, &selection)?;
+        if ctx.cli.json {
+            let mut warnings = plan.warnings.clone();
+            let data = rollback_json_data_dry_run(plan, &mut warnings)?;
+            let mut envelope = JsonEnvelope::ok("rollback", data)
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+            envelope.warnings = warnings;
+            print_json(&envelope)?;
+        } else {
+            for w in &plan.warnings {
+                eprintln!("Warning: {w}");
+            }
+            println!(
+                "Rollback to snapshot {} (current: {}): +{} ~{} -{}",
+                plan.snapshot_id,
+                plan.current_head,
+                plan.plan.summary.create,
+                plan.plan.summary.update,
+                plan.plan.summary.delete
+            );
+            super::super::util::print_diff(&plan.plan, &plan.desired)?;
+            print_local_edits(&plan);
+        }
+        return Ok(());
+    }
+
+    // Interactive runs may confirm discarding local edits instead of failing.
+    let mut discard_local_edits = discard_local_edits;
+    if !ctx.cli.json && !discard_local_edits && std::io::stdin().is_terminal() {
+        let plan = rollback_dry_run(ctx.home, snapshot_id, &selection)?;
+        if !plan.local_edits.is_empty() {
+            print_local_edits(&plan);
+            if !super::super::util::confirm("Discard these local edits?")? {
+                println!("Aborted");
+                return Ok(());
+            }
+            discard_local_edits = true;
+        }
+    }
+
+    let (plan, event) = rollback(
+        ctx.home,
+        ctx.repo,
+        ctx.cli.machine.as_deref(),
+        snapshot_id,
+        &selection,
+        discard_local_edits,
+        ctx.cli.json,
+        ctx.cli.yes,
+    )?;
     if ctx.cli.json {
-        let data = rollback_json_data(snapshot_id, &event.id);
-        let envelope = JsonEnvelope::ok("rollback", data)
+        let data = rollback_json_data(&plan.snapshot_id, &event.id, &event.hooks);
+        let mut envelope = JsonEnvelope::ok("rollback", data)
             .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+        envelope.warnings = plan.warnings;
         print_json(&envelope)?;
     } else {
-        println!("Rolled back to snapshot {snapshot_id}. Event: {}", event.id);
+        for w in &plan.warnings {
+            eprintln!("Warning: {w}");
+        }
+        let scope = if plan.partial { " (partial)" } else { "" };
+        println!(
+            "Rolled back to snapshot {}{scope}. Event: {}",
+            plan.snapshot_id, event.id
+        );
     }
 
     Ok(())
 }
+
+fn print_local_edits(plan: &crate::rollback_plan::RollbackPlan) {
+    if plan.local_edits.is_empty() {
+        return;
+    }
+    println!(
+        "{} file(s) were edited after the current deployment and would be overwritten:",
+        plan.local_edits.len()
+    );
+    for e in &plan.local_edits {
+        println!("  {} {}", e.target, e.path);
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+
+use crate::handlers::snapshot::snapshot_diff;
+use crate::output::{JsonEnvelope, print_json};
+use crate::snapshot_archive::SnapshotArchive;
+use crate::snapshot_tags::{SnapshotTags, resolve_snapshot};
+use crate::state::{DeploymentSnapshot, list_snapshots};
+
+use super::super::args::SnapshotCommands;
+use super::Ctx;
+
+pub(crate) fn run(ctx: &Ctx<'_>, command: &SnapshotCommands) -> anyhow::Result<()> {
+    match command {
+        SnapshotCommands::List => {
+            let snapshots = list_snapshots(ctx.home)?;
+            let tags = SnapshotTags::load(ctx.home)?;
+            if ctx.cli.json {
+                let items: Vec<serde_json::Value> =
+                    snapshots.iter().map(|s| snapshot_json(s, &tags)).collect();
+                let tag_ids: BTreeMap<&String, &String> = tags
+                    .tags
+                    .iter()
+                    .map(|(name, t)| (name, &t.snapshot_id))
+                    .collect();
+                let envelope = JsonEnvelope::ok(
+                    "snapshot.list",
+                    serde_json::json!({
+                        "snapshots": items,
+                        "tags": tag_ids,
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else if snapshots.is_empty() {
+                println!("No snapshots (run `agentpack deploy --apply` to create one)");
+            } else {
+                for s in &snapshots {
+                    let names = tags.names_for(&s.id);
+                    let names = if names.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" [{}]", names.join(", "))
+                    };
+                    let lockfile = s
+                        .lockfile_sha256
+                        .as_deref()
+                        .map(|sha| sha.chars().take(12).collect::<String>())
+                        .unwrap_or_else(|| "-".to_string());
+                    println!(
+                        "{}  {:<9} {}  targets={}  changes={}  lockfile={lockfile}{names}",
+                        s.id,
+                        s.kind,
+                        s.created_at,
+                        s.targets.join(","),
+                        s.changes.len(),
+                    );
+                }
+            }
+        }
+        SnapshotCommands::Show { snapshot } => {
+            let snapshot = load(ctx, snapshot)?;
+            let tags = SnapshotTags::load(ctx.home)?;
+            if ctx.cli.json {
+                let changes: Vec<serde_json::Value> = snapshot
+                    .changes
+                    .iter()
+                    .map(|c| {
+                        serde_json::json!({
+                            "target": c.target,
+                            "op": c.op,
+                            "path": c.path,
+                            "path_posix": crate::paths::path_to_posix_string(c.path.as_ref()),
+                            "before_sha256": c.before_sha256,
+                            "after_sha256": c.after_sha256,
+                            "backup_path": c.backup_path,
+                            "before_link_target": c.before_link_target,
+                        })
+                    })
+                    .collect();
+                let mut data = snapshot_json(&snapshot, &tags);
+                if let Some(obj) = data.as_object_mut() {
+                    obj.insert("changes".to_string(), serde_json::Value::Array(changes));
+                    obj.insert(
+                        "managed_files".to_string(),
+                        serde_json::to_value(&snapshot.managed_files)?,
+                    );
+                }
+                let envelope = JsonEnvelope::ok("snapshot.show", data)
+                    .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else {
+                println!(
+                    "Snapshot {} ({}, {})",
+                    snapshot.id, snapshot.kind, snapshot.created_at
+                );
+                if let Some(to) = &snapshot.rolled_back_to {
+                    println!("Rolled back to {to}");
+                }
+                let names = tags.names_for(&snapshot.id);
+                if !names.is_empty() {
+                    println!("Tags: {}", names.join(", "));
+                }
+                println!(
+                    "Lockfile: {}",
+                    snapshot.lockfile_sha256.as_deref().unwrap_or("-")
+                );
+                for c in &snapshot.changes {
+                    println!("{} {} {}", c.op, c.target, c.path);
+                }
+            }
+        }
+        SnapshotCommands::Diff { from, to } => {
+            let from = load(ctx, from)?;
+            let to = load(ctx, to)?;
+            let files = snapshot_diff(ctx.home, &from, &to)?;
+            if ctx.cli.json {
+                let count = |status: &str| files.iter().filter(|f| f.status == status).count();
+                let envelope = JsonEnvelope::ok(
+                    "snapshot.diff",
+                    serde_json::json!({
+                        "from": from.id,
+                        "to": to.id,
+                        "summary": {
+                            "added": count("added"),
+                            "removed": count("removed"),
+                            "modified": count("modified"),
+                        },
+                        "files": files,
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else if files.is_empty() {
+                println!(
+                    "No managed file differences between {} and {}",
+                    from.id, to.id
+                );
+            } else {
+                for f in &files {
+                    println!("{} {} {}", f.status, f.target, f.path);
+                    match &f.diff {
+                        Some(diff) => print!("{diff}"),
+                        None => println!("(content not available or binary)"),
+                    }
+                }
+            }
+        }
+        SnapshotCommands::Tag { snapshot, name } => {
+            super::super::util::require_yes_for_json_mutation(ctx.cli, "snapshot tag")?;
+            let id = resolve_snapshot(ctx.home, snapshot)?;
+            let mut tags = SnapshotTags::load(ctx.home)?;
+            let previous = tags.set(name, &id)?;
+            tags.save(ctx.home)?;
+
+            if ctx.cli.json {
+                let envelope = JsonEnvelope::ok(
+                    "snapshot.tag",
+                    serde_json::json!({
+                        "tag": name,
+                        "snapshot_id": id,
+                        "previous_snapshot_id": previous,
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else if let Some(previous) = previous {
+                println!("Moved tag {name} from {previous} to snapshot {id}");
+            } else {
+                println!("Tagged snapshot {id} as {name}");
+            }
+        }
+        SnapshotCommands::Export { snapshot, out } => {
+            let id = resolve_snapshot(ctx.home, snapshot)?;
+            let archive = SnapshotArchive::export(ctx.home, &id)?;
+            archive.save(out)?;
+
+            if ctx.cli.json {
+                let envelope = JsonEnvelope::ok(
+                    "snapshot.export",
+                    serde_json::json!({
+                        "snapshot_id": id,
+                        "archive": {
+                            "path": out.display().to_string(),
+                            "path_posix": crate::paths::path_to_posix_string(out),
+                            "archive_hash": archive.archive_hash,
+                        },
+                        "files": archive.files.len(),
+                        "tags": archive.tags,
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                print_json(&envelope)?;
+            } else {
+                println!(
+                    "Exported snapshot {id} ({} files) to {}",
+                    archive.files.len(),
+                    out.display()
+                );
+            }
+        }
+        SnapshotCommands::Import { file } => {
+            super::super::util::require_yes_for_json_mutation(ctx.cli, "snapshot import")?;
+            let archive = SnapshotArchive::load(file)?;
+            let outcome = archive.import(ctx.home)?;
+
+            if ctx.cli.json {
+                let mut envelope = JsonEnvelope::ok(
+                    "snapshot.import",
+                    serde_json::json!({
+                        "snapshot_id": outcome.snapshot_id,
+                        "imported": outcome.imported,
+                        "files": outcome.files,
+                        "tags_added": outcome.tags_added,
+                    }),
+                )
+                .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+                envelope.warnings = outcome.warnings;
+                print_json(&envelope)?;
+            } else {
+                for w in &outcome.warnings {
+                    eprintln!("Warning: {w}");
+                }
+                if outcome.imported {
+                    println!(
+                        "Imported snapshot {} ({} files)",
+                        outcome.snapshot_id, outcome.files
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn load(ctx: &Ctx<'_>, reference: &str) -> anyhow::Result<DeploymentSnapshot> {
+    let id = resolve_snapshot(ctx.home, reference)?;
+    let path = DeploymentSnapshot::path(ctx.home, &id);
+    DeploymentSnapshot::load(&path).with_context(|| format!("load snapshot {}", path.display()))
+}
+
+fn snapshot_json(s: &DeploymentSnapshot, tags: &SnapshotTags) -> serde_json::Value {
+    let mut ops: BTreeMap<&str, u64> = BTreeMap::new();
+    for c in &s.changes {
+        *ops.entry(c.op.as_str()).or_default() += 1;
+    }
+    serde_json::json!({
+        "id": s.id,
+        "kind": s.kind,
+        "created_at": s.created_at,
+        "targets": s.targets,
+        "tags": tags.names_for(&s.id),
+        "change_counts": {
+            "total": s.changes.len(),
+            "by_op": ops,
+        },
+        "managed_file_count": s.managed_files.len(),
+        "lockfile_sha256": s.lockfile_sha256,
+        "rolled_back_to": s.rolled_back_to,
+    })
+}
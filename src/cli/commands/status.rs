@@ -67,6 +67,7 @@ pub(crate) fn run(ctx: &Ctx<'_>, only: &[crate::cli::args::StatusOnly]) -> anyho
         summary.modified,
         summary.missing,
         summary.extra,
+        summary.permissions,
         any_manifest,
         report.needs_deploy_apply,
     ) {
@@ -81,6 +82,7 @@ pub(crate) fn run(ctx: &Ctx<'_>, only: &[crate::cli::args::StatusOnly]) -> anyho
             crate::cli::args::StatusOnly::Missing => "missing",
             crate::cli::args::StatusOnly::Modified => "modified",
             crate::cli::args::StatusOnly::Extra => "extra",
+            crate::cli::args::StatusOnly::Permissions => "permissions",
         })
         .collect();
 
@@ -135,17 +137,17 @@ pub(crate) fn run(ctx: &Ctx<'_>, only: &[crate::cli::args::StatusOnly]) -> anyho
         println!("Drift ({}):", drift.len());
         if let Some(total) = summary_total_opt {
             println!(
-                "Summary (filtered): modified={} missing={} extra={}",
-                summary.modified, summary.missing, summary.extra
+                "Summary (filtered): modified={} missing={} extra={} permissions={}",
+                summary.modified, summary.missing, summary.extra, summary.permissions
             );
             println!(
-                "Summary (total): modified={} missing={} extra={}",
-                total.modified, total.missing, total.extra
+                "Summary (total): modified={} missing={} extra={} permissions={}",
+                total.modified, total.missing, total.extra, total.permissions
             );
         } else {
             println!(
-                "Summary: modified={} missing={} extra={}",
-                summary.modified, summary.missing, summary.extra
+                "Summary: modified={} missing={} extra={} permissions={}",
+                summary.modified, summary.missing, summary.extra, summary.permissions
             );
         }
         drift.sort_by(|a, b| {
@@ -174,12 +176,13 @@ pub(crate) fn run(ctx: &Ctx<'_>, only: &[crate::cli::args::StatusOnly]) -> anyho
             if last_group.as_ref() != Some(&group) {
                 let group_summary = by_root.get(&group).copied().unwrap_or_default();
                 println!(
-                    "Root: {} ({}) modified={} missing={} extra={}",
+                    "Root: {} ({}) modified={} missing={} extra={} permissions={}",
                     root,
                     d.target,
                     group_summary.modified,
                     group_summary.missing,
-                    group_summary.extra
+                    group_summary.extra,
+                    group_summary.permissions
                 );
                 last_group = Some(group);
             }
@@ -3,6 +3,7 @@ use anyhow::Context as _;
 use crate::config::Manifest;
 use crate::lockfile::{Lockfile, generate_lockfile, hash_tree};
 use crate::output::{JsonEnvelope, print_json};
+use crate::state_lock::StateLock;
 use crate::store::Store;
 use crate::user_error::UserError;
 
@@ -51,6 +52,11 @@ pub(crate) fn run(
         return Err(UserError::confirm_required("update"));
     }
 
+    let _state_lock = if will_write {
+        Some(StateLock::acquire(ctx.home, "update")?)
+    } else {
+        None
+    };
     let mut steps: Vec<UpdateStep> = Vec::new();
     let store = Store::new(ctx.home);
 
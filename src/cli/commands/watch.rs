@@ -0,0 +1,483 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::deploy::{MergeStatus, PlanChange, PlanResult, UpdateKind};
+use crate::engine::Engine;
+use crate::handlers::deploy::{ConfirmationStyle, DeployApplyOutcome, deploy_apply_in};
+use crate::handlers::read_only::{ReadOnlyContext, read_only_context_in};
+use crate::output::{JsonEnvelope, JsonError, print_json_line};
+use crate::watch::{Fingerprint, ManagedDrift, WatchSet, managed_drift};
+
+use super::Ctx;
+
+/// Polls never run more often than this, whatever `--interval-ms` says.
+const MIN_INTERVAL_MS: u64 = 50;
+
+/// Which kind of watched path changed since the last cycle.
+#[derive(Debug, Clone, Default)]
+struct Trigger {
+    inputs: bool,
+    /// Changed inputs outside overlay dirs and `local_path` sources; they block auto-apply.
+    non_local: BTreeSet<PathBuf>,
+    deployed: bool,
+}
+
+impl Trigger {
+    fn any(&self) -> bool {
+        self.inputs || self.deployed
+    }
+
+    fn merge(mut self, other: Trigger) -> Trigger {
+        self.inputs |= other.inputs;
+        self.non_local.extend(other.non_local);
+        self.deployed |= other.deployed;
+        self
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        let mut out = Vec::new();
+        if self.inputs {
+            out.push("inputs");
+        }
+        if self.deployed {
+            out.push("deployed");
+        }
+        out
+    }
+}
+
+/// What earlier cycles reported, so each cycle only prints what changed.
+#[derive(Default)]
+struct Reported {
+    first: bool,
+    changes: BTreeMap<(String, String), PlanChange>,
+    drift: BTreeMap<(String, String), ManagedDrift>,
+    warnings: BTreeSet<String>,
+}
+
+pub(crate) fn run(
+    ctx: &Ctx<'_>,
+    apply: bool,
+    interval_ms: u64,
+    debounce_ms: u64,
+) -> anyhow::Result<()> {
+    let apply = apply && !ctx.cli.dry_run;
+    if apply {
+        super::super::util::require_yes_for_json_mutation(ctx.cli, "watch --apply")?;
+    }
+
+    let interval = Duration::from_millis(interval_ms.max(MIN_INTERVAL_MS));
+    let debounce = Duration::from_millis(debounce_ms);
+    emit(
+        ctx,
+        serde_json::json!({
+            "event": "started",
+            "repo_dir": ctx.repo.repo_dir,
+            "repo_dir_posix": crate::paths::path_to_posix_string(&ctx.repo.repo_dir),
+            "apply": apply,
+            "interval_ms": interval.as_millis() as u64,
+            "debounce_ms": debounce_ms,
+        }),
+        Vec::new(),
+    )?;
+    if !ctx.cli.json {
+        println!(
+            "Watching {} (every {}ms{}); press Ctrl-C to stop",
+            ctx.repo.repo_dir.display(),
+            interval.as_millis(),
+            if apply { ", auto-apply" } else { "" }
+        );
+    }
+
+    let mut reported = Reported {
+        first: true,
+        ..Default::default()
+    };
+    let all = Trigger {
+        inputs: true,
+        deployed: true,
+        ..Default::default()
+    };
+    // The startup cycle only reports; applying waits for a change to local inputs. A broken
+    // config is reported like in any later cycle, and the repo is watched until it is fixed.
+    let (mut set, mut last) = match cycle(ctx, &mut reported, &all, false, &[]) {
+        Ok(next) => next,
+        Err(err) => {
+            let set = WatchSet {
+                inputs: vec![ctx.repo.repo_dir.clone()],
+                ..Default::default()
+            };
+            let last = set.fingerprint();
+            emit_error(ctx, &err)?;
+            (set, last)
+        }
+    };
+    let mut pending: Option<(Trigger, Instant)> = None;
+
+    loop {
+        std::thread::sleep(interval);
+        let now = set.fingerprint();
+        let changed = Trigger {
+            inputs: now.0 != last.0,
+            non_local: set.non_local_changes(&now.0, &last.0).into_iter().collect(),
+            deployed: now.1 != last.1,
+        };
+        if changed.any() {
+            last = now;
+            let trigger = pending.take().map(|(t, _)| t).unwrap_or_default();
+            pending = Some((trigger.merge(changed), Instant::now()));
+            continue;
+        }
+
+        if pending
+            .as_ref()
+            .is_none_or(|(_, since)| since.elapsed() < debounce)
+        {
+            continue;
+        }
+        let Some((trigger, _)) = pending.take() else {
+            continue;
+        };
+        match cycle(ctx, &mut reported, &trigger, apply, &set.inputs) {
+            Ok((next, fingerprint)) => (set, last) = (next, fingerprint),
+            Err(err) => {
+                // Fingerprint first so that a fix made in reaction to the error is noticed.
+                last = set.fingerprint();
+                emit_error(ctx, &err)?;
+            }
+        }
+    }
+}
+
+/// Reloads the engine, re-renders and re-plans; reports what changed and, if asked, applies
+/// after a change to local inputs.
+///
+/// Inputs are fingerprinted before anything is read, so an edit made while the cycle runs
+/// triggers the next one; deployed files are fingerprinted after our own writes.
+fn cycle(
+    ctx: &Ctx<'_>,
+    reported: &mut Reported,
+    trigger: &Trigger,
+    apply: bool,
+    prev_inputs: &[PathBuf],
+) -> anyhow::Result<(WatchSet, (Fingerprint, Fingerprint))> {
+    let before = Fingerprint::of_trees(prev_inputs);
+    let engine = Engine::load(ctx.cli.repo.as_deref(), ctx.cli.machine.as_deref())?;
+    let inputs = WatchSet::input_paths(&engine);
+    let local = WatchSet::local_input_paths(&engine);
+    let inputs_fingerprint = if inputs == prev_inputs {
+        before
+    } else {
+        Fingerprint::of_trees(&inputs)
+    };
+    let ReadOnlyContext {
+        desired,
+        plan,
+        warnings,
+        roots,
+        ..
+    } = read_only_context_in(&engine, &ctx.cli.profile, &ctx.cli.target)?;
+    let drift = managed_drift(&roots);
+
+    report_plan(ctx, reported, &plan, warnings, trigger)?;
+    report_drift(ctx, reported, &drift)?;
+
+    if apply && trigger.inputs {
+        if let Some(skipped) = apply_blocked(trigger, &plan, &drift) {
+            if !ctx.cli.json {
+                println!(
+                    "Not applying ({}): {}",
+                    skipped["reason_code"].as_str().unwrap_or_default(),
+                    skipped["paths"]
+                        .as_array()
+                        .map(|p| p
+                            .iter()
+                            .filter_map(|v| v.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "))
+                        .unwrap_or_default()
+                );
+            }
+            emit(ctx, skipped, Vec::new())?;
+        } else {
+            let outcome = deploy_apply_in(
+                &engine,
+                &plan,
+                &desired,
+                &roots,
+                false,
+                true,
+                ConfirmationStyle::Explicit,
+            )?;
+            if let DeployApplyOutcome::Applied { snapshot_id, hooks } = outcome {
+                let hook_warnings = crate::hooks::failure_warnings(&hooks);
+                if !ctx.cli.json {
+                    for w in &hook_warnings {
+                        eprintln!("Warning: {w}");
+                    }
+                    println!(
+                        "Applied snapshot {snapshot_id}: +{} ~{} -{}",
+                        plan.summary.create, plan.summary.update, plan.summary.delete
+                    );
+                }
+                let mut data = serde_json::json!({
+                    "event": "applied",
+                    "snapshot_id": snapshot_id,
+                    "summary": plan.summary,
+                });
+                if !hooks.is_empty()
+                    && let Some(obj) = data.as_object_mut()
+                {
+                    obj.insert("hooks".to_string(), serde_json::json!(hooks));
+                }
+                emit(ctx, data, hook_warnings)?;
+                reported.changes.clear();
+                report_drift(ctx, reported, &managed_drift(&roots))?;
+            }
+        }
+    }
+
+    let deployed = WatchSet::deployed_paths(&roots, &desired);
+    let deployed_fingerprint = Fingerprint::of_files(&deployed);
+    Ok((
+        WatchSet {
+            inputs,
+            local,
+            deployed,
+        },
+        (inputs_fingerprint, deployed_fingerprint),
+    ))
+}
+
+/// Auto-apply only follows edits to overlays and `local_path` sources, and only writes what
+/// agentpack owns and nobody edited: config or lockfile changes, adopting unmanaged files and
+/// overwriting local edits (other than a clean merge) are left to an explicit `deploy --apply`.
+fn apply_blocked(
+    trigger: &Trigger,
+    plan: &PlanResult,
+    drift: &[ManagedDrift],
+) -> Option<serde_json::Value> {
+    if !trigger.non_local.is_empty() {
+        let paths: Vec<String> = trigger
+            .non_local
+            .iter()
+            .map(|p| crate::paths::path_to_posix_string(p))
+            .collect();
+        return Some(serde_json::json!({
+            "event": "apply_skipped",
+            "reason_code": "config_changed",
+            "paths": paths,
+            "next_actions": ["run_deploy_apply"],
+        }));
+    }
+
+    let adopt: Vec<&str> = plan
+        .changes
+        .iter()
+        .filter(|c| matches!(c.update_kind, Some(UpdateKind::AdoptUpdate)))
+        .map(|c| c.path_posix.as_str())
+        .collect();
+    if !adopt.is_empty() {
+        return Some(serde_json::json!({
+            "event": "apply_skipped",
+            "reason_code": "adopt_required",
+            "paths": adopt,
+            "next_actions": ["run_deploy_apply_adopt"],
+        }));
+    }
+
+    let edited: BTreeSet<(&str, &str)> = drift
+        .iter()
+        .filter(|d| d.kind == "modified")
+        .map(|d| (d.target.as_str(), d.path.as_str()))
+        .collect();
+    let overwrites: Vec<&str> = plan
+        .changes
+        .iter()
+        .filter(|c| c.merge != Some(MergeStatus::Clean))
+        .filter(|c| edited.contains(&(c.target.as_str(), c.path.as_str())))
+        .map(|c| c.path_posix.as_str())
+        .collect();
+    if !overwrites.is_empty() {
+        return Some(serde_json::json!({
+            "event": "apply_skipped",
+            "reason_code": "local_edits",
+            "paths": overwrites,
+            "next_actions": ["run_evolve_propose", "run_deploy_apply"],
+        }));
+    }
+    None
+}
+
+fn report_plan(
+    ctx: &Ctx<'_>,
+    reported: &mut Reported,
+    plan: &PlanResult,
+    warnings: Vec<String>,
+    trigger: &Trigger,
+) -> anyhow::Result<()> {
+    let current: BTreeMap<(String, String), PlanChange> = plan
+        .changes
+        .iter()
+        .map(|c| ((c.target.clone(), c.path.clone()), c.clone()))
+        .collect();
+    let changed: Vec<&PlanChange> = current
+        .iter()
+        .filter(|(k, c)| {
+            reported.changes.get(*k).is_none_or(|prev| {
+                std::mem::discriminant(&prev.op) != std::mem::discriminant(&c.op)
+                    || (&prev.before_sha256, &prev.after_sha256)
+                        != (&c.before_sha256, &c.after_sha256)
+            })
+        })
+        .map(|(_, c)| c)
+        .collect();
+    let resolved: Vec<serde_json::Value> = reported
+        .changes
+        .iter()
+        .filter(|(k, _)| !current.contains_key(*k))
+        .map(|(_, c)| path_json(&c.target, &c.path, &c.path_posix))
+        .collect();
+    let new_warnings: Vec<String> = warnings
+        .into_iter()
+        .filter(|w| !reported.warnings.contains(w))
+        .collect();
+
+    let first = std::mem::take(&mut reported.first);
+    if first || !changed.is_empty() || !resolved.is_empty() {
+        if !ctx.cli.json {
+            println!(
+                "Plan: +{} ~{} -{}",
+                plan.summary.create, plan.summary.update, plan.summary.delete
+            );
+            for c in &changed {
+                println!("  {:?} {} {}", c.op, c.target, c.path);
+            }
+            for r in &resolved {
+                println!(
+                    "  resolved {} {}",
+                    r["target"].as_str().unwrap_or_default(),
+                    r["path"].as_str().unwrap_or_default()
+                );
+            }
+        }
+        emit(
+            ctx,
+            serde_json::json!({
+                "event": "plan",
+                "trigger": trigger.names(),
+                "summary": plan.summary,
+                "changes": changed,
+                "resolved": resolved,
+            }),
+            new_warnings.clone(),
+        )?;
+    } else if !new_warnings.is_empty() {
+        emit(
+            ctx,
+            serde_json::json!({"event": "warnings"}),
+            new_warnings.clone(),
+        )?;
+    }
+    if !ctx.cli.json {
+        for w in &new_warnings {
+            eprintln!("Warning: {w}");
+        }
+    }
+
+    reported.warnings.extend(new_warnings);
+    reported.changes = current;
+    Ok(())
+}
+
+fn report_drift(
+    ctx: &Ctx<'_>,
+    reported: &mut Reported,
+    drift: &[ManagedDrift],
+) -> anyhow::Result<()> {
+    let current: BTreeMap<(String, String), ManagedDrift> = drift
+        .iter()
+        .map(|d| ((d.target.clone(), d.path.clone()), d.clone()))
+        .collect();
+    let changed: Vec<&ManagedDrift> = current
+        .iter()
+        .filter(|(k, d)| reported.drift.get(*k) != Some(*d))
+        .map(|(_, d)| d)
+        .collect();
+    let resolved: Vec<serde_json::Value> = reported
+        .drift
+        .iter()
+        .filter(|(k, _)| !current.contains_key(*k))
+        .map(|(_, d)| path_json(&d.target, &d.path, &d.path_posix))
+        .collect();
+
+    if !changed.is_empty() || !resolved.is_empty() {
+        if !ctx.cli.json {
+            for d in &changed {
+                println!("Drift: {} {} {}", d.kind, d.target, d.path);
+            }
+            for r in &resolved {
+                println!(
+                    "Drift resolved: {} {}",
+                    r["target"].as_str().unwrap_or_default(),
+                    r["path"].as_str().unwrap_or_default()
+                );
+            }
+        }
+        emit(
+            ctx,
+            serde_json::json!({
+                "event": "drift",
+                "drift": changed,
+                "resolved": resolved,
+                "total": current.len(),
+            }),
+            Vec::new(),
+        )?;
+    }
+
+    reported.drift = current;
+    Ok(())
+}
+
+fn path_json(target: &str, path: &str, path_posix: &str) -> serde_json::Value {
+    serde_json::json!({
+        "target": target,
+        "path": path,
+        "path_posix": path_posix,
+    })
+}
+
+/// In `--json` mode every event is one envelope on its own line; human output is printed by
+/// the callers.
+fn emit(ctx: &Ctx<'_>, data: serde_json::Value, warnings: Vec<String>) -> anyhow::Result<()> {
+    if !ctx.cli.json {
+        return Ok(());
+    }
+
+    let mut envelope = JsonEnvelope::ok("watch", data)
+        .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+    envelope.warnings = warnings;
+    print_json_line(&envelope)
+}
+
+fn emit_error(ctx: &Ctx<'_>, err: &anyhow::Error) -> anyhow::Result<()> {
+    if !ctx.cli.json {
+        if !super::super::human::print_user_error_human(err) {
+            eprintln!("Error: {err:#}");
+        }
+        return Ok(());
+    }
+
+    let (code, message, details) = crate::user_error::anyhow_error_parts_for_envelope(err);
+    let mut envelope = JsonEnvelope::ok("watch", serde_json::json!({"event": "error"}))
+        .with_command_meta(ctx.cli.command_id(), ctx.cli.command_path());
+    envelope.ok = false;
+    envelope.errors = vec![JsonError {
+        code: code.to_string(),
+        message: message.into_owned(),
+        details,
+    }];
+    print_json_line(&envelope)
+}
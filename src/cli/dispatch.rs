@@ -1,4 +1,5 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory as _, FromArgMatches as _};
 
 use super::args::*;
 use super::human::print_user_error_human;
@@ -7,7 +8,11 @@ use super::json::print_anyhow_error;
 use crate::paths::{AgentpackHome, RepoPaths};
 
 pub fn run() -> std::process::ExitCode {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if matches.value_source("profile") == Some(ValueSource::DefaultValue) {
+        cli.profile = crate::machine::implicit_profile(cli.repo.as_deref(), cli.machine.as_deref());
+    }
     let force_human_errors = matches!(cli.command, Commands::Mcp { .. });
     match run_with(&cli) {
         Ok(()) => std::process::ExitCode::SUCCESS,
@@ -82,20 +87,40 @@ fn run_with(cli: &Cli) -> anyhow::Result<()> {
         Commands::Fetch => {
             super::commands::fetch::run(&ctx)?;
         }
-        Commands::Preview { diff } => {
-            super::commands::preview::run(&ctx, *diff)?;
+        Commands::Preview { diff, filter } => {
+            super::commands::preview::run(&ctx, *diff, &filter.to_filter())?;
         }
         Commands::Overlay { command } => {
             super::commands::overlay::run(&ctx, command)?;
         }
-        Commands::Plan => {
-            super::commands::plan::run(&ctx)?;
+        Commands::Plan { out, filter } => {
+            super::commands::plan::run(&ctx, out.as_deref(), &filter.to_filter())?;
         }
         Commands::Diff => {
             super::commands::diff::run(&ctx)?;
         }
-        Commands::Deploy { apply, adopt } => {
-            super::commands::deploy::run(&ctx, *apply, *adopt)?;
+        Commands::Deploy {
+            apply,
+            adopt,
+            all_projects,
+            plan,
+            filter,
+        } => {
+            let filter = filter.to_filter();
+            if *all_projects {
+                super::commands::deploy::run_all_projects(&ctx, *apply, *adopt, &filter)?;
+            } else if let Some(plan) = plan {
+                super::commands::deploy::run_saved_plan(&ctx, plan, *apply, *adopt)?;
+            } else {
+                super::commands::deploy::run(&ctx, *apply, *adopt, &filter)?;
+            }
+        }
+        Commands::Watch {
+            apply,
+            interval_ms,
+            debounce_ms,
+        } => {
+            super::commands::watch::run(&ctx, *apply, *interval_ms, *debounce_ms)?;
         }
         Commands::Status { only } => {
             super::commands::status::run(&ctx, only)?;
@@ -113,6 +138,9 @@ fn run_with(cli: &Cli) -> anyhow::Result<()> {
         Commands::Doctor { fix } => {
             super::commands::doctor::run(&ctx, *fix)?;
         }
+        Commands::Project { command } => {
+            super::commands::project::run(&ctx, command)?;
+        }
         Commands::Remote { command } => {
             super::commands::remote::run(&ctx, command)?;
         }
@@ -137,8 +165,15 @@ fn run_with(cli: &Cli) -> anyhow::Result<()> {
         Commands::Bootstrap { scope } => {
             super::commands::bootstrap::run(&ctx, *scope)?;
         }
-        Commands::Rollback { to } => {
-            super::commands::rollback::run(&ctx, to)?;
+        Commands::Rollback {
+            to,
+            discard_local_edits,
+            filter,
+        } => {
+            super::commands::rollback::run(&ctx, to, *discard_local_edits, &filter.to_filter())?;
+        }
+        Commands::Snapshot { command } => {
+            super::commands::snapshot::run(&ctx, command)?;
         }
     }
 
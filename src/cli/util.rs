@@ -20,11 +20,17 @@ pub(crate) const MUTATING_COMMAND_IDS: &[&str] = &[
     "fetch",
     "update",
     "deploy --apply",
+    "watch --apply",
     "rollback",
     "bootstrap",
     "doctor --fix",
     "overlay edit",
     "overlay rebase",
+    "overlay capture",
+    "project add",
+    "project remove",
+    "snapshot tag",
+    "snapshot import",
     "remote set",
     "sync",
     "record",
@@ -66,63 +72,50 @@ pub(crate) fn filter_managed(
 
 pub(crate) fn overlay_dir_for_scope(
     engine: &Engine,
+    profile: &str,
     module_id: &str,
     scope: OverlayScope,
-) -> PathBuf {
-    let fs_key = crate::ids::module_fs_key(module_id);
-    let canonical = match scope {
-        OverlayScope::Global => engine.repo.repo_dir.join("overlays").join(&fs_key),
-        OverlayScope::Machine => engine
-            .repo
-            .repo_dir
-            .join("overlays/machines")
-            .join(&engine.machine_id)
-            .join(&fs_key),
-        OverlayScope::Project => engine
-            .repo
-            .repo_dir
+) -> anyhow::Result<PathBuf> {
+    let repo_dir = &engine.repo.repo_dir;
+    let scope_root = match scope {
+        OverlayScope::Global => repo_dir.join("overlays"),
+        OverlayScope::Profile => return engine.profile_overlay_dir(profile, module_id),
+        OverlayScope::Group => {
+            let Some(group) = engine.machine_group()? else {
+                return Err(anyhow::Error::new(
+                    UserError::new(
+                        "E_CONFIG_INVALID",
+                        format!(
+                            "machine {} does not belong to any machine group",
+                            engine.machine_id
+                        ),
+                    )
+                    .with_details(serde_json::json!({
+                        "module_id": module_id,
+                        "machine_id": engine.machine_id,
+                        "groups": engine.manifest.machines.keys().collect::<Vec<_>>(),
+                        "reason_code": "machine_group_missing",
+                        "next_actions": ["edit_manifest_machines", "retry_command"],
+                    })),
+                ));
+            };
+            return Ok(crate::engine::overlay_dir_group(repo_dir, group, module_id));
+        }
+        OverlayScope::Machine => repo_dir.join("overlays/machines").join(&engine.machine_id),
+        OverlayScope::Project => repo_dir
             .join("projects")
             .join(&engine.project.project_id)
-            .join("overlays")
-            .join(&fs_key),
+            .join("overlays"),
     };
 
+    let fs_key = crate::ids::module_fs_key(module_id);
+    let canonical = scope_root.join(&fs_key);
     let legacy_fs_key = crate::ids::module_fs_key_unbounded(module_id);
-    let legacy_fs_key = (legacy_fs_key != fs_key).then(|| match scope {
-        OverlayScope::Global => engine.repo.repo_dir.join("overlays").join(&legacy_fs_key),
-        OverlayScope::Machine => engine
-            .repo
-            .repo_dir
-            .join("overlays/machines")
-            .join(&engine.machine_id)
-            .join(&legacy_fs_key),
-        OverlayScope::Project => engine
-            .repo
-            .repo_dir
-            .join("projects")
-            .join(&engine.project.project_id)
-            .join("overlays")
-            .join(&legacy_fs_key),
-    });
-
-    let legacy = crate::ids::is_safe_legacy_path_component(module_id).then(|| match scope {
-        OverlayScope::Global => engine.repo.repo_dir.join("overlays").join(module_id),
-        OverlayScope::Machine => engine
-            .repo
-            .repo_dir
-            .join("overlays/machines")
-            .join(&engine.machine_id)
-            .join(module_id),
-        OverlayScope::Project => engine
-            .repo
-            .repo_dir
-            .join("projects")
-            .join(&engine.project.project_id)
-            .join("overlays")
-            .join(module_id),
-    });
+    let legacy_fs_key = (legacy_fs_key != fs_key).then(|| scope_root.join(&legacy_fs_key));
+    let legacy =
+        crate::ids::is_safe_legacy_path_component(module_id).then(|| scope_root.join(module_id));
 
-    if canonical.exists() {
+    Ok(if canonical.exists() {
         canonical
     } else if legacy_fs_key.as_ref().is_some_and(|p| p.exists()) {
         legacy_fs_key.expect("legacy fs_key exists")
@@ -130,7 +123,7 @@ pub(crate) fn overlay_dir_for_scope(
         legacy.expect("legacy exists")
     } else {
         canonical
-    }
+    })
 }
 
 pub(crate) struct ManifestModuleIdsIndex {
@@ -236,33 +229,36 @@ pub(crate) fn module_rel_path_for_output(
     }
 }
 
-pub(crate) fn source_layer_for_module_file(
+/// Layers that contribute a module file, low → high precedence: `upstream`, then the overlay
+/// scopes (`global`, `profile`, `group`, `machine`, `project`) holding an override file or a
+/// patch for it.
+pub(crate) fn source_layers_for_module_file(
     engine: &Engine,
     module: &Module,
+    profile: &str,
     module_rel_path: &str,
-) -> anyhow::Result<String> {
+) -> anyhow::Result<Vec<String>> {
     let rel = std::path::Path::new(module_rel_path);
+    let patch_rel = format!(".agentpack/patches/{module_rel_path}.patch");
 
-    let global = overlay_dir_for_scope(engine, &module.id, OverlayScope::Global);
-    let machine = overlay_dir_for_scope(engine, &module.id, OverlayScope::Machine);
-    let project = overlay_dir_for_scope(engine, &module.id, OverlayScope::Project);
-
-    if project.join(rel).exists() {
-        return Ok("project".to_string());
-    }
-    if machine.join(rel).exists() {
-        return Ok("machine".to_string());
-    }
-    if global.join(rel).exists() {
-        return Ok("global".to_string());
+    let mut layers = Vec::new();
+    for (scope, dir) in engine.overlay_dirs(module, profile)? {
+        if dir.join(rel).exists() || dir.join(&patch_rel).exists() {
+            layers.push(scope.to_string());
+        }
     }
 
-    let upstream = resolve_upstream_module_root(&engine.home, &engine.repo, module)?;
-    if upstream.join(rel).exists() {
-        return Ok("upstream".to_string());
+    match resolve_upstream_module_root(&engine.home, &engine.repo, module) {
+        Ok(upstream) => {
+            if upstream.join(rel).exists() {
+                layers.insert(0, "upstream".to_string());
+            }
+        }
+        Err(err) if layers.is_empty() => return Err(err),
+        Err(_) => {}
     }
 
-    Ok("missing".to_string())
+    Ok(layers)
 }
 
 pub(crate) fn module_name_from_id(module_id: &str) -> String {
@@ -293,7 +289,7 @@ pub(crate) fn print_diff(
         } else {
             desired
                 .get(&desired_key)
-                .and_then(|f| String::from_utf8(f.bytes.clone()).ok())
+                .and_then(|f| String::from_utf8(f.deployed_bytes().to_vec()).ok())
         };
 
         println!("\n=== {} {} ===", c.target, c.path);
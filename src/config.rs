@@ -37,6 +37,25 @@ fn default_enabled() -> bool {
     true
 }
 
+/// Module metadata key declaring project-relative directories (or globs) for nested instructions.
+pub const METADATA_SUBPATH: &str = "subpath";
+
+impl Module {
+    /// Nested instructions subpaths from `metadata.subpath` (a string or a list of strings).
+    ///
+    /// Returns an empty list when unset or malformed (malformed values are rejected at load time).
+    pub fn instructions_subpaths(&self) -> Vec<String> {
+        match self.metadata.get(METADATA_SUBPATH) {
+            Some(serde_yaml::Value::String(s)) => vec![s.clone()],
+            Some(serde_yaml::Value::Sequence(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Source {
     #[serde(default)]
@@ -94,12 +113,31 @@ pub struct Profile {
     pub include_modules: Vec<String>,
     #[serde(default)]
     pub exclude_modules: Vec<String>,
+    /// Per-module nested instructions subpaths (module id -> project-relative dirs or globs).
+    ///
+    /// Overrides the module's `metadata.subpath` for this profile.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub instructions_subpaths: BTreeMap<String, Vec<String>>,
 }
 
+/// A named set of machines that share group overlays and, optionally, a default profile.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachineGroup {
+    /// Machine ids or `*`/`?` globs, matched against the normalized machine id.
+    #[serde(rename = "match")]
+    pub patterns: Vec<String>,
+    /// Profile used on machines of this group when `--profile` is not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TargetMode {
     Files,
+    /// Deploy verbatim outputs of local_path modules as symlinks into the config repo.
+    Symlink,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,6 +156,144 @@ pub struct TargetConfig {
     pub options: BTreeMap<String, serde_yaml::Value>,
 }
 
+/// How instructions modules are written by a custom target.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomAggregateMode {
+    /// One file for all instructions modules (per-module section markers when >1).
+    #[default]
+    Concat,
+    /// One file per instructions module (`path` must contain `{module_name}`).
+    PerModule,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomInstructionsOutput {
+    pub path: String,
+    #[serde(default)]
+    pub aggregate: CustomAggregateMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frontmatter: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomSkillOutput {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomFileOutput {
+    pub dir: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+}
+
+/// A declarative target rendered by the generic custom target adapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomTargetConfig {
+    pub scope: TargetScope,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<CustomInstructionsOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<CustomSkillOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<CustomFileOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<CustomFileOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scan_extras: Vec<String>,
+}
+
+impl CustomTargetConfig {
+    /// Path templates of the directories that act as managed roots, with the
+    /// module type they belong to.
+    pub fn root_templates(&self) -> Vec<(&'static str, String)> {
+        let mut out = Vec::new();
+        if let Some(o) = &self.instructions {
+            out.push(("instructions", parent_template(&o.path)));
+        }
+        if let Some(o) = &self.skill {
+            out.push(("skill", parent_template(&o.path)));
+        }
+        if let Some(o) = &self.prompt {
+            out.push(("prompt", normalize_template(&o.dir)));
+        }
+        if let Some(o) = &self.command {
+            out.push(("command", normalize_template(&o.dir)));
+        }
+        out
+    }
+}
+
+/// An out-of-process target adapter (see `target_plugin` for the protocol).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginTargetConfig {
+    /// Program and arguments. A relative program path containing `/` is resolved
+    /// against the config repo; the plugin runs with the config repo as cwd.
+    pub command: Vec<String>,
+    pub scope: TargetScope,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub options: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// Commands run around `deploy --apply` and `rollback` (see `hooks` for the payload).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    /// Run before anything is written; a failing hook aborts the deploy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_deploy: Vec<HookConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_deploy: Vec<HookConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_rollback: Vec<HookConfig>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_deploy.is_empty() && self.post_deploy.is_empty() && self.post_rollback.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    /// Program and arguments. A relative program path containing `/` is resolved
+    /// against the config repo; the hook runs with the config repo as cwd.
+    pub command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+pub(crate) const TEMPLATE_PROJECT_ROOT: &str = "{project_root}";
+pub(crate) const TEMPLATE_MODULE_NAME: &str = "{module_name}";
+
+fn normalize_template(template: &str) -> String {
+    let t = template.trim().replace('\\', "/");
+    let trimmed = t.trim_end_matches('/');
+    if trimmed.is_empty() {
+        t
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn parent_template(template: &str) -> String {
+    let t = normalize_template(template);
+    match t.rsplit_once('/') {
+        Some(("", _)) => "/".to_string(),
+        Some((parent, _)) => parent.to_string(),
+        None => t,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Manifest {
     pub version: u32,
@@ -125,8 +301,17 @@ pub struct Manifest {
     pub profiles: BTreeMap<String, Profile>,
     #[serde(default)]
     pub targets: BTreeMap<String, TargetConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_targets: BTreeMap<String, CustomTargetConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugin_targets: BTreeMap<String, PluginTargetConfig>,
     #[serde(default)]
     pub modules: Vec<Module>,
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+    /// Machine groups (group name -> member machine ids/globs).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub machines: BTreeMap<String, MachineGroup>,
 }
 
 impl Manifest {
@@ -178,6 +363,16 @@ impl Manifest {
         Ok(())
     }
 
+    /// Whether `target` is declared in the manifest itself (`custom_targets:` or `plugin_targets:`).
+    pub fn is_declared_target(&self, target: &str) -> bool {
+        self.custom_targets.contains_key(target) || self.plugin_targets.contains_key(target)
+    }
+
+    /// Names of all targets declared in the manifest itself.
+    pub fn declared_target_names(&self) -> impl Iterator<Item = &String> {
+        self.custom_targets.keys().chain(self.plugin_targets.keys())
+    }
+
     pub fn repo_root(&self, manifest_path: &Path) -> PathBuf {
         manifest_path
             .parent()
@@ -287,12 +482,21 @@ fn validate_manifest(manifest: &Manifest) -> anyhow::Result<()> {
         }
     }
 
+    for (name, cfg) in &manifest.custom_targets {
+        validate_custom_target(manifest, name, cfg)?;
+    }
+    for (name, cfg) in &manifest.plugin_targets {
+        validate_plugin_target(manifest, name, cfg)?;
+    }
+    validate_hooks(&manifest.hooks)?;
+
     if !manifest.profiles.contains_key("default") {
         return Err(anyhow::Error::new(
             UserError::new("E_CONFIG_INVALID", "missing required profile: default")
                 .with_details(serde_json::json!({ "profile": "default" })),
         ));
     }
+    validate_machine_groups(manifest)?;
 
     let mut ids = BTreeSet::new();
     for m in &manifest.modules {
@@ -304,7 +508,7 @@ fn validate_manifest(manifest: &Manifest) -> anyhow::Result<()> {
         }
 
         for t in &m.targets {
-            if !crate::target_registry::is_compiled_target(t) {
+            if !crate::target_registry::is_compiled_target(t) && !manifest.is_declared_target(t) {
                 return Err(anyhow::Error::new(
                     UserError::new(
                         "E_TARGET_UNSUPPORTED",
@@ -326,6 +530,8 @@ fn validate_manifest(manifest: &Manifest) -> anyhow::Result<()> {
             }
         }
 
+        validate_module_subpaths(m)?;
+
         match m.source.kind() {
             SourceKind::LocalPath | SourceKind::Git => {}
             SourceKind::Invalid => {
@@ -343,5 +549,460 @@ fn validate_manifest(manifest: &Manifest) -> anyhow::Result<()> {
         }
     }
 
+    for (profile_name, profile) in &manifest.profiles {
+        for (module_id, subpaths) in &profile.instructions_subpaths {
+            let Some(m) = manifest.modules.iter().find(|m| &m.id == module_id) else {
+                return Err(instructions_subpath_invalid(
+                    module_id,
+                    Some(profile_name),
+                    format!(
+                        "profile {profile_name} sets instructions_subpaths for unknown module {module_id}"
+                    ),
+                    serde_json::json!({}),
+                ));
+            };
+            if m.module_type != ModuleType::Instructions {
+                return Err(instructions_subpath_invalid(
+                    module_id,
+                    Some(profile_name),
+                    format!(
+                        "instructions_subpaths only applies to instructions modules ({module_id})"
+                    ),
+                    serde_json::json!({}),
+                ));
+            }
+            for subpath in subpaths {
+                validate_subpath_pattern(module_id, Some(profile_name), subpath)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_module_subpaths(m: &Module) -> anyhow::Result<()> {
+    let Some(value) = m.metadata.get(METADATA_SUBPATH) else {
+        return Ok(());
+    };
+    let well_formed = match value {
+        serde_yaml::Value::String(_) => true,
+        serde_yaml::Value::Sequence(items) => items.iter().all(|v| v.as_str().is_some()),
+        _ => false,
+    };
+    if !well_formed {
+        return Err(instructions_subpath_invalid(
+            &m.id,
+            None,
+            format!(
+                "module {}: metadata.{METADATA_SUBPATH} must be a string or a list of strings",
+                m.id
+            ),
+            serde_json::json!({}),
+        ));
+    }
+    if m.module_type != ModuleType::Instructions {
+        return Err(instructions_subpath_invalid(
+            &m.id,
+            None,
+            format!(
+                "module {}: metadata.{METADATA_SUBPATH} is only supported for instructions modules",
+                m.id
+            ),
+            serde_json::json!({}),
+        ));
+    }
+    for subpath in m.instructions_subpaths() {
+        validate_subpath_pattern(&m.id, None, &subpath)?;
+    }
+    Ok(())
+}
+
+fn validate_subpath_pattern(
+    module_id: &str,
+    profile: Option<&str>,
+    subpath: &str,
+) -> anyhow::Result<()> {
+    let trimmed = subpath.trim_end_matches('/');
+    let invalid = trimmed.is_empty()
+        || trimmed.starts_with('/')
+        || trimmed.contains('\\')
+        || trimmed.contains(':')
+        || trimmed
+            .split('/')
+            .any(|seg| seg.is_empty() || seg == "." || seg == "..");
+    if invalid {
+        return Err(instructions_subpath_invalid(
+            module_id,
+            profile,
+            format!(
+                "module {module_id}: invalid instructions subpath {subpath:?} (expected a project-relative directory or glob)"
+            ),
+            serde_json::json!({ "subpath": subpath }),
+        ));
+    }
+    Ok(())
+}
+
+fn instructions_subpath_invalid(
+    module_id: &str,
+    profile: Option<&str>,
+    message: String,
+    details: serde_json::Value,
+) -> anyhow::Error {
+    let mut obj = serde_json::json!({
+        "module_id": module_id,
+        "reason_code": "instructions_subpath_invalid",
+        "next_actions": ["edit_manifest_modules", "retry_command"],
+    });
+    if let Some(obj) = obj.as_object_mut() {
+        if let Some(profile) = profile {
+            obj.insert("profile".to_string(), serde_json::json!(profile));
+        }
+        if let serde_json::Value::Object(extra) = details {
+            obj.extend(extra);
+        }
+    }
+    anyhow::Error::new(UserError::new("E_CONFIG_INVALID", message).with_details(obj))
+}
+
+fn custom_target_invalid(name: &str, message: String, details: serde_json::Value) -> anyhow::Error {
+    declared_target_invalid(
+        "custom_target_invalid",
+        "edit_manifest_custom_targets",
+        name,
+        message,
+        details,
+    )
+}
+
+pub(crate) fn plugin_target_invalid(
+    name: &str,
+    message: String,
+    details: serde_json::Value,
+) -> anyhow::Error {
+    declared_target_invalid(
+        "plugin_target_invalid",
+        "edit_manifest_plugin_targets",
+        name,
+        message,
+        details,
+    )
+}
+
+fn declared_target_invalid(
+    reason_code: &str,
+    next_action: &str,
+    name: &str,
+    message: String,
+    details: serde_json::Value,
+) -> anyhow::Error {
+    let mut obj = serde_json::json!({
+        "target": name,
+        "reason_code": reason_code,
+        "next_actions": [next_action, "retry_command"],
+    });
+    if let (Some(obj), serde_json::Value::Object(extra)) = (obj.as_object_mut(), details) {
+        obj.extend(extra);
+    }
+    anyhow::Error::new(UserError::new("E_CONFIG_INVALID", message).with_details(obj))
+}
+
+fn validate_declared_target_name(
+    manifest: &Manifest,
+    name: &str,
+    kind: &str,
+    invalid: fn(&str, String, serde_json::Value) -> anyhow::Error,
+) -> anyhow::Result<()> {
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid_name || name == "all" {
+        return Err(invalid(
+            name,
+            format!("invalid {kind} target name: {name:?}"),
+            serde_json::json!({
+                "hint": "use lowercase ascii letters, digits, '_' or '-' (and not `all`)",
+            }),
+        ));
+    }
+    if crate::target_registry::is_builtin_target(name) {
+        return Err(invalid(
+            name,
+            format!("{kind} target {name} conflicts with a built-in target"),
+            serde_json::json!({ "builtin": crate::target_registry::BUILTIN_TARGETS }),
+        ));
+    }
+    if manifest.targets.contains_key(name) {
+        return Err(invalid(
+            name,
+            format!("{kind} target {name} must not also be listed under `targets:`"),
+            serde_json::json!({}),
+        ));
+    }
+    if kind != "custom" && manifest.custom_targets.contains_key(name) {
+        return Err(invalid(
+            name,
+            format!("{kind} target {name} is also declared under `custom_targets:`"),
+            serde_json::json!({}),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_plugin_target(
+    manifest: &Manifest,
+    name: &str,
+    cfg: &PluginTargetConfig,
+) -> anyhow::Result<()> {
+    validate_declared_target_name(manifest, name, "plugin", plugin_target_invalid)?;
+
+    if cfg.command.first().is_none_or(|c| c.trim().is_empty()) {
+        return Err(plugin_target_invalid(
+            name,
+            format!("plugin target {name} requires a non-empty command"),
+            serde_json::json!({ "field": "command" }),
+        ));
+    }
+    if cfg.timeout_ms == Some(0) {
+        return Err(plugin_target_invalid(
+            name,
+            format!("plugin target {name}: timeout_ms must be greater than 0"),
+            serde_json::json!({ "field": "timeout_ms" }),
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_hooks(hooks: &Hooks) -> anyhow::Result<()> {
+    let stages = [
+        ("pre_deploy", &hooks.pre_deploy),
+        ("post_deploy", &hooks.post_deploy),
+        ("post_rollback", &hooks.post_rollback),
+    ];
+    for (stage, list) in stages {
+        for (idx, hook) in list.iter().enumerate() {
+            let (field, message) = if hook.command.first().is_none_or(|c| c.trim().is_empty()) {
+                (
+                    "command",
+                    format!("hooks.{stage}[{idx}] requires a non-empty command"),
+                )
+            } else if hook.timeout_ms == Some(0) {
+                (
+                    "timeout_ms",
+                    format!("hooks.{stage}[{idx}]: timeout_ms must be greater than 0"),
+                )
+            } else {
+                continue;
+            };
+            return Err(anyhow::Error::new(
+                UserError::new("E_CONFIG_INVALID", message).with_details(serde_json::json!({
+                    "hook": stage,
+                    "index": idx,
+                    "field": field,
+                    "reason_code": "hook_invalid",
+                    "next_actions": ["edit_manifest_hooks", "retry_command"],
+                })),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_machine_groups(manifest: &Manifest) -> anyhow::Result<()> {
+    for (name, group) in &manifest.machines {
+        let (field, message) = if name.is_empty()
+            || crate::machine::normalize_machine_id(name) != *name
+        {
+            (
+                "name",
+                format!(
+                    "invalid machine group name {name:?} (use lowercase letters, digits, '-' or '_')"
+                ),
+            )
+        } else if group.patterns.is_empty() {
+            (
+                "match",
+                format!("machines.{name}.match must list at least one machine id or glob"),
+            )
+        } else if let Some(pattern) = group.patterns.iter().find(|p| {
+            p.is_empty()
+                || !p.chars().all(|c| {
+                    c.is_ascii_lowercase()
+                        || c.is_ascii_digit()
+                        || matches!(c, '-' | '_' | '*' | '?')
+                })
+        }) {
+            (
+                "match",
+                format!(
+                    "machines.{name}.match: invalid pattern {pattern:?} (use normalized machine ids with optional * or ?)"
+                ),
+            )
+        } else if let Some(profile) = group
+            .default_profile
+            .as_ref()
+            .filter(|p| !manifest.profiles.contains_key(p.as_str()))
+        {
+            (
+                "default_profile",
+                format!("machines.{name}.default_profile refers to unknown profile {profile}"),
+            )
+        } else {
+            continue;
+        };
+        return Err(anyhow::Error::new(
+            UserError::new("E_CONFIG_INVALID", message).with_details(serde_json::json!({
+                "group": name,
+                "field": field,
+                "reason_code": "machine_group_invalid",
+                "next_actions": ["edit_manifest_machines", "retry_command"],
+            })),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_custom_target(
+    manifest: &Manifest,
+    name: &str,
+    cfg: &CustomTargetConfig,
+) -> anyhow::Result<()> {
+    validate_declared_target_name(manifest, name, "custom", custom_target_invalid)?;
+
+    let mut templates: Vec<(&str, &str)> = Vec::new();
+    if let Some(o) = &cfg.instructions {
+        templates.push(("instructions.path", o.path.as_str()));
+    }
+    if let Some(o) = &cfg.skill {
+        templates.push(("skill.path", o.path.as_str()));
+    }
+    if let Some(o) = &cfg.prompt {
+        templates.push(("prompt.dir", o.dir.as_str()));
+    }
+    if let Some(o) = &cfg.command {
+        templates.push(("command.dir", o.dir.as_str()));
+    }
+    if templates.is_empty() {
+        return Err(custom_target_invalid(
+            name,
+            format!("custom target {name} declares no outputs"),
+            serde_json::json!({
+                "hint": "declare at least one of: instructions, skill, prompt, command",
+            }),
+        ));
+    }
+
+    let allows_user = matches!(cfg.scope, TargetScope::User | TargetScope::Both);
+    let allows_project = matches!(cfg.scope, TargetScope::Project | TargetScope::Both);
+    for (field, template) in &templates {
+        let t = normalize_template(template);
+        let is_project = t.starts_with(TEMPLATE_PROJECT_ROOT);
+        let is_user = t == "~" || t.starts_with("~/") || Path::new(&t).is_absolute();
+        if !is_project && !is_user {
+            return Err(custom_target_invalid(
+                name,
+                format!(
+                    "custom target {name}: {field} must start with `~/`, `{TEMPLATE_PROJECT_ROOT}` or an absolute path"
+                ),
+                serde_json::json!({ "field": field, "template": template }),
+            ));
+        }
+        if is_project && !allows_project {
+            return Err(custom_target_invalid(
+                name,
+                format!(
+                    "custom target {name}: {field} uses {TEMPLATE_PROJECT_ROOT} but scope does not include project"
+                ),
+                serde_json::json!({ "field": field, "template": template, "allowed_scopes": ["project","both"] }),
+            ));
+        }
+        if is_user && !allows_user {
+            return Err(custom_target_invalid(
+                name,
+                format!(
+                    "custom target {name}: {field} is a user path but scope does not include user"
+                ),
+                serde_json::json!({ "field": field, "template": template, "allowed_scopes": ["user","both"] }),
+            ));
+        }
+        if t.split('/').any(|seg| seg == "..") {
+            return Err(custom_target_invalid(
+                name,
+                format!("custom target {name}: {field} must not contain `..`"),
+                serde_json::json!({ "field": field, "template": template }),
+            ));
+        }
+
+        let wants_module_name = match *field {
+            "skill.path" => true,
+            "instructions.path" => cfg
+                .instructions
+                .as_ref()
+                .is_some_and(|o| o.aggregate == CustomAggregateMode::PerModule),
+            _ => false,
+        };
+        let (dir_part, last) = t.rsplit_once('/').unwrap_or(("", t.as_str()));
+        let placement_ok = if wants_module_name {
+            last.contains(TEMPLATE_MODULE_NAME) && !dir_part.contains(TEMPLATE_MODULE_NAME)
+        } else {
+            !t.contains(TEMPLATE_MODULE_NAME)
+        };
+        if !placement_ok {
+            return Err(custom_target_invalid(
+                name,
+                if wants_module_name {
+                    format!(
+                        "custom target {name}: {field} must contain {TEMPLATE_MODULE_NAME} in its last path component"
+                    )
+                } else {
+                    format!("custom target {name}: {field} must not contain {TEMPLATE_MODULE_NAME}")
+                },
+                serde_json::json!({ "field": field, "template": template }),
+            ));
+        }
+    }
+
+    if let Some(o) = &cfg.instructions {
+        if o.aggregate == CustomAggregateMode::Concat
+            && o.frontmatter
+                .as_deref()
+                .is_some_and(|f| f.contains(TEMPLATE_MODULE_NAME) || f.contains("{module_id}"))
+        {
+            return Err(custom_target_invalid(
+                name,
+                format!(
+                    "custom target {name}: frontmatter placeholders require aggregate: per_module"
+                ),
+                serde_json::json!({ "field": "instructions.frontmatter" }),
+            ));
+        }
+    }
+    for o in [&cfg.prompt, &cfg.command].into_iter().flatten() {
+        if let Some(ext) = &o.extension {
+            if !ext.starts_with('.') || ext.contains('/') || ext.contains('\\') {
+                return Err(custom_target_invalid(
+                    name,
+                    format!(
+                        "custom target {name}: invalid extension {ext:?} (expected e.g. \".md\")"
+                    ),
+                    serde_json::json!({ "extension": ext }),
+                ));
+            }
+        }
+    }
+
+    let roots: Vec<String> = cfg.root_templates().into_iter().map(|(_, r)| r).collect();
+    for extra in &cfg.scan_extras {
+        if !roots.contains(&normalize_template(extra)) {
+            return Err(custom_target_invalid(
+                name,
+                format!("custom target {name}: scan_extras entry {extra:?} is not a managed root"),
+                serde_json::json!({ "field": "scan_extras", "template": extra, "roots": roots }),
+            ));
+        }
+    }
+
     Ok(())
 }
@@ -2,9 +2,10 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::Context as _;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::hash::sha256_hex;
+use crate::output_spans::{OutputSpan, SpannedOutput};
 use crate::user_error::UserError;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -13,10 +14,28 @@ pub struct TargetPath {
     pub path: PathBuf,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DesiredFile {
     pub bytes: Vec<u8>,
     pub module_ids: Vec<String>,
+    /// When set (symlink mode), the path is deployed as a symlink to this absolute path.
+    pub link_target: Option<PathBuf>,
+    /// POSIX permission bits to deploy with; `None` leaves the platform default.
+    pub mode: Option<u32>,
+    /// Written instead of `bytes` when local edits were merged in. Target manifests and
+    /// snapshots record it as the deployed content, and keep `bytes` (the upstream content) as
+    /// the next merge base.
+    pub merged: Option<Vec<u8>>,
+    /// Which byte ranges of `bytes` came from which module and which the target adapter
+    /// synthesized; empty when the adapter did not record them.
+    pub spans: Vec<OutputSpan>,
+}
+
+impl DesiredFile {
+    /// The bytes apply writes to disk.
+    pub fn deployed_bytes(&self) -> &[u8] {
+        self.merged.as_deref().unwrap_or(&self.bytes)
+    }
 }
 
 pub type DesiredState = BTreeMap<TargetPath, DesiredFile>;
@@ -29,7 +48,50 @@ pub fn insert_desired_file(
     bytes: Vec<u8>,
     module_ids: Vec<String>,
 ) -> anyhow::Result<()> {
-    let target = target.into();
+    insert_desired_file_with_mode(desired, target, path, bytes, None, module_ids)
+}
+
+/// Like [`insert_desired_file`], but records the POSIX mode of the source file.
+pub fn insert_desired_file_with_mode(
+    desired: &mut DesiredState,
+    target: impl Into<String>,
+    path: PathBuf,
+    bytes: Vec<u8>,
+    mode: Option<u32>,
+    module_ids: Vec<String>,
+) -> anyhow::Result<()> {
+    insert_desired_entry(
+        desired,
+        target.into(),
+        path,
+        bytes,
+        mode,
+        Vec::new(),
+        module_ids,
+    )
+}
+
+/// Like [`insert_desired_file`], but keeps the output's module/synthesized spans.
+pub fn insert_desired_output(
+    desired: &mut DesiredState,
+    target: impl Into<String>,
+    path: PathBuf,
+    output: SpannedOutput,
+    module_ids: Vec<String>,
+) -> anyhow::Result<()> {
+    let (bytes, spans) = output.into_parts();
+    insert_desired_entry(desired, target.into(), path, bytes, None, spans, module_ids)
+}
+
+fn insert_desired_entry(
+    desired: &mut DesiredState,
+    target: String,
+    path: PathBuf,
+    bytes: Vec<u8>,
+    mode: Option<u32>,
+    spans: Vec<OutputSpan>,
+    module_ids: Vec<String>,
+) -> anyhow::Result<()> {
     let path_str = path.to_string_lossy().to_string();
     let key = TargetPath {
         target: target.clone(),
@@ -42,6 +104,10 @@ pub fn insert_desired_file(
             merged.extend(existing.module_ids.iter().cloned());
             merged.extend(module_ids);
             existing.module_ids = merged.into_iter().collect();
+            existing.mode = existing.mode.or(mode);
+            if existing.spans.is_empty() {
+                existing.spans = spans;
+            }
             return Ok(());
         }
 
@@ -73,11 +139,21 @@ pub fn insert_desired_file(
         ));
     }
 
-    desired.insert(key, DesiredFile { bytes, module_ids });
+    desired.insert(
+        key,
+        DesiredFile {
+            bytes,
+            module_ids,
+            link_target: None,
+            mode,
+            merged: None,
+            spans,
+        },
+    );
     Ok(())
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Op {
     Create,
@@ -85,14 +161,22 @@ pub enum Op {
     Delete,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum UpdateKind {
     ManagedUpdate,
     AdoptUpdate,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Outcome of merging a locally edited managed file with new upstream content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStatus {
+    Clean,
+    Conflict,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanChange {
     pub target: String,
     pub op: Op,
@@ -103,16 +187,21 @@ pub struct PlanChange {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub update_kind: Option<UpdateKind>,
     pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
+    /// Set when this update three-way merges local edits (see `local_edits`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge: Option<MergeStatus>,
 }
 
-#[derive(Debug, Clone, Serialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PlanSummary {
     pub create: u64,
     pub update: u64,
     pub delete: u64,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanResult {
     pub changes: Vec<PlanChange>,
     pub summary: PlanSummary,
@@ -123,19 +212,56 @@ pub fn plan(desired: &DesiredState, managed: Option<&ManagedPaths>) -> anyhow::R
 
     for (tp, desired_file) in desired {
         let after_sha = sha256_hex(&desired_file.bytes);
+        let link_target = desired_file
+            .link_target
+            .as_ref()
+            .map(|p| p.to_string_lossy().to_string());
+        if let Some(link) = &desired_file.link_target {
+            if crate::fs::is_symlink(&tp.path) {
+                let current = std::fs::read_link(&tp.path)
+                    .with_context(|| format!("read link {}", tp.path.display()))?;
+                if &current == link {
+                    continue;
+                }
+                changes.push(PlanChange {
+                    target: tp.target.clone(),
+                    op: Op::Update,
+                    path: tp.path.to_string_lossy().to_string(),
+                    path_posix: crate::paths::path_to_posix_string(&tp.path),
+                    before_sha256: std::fs::read(&tp.path).ok().map(|b| sha256_hex(&b)),
+                    after_sha256: Some(after_sha),
+                    update_kind: Some(update_kind_for(managed, tp)),
+                    reason: "link target differs".to_string(),
+                    link_target,
+                    merge: None,
+                });
+                continue;
+            }
+        }
+
         match std::fs::read(&tp.path) {
             Ok(existing) => {
                 let before_sha = sha256_hex(&existing);
-                if before_sha != after_sha {
-                    let update_kind = match managed {
-                        Some(managed) if managed.contains(tp) => UpdateKind::ManagedUpdate,
-                        _ => UpdateKind::AdoptUpdate,
-                    };
+                let is_link = crate::fs::is_symlink(&tp.path);
+                let kind_changes = desired_file.link_target.is_some() != is_link;
+                let update_kind = update_kind_for(managed, tp);
+                // Only files agentpack already manages get their executable bit corrected.
+                let mode_changes = !is_link
+                    && matches!(update_kind, UpdateKind::ManagedUpdate)
+                    && crate::fs::executable_bit_differs(&tp.path, desired_file.mode);
+                if before_sha != after_sha || kind_changes || mode_changes {
                     let reason = match update_kind {
-                        UpdateKind::ManagedUpdate => "content differs".to_string(),
                         UpdateKind::AdoptUpdate => {
                             "would overwrite unmanaged existing file".to_string()
                         }
+                        UpdateKind::ManagedUpdate if !kind_changes && before_sha != after_sha => {
+                            "content differs".to_string()
+                        }
+                        UpdateKind::ManagedUpdate if !kind_changes => "mode differs".to_string(),
+                        UpdateKind::ManagedUpdate if is_link => {
+                            "replace symlink with file".to_string()
+                        }
+                        UpdateKind::ManagedUpdate => "replace file with symlink".to_string(),
                     };
                     changes.push(PlanChange {
                         target: tp.target.clone(),
@@ -146,6 +272,8 @@ pub fn plan(desired: &DesiredState, managed: Option<&ManagedPaths>) -> anyhow::R
                         after_sha256: Some(after_sha),
                         update_kind: Some(update_kind),
                         reason,
+                        link_target,
+                        merge: None,
                     });
                 }
             }
@@ -159,6 +287,8 @@ pub fn plan(desired: &DesiredState, managed: Option<&ManagedPaths>) -> anyhow::R
                     after_sha256: Some(after_sha),
                     update_kind: None,
                     reason: "file missing".to_string(),
+                    link_target,
+                    merge: None,
                 });
             }
             Err(err) => {
@@ -294,17 +424,19 @@ pub fn plan(desired: &DesiredState, managed: Option<&ManagedPaths>) -> anyhow::R
             if desired.contains_key(tp) {
                 continue;
             }
-            if tp.path.exists() {
-                let before_sha = sha256_hex(&std::fs::read(&tp.path)?);
+            if tp.path.exists() || crate::fs::is_symlink(&tp.path) {
+                let before_sha = std::fs::read(&tp.path).ok().map(|b| sha256_hex(&b));
                 changes.push(PlanChange {
                     target: tp.target.clone(),
                     op: Op::Delete,
                     path: tp.path.to_string_lossy().to_string(),
                     path_posix: crate::paths::path_to_posix_string(&tp.path),
-                    before_sha256: Some(before_sha),
+                    before_sha256: before_sha,
                     after_sha256: None,
                     update_kind: None,
                     reason: "no longer managed".to_string(),
+                    link_target: None,
+                    merge: None,
                 });
             }
         }
@@ -326,6 +458,13 @@ pub fn plan(desired: &DesiredState, managed: Option<&ManagedPaths>) -> anyhow::R
     Ok(PlanResult { changes, summary })
 }
 
+fn update_kind_for(managed: Option<&ManagedPaths>, tp: &TargetPath) -> UpdateKind {
+    match managed {
+        Some(managed) if managed.contains(tp) => UpdateKind::ManagedUpdate,
+        _ => UpdateKind::AdoptUpdate,
+    }
+}
+
 pub fn load_managed_paths_from_snapshot(
     snapshot: &crate::state::DeploymentSnapshot,
 ) -> anyhow::Result<ManagedPaths> {
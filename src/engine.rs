@@ -1,16 +1,20 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::Context as _;
 
-use crate::config::{Manifest, Module};
+use crate::config::{METADATA_SUBPATH, Manifest, Module, ModuleType, TargetMode};
 use crate::deploy::DesiredState;
 use crate::lockfile::Lockfile;
 use crate::overlay::resolve_upstream_module_root;
 use crate::paths::{AgentpackHome, RepoPaths};
 use crate::project::ProjectContext;
 use crate::store::{Store, sanitize_module_id};
-use crate::target_adapters::adapter_for;
+use crate::target_adapters::{
+    TargetAdapter as _, adapter_for, custom_adapter_for, plugin_adapter_for,
+};
 use crate::targets::{TargetRoot, dedup_roots};
+use crate::user_error::UserError;
 use crate::validate::validate_materialized_module;
 
 #[derive(Debug)]
@@ -35,24 +39,24 @@ impl Engine {
     pub fn load(
         repo_override: Option<&Path>,
         machine_override: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let cwd = std::env::current_dir().context("get cwd")?;
+        Self::load_in(repo_override, machine_override, &cwd)
+    }
+
+    /// Loads an engine whose project context is detected from `cwd` instead of the process cwd.
+    pub fn load_in(
+        repo_override: Option<&Path>,
+        machine_override: Option<&str>,
+        cwd: &Path,
     ) -> anyhow::Result<Self> {
         let home = AgentpackHome::resolve()?;
         let repo = RepoPaths::resolve(&home, repo_override)?;
         let manifest = Manifest::load(&repo.manifest_path).context("load manifest")?;
         let lockfile = Lockfile::load(&repo.lockfile_path).ok();
         let store = Store::new(&home);
-        let cwd = std::env::current_dir().context("get cwd")?;
-        let project = ProjectContext::detect(&cwd).context("detect project")?;
-        let machine_id = if let Some(m) = machine_override {
-            let normalized = crate::machine::normalize_machine_id(m);
-            if normalized.is_empty() {
-                crate::machine::detect_machine_id()?
-            } else {
-                normalized
-            }
-        } else {
-            crate::machine::detect_machine_id()?
-        };
+        let project = ProjectContext::detect(cwd).context("detect project")?;
+        let machine_id = crate::machine::resolve_machine_id(machine_override)?;
         Ok(Self {
             home,
             repo,
@@ -70,6 +74,8 @@ impl Engine {
         target_filter: &str,
     ) -> anyhow::Result<RenderResult> {
         let modules = self.select_modules(profile)?;
+        let modules = self.with_profile_subpaths(profile, modules);
+        let modules: Vec<&Module> = modules.iter().collect();
         let mut desired = DesiredState::new();
         let mut warnings = Vec::new();
         let mut roots = Vec::new();
@@ -77,7 +83,47 @@ impl Engine {
         let targets = crate::target_selection::selected_targets(&self.manifest, target_filter)?;
         for target in targets {
             if let Some(adapter) = adapter_for(target.as_str()) {
-                adapter.render(self, &modules, &mut desired, &mut warnings, &mut roots)?;
+                adapter.render(
+                    self,
+                    &modules,
+                    profile,
+                    &mut desired,
+                    &mut warnings,
+                    &mut roots,
+                )?;
+            } else if let Some(adapter) = custom_adapter_for(&self.manifest, target.as_str()) {
+                adapter.render(
+                    self,
+                    &modules,
+                    profile,
+                    &mut desired,
+                    &mut warnings,
+                    &mut roots,
+                )?;
+            } else if let Some(adapter) = plugin_adapter_for(&self.manifest, target.as_str()) {
+                adapter.render(
+                    self,
+                    &modules,
+                    profile,
+                    &mut desired,
+                    &mut warnings,
+                    &mut roots,
+                )?;
+            }
+
+            if self
+                .manifest
+                .targets
+                .get(target.as_str())
+                .is_some_and(|cfg| cfg.mode == TargetMode::Symlink)
+            {
+                self.link_symlink_mode_outputs(
+                    &target,
+                    &modules,
+                    profile,
+                    &mut desired,
+                    &mut warnings,
+                )?;
             }
         }
 
@@ -115,9 +161,39 @@ impl Engine {
         Ok(out)
     }
 
+    /// Applies the profile's `instructions_subpaths` on top of each module's `metadata.subpath`.
+    fn with_profile_subpaths(&self, profile_name: &str, modules: Vec<&Module>) -> Vec<Module> {
+        let overrides = self
+            .manifest
+            .profiles
+            .get(profile_name)
+            .map(|p| &p.instructions_subpaths);
+        modules
+            .into_iter()
+            .map(|m| {
+                let mut m = m.clone();
+                if let Some(subpaths) = overrides.and_then(|o| o.get(&m.id)) {
+                    m.metadata.insert(
+                        METADATA_SUBPATH.to_string(),
+                        serde_yaml::Value::Sequence(
+                            subpaths
+                                .iter()
+                                .map(|s| serde_yaml::Value::String(s.clone()))
+                                .collect(),
+                        ),
+                    );
+                }
+                m
+            })
+            .collect()
+    }
+
+    /// Composes a module's upstream files with its overlays (including `profile`'s layer) in a
+    /// temp dir.
     pub(crate) fn materialize_module(
         &self,
         module: &Module,
+        profile: &str,
         warnings: &mut Vec<String>,
     ) -> anyhow::Result<(tempfile::TempDir, PathBuf)> {
         let tmp = tempfile::tempdir().context("create tempdir")?;
@@ -125,58 +201,269 @@ impl Engine {
         std::fs::create_dir_all(&dst).context("create module dir")?;
 
         let upstream = resolve_upstream_module_root(&self.home, &self.repo, module)?;
+        let layers = self.overlay_dirs(module, profile)?;
+        for (scope, dir) in &layers {
+            warnings.extend(crate::overlay::overlay_drift_warnings(
+                &module.id, scope, &upstream, dir,
+            )?);
+        }
+
+        let overlays: Vec<_> = layers
+            .iter()
+            .map(|(scope, dir)| crate::overlay::OverlayLayer { scope, dir })
+            .collect();
+        crate::overlay::compose_module_tree(&module.id, &upstream, &overlays, &dst)?;
+        validate_materialized_module(&module.module_type, &module.id, &dst)
+            .context("validate module")?;
+
+        Ok((tmp, dst))
+    }
+
+    /// The profile overlay dir of a module, for `--scope profile` (the profile must exist).
+    pub(crate) fn profile_overlay_dir(
+        &self,
+        profile: &str,
+        module_id: &str,
+    ) -> anyhow::Result<PathBuf> {
+        if !self.manifest.profiles.contains_key(profile) {
+            return Err(anyhow::Error::new(
+                UserError::new("E_CONFIG_INVALID", format!("profile not found: {profile}"))
+                    .with_details(serde_json::json!({
+                        "module_id": module_id,
+                        "profile": profile,
+                        "profiles": self.manifest.profiles.keys().collect::<Vec<_>>(),
+                        "reason_code": "profile_unknown",
+                        "next_actions": ["edit_manifest_profiles", "retry_command"],
+                    })),
+            ));
+        }
+        Ok(overlay_dir_profile(&self.repo.repo_dir, profile, module_id))
+    }
+
+    /// The `machines:` group this machine belongs to, if any.
+    pub fn machine_group(&self) -> anyhow::Result<Option<&str>> {
+        crate::machine::machine_group(&self.manifest, &self.machine_id)
+    }
+
+    /// Overlay dirs for a module by scope, in precedence order: global, `profile`, group (only
+    /// when the machine is in a group), machine, project. Existing legacy paths are preferred.
+    pub(crate) fn overlay_dirs(
+        &self,
+        module: &Module,
+        profile: &str,
+    ) -> anyhow::Result<Vec<(&'static str, PathBuf)>> {
         let global = overlay_dir_global(&self.repo.repo_dir, &module.id);
         let machine = overlay_dir_machine(&self.repo.repo_dir, &self.machine_id, &module.id);
         let project =
             overlay_dir_project(&self.repo.repo_dir, &self.project.project_id, &module.id);
 
-        let global = overlay_dir_prefer_existing(
-            &global,
-            &overlay_dir_global_fallbacks(&self.repo.repo_dir, &module.id),
-        );
-        let machine = overlay_dir_prefer_existing(
-            &machine,
-            &overlay_dir_machine_fallbacks(&self.repo.repo_dir, &self.machine_id, &module.id),
-        );
-        let project = overlay_dir_prefer_existing(
-            &project,
-            &overlay_dir_project_fallbacks(
-                &self.repo.repo_dir,
-                &self.project.project_id,
-                &module.id,
+        let mut out = vec![(
+            "global",
+            overlay_dir_prefer_existing(
+                &global,
+                &overlay_dir_global_fallbacks(&self.repo.repo_dir, &module.id),
             ),
-        );
+        )];
+        out.push((
+            "profile",
+            overlay_dir_profile(&self.repo.repo_dir, profile, &module.id),
+        ));
+        if let Some(group) = self.machine_group()? {
+            out.push((
+                "group",
+                overlay_dir_group(&self.repo.repo_dir, group, &module.id),
+            ));
+        }
+        out.push((
+            "machine",
+            overlay_dir_prefer_existing(
+                &machine,
+                &overlay_dir_machine_fallbacks(&self.repo.repo_dir, &self.machine_id, &module.id),
+            ),
+        ));
+        out.push((
+            "project",
+            overlay_dir_prefer_existing(
+                &project,
+                &overlay_dir_project_fallbacks(
+                    &self.repo.repo_dir,
+                    &self.project.project_id,
+                    &module.id,
+                ),
+            ),
+        ));
+        Ok(out)
+    }
 
-        warnings.extend(crate::overlay::overlay_drift_warnings(
-            &module.id, "global", &upstream, &global,
-        )?);
-        warnings.extend(crate::overlay::overlay_drift_warnings(
-            &module.id, "machine", &upstream, &machine,
-        )?);
-        warnings.extend(crate::overlay::overlay_drift_warnings(
-            &module.id, "project", &upstream, &project,
-        )?);
-
-        let overlays = [
-            crate::overlay::OverlayLayer {
-                scope: "global",
-                dir: &global,
-            },
-            crate::overlay::OverlayLayer {
-                scope: "machine",
-                dir: &machine,
-            },
-            crate::overlay::OverlayLayer {
-                scope: "project",
-                dir: &project,
-            },
-        ];
-        crate::overlay::compose_module_tree(&module.id, &upstream, &overlays, &dst)?;
-        validate_materialized_module(&module.module_type, &module.id, &dst)
-            .context("validate module")?;
+    /// Every existing overlay dir of a module in the config repo, across all profiles, groups,
+    /// machines and projects (not just the current ones), in precedence order. Entries are
+    /// `(scope, scope_id, dir)`; the global scope has no id.
+    pub(crate) fn existing_overlay_dirs(
+        &self,
+        module: &Module,
+    ) -> anyhow::Result<Vec<(&'static str, Option<String>, PathBuf)>> {
+        let repo_dir = &self.repo.repo_dir;
+        let mut out = vec![(
+            "global",
+            None,
+            overlay_dir_prefer_existing(
+                &overlay_dir_global(repo_dir, &module.id),
+                &overlay_dir_global_fallbacks(repo_dir, &module.id),
+            ),
+        )];
+        for profile in subdir_names(&repo_dir.join("overlays/profiles"))? {
+            let dir = overlay_dir_profile(repo_dir, &profile, &module.id);
+            out.push(("profile", Some(profile), dir));
+        }
+        for group in subdir_names(&repo_dir.join("overlays/groups"))? {
+            let dir = overlay_dir_group(repo_dir, &group, &module.id);
+            out.push(("group", Some(group), dir));
+        }
+        for machine_id in subdir_names(&repo_dir.join("overlays/machines"))? {
+            let dir = overlay_dir_prefer_existing(
+                &overlay_dir_machine(repo_dir, &machine_id, &module.id),
+                &overlay_dir_machine_fallbacks(repo_dir, &machine_id, &module.id),
+            );
+            out.push(("machine", Some(machine_id), dir));
+        }
+        for project_id in subdir_names(&repo_dir.join("projects"))? {
+            let dir = overlay_dir_prefer_existing(
+                &overlay_dir_project(repo_dir, &project_id, &module.id),
+                &overlay_dir_project_fallbacks(repo_dir, &project_id, &module.id),
+            );
+            out.push(("project", Some(project_id), dir));
+        }
+        out.retain(|(_, _, dir)| dir.is_dir());
+        Ok(out)
+    }
 
-        Ok((tmp, dst))
+    /// Turn verbatim outputs of local_path modules into symlinks for a `mode: symlink` target.
+    ///
+    /// Outputs that cannot be linked (overlays, rendered/templated content, non-local sources)
+    /// stay regular files and produce one warning per module.
+    fn link_symlink_mode_outputs(
+        &self,
+        target: &str,
+        modules: &[&Module],
+        profile: &str,
+        desired: &mut DesiredState,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut upstream_files: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+        let mut copied: BTreeMap<String, &'static str> = BTreeMap::new();
+
+        for (tp, file) in desired.iter_mut() {
+            if tp.target != target || file.module_ids.len() != 1 {
+                continue;
+            }
+            let Some(module) = modules.iter().find(|m| m.id == file.module_ids[0]) else {
+                continue;
+            };
+            if matches!(module.module_type, ModuleType::Instructions) {
+                continue;
+            }
+
+            if module.source.local_path.is_none() {
+                copied
+                    .entry(module.id.clone())
+                    .or_insert("not a local_path source");
+                continue;
+            }
+            if self
+                .overlay_dirs(module, profile)?
+                .iter()
+                .any(|(_, d)| d.is_dir())
+            {
+                copied
+                    .entry(module.id.clone())
+                    .or_insert("overlays applied");
+                continue;
+            }
+
+            if !upstream_files.contains_key(&module.id) {
+                let upstream = resolve_upstream_module_root(&self.home, &self.repo, module)?;
+                upstream_files.insert(module.id.clone(), list_module_files(&upstream)?);
+            }
+            let files = &upstream_files[&module.id];
+
+            let out_path = tp.path.to_string_lossy().replace('\\', "/");
+            let mut same_bytes = Vec::new();
+            for (rel, abs) in files {
+                if std::fs::read(abs).ok().as_deref() == Some(file.bytes.as_slice()) {
+                    same_bytes.push((rel, abs));
+                }
+            }
+            let source = same_bytes
+                .iter()
+                .filter(|(rel, _)| out_path.ends_with(&format!("/{rel}")))
+                .max_by_key(|(rel, _)| rel.len())
+                .or(if same_bytes.len() == 1 {
+                    same_bytes.first()
+                } else {
+                    None
+                })
+                .map(|(_, abs)| *abs);
+
+            let Some(source) = source else {
+                copied
+                    .entry(module.id.clone())
+                    .or_insert("output is templated or rendered");
+                continue;
+            };
+            let source = std::fs::canonicalize(source)
+                .with_context(|| format!("canonicalize {}", source.display()))?;
+            file.link_target = Some(source);
+        }
+
+        for (module_id, reason) in copied {
+            warnings.push(format!(
+                "target {target}: module {module_id} is deployed as a copy in symlink mode ({reason})"
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn list_module_files(root: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    if root.is_file() {
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return Ok(vec![(name, root.to_path_buf())]);
+    }
+
+    let mut out = Vec::new();
+    for entry in walkdir::WalkDir::new(root).follow_links(false) {
+        let entry = entry.with_context(|| format!("walk {}", root.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry
+            .path()
+            .strip_prefix(root)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+        out.push((rel, entry.path().to_path_buf()));
+    }
+    Ok(out)
+}
+
+/// Sorted names of the directories directly under `dir` (empty if it does not exist).
+fn subdir_names(dir: &Path) -> anyhow::Result<Vec<String>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("read {}", dir.display()))? {
+        let entry = entry.with_context(|| format!("read {}", dir.display()))?;
+        if entry.path().is_dir() {
+            out.push(entry.file_name().to_string_lossy().to_string());
+        }
     }
+    out.sort();
+    Ok(out)
 }
 
 fn overlay_dir_global(repo_dir: &Path, module_id: &str) -> PathBuf {
@@ -201,6 +488,20 @@ fn overlay_dir_global_fallbacks(repo_dir: &Path, module_id: &str) -> Vec<PathBuf
     out
 }
 
+pub(crate) fn overlay_dir_profile(repo_dir: &Path, profile: &str, module_id: &str) -> PathBuf {
+    repo_dir
+        .join("overlays/profiles")
+        .join(profile)
+        .join(crate::ids::module_fs_key(module_id))
+}
+
+pub(crate) fn overlay_dir_group(repo_dir: &Path, group: &str, module_id: &str) -> PathBuf {
+    repo_dir
+        .join("overlays/groups")
+        .join(group)
+        .join(crate::ids::module_fs_key(module_id))
+}
+
 fn overlay_dir_machine(repo_dir: &Path, machine_id: &str, module_id: &str) -> PathBuf {
     repo_dir
         .join("overlays/machines")
@@ -154,10 +154,23 @@ pub fn copy_file(src: &Path, dst: &Path) -> anyhow::Result<()> {
 }
 
 pub fn write_atomic(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
-    write_atomic_impl(path, bytes, fsync_enabled()).map_err(|err| classify_write_error(path, err))
+    write_atomic_with_mode(path, bytes, None)
 }
 
-fn write_atomic_impl(path: &Path, bytes: &[u8], fsync: bool) -> anyhow::Result<()> {
+/// Like [`write_atomic`], but applies POSIX permission bits (e.g. `0o755`) before the rename.
+///
+/// `mode` is ignored on non-unix platforms.
+pub fn write_atomic_with_mode(path: &Path, bytes: &[u8], mode: Option<u32>) -> anyhow::Result<()> {
+    write_atomic_impl(path, bytes, mode, fsync_enabled())
+        .map_err(|err| classify_write_error(path, err))
+}
+
+fn write_atomic_impl(
+    path: &Path,
+    bytes: &[u8],
+    mode: Option<u32>,
+    fsync: bool,
+) -> anyhow::Result<()> {
     let parent = path
         .parent()
         .with_context(|| format!("invalid path: {}", path.display()))?;
@@ -167,6 +180,16 @@ fn write_atomic_impl(path: &Path, bytes: &[u8], fsync: bool) -> anyhow::Result<(
     tmp.write_all(bytes).context("write temp file")?;
     tmp.flush().context("flush temp file")?;
 
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt as _;
+        tmp.as_file()
+            .set_permissions(std::fs::Permissions::from_mode(mode & 0o7777))
+            .context("set temp file permissions")?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
     if fsync {
         tmp.as_file()
             .sync_all()
@@ -186,6 +209,77 @@ fn write_atomic_impl(path: &Path, bytes: &[u8], fsync: bool) -> anyhow::Result<(
     Ok(())
 }
 
+/// Atomically (re)points `path` at `target` as a symlink, replacing any existing file or link.
+pub fn write_symlink(path: &Path, target: &Path) -> anyhow::Result<()> {
+    write_symlink_impl(path, target).map_err(|err| classify_write_error(path, err))
+}
+
+fn write_symlink_impl(path: &Path, target: &Path) -> anyhow::Result<()> {
+    let parent = path
+        .parent()
+        .with_context(|| format!("invalid path: {}", path.display()))?;
+    std::fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+
+    let name = path
+        .file_name()
+        .with_context(|| format!("invalid path: {}", path.display()))?
+        .to_string_lossy();
+    let nanos = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+    let tmp = parent.join(format!(".{name}.agentpack-link-{nanos}"));
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, &tmp)
+        .with_context(|| format!("symlink {} -> {}", tmp.display(), target.display()))?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(target, &tmp)
+        .with_context(|| format!("symlink {} -> {}", tmp.display(), target.display()))?;
+
+    if let Err(err) = std::fs::rename(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(err).with_context(|| format!("persist {}", path.display()));
+    }
+    Ok(())
+}
+
+/// POSIX permission bits of `path` (following symlinks), or `None` where modes are not tracked.
+pub fn file_mode(path: &Path) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        std::fs::metadata(path)
+            .ok()
+            .map(|m| m.permissions().mode() & 0o7777)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// The mode of `path` when it is executable, `None` otherwise: like git, only the executable
+/// bit of deployed files is tracked.
+pub fn executable_mode(path: &Path) -> Option<u32> {
+    file_mode(path).filter(|mode| mode & 0o111 != 0)
+}
+
+/// Whether the executable bit of `path` disagrees with a mode recorded by [`executable_mode`].
+///
+/// Always `false` where modes are not tracked.
+pub fn executable_bit_differs(path: &Path, mode: Option<u32>) -> bool {
+    file_mode(path).is_some_and(|actual| (actual & 0o111 != 0) != mode.is_some())
+}
+
+/// Whether `path` itself is a symlink (without following it).
+pub fn is_symlink(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// Whether anything (file, directory or possibly dangling symlink) exists at `path`.
+pub fn path_present(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok()
+}
+
 fn classify_write_error(path: &Path, err: anyhow::Error) -> anyhow::Error {
     let Some(io) = err.chain().find_map(|e| e.downcast_ref::<std::io::Error>()) else {
         return err;
@@ -372,10 +466,22 @@ mod tests {
     fn write_atomic_with_fsync_enabled_does_not_fail() {
         let td = tempfile::tempdir().unwrap();
         let path = td.path().join("out.txt");
-        write_atomic_impl(&path, b"hello", true).unwrap();
+        write_atomic_impl(&path, b"hello", None, true).unwrap();
         assert_eq!(std::fs::read(&path).unwrap(), b"hello");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_with_mode_sets_permissions() {
+        let td = tempfile::tempdir().unwrap();
+        let path = td.path().join("run.sh");
+        write_atomic_with_mode(&path, b"#!/bin/sh\n", Some(0o755)).unwrap();
+        assert_eq!(file_mode(&path), Some(0o755));
+
+        write_atomic_with_mode(&path, b"#!/bin/sh\n", Some(0o644)).unwrap();
+        assert_eq!(file_mode(&path), Some(0o644));
+    }
+
     #[cfg(unix)]
     #[test]
     fn copy_tree_follows_symlinked_dir_root() {
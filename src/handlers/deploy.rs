@@ -1,4 +1,5 @@
 use crate::engine::Engine;
+use crate::hooks::{HookResult, HookStage, hook_payload, run_hooks};
 use crate::targets::TargetRoot;
 use crate::user_error::UserError;
 
@@ -16,7 +17,11 @@ pub(crate) enum ConfirmationStyle {
 pub(crate) enum DeployApplyOutcome {
     NoChanges,
     NeedsConfirmation,
-    Applied { snapshot_id: String },
+    Applied {
+        snapshot_id: String,
+        /// `pre_deploy` and `post_deploy` hook results (see `hooks`).
+        hooks: Vec<HookResult>,
+    },
 }
 
 pub(crate) fn deploy_apply_in(
@@ -46,6 +51,7 @@ pub(crate) fn deploy_apply_in(
     }
 
     ensure_adopt_ok(plan, adopt)?;
+    ensure_no_merge_conflicts(plan)?;
 
     let needs_manifests = crate::target_manifest::manifests_missing_for_desired(roots, desired);
     if plan.changes.is_empty() && !needs_manifests {
@@ -56,6 +62,34 @@ pub(crate) fn deploy_apply_in(
         return Ok(DeployApplyOutcome::NeedsConfirmation);
     }
 
+    let mut targets: Vec<String> = roots.iter().map(|r| r.target.clone()).collect();
+    targets.sort();
+    targets.dedup();
+    let payload = |stage, snapshot_id: Option<&str>| {
+        hook_payload(
+            stage,
+            snapshot_id,
+            &targets,
+            serde_json::json!(plan.changes),
+            serde_json::json!({
+                "summary": plan.summary,
+                "project_root": engine.project.project_root,
+            }),
+        )
+    };
+    let hooks = &engine.manifest.hooks;
+
+    let mut hook_results = run_hooks(
+        &engine.repo.repo_dir,
+        hooks,
+        HookStage::PreDeploy,
+        &payload(HookStage::PreDeploy, None),
+    )?;
+    if let Some(failed) = hook_results.iter().find(|r| !r.success) {
+        let _ = record_deploy_hooks(engine, None, &targets, &hook_results);
+        return Err(crate::hooks::hook_failed(failed));
+    }
+
     let lockfile_path = engine
         .repo
         .lockfile_path
@@ -64,12 +98,73 @@ pub(crate) fn deploy_apply_in(
     let snapshot =
         crate::apply::apply_plan(&engine.home, "deploy", plan, desired, lockfile_path, roots)?;
 
+    hook_results.extend(run_hooks(
+        &engine.repo.repo_dir,
+        hooks,
+        HookStage::PostDeploy,
+        &payload(HookStage::PostDeploy, Some(&snapshot.id)),
+    )?);
+    crate::hooks::record_in_snapshot(&engine.home, &snapshot.id, &hook_results)?;
+    record_deploy_hooks(engine, Some(&snapshot.id), &targets, &hook_results)?;
+
     Ok(DeployApplyOutcome::Applied {
         snapshot_id: snapshot.id,
+        hooks: hook_results,
     })
 }
 
-fn ensure_adopt_ok(plan: &crate::deploy::PlanResult, adopt: bool) -> anyhow::Result<()> {
+fn record_deploy_hooks(
+    engine: &Engine,
+    snapshot_id: Option<&str>,
+    targets: &[String],
+    results: &[HookResult],
+) -> anyhow::Result<()> {
+    crate::hooks::record_hook_events(
+        &engine.home,
+        &engine.machine_id,
+        "deploy --apply",
+        snapshot_id,
+        targets,
+        results,
+    )
+}
+
+/// Refuses with `E_MERGE_CONFLICT` before hooks run or anything is written when local edits of
+/// a managed file conflict with its upstream changes.
+fn ensure_no_merge_conflicts(plan: &crate::deploy::PlanResult) -> anyhow::Result<()> {
+    let conflicts: Vec<serde_json::Value> = plan
+        .changes
+        .iter()
+        .filter(|c| c.merge == Some(crate::deploy::MergeStatus::Conflict))
+        .map(|c| {
+            serde_json::json!({
+                "target": c.target,
+                "path": c.path,
+                "path_posix": c.path_posix,
+            })
+        })
+        .collect();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow::Error::new(
+        UserError::new(
+            "E_MERGE_CONFLICT",
+            format!(
+                "refusing to deploy: {} file(s) have local edits that conflict with upstream changes",
+                conflicts.len()
+            ),
+        )
+        .with_details(serde_json::json!({
+            "conflicts": conflicts,
+            "reason_code": "merge_conflict",
+            "next_actions": ["preview_diff", "resolve_local_edits", "run_evolve_propose"],
+        })),
+    ))
+}
+
+pub(crate) fn ensure_adopt_ok(plan: &crate::deploy::PlanResult, adopt: bool) -> anyhow::Result<()> {
     let adopt_updates: Vec<&crate::deploy::PlanChange> = plan
         .changes
         .iter()
@@ -24,12 +24,21 @@ pub(crate) struct DoctorGitignoreFix {
     pub(crate) updated: bool,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct DoctorInterruptedApply {
+    #[serde(flatten)]
+    pub(crate) apply: crate::journal::InterruptedApply,
+    pub(crate) restored: bool,
+}
+
 pub(crate) struct DoctorReport {
     pub(crate) machine_id: String,
     pub(crate) roots: Vec<DoctorRootCheck>,
     pub(crate) gitignore_fixes: Vec<DoctorGitignoreFix>,
+    pub(crate) interrupted_applies: Vec<DoctorInterruptedApply>,
     pub(crate) warnings: Vec<String>,
     pub(crate) needs_gitignore_fix: bool,
+    pub(crate) needs_restore: bool,
 }
 
 pub(crate) fn doctor_report_in(
@@ -124,12 +133,59 @@ pub(crate) fn doctor_report_in(
         }
     }
 
+    // A journal is only "interrupted" if no other process is applying right now.
+    let _lock = if fix {
+        Some(crate::state_lock::StateLock::acquire(
+            &engine.home,
+            "doctor --fix",
+        )?)
+    } else {
+        None
+    };
+    let holder = if fix {
+        None
+    } else {
+        crate::state_lock::StateLock::probe(&engine.home)?
+    };
+    let interrupted = match &holder {
+        Some(holder) => {
+            warnings.push(format!(
+                "another agentpack process is applying changes (pid {}: {}); skipped the interrupted apply check",
+                holder.pid, holder.command
+            ));
+            Vec::new()
+        }
+        None => crate::journal::find_interrupted(&engine.home)?,
+    };
+    let needs_restore = !interrupted.is_empty();
+    if fix && needs_restore {
+        crate::journal::restore_all_interrupted(&engine.home)?;
+    } else {
+        for apply in &interrupted {
+            warnings.push(format!(
+                "interrupted {} (snapshot {}) left {} path(s) partially applied; run `agentpack doctor --fix` to restore them",
+                if apply.kind.is_empty() { "apply" } else { apply.kind.as_str() },
+                apply.snapshot_id,
+                apply.changes,
+            ));
+        }
+    }
+    let interrupted_applies = interrupted
+        .into_iter()
+        .map(|apply| DoctorInterruptedApply {
+            apply,
+            restored: fix,
+        })
+        .collect();
+
     Ok(DoctorReport {
         machine_id: engine.machine_id.clone(),
         roots: checks,
         gitignore_fixes,
+        interrupted_applies,
         warnings,
         needs_gitignore_fix: !repos_to_fix.is_empty(),
+        needs_restore,
     })
 }
 
@@ -1,8 +1,9 @@
 use anyhow::Context as _;
 
-use crate::config::{Module, ModuleType};
-use crate::deploy::TargetPath;
+use crate::config::{GitSource, Module, ModuleType, SourceKind};
+use crate::deploy::{DesiredFile, TargetPath};
 use crate::engine::Engine;
+use crate::state_lock::StateLock;
 use crate::user_error::UserError;
 use time::macros::format_description;
 
@@ -99,6 +100,11 @@ pub(crate) fn evolve_restore_in(
         return Ok(EvolveRestoreOutcome::NeedsConfirmation);
     }
 
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(StateLock::acquire(&engine.home, "evolve restore")?)
+    };
     let mut restored: Vec<EvolveRestoreItem> = Vec::new();
     for (tp, bytes, module_ids) in missing {
         if !dry_run {
@@ -133,6 +139,7 @@ pub(crate) fn evolve_restore_in(
 #[serde(rename_all = "snake_case")]
 pub(crate) enum EvolveScope {
     Global,
+    Profile,
     Machine,
     Project,
 }
@@ -143,6 +150,32 @@ pub(crate) enum EvolveProposeOutcome {
     Noop(EvolveProposeNoopReport),
     DryRun(EvolveProposeDryRunReport),
     Created(EvolveProposeCreatedReport),
+    Upstream(EvolveProposeUpstreamReport),
+}
+
+#[derive(Debug)]
+pub(crate) struct EvolveProposeUpstreamReport {
+    pub(crate) proposals: Vec<EvolveProposeUpstreamItem>,
+    pub(crate) skipped: Vec<EvolveProposeSkippedItem>,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// A commit on a new branch in a local clone of a git module's source, plus its patch series.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct EvolveProposeUpstreamItem {
+    pub(crate) module_id: String,
+    pub(crate) url: String,
+    pub(crate) base_commit: String,
+    pub(crate) subdir: String,
+    pub(crate) branch: String,
+    pub(crate) commit: String,
+    pub(crate) clone_dir: String,
+    pub(crate) clone_dir_posix: String,
+    /// Changed files, relative to the clone root.
+    pub(crate) files: Vec<String>,
+    /// `git format-patch` output, one file per commit.
+    pub(crate) patches: Vec<String>,
+    pub(crate) patches_posix: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -177,6 +210,19 @@ pub(crate) struct EvolveProposeItem {
     pub(crate) target: String,
     pub(crate) path: String,
     pub(crate) path_posix: String,
+    /// How divergent edits of this module file across targets were reconciled
+    /// (`prefer_target` or `merged`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) resolution: Option<String>,
+}
+
+/// One target's edited copy of a module file that was edited differently elsewhere.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct EvolveProposeVariant {
+    pub(crate) target: String,
+    pub(crate) path: String,
+    pub(crate) path_posix: String,
+    pub(crate) sha256: String,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -200,6 +246,11 @@ pub(crate) struct EvolveProposeSkippedItem {
     pub(crate) module_ids: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub(crate) suggestions: Vec<EvolveProposeSuggestion>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub(crate) variants: Vec<EvolveProposeVariant>,
+    /// Unified diff from the first variant to each other variant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) diff: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, serde::Serialize)]
@@ -209,6 +260,20 @@ pub(crate) struct EvolveProposeSummary {
     pub(crate) skipped_missing: u64,
     pub(crate) skipped_multi_module: u64,
     pub(crate) skipped_read_error: u64,
+    pub(crate) skipped_divergent: u64,
+    pub(crate) skipped_synthesized: u64,
+}
+
+/// What to do when the same module file was edited differently in several targets.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) enum EvolveDivergentResolution<'a> {
+    /// Report the divergence and propose none of the variants.
+    #[default]
+    Skip,
+    /// Propose the variant deployed for this target.
+    PreferTarget(&'a str),
+    /// Three-way merge the variants against the deployed content; conflicts are still skipped.
+    Merge,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -219,12 +284,26 @@ pub(crate) struct EvolveProposeInput<'a> {
     pub(crate) module_filter: Option<&'a str>,
     pub(crate) scope: EvolveScope,
     pub(crate) branch_override: Option<&'a str>,
+    pub(crate) divergent: EvolveDivergentResolution<'a>,
+    /// Propose into the git source of each module instead of config repo overlays.
+    pub(crate) upstream: bool,
     pub(crate) dry_run: bool,
     pub(crate) confirmed: bool,
     pub(crate) json: bool,
 }
 
-type MarkedSectionCandidates = Vec<(String, Vec<u8>)>;
+type MarkedSectionCandidates = Vec<(String, Vec<u8>, Vec<u8>)>;
+
+/// A drifted output mapped back to a single module: the edited bytes plus the deployed (desired)
+/// bytes they were edited from.
+#[derive(Debug, Clone)]
+struct ProposeCandidate {
+    module_id: String,
+    output: TargetPath,
+    bytes: Vec<u8>,
+    base: Vec<u8>,
+    resolution: Option<&'static str>,
+}
 
 fn try_propose_marked_instructions_sections(
     desired_bytes: &[u8],
@@ -269,7 +348,11 @@ fn try_propose_marked_instructions_sections(
             continue;
         };
         if desired_text != actual_text {
-            out.push((module_id.clone(), actual_text.as_bytes().to_vec()));
+            out.push((
+                module_id.clone(),
+                actual_text.as_bytes().to_vec(),
+                desired_text.as_bytes().to_vec(),
+            ));
         }
     }
 
@@ -280,10 +363,46 @@ fn try_propose_marked_instructions_sections(
     }
 }
 
+enum SpanProposal {
+    /// The output has no synthesized text to anchor on.
+    NotApplicable,
+    Sections(MarkedSectionCandidates),
+    SynthesizedEdited,
+}
+
+/// Maps drift back to modules through the byte spans the target adapter recorded, for outputs
+/// that carry synthesized text (Cursor/custom frontmatter, aggregation separators).
+fn try_propose_output_spans(desired_file: &DesiredFile, actual_bytes: &[u8]) -> SpanProposal {
+    if !desired_file.spans.iter().any(|s| s.module_id.is_none()) {
+        return SpanProposal::NotApplicable;
+    }
+    let Some(slices) = crate::output_spans::split_module_edits(
+        &desired_file.bytes,
+        &desired_file.spans,
+        actual_bytes,
+    ) else {
+        return SpanProposal::SynthesizedEdited;
+    };
+
+    let out = slices
+        .into_iter()
+        .filter_map(|slice| {
+            let desired = &desired_file.bytes[slice.desired];
+            let actual = &actual_bytes[slice.actual];
+            (desired != actual).then(|| (slice.module_id, actual.to_vec(), desired.to_vec()))
+        })
+        .collect();
+    SpanProposal::Sections(out)
+}
+
 fn evolve_propose_reason_message(reason: &str) -> String {
     match reason {
         "missing" => "expected managed output is missing on disk (use evolve.restore or deploy to recreate)".to_string(),
         "multi_module_output" => "output is produced by multiple modules and cannot be proposed safely (add markers or split outputs)".to_string(),
+        "synthesized_text_edited" => "edits touch text generated by the target adapter (frontmatter, separators or markers) and cannot be attributed to a module".to_string(),
+        "divergent_edits" => "the same module file was edited differently in several targets (choose a variant with --prefer-target or reconcile with --merge)".to_string(),
+        "not_git_source" => "module is not sourced from git and cannot be proposed upstream (propose an overlay instead)".to_string(),
+        "upstream_conflict" => "the local edit does not apply cleanly to the module's locked upstream content".to_string(),
         _ => reason.to_string(),
     }
 }
@@ -310,6 +429,26 @@ fn evolve_propose_suggestions(reason: &str) -> Vec<EvolveProposeSuggestion> {
                 reason: "avoid multi-module outputs that cannot be proposed safely".to_string(),
             },
         ],
+        "synthesized_text_edited" => vec![
+            EvolveProposeSuggestion {
+                action: "Keep edits inside module content (outside generated frontmatter, separators and markers)".to_string(),
+                reason: "only module content can be mapped back to an overlay".to_string(),
+            },
+            EvolveProposeSuggestion {
+                action: "agentpack overlay edit <module_id>".to_string(),
+                reason: "change generated-adjacent content in the module's overlay directly".to_string(),
+            },
+        ],
+        "divergent_edits" => vec![
+            EvolveProposeSuggestion {
+                action: "agentpack evolve propose --prefer-target <target>".to_string(),
+                reason: "propose the variant edited for one target".to_string(),
+            },
+            EvolveProposeSuggestion {
+                action: "agentpack evolve propose --merge".to_string(),
+                reason: "three-way merge the variants when their edits do not overlap".to_string(),
+            },
+        ],
         _ => Vec::new(),
     }
 }
@@ -438,6 +577,158 @@ fn module_rel_path_for_output(
     }
 }
 
+struct DivergentContext<'a, 'b> {
+    resolution: EvolveDivergentResolution<'a>,
+    action_prefix: &'a str,
+    summary: &'b mut EvolveProposeSummary,
+    skipped: &'b mut Vec<EvolveProposeSkippedItem>,
+    warnings: &'b mut Vec<String>,
+}
+
+/// Groups candidates by module file and reconciles groups whose targets carry different edits
+/// (e.g. the same skill edited one way under `.codex/skills` and another under `.claude/skills`).
+/// Unresolved groups are dropped from the proposal and reported as `divergent_edits`.
+fn reconcile_divergent_candidates(
+    engine: &Engine,
+    roots: &[crate::targets::TargetRoot],
+    candidates: Vec<ProposeCandidate>,
+    ctx: DivergentContext<'_, '_>,
+) -> Vec<ProposeCandidate> {
+    let mut groups: std::collections::BTreeMap<(String, String), Vec<ProposeCandidate>> =
+        std::collections::BTreeMap::new();
+    for candidate in candidates {
+        let module_rel = engine
+            .manifest
+            .modules
+            .iter()
+            .find(|m| m.id == candidate.module_id)
+            .and_then(|m| {
+                module_rel_path_for_output(m, &candidate.module_id, &candidate.output, roots)
+            })
+            .unwrap_or_else(|| crate::paths::path_to_posix_string(&candidate.output.path));
+        groups
+            .entry((candidate.module_id.clone(), module_rel))
+            .or_default()
+            .push(candidate);
+    }
+
+    let mut out = Vec::new();
+    for ((module_id, module_rel), mut group) in groups {
+        group.sort_by(|a, b| {
+            (a.output.target.as_str(), &a.output.path)
+                .cmp(&(b.output.target.as_str(), &b.output.path))
+        });
+        let mut distinct: Vec<&ProposeCandidate> = Vec::new();
+        for candidate in &group {
+            if !distinct.iter().any(|d| d.bytes == candidate.bytes) {
+                distinct.push(candidate);
+            }
+        }
+        if distinct.len() < 2 {
+            out.extend(group);
+            continue;
+        }
+
+        let resolved: Option<(Vec<u8>, &'static str)> = match ctx.resolution {
+            EvolveDivergentResolution::Skip => None,
+            EvolveDivergentResolution::PreferTarget(target) => group
+                .iter()
+                .find(|c| c.output.target == target)
+                .map(|c| (c.bytes.clone(), "prefer_target")),
+            EvolveDivergentResolution::Merge => {
+                let base = &distinct[0].base;
+                let mut merged = distinct[0].bytes.clone();
+                let mut conflicted = false;
+                for other in &distinct[1..] {
+                    let outcome = crate::merge::merge_three_way(base, &merged, &other.bytes, None);
+                    if outcome.conflicted {
+                        conflicted = true;
+                        break;
+                    }
+                    merged = outcome.merged;
+                }
+                if conflicted {
+                    ctx.warnings.push(format!(
+                        "evolve.propose: could not merge divergent edits of {module_id} {module_rel}: the edits overlap"
+                    ));
+                    None
+                } else {
+                    Some((merged, "merged"))
+                }
+            }
+        };
+
+        if let Some((bytes, resolution)) = resolved {
+            out.extend(group.into_iter().map(|c| ProposeCandidate {
+                bytes: bytes.clone(),
+                resolution: Some(resolution),
+                ..c
+            }));
+            continue;
+        }
+
+        let n = group.len() as u64;
+        ctx.summary.drifted_proposeable -= n;
+        ctx.summary.drifted_skipped += n;
+        ctx.summary.skipped_divergent += n;
+
+        let first = distinct[0];
+        let first_name = format!("{}:{}", first.output.target, first.output.path.display());
+        let diff: String = distinct[1..]
+            .iter()
+            .map(|other| {
+                crate::diff::unified_diff(
+                    &String::from_utf8_lossy(&first.bytes),
+                    &String::from_utf8_lossy(&other.bytes),
+                    &first_name,
+                    &format!("{}:{}", other.output.target, other.output.path.display()),
+                )
+            })
+            .collect();
+
+        let mut targets: Vec<&str> = group.iter().map(|c| c.output.target.as_str()).collect();
+        targets.dedup();
+        let mut next_actions: Vec<String> = targets
+            .iter()
+            .map(|target| {
+                format!(
+                    "{} evolve propose --module-id {module_id} --prefer-target {target} --yes --json",
+                    ctx.action_prefix
+                )
+            })
+            .collect();
+        next_actions.push(format!(
+            "{} evolve propose --module-id {module_id} --merge --yes --json",
+            ctx.action_prefix
+        ));
+
+        let reason = "divergent_edits".to_string();
+        ctx.skipped.push(EvolveProposeSkippedItem {
+            target: first.output.target.clone(),
+            path: first.output.path.to_string_lossy().to_string(),
+            path_posix: crate::paths::path_to_posix_string(&first.output.path),
+            reason_code: reason.clone(),
+            reason_message: evolve_propose_reason_message(&reason),
+            next_actions,
+            reason,
+            module_id: Some(module_id.clone()),
+            module_ids: Vec::new(),
+            suggestions: evolve_propose_suggestions("divergent_edits"),
+            variants: group
+                .iter()
+                .map(|c| EvolveProposeVariant {
+                    target: c.output.target.clone(),
+                    path: c.output.path.to_string_lossy().to_string(),
+                    path_posix: crate::paths::path_to_posix_string(&c.output.path),
+                    sha256: crate::hash::sha256_hex(&c.bytes),
+                })
+                .collect(),
+            diff: Some(diff),
+        });
+    }
+    out
+}
+
 pub(crate) fn evolve_propose_in(
     engine: &Engine,
     input: EvolveProposeInput<'_>,
@@ -449,6 +740,8 @@ pub(crate) fn evolve_propose_in(
         module_filter,
         scope,
         branch_override,
+        divergent,
+        upstream,
         dry_run,
         confirmed,
         json,
@@ -459,8 +752,8 @@ pub(crate) fn evolve_propose_in(
     let roots = render.roots;
 
     let mut summary = EvolveProposeSummary::default();
-    let mut candidates: Vec<(String, TargetPath, Vec<u8>)> = Vec::new();
-    let mut instructions_sections: std::collections::BTreeMap<String, Vec<u8>> =
+    let mut candidates: Vec<ProposeCandidate> = Vec::new();
+    let mut proposed_sections: std::collections::BTreeMap<String, Vec<Vec<u8>>> =
         std::collections::BTreeMap::new();
     let mut skipped: Vec<EvolveProposeSkippedItem> = Vec::new();
     let mut warnings: Vec<String> = Vec::new();
@@ -494,6 +787,73 @@ pub(crate) fn evolve_propose_in(
             continue;
         }
 
+        if let Some(actual) = &actual {
+            let marked = if desired_file.module_ids.len() > 1 {
+                try_propose_marked_instructions_sections(
+                    &desired_file.bytes,
+                    actual,
+                    &desired_file.module_ids,
+                )?
+            } else {
+                None
+            };
+            let sections = match marked {
+                Some(sections) => Some(sections),
+                None => match try_propose_output_spans(desired_file, actual) {
+                    SpanProposal::Sections(sections) => Some(sections),
+                    SpanProposal::NotApplicable => None,
+                    // Unmappable aggregated outputs are reported as `multi_module_output` below.
+                    SpanProposal::SynthesizedEdited if desired_file.module_ids.len() > 1 => None,
+                    SpanProposal::SynthesizedEdited => {
+                        summary.drifted_skipped += 1;
+                        summary.skipped_synthesized += 1;
+                        let reason = "synthesized_text_edited".to_string();
+                        skipped.push(EvolveProposeSkippedItem {
+                            target: tp.target.clone(),
+                            path: tp.path.to_string_lossy().to_string(),
+                            path_posix: crate::paths::path_to_posix_string(&tp.path),
+                            reason_code: reason.clone(),
+                            reason_message: evolve_propose_reason_message(&reason),
+                            next_actions: Vec::new(),
+                            reason,
+                            module_id: desired_file.module_ids.first().cloned(),
+                            module_ids: Vec::new(),
+                            suggestions: evolve_propose_suggestions("synthesized_text_edited"),
+                            variants: Vec::new(),
+                            diff: None,
+                        });
+                        continue;
+                    }
+                },
+            };
+            if let Some(section_candidates) = sections {
+                for (module_id, bytes, base) in section_candidates {
+                    // Identical edits of a module's content across outputs are proposed once;
+                    // differing ones are reconciled below.
+                    if proposed_sections
+                        .get(&module_id)
+                        .is_some_and(|prev| prev.contains(&bytes))
+                    {
+                        continue;
+                    }
+
+                    proposed_sections
+                        .entry(module_id.clone())
+                        .or_default()
+                        .push(bytes.clone());
+                    summary.drifted_proposeable += 1;
+                    candidates.push(ProposeCandidate {
+                        module_id,
+                        output: tp.clone(),
+                        bytes,
+                        base,
+                        resolution: None,
+                    });
+                }
+                continue;
+            }
+        }
+
         if desired_file.module_ids.len() != 1 {
             match &actual {
                 None => {
@@ -511,35 +871,11 @@ pub(crate) fn evolve_propose_in(
                         module_id: None,
                         module_ids: desired_file.module_ids.clone(),
                         suggestions: evolve_propose_suggestions("missing"),
+                        variants: Vec::new(),
+                        diff: None,
                     });
                 }
-                Some(actual) => {
-                    if let Some(section_candidates) = try_propose_marked_instructions_sections(
-                        &desired_file.bytes,
-                        actual,
-                        &desired_file.module_ids,
-                    )? {
-                        for (module_id, bytes) in section_candidates {
-                            if let Some(prev) = instructions_sections.get(&module_id) {
-                                if prev != &bytes {
-                                    warnings.push(format!(
-                                        "evolve.propose: skipped {} {} section for {}: conflicting edits across aggregated outputs",
-                                        tp.target,
-                                        tp.path.display(),
-                                        module_id
-                                    ));
-                                    continue;
-                                }
-                                continue;
-                            }
-
-                            instructions_sections.insert(module_id.clone(), bytes.clone());
-                            summary.drifted_proposeable += 1;
-                            candidates.push((module_id, tp.clone(), bytes));
-                        }
-                        continue;
-                    }
-
+                Some(_) => {
                     summary.drifted_skipped += 1;
                     summary.skipped_multi_module += 1;
                     let reason = "multi_module_output".to_string();
@@ -554,6 +890,8 @@ pub(crate) fn evolve_propose_in(
                         module_id: None,
                         module_ids: desired_file.module_ids.clone(),
                         suggestions: evolve_propose_suggestions("multi_module_output"),
+                        variants: Vec::new(),
+                        diff: None,
                     });
                 }
             }
@@ -564,7 +902,13 @@ pub(crate) fn evolve_propose_in(
         match actual {
             Some(actual) => {
                 summary.drifted_proposeable += 1;
-                candidates.push((module_id, tp.clone(), actual));
+                candidates.push(ProposeCandidate {
+                    module_id,
+                    output: tp.clone(),
+                    bytes: actual,
+                    base: desired_file.bytes.clone(),
+                    resolution: None,
+                });
             }
             None => {
                 summary.drifted_skipped += 1;
@@ -584,18 +928,45 @@ pub(crate) fn evolve_propose_in(
                     module_id: Some(module_id),
                     module_ids: Vec::new(),
                     suggestions: evolve_propose_suggestions("missing"),
+                    variants: Vec::new(),
+                    diff: None,
                 });
             }
         }
     }
 
+    let candidates = reconcile_divergent_candidates(
+        engine,
+        &roots,
+        candidates,
+        DivergentContext {
+            resolution: divergent,
+            action_prefix,
+            summary: &mut summary,
+            skipped: &mut skipped,
+            warnings: &mut warnings,
+        },
+    );
+    let candidates = if upstream {
+        retain_git_sourced_candidates(
+            engine,
+            candidates,
+            action_prefix,
+            &mut summary,
+            &mut skipped,
+        )
+    } else {
+        candidates
+    };
+
     let mut items: Vec<EvolveProposeItem> = candidates
         .iter()
-        .map(|(module_id, tp, _)| EvolveProposeItem {
-            module_id: module_id.clone(),
-            target: tp.target.clone(),
-            path: tp.path.to_string_lossy().to_string(),
-            path_posix: crate::paths::path_to_posix_string(&tp.path),
+        .map(|c| EvolveProposeItem {
+            module_id: c.module_id.clone(),
+            target: c.output.target.clone(),
+            path: c.output.path.to_string_lossy().to_string(),
+            path_posix: crate::paths::path_to_posix_string(&c.output.path),
+            resolution: c.resolution.map(str::to_string),
         })
         .collect();
     items.sort_by(|a, b| {
@@ -641,6 +1012,17 @@ pub(crate) fn evolve_propose_in(
         return Ok(EvolveProposeOutcome::NeedsConfirmation);
     }
 
+    if upstream {
+        return propose_upstream(
+            engine,
+            &roots,
+            candidates,
+            branch_override,
+            skipped,
+            warnings,
+        );
+    }
+
     let repo_dir = engine.repo.repo_dir.as_path();
     if !repo_dir.join(".git").exists() {
         return Err(UserError::git_repo_required("evolve propose", repo_dir));
@@ -657,6 +1039,7 @@ pub(crate) fn evolve_propose_in(
     let branch = branch_override.map(|s| s.to_string()).unwrap_or_else(|| {
         let scope_str = match scope {
             EvolveScope::Global => "global",
+            EvolveScope::Profile => "profile",
             EvolveScope::Machine => "machine",
             EvolveScope::Project => "project",
         };
@@ -673,29 +1056,29 @@ pub(crate) fn evolve_propose_in(
                 "multi".to_string()
             }
         };
-        let now = time::OffsetDateTime::now_utc();
-        let timestamp = now
-            .format(format_description!(
-                "[year][month][day]T[hour][minute][second]Z"
-            ))
-            .unwrap_or_else(|_| "unknown".to_string());
-        let nanos = now.nanosecond();
-        format!("evolve/propose-{scope_str}-{module}-{timestamp}-{nanos:09}")
+        format!(
+            "evolve/propose-{scope_str}-{module}-{}",
+            proposal_timestamp()
+        )
     });
 
     crate::git::git_in(repo_dir, &["checkout", "-b", branch.as_str()])?;
 
     let mut touched = Vec::new();
-    for (module_id, output, actual) in &candidates {
+    for candidate in &candidates {
+        let module_id = &candidate.module_id;
         let Some(module) = engine.manifest.modules.iter().find(|m| m.id == *module_id) else {
             continue;
         };
-        let Some(module_rel) = module_rel_path_for_output(module, module_id, output, &roots) else {
+        let Some(module_rel) =
+            module_rel_path_for_output(module, module_id, &candidate.output, &roots)
+        else {
             continue;
         };
 
         let overlay_dir = match scope {
             EvolveScope::Global => overlay_dir_for_scope(engine, module_id, OverlayScope::Global),
+            EvolveScope::Profile => engine.profile_overlay_dir(profile, module_id)?,
             EvolveScope::Machine => overlay_dir_for_scope(engine, module_id, OverlayScope::Machine),
             EvolveScope::Project => overlay_dir_for_scope(engine, module_id, OverlayScope::Project),
         };
@@ -705,14 +1088,16 @@ pub(crate) fn evolve_propose_in(
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("create {}", parent.display()))?;
         }
-        crate::fs::write_atomic(&dst, actual)
+        crate::fs::write_atomic(&dst, &candidate.bytes)
             .with_context(|| format!("write {}", dst.display()))?;
-        touched.push(
-            dst.strip_prefix(&engine.repo.repo_dir)
-                .unwrap_or(&dst)
-                .to_string_lossy()
-                .to_string(),
-        );
+        let rel = dst
+            .strip_prefix(&engine.repo.repo_dir)
+            .unwrap_or(&dst)
+            .to_string_lossy()
+            .to_string();
+        if !touched.contains(&rel) {
+            touched.push(rel);
+        }
     }
 
     if touched.is_empty() {
@@ -753,3 +1138,312 @@ pub(crate) fn evolve_propose_in(
         commit_warning,
     }))
 }
+
+fn proposal_timestamp() -> String {
+    let now = time::OffsetDateTime::now_utc();
+    let timestamp = now
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("{timestamp}-{:09}", now.nanosecond())
+}
+
+/// Drops candidates whose module is not sourced from git, reporting them as `not_git_source`.
+fn retain_git_sourced_candidates(
+    engine: &Engine,
+    candidates: Vec<ProposeCandidate>,
+    action_prefix: &str,
+    summary: &mut EvolveProposeSummary,
+    skipped: &mut Vec<EvolveProposeSkippedItem>,
+) -> Vec<ProposeCandidate> {
+    let mut out = Vec::new();
+    for candidate in candidates {
+        let is_git = engine
+            .manifest
+            .modules
+            .iter()
+            .find(|m| m.id == candidate.module_id)
+            .is_some_and(|m| m.source.kind() == SourceKind::Git);
+        if is_git {
+            out.push(candidate);
+            continue;
+        }
+
+        summary.drifted_proposeable -= 1;
+        summary.drifted_skipped += 1;
+        let reason = "not_git_source".to_string();
+        skipped.push(EvolveProposeSkippedItem {
+            target: candidate.output.target.clone(),
+            path: candidate.output.path.to_string_lossy().to_string(),
+            path_posix: crate::paths::path_to_posix_string(&candidate.output.path),
+            reason_code: reason.clone(),
+            reason_message: evolve_propose_reason_message(&reason),
+            next_actions: vec![format!(
+                "{action_prefix} evolve propose --module-id {} --yes --json",
+                candidate.module_id
+            )],
+            reason,
+            module_id: Some(candidate.module_id),
+            module_ids: Vec::new(),
+            suggestions: Vec::new(),
+            variants: Vec::new(),
+            diff: None,
+        });
+    }
+    out
+}
+
+/// The module's source pinned to the lockfile commit (or the manifest ref when unlocked).
+fn locked_git_source(
+    engine: &Engine,
+    module: &Module,
+) -> anyhow::Result<crate::lockfile::ResolvedGitSource> {
+    if let Ok(lock) = crate::lockfile::Lockfile::load(&engine.repo.lockfile_path) {
+        if let Some(git) = lock
+            .modules
+            .iter()
+            .find(|m| m.id == module.id)
+            .and_then(|m| m.resolved_source.git.clone())
+        {
+            return Ok(git);
+        }
+    }
+    let src = module.source.git.as_ref().context("missing git source")?;
+    let commit = crate::store::Store::new(&engine.home).resolve_git_commit(src)?;
+    Ok(crate::lockfile::ResolvedGitSource {
+        url: src.url.clone(),
+        commit,
+        subdir: src.subdir.clone(),
+    })
+}
+
+const UPSTREAM_FALLBACK_NAME: &str = "agentpack";
+const UPSTREAM_FALLBACK_EMAIL: &str = "agentpack@localhost";
+
+/// For each git-sourced module: clones the locked store checkout (with `origin` set to the
+/// module's URL), applies the captured edits (three-way, deployed content as base) on a new
+/// branch, commits, and writes a `git format-patch` series next to the clone. Nothing is pushed.
+fn propose_upstream(
+    engine: &Engine,
+    roots: &[crate::targets::TargetRoot],
+    candidates: Vec<ProposeCandidate>,
+    branch_override: Option<&str>,
+    mut skipped: Vec<EvolveProposeSkippedItem>,
+    mut warnings: Vec<String>,
+) -> anyhow::Result<EvolveProposeOutcome> {
+    let mut by_module: std::collections::BTreeMap<String, Vec<ProposeCandidate>> =
+        std::collections::BTreeMap::new();
+    for candidate in candidates {
+        by_module
+            .entry(candidate.module_id.clone())
+            .or_default()
+            .push(candidate);
+    }
+
+    let store = crate::store::Store::new(&engine.home);
+    let mut proposals = Vec::new();
+    for (module_id, candidates) in by_module {
+        let Some(module) = engine.manifest.modules.iter().find(|m| m.id == module_id) else {
+            continue;
+        };
+        let locked = locked_git_source(engine, module)?;
+        let checkout = store.ensure_git_checkout(
+            &module_id,
+            &GitSource {
+                url: locked.url.clone(),
+                ref_name: locked.commit.clone(),
+                subdir: locked.subdir.clone(),
+                shallow: false,
+            },
+            &locked.commit,
+        )?;
+
+        let work_dir = engine.home.state_dir.join("evolve/upstream").join(format!(
+            "{}-{}",
+            crate::ids::module_fs_key(&module_id),
+            proposal_timestamp()
+        ));
+        let clone_dir = work_dir.join("repo");
+        std::fs::create_dir_all(&work_dir)
+            .with_context(|| format!("create {}", work_dir.display()))?;
+        crate::git::git_in(
+            &work_dir,
+            &[
+                "clone",
+                "--quiet",
+                "--no-checkout",
+                &checkout.to_string_lossy(),
+                &clone_dir.to_string_lossy(),
+            ],
+        )?;
+        // The clone's origin would be the agentpack store; point it at the module's repo so the
+        // proposal branch can be pushed as is.
+        crate::git::git_in(&clone_dir, &["remote", "set-url", "origin", &locked.url])?;
+        let branch = branch_override.map(str::to_string).unwrap_or_else(|| {
+            format!(
+                "evolve/upstream-{}-{}",
+                crate::store::sanitize_module_id(&module_id),
+                proposal_timestamp()
+            )
+        });
+        crate::git::git_in(
+            &clone_dir,
+            &["checkout", "--quiet", "-b", &branch, &locked.commit],
+        )?;
+
+        let module_root = crate::store::Store::module_root_in_checkout(&clone_dir, &locked.subdir);
+        let mut files: Vec<String> = Vec::new();
+        for candidate in &candidates {
+            let Some(module_rel) =
+                module_rel_path_for_output(module, &module_id, &candidate.output, roots)
+            else {
+                continue;
+            };
+            let dst = module_root.join(&module_rel);
+            let ours = std::fs::read(&dst).unwrap_or_default();
+            let outcome =
+                crate::merge::merge_three_way(&candidate.base, &ours, &candidate.bytes, None);
+            if outcome.conflicted {
+                warnings.push(format!(
+                    "evolve.propose: local edit of {module_id} {module_rel} does not apply to {} at {}",
+                    locked.url, locked.commit
+                ));
+                let reason = "upstream_conflict".to_string();
+                skipped.push(EvolveProposeSkippedItem {
+                    target: candidate.output.target.clone(),
+                    path: candidate.output.path.to_string_lossy().to_string(),
+                    path_posix: crate::paths::path_to_posix_string(&candidate.output.path),
+                    reason_code: reason.clone(),
+                    reason_message: evolve_propose_reason_message(&reason),
+                    next_actions: Vec::new(),
+                    reason,
+                    module_id: Some(module_id.clone()),
+                    module_ids: Vec::new(),
+                    suggestions: Vec::new(),
+                    variants: Vec::new(),
+                    diff: None,
+                });
+                continue;
+            }
+            if let Some(parent) = dst.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("create {}", parent.display()))?;
+            }
+            crate::fs::write_atomic(&dst, &outcome.merged)
+                .with_context(|| format!("write {}", dst.display()))?;
+            let rel = dst
+                .strip_prefix(&clone_dir)
+                .unwrap_or(&dst)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if !files.contains(&rel) {
+                files.push(rel);
+            }
+        }
+
+        crate::git::git_in(&clone_dir, &["add", "-A"])?;
+        let status = crate::git::git_in(&clone_dir, &["status", "--porcelain"])?;
+        if status.trim().is_empty() {
+            warnings.push(format!(
+                "evolve.propose: no upstream changes for {module_id} (edits already match {})",
+                locked.commit
+            ));
+            continue;
+        }
+        // The clone is scratch space: without a git identity, commit under a placeholder one
+        // rather than failing (the user's identity is used whenever git can determine it).
+        let message = format!("evolve: update {module_id}");
+        let mut args = Vec::new();
+        let has_identity = ["GIT_AUTHOR_IDENT", "GIT_COMMITTER_IDENT"]
+            .iter()
+            .all(|var| crate::git::git_in(&clone_dir, &["var", var]).is_ok());
+        if !has_identity {
+            warnings.push(format!(
+                "evolve.propose: no git identity configured; committed {module_id} as {UPSTREAM_FALLBACK_NAME} <{UPSTREAM_FALLBACK_EMAIL}>"
+            ));
+            args.extend([
+                "-c".to_string(),
+                format!("user.name={UPSTREAM_FALLBACK_NAME}"),
+                "-c".to_string(),
+                format!("user.email={UPSTREAM_FALLBACK_EMAIL}"),
+            ]);
+        }
+        args.extend(["commit", "--quiet", "-m", message.as_str()].map(str::to_string));
+        let commit = std::process::Command::new("git")
+            .current_dir(&clone_dir)
+            .args(&args)
+            .output();
+        let commit_warning = match commit {
+            Ok(out) if out.status.success() => None,
+            Ok(out) => Some(format!(
+                "git commit failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            )),
+            Err(err) => Some(format!("failed to run git commit: {err}")),
+        };
+        if let Some(warning) = commit_warning {
+            warnings.push(format!(
+                "evolve.propose: {warning}; the edits of {module_id} are staged in {}",
+                clone_dir.display()
+            ));
+            continue;
+        }
+        let commit = crate::git::git_in(&clone_dir, &["rev-parse", "HEAD"])?
+            .trim()
+            .to_string();
+
+        let patches_dir = work_dir.join("patches");
+        let listed = crate::git::git_in(
+            &clone_dir,
+            &[
+                "format-patch",
+                "-o",
+                &patches_dir.to_string_lossy(),
+                &format!("{}..HEAD", locked.commit),
+            ],
+        )?;
+        let patches: Vec<String> = listed
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+
+        proposals.push(EvolveProposeUpstreamItem {
+            module_id: module_id.clone(),
+            url: locked.url.clone(),
+            base_commit: locked.commit.clone(),
+            subdir: locked.subdir.clone(),
+            branch,
+            commit,
+            clone_dir: clone_dir.to_string_lossy().to_string(),
+            clone_dir_posix: crate::paths::path_to_posix_string(&clone_dir),
+            files,
+            patches_posix: patches.iter().map(|p| p.replace('\\', "/")).collect(),
+            patches,
+        });
+    }
+
+    if proposals.is_empty() {
+        return Err(anyhow::Error::new(
+            UserError::new(
+                "E_EVOLVE_NO_UPSTREAM_PROPOSALS",
+                "no upstream proposals (local edits conflict with or already match upstream)",
+            )
+            .with_details(serde_json::json!({
+                "skipped": skipped,
+                "warnings": warnings,
+                "reason_code": "no_upstream_proposals",
+                "next_actions": ["review_skipped", "propose_overlay", "retry_command"],
+            })),
+        ));
+    }
+
+    Ok(EvolveProposeOutcome::Upstream(
+        EvolveProposeUpstreamReport {
+            proposals,
+            skipped,
+            warnings,
+        },
+    ))
+}
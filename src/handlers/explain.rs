@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+use crate::deploy::TargetPath;
+use crate::engine::Engine;
+use crate::overlay::{
+    LayerSource, OverlayLayer, blame_module_file, carry_line_origins, resolve_upstream_module_root,
+};
+use crate::user_error::UserError;
+
+/// Where a line of a deployed file came from.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct LineOrigin {
+    /// `upstream`, an overlay scope (`global`, `profile`, `group`, `machine`, `project`), `render`
+    /// (added by the target adapter: template substitution, aggregation markers, frontmatter) or
+    /// `local` (edited on disk since deploy).
+    pub layer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_id: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub patch: bool,
+}
+
+impl LineOrigin {
+    fn unattributed(layer: &str) -> Self {
+        Self {
+            layer: layer.to_string(),
+            module_id: None,
+            patch: false,
+        }
+    }
+
+    /// `layer`, `layer+patch`, or `render`/`local`.
+    pub(crate) fn label(&self) -> String {
+        if self.patch {
+            format!("{}+patch", self.layer)
+        } else {
+            self.layer.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct ExplainFileModule {
+    pub module_id: String,
+    pub module_type: Option<String>,
+    /// Module-relative path of the source file (`None` when it cannot be derived).
+    pub module_path: Option<String>,
+}
+
+/// Consecutive lines with the same origin (1-based `start`).
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct ExplainFileHunk {
+    pub start: usize,
+    pub lines: usize,
+    pub origin: LineOrigin,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub(crate) struct ExplainFileReport {
+    pub target: String,
+    pub path: PathBuf,
+    pub modules: Vec<ExplainFileModule>,
+    /// The file exists on disk (annotations describe the on-disk content, else the desired one).
+    pub deployed: bool,
+    /// The on-disk content differs from the desired content.
+    pub modified: bool,
+    pub lines: Vec<(String, LineOrigin)>,
+    pub warnings: Vec<String>,
+}
+
+impl ExplainFileReport {
+    pub(crate) fn hunks(&self) -> Vec<ExplainFileHunk> {
+        let mut out: Vec<ExplainFileHunk> = Vec::new();
+        for (idx, (text, origin)) in self.lines.iter().enumerate() {
+            match out.last_mut() {
+                Some(hunk) if hunk.origin == *origin => {
+                    hunk.lines += 1;
+                    hunk.text.push_str(text);
+                    hunk.text.push('\n');
+                }
+                _ => out.push(ExplainFileHunk {
+                    start: idx + 1,
+                    lines: 1,
+                    origin: origin.clone(),
+                    text: format!("{text}\n"),
+                }),
+            }
+        }
+        out
+    }
+}
+
+/// Maps a deployed path back to its modules (target manifest `module_ids`, else the desired
+/// state) and annotates every line with the layer that produced it.
+pub(crate) fn explain_file_report(
+    engine: &Engine,
+    profile: &str,
+    target_filter: &str,
+    path: &Path,
+) -> anyhow::Result<ExplainFileReport> {
+    let render = engine.desired_state(profile, target_filter)?;
+    let mut warnings = render.warnings;
+
+    let abs = std::path::absolute(engine.project.cwd.join(path))?;
+    let Some((tp, desired)) = find_desired(&render.desired, &abs) else {
+        return Err(anyhow::Error::new(
+            UserError::new(
+                "E_PATH_NOT_MANAGED",
+                format!(
+                    "{} is not produced by any module for profile {profile} (target {target_filter})",
+                    abs.display()
+                ),
+            )
+            .with_details(serde_json::json!({
+                "path": abs,
+                "path_posix": crate::paths::path_to_posix_string(&abs),
+                "profile": profile,
+                "target": target_filter,
+                "reason_code": "path_not_managed",
+                "next_actions": ["rerun_plan", "retry_command"],
+            })),
+        ));
+    };
+
+    let manifest_index = crate::cli::util::load_manifest_module_ids(&render.roots)?;
+    warnings.extend(manifest_index.warnings);
+    let module_ids = manifest_index
+        .index
+        .get(tp)
+        .cloned()
+        .unwrap_or_else(|| desired.module_ids.clone());
+
+    let desired_text = String::from_utf8_lossy(&desired.bytes).into_owned();
+    let mut lines: Vec<(String, Option<LineOrigin>)> = desired_text
+        .lines()
+        .map(|line| (line.to_string(), None))
+        .collect();
+
+    let mut modules = Vec::new();
+    for module_id in module_ids {
+        let module = engine.manifest.modules.iter().find(|m| m.id == module_id);
+        let module_path = module.and_then(|m| {
+            crate::cli::util::module_rel_path_for_output(m, &module_id, tp, &render.roots)
+        });
+        modules.push(ExplainFileModule {
+            module_id: module_id.clone(),
+            module_type: module.map(|m| format!("{:?}", m.module_type)),
+            module_path: module_path.clone(),
+        });
+        let (Some(module), Some(rel)) = (module, module_path) else {
+            continue;
+        };
+
+        let upstream = resolve_upstream_module_root(&engine.home, &engine.repo, module)?;
+        let layers = engine.overlay_dirs(module, profile)?;
+        let overlays: Vec<_> = layers
+            .iter()
+            .map(|(scope, dir)| OverlayLayer { scope, dir })
+            .collect();
+        match blame_module_file(&module.id, &upstream, &overlays, &rel)? {
+            Some(blamed) => attribute_module_lines(&mut lines, &module.id, &blamed),
+            None => warnings.push(format!(
+                "explain file: module {module_id} has no file {rel} after composing its overlays"
+            )),
+        }
+    }
+
+    let desired_lines: Vec<(String, LineOrigin)> = lines
+        .into_iter()
+        .map(|(text, origin)| (text, origin.unwrap_or(LineOrigin::unattributed("render"))))
+        .collect();
+
+    let on_disk = std::fs::read(&tp.path).ok();
+    let deployed = on_disk.is_some();
+    let modified = on_disk
+        .as_ref()
+        .is_some_and(|bytes| bytes.as_slice() != desired.deployed_bytes());
+    let lines = match on_disk {
+        Some(bytes) if modified => carry_line_origins(
+            &desired_lines,
+            &String::from_utf8_lossy(&bytes),
+            &LineOrigin::unattributed("local"),
+        ),
+        _ => desired_lines,
+    };
+
+    Ok(ExplainFileReport {
+        target: tp.target.clone(),
+        path: tp.path.clone(),
+        modules,
+        deployed,
+        modified,
+        lines,
+        warnings,
+    })
+}
+
+pub(crate) fn explain_file_json_data(
+    profile: &str,
+    report: &ExplainFileReport,
+) -> serde_json::Value {
+    let mut summary: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, origin) in &report.lines {
+        *summary.entry(origin.label()).or_default() += 1;
+    }
+    serde_json::json!({
+        "profile": profile,
+        "target": report.target,
+        "path": report.path,
+        "path_posix": crate::paths::path_to_posix_string(&report.path),
+        "modules": report.modules,
+        "deployed": report.deployed,
+        "modified": report.modified,
+        "hunks": report.hunks(),
+        "summary": summary,
+    })
+}
+
+fn find_desired<'a>(
+    desired: &'a crate::deploy::DesiredState,
+    abs: &Path,
+) -> Option<(&'a TargetPath, &'a crate::deploy::DesiredFile)> {
+    if let Some(found) = desired.iter().find(|(tp, _)| tp.path == abs) {
+        return Some(found);
+    }
+    // Fall back to comparing resolved paths (symlinked workspaces, `..` segments).
+    let canonical = std::fs::canonicalize(abs).ok()?;
+    desired.iter().find(|(tp, _)| {
+        tp.path.file_name() == abs.file_name()
+            && std::fs::canonicalize(&tp.path).is_ok_and(|p| p == canonical)
+    })
+}
+
+/// Claims the output lines that match the module's composed file and are not yet attributed.
+fn attribute_module_lines(
+    lines: &mut [(String, Option<LineOrigin>)],
+    module_id: &str,
+    blamed: &[(String, LayerSource)],
+) {
+    let module_lines: Vec<&str> = blamed.iter().map(|(line, _)| line.as_str()).collect();
+    let output_lines: Vec<&str> = lines.iter().map(|(line, _)| line.as_str()).collect();
+    let ops = capture_diff_slices(Algorithm::Myers, &module_lines, &output_lines);
+    for op in ops {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for i in 0..len {
+                let slot = &mut lines[new_index + i].1;
+                if slot.is_none() {
+                    let source = &blamed[old_index + i].1;
+                    *slot = Some(LineOrigin {
+                        layer: source.layer.clone(),
+                        module_id: Some(module_id.to_string()),
+                        patch: source.patch,
+                    });
+                }
+            }
+        }
+    }
+}
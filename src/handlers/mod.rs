@@ -1,6 +1,9 @@
 pub(crate) mod deploy;
 pub(crate) mod doctor;
 pub(crate) mod evolve;
+pub(crate) mod explain;
+pub(crate) mod overlay;
 pub(crate) mod read_only;
 pub(crate) mod rollback;
+pub(crate) mod snapshot;
 pub(crate) mod status;
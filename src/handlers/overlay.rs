@@ -0,0 +1,85 @@
+use crate::engine::Engine;
+use crate::overlay::{OverlayInfo, OverlayStatus, inspect_overlay, resolve_upstream_module_root};
+
+#[derive(Debug, Default, serde::Serialize)]
+pub(crate) struct OverlayListSummary {
+    pub total: u64,
+    pub clean: u64,
+    pub drifted: u64,
+    pub no_baseline: u64,
+    pub upstream_unavailable: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct OverlayListReport {
+    pub overlays: Vec<OverlayInfo>,
+    pub summary: OverlayListSummary,
+    pub warnings: Vec<String>,
+}
+
+/// Inventory of existing overlays for every manifest module, across every scope in the config
+/// repo (all profiles, groups, machines and projects, not just the current ones), in precedence
+/// order. An overlay that cannot be inspected is reported as a warning.
+pub(crate) fn overlay_list_report(engine: &Engine) -> anyhow::Result<OverlayListReport> {
+    let mut report = OverlayListReport::default();
+
+    for module in &engine.manifest.modules {
+        let dirs = engine.existing_overlay_dirs(module)?;
+        if dirs.is_empty() {
+            continue;
+        }
+
+        let upstream = match resolve_upstream_module_root(&engine.home, &engine.repo, module) {
+            Ok(root) if root.is_dir() => Some(root),
+            Ok(root) => {
+                report.warnings.push(format!(
+                    "overlay list: upstream for module {} not found at {}",
+                    module.id,
+                    root.display()
+                ));
+                None
+            }
+            Err(err) => {
+                report.warnings.push(format!(
+                    "overlay list: failed to resolve upstream for module {}: {err:#}",
+                    module.id
+                ));
+                None
+            }
+        };
+
+        for (scope, scope_id, dir) in dirs {
+            let mut info = match inspect_overlay(&module.id, scope, upstream.as_deref(), &dir) {
+                Ok(Some(info)) => info,
+                Ok(None) => continue,
+                Err(err) => {
+                    report.warnings.push(format!(
+                        "overlay list: failed to inspect {scope} overlay {} for module {}: {err:#}",
+                        dir.display(),
+                        module.id
+                    ));
+                    continue;
+                }
+            };
+            info.scope_id = scope_id;
+            let summary = &mut report.summary;
+            summary.total += 1;
+            match info.status {
+                OverlayStatus::Clean => summary.clean += 1,
+                OverlayStatus::Drifted => summary.drifted += 1,
+                OverlayStatus::NoBaseline => summary.no_baseline += 1,
+                OverlayStatus::UpstreamUnavailable => summary.upstream_unavailable += 1,
+            }
+            report.overlays.push(info);
+        }
+    }
+
+    Ok(report)
+}
+
+pub(crate) fn overlay_list_json_data(report: &OverlayListReport) -> serde_json::Value {
+    serde_json::json!({
+        "overlays": report.overlays,
+        "summary": report.summary,
+    })
+}
@@ -1,11 +1,15 @@
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 
 use crate::deploy::ManagedPaths;
 use crate::deploy::load_managed_paths_from_snapshot;
 use crate::deploy::plan as compute_plan;
+use crate::deploy::{Op, PlanSummary};
 use crate::engine::Engine;
+use crate::paths::AgentpackHome;
+use crate::plan_filter::{PlanFilter, apply_plan_filter};
 use crate::state::latest_snapshot;
-use crate::targets::TargetRoot;
+use crate::targets::{TargetRoot, best_root_for};
 
 #[derive(Debug)]
 pub(crate) struct ReadOnlyContext {
@@ -16,6 +20,53 @@ pub(crate) struct ReadOnlyContext {
     pub(crate) roots: Vec<TargetRoot>,
 }
 
+impl ReadOnlyContext {
+    /// Narrows the plan to `--module`/`--path` (see `plan_filter`).
+    pub(crate) fn filtered(
+        mut self,
+        home: &AgentpackHome,
+        filter: &PlanFilter,
+    ) -> anyhow::Result<Self> {
+        apply_plan_filter(
+            home,
+            &self.roots,
+            filter,
+            &mut self.plan,
+            &mut self.desired,
+            &mut self.warnings,
+        )?;
+        Ok(self)
+    }
+
+    /// Drops the roots in `claimed` (and every output that belongs to them).
+    ///
+    /// Used by `deploy --all-projects`: user-scope roots (e.g. `~/.codex`) are shared by every
+    /// project, so only the first project that plans them owns their changes.
+    pub(crate) fn without_roots(mut self, claimed: &BTreeSet<(String, PathBuf)>) -> Self {
+        let is_claimed = |roots: &[TargetRoot], target: &str, path: &Path| {
+            best_root_for(roots, target, path)
+                .is_some_and(|r| claimed.contains(&(r.target.clone(), r.root.clone())))
+        };
+        let roots = self.roots.clone();
+        self.desired
+            .retain(|tp, _| !is_claimed(&roots, &tp.target, &tp.path));
+        self.plan
+            .changes
+            .retain(|c| !is_claimed(&roots, &c.target, Path::new(&c.path)));
+        self.plan.summary = PlanSummary::default();
+        for change in &self.plan.changes {
+            match change.op {
+                Op::Create => self.plan.summary.create += 1,
+                Op::Update => self.plan.summary.update += 1,
+                Op::Delete => self.plan.summary.delete += 1,
+            }
+        }
+        self.roots
+            .retain(|r| !claimed.contains(&(r.target.clone(), r.root.clone())));
+        self
+    }
+}
+
 pub(crate) fn read_only_context(
     repo_override: Option<&Path>,
     machine_override: Option<&str>,
@@ -30,15 +81,49 @@ pub(crate) fn read_only_context_in(
     engine: &Engine,
     profile: &str,
     target_filter: &str,
+) -> anyhow::Result<ReadOnlyContext> {
+    context_in(engine, profile, target_filter, true)
+}
+
+/// Like `read_only_context_in`, but never falls back to the latest snapshot for managed paths.
+///
+/// Used when planning registered projects other than the cwd: the latest snapshot may belong
+/// to a different project, so only target manifests are trusted.
+pub(crate) fn registered_project_context_in(
+    engine: &Engine,
+    profile: &str,
+    target_filter: &str,
+) -> anyhow::Result<ReadOnlyContext> {
+    context_in(engine, profile, target_filter, false)
+}
+
+fn context_in(
+    engine: &Engine,
+    profile: &str,
+    target_filter: &str,
+    snapshot_fallback: bool,
 ) -> anyhow::Result<ReadOnlyContext> {
     let targets = crate::target_selection::selected_targets(&engine.manifest, target_filter)?;
     let render = engine.desired_state(profile, target_filter)?;
-    let desired = render.desired;
+    let mut desired = render.desired;
     let mut warnings = render.warnings;
     let roots = render.roots;
 
-    let managed_paths = managed_paths_for_plan(engine, &roots, target_filter, &mut warnings)?;
-    let plan = compute_plan(&desired, managed_paths.as_ref())?;
+    let managed_paths = managed_paths_for_plan(
+        engine,
+        &roots,
+        target_filter,
+        snapshot_fallback,
+        &mut warnings,
+    )?;
+    let mut plan = compute_plan(&desired, managed_paths.as_ref())?;
+    crate::local_edits::merge_local_edits(
+        &engine.home,
+        &roots,
+        &mut plan,
+        &mut desired,
+        &mut warnings,
+    )?;
 
     Ok(ReadOnlyContext {
         targets,
@@ -53,6 +138,7 @@ fn managed_paths_for_plan(
     engine: &Engine,
     roots: &[TargetRoot],
     target_filter: &str,
+    snapshot_fallback: bool,
     warnings: &mut Vec<String>,
 ) -> anyhow::Result<Option<ManagedPaths>> {
     let managed_paths_from_manifest =
@@ -67,6 +153,10 @@ fn managed_paths_for_plan(
         )));
     }
 
+    if !snapshot_fallback {
+        return Ok(None);
+    }
+
     let latest = latest_snapshot(&engine.home, &["deploy", "rollback"])?;
     Ok(latest
         .as_ref()
@@ -1,17 +1,90 @@
 use anyhow::Context as _;
 
-use crate::paths::AgentpackHome;
+use crate::config::Manifest;
+use crate::hooks::{HookStage, hook_payload, run_hooks};
+use crate::paths::{AgentpackHome, RepoPaths};
+use crate::rollback_plan::{RollbackPlan, RollbackSelection};
+use crate::state::DeploymentSnapshot;
 use crate::user_error::UserError;
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn rollback(
     home: &AgentpackHome,
+    repo: &RepoPaths,
+    machine_override: Option<&str>,
     snapshot_id: &str,
+    selection: &RollbackSelection,
+    discard_local_edits: bool,
     json: bool,
     yes: bool,
-) -> anyhow::Result<crate::state::DeploymentSnapshot> {
+) -> anyhow::Result<(RollbackPlan, DeploymentSnapshot)> {
     if json && !yes {
         return Err(UserError::confirm_required("rollback"));
     }
 
-    crate::apply::rollback(home, snapshot_id).context("rollback")
+    let (mut plan, mut event) =
+        crate::apply::rollback(home, snapshot_id, selection, discard_local_edits)
+            .context("rollback")?;
+    run_post_rollback_hooks(home, repo, machine_override, &mut plan, &mut event)?;
+    Ok((plan, event))
+}
+
+pub(crate) fn rollback_dry_run(
+    home: &AgentpackHome,
+    snapshot_id: &str,
+    selection: &RollbackSelection,
+) -> anyhow::Result<RollbackPlan> {
+    crate::rollback_plan::plan_rollback(home, snapshot_id, selection).context("rollback")
+}
+
+/// Rollback is a recovery tool, so a config repo that cannot be loaded skips the hooks (with a
+/// warning) instead of failing.
+fn run_post_rollback_hooks(
+    home: &AgentpackHome,
+    repo: &RepoPaths,
+    machine_override: Option<&str>,
+    plan: &mut RollbackPlan,
+    event: &mut DeploymentSnapshot,
+) -> anyhow::Result<()> {
+    if !repo.manifest_path.exists() {
+        return Ok(());
+    }
+    let hooks = match Manifest::load(&repo.manifest_path) {
+        Ok(manifest) => manifest.hooks,
+        Err(err) => {
+            plan.warnings
+                .push(format!("post_rollback hooks skipped: {err:#}"));
+            return Ok(());
+        }
+    };
+    if hooks.post_rollback.is_empty() {
+        return Ok(());
+    }
+
+    let payload = hook_payload(
+        HookStage::PostRollback,
+        Some(&event.id),
+        &plan.targets,
+        serde_json::json!(plan.plan.changes),
+        serde_json::json!({
+            "rolled_back_to": plan.snapshot_id,
+            "partial": plan.partial,
+        }),
+    );
+    let results = run_hooks(&repo.repo_dir, &hooks, HookStage::PostRollback, &payload)?;
+    crate::hooks::record_in_snapshot(home, &event.id, &results)?;
+    // Same machine id as the deploy hooks record (`Engine::machine_id`), honouring `--machine`.
+    let machine_id = crate::machine::resolve_machine_id(machine_override)?;
+    crate::hooks::record_hook_events(
+        home,
+        &machine_id,
+        "rollback",
+        Some(&event.id),
+        &plan.targets,
+        &results,
+    )?;
+    plan.warnings
+        .extend(crate::hooks::failure_warnings(&results));
+    event.hooks = results;
+    Ok(())
 }
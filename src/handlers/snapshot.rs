@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::diff::unified_diff;
+use crate::hash::sha256_hex;
+use crate::paths::AgentpackHome;
+use crate::state::{DeploymentSnapshot, ManagedFile, list_snapshots};
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct SnapshotFileDiff {
+    pub(crate) target: String,
+    pub(crate) path: String,
+    pub(crate) path_posix: String,
+    /// `added` / `removed` / `modified`
+    pub(crate) status: &'static str,
+    pub(crate) from_sha256: Option<String>,
+    pub(crate) to_sha256: Option<String>,
+    /// Unified diff when the content of both sides was found and is text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) diff: Option<String>,
+}
+
+/// Compares the managed files recorded by two snapshots.
+///
+/// Content comes from any snapshot's state files or backups whose bytes hash to the recorded
+/// sha256, so older snapshots without state files can still be diffed while a later deploy
+/// backed up the same content.
+pub(crate) fn snapshot_diff(
+    home: &AgentpackHome,
+    from: &DeploymentSnapshot,
+    to: &DeploymentSnapshot,
+) -> anyhow::Result<Vec<SnapshotFileDiff>> {
+    let snapshots = list_snapshots(home)?;
+    let files_of = |s: &DeploymentSnapshot| -> BTreeMap<(String, String), ManagedFile> {
+        s.managed_files
+            .iter()
+            .map(|f| ((f.target.clone(), f.path.clone()), f.clone()))
+            .collect()
+    };
+    let from_files = files_of(from);
+    let to_files = files_of(to);
+
+    let mut keys: Vec<&(String, String)> = from_files.keys().chain(to_files.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut out = Vec::new();
+    for key in keys {
+        let (before, after) = (from_files.get(key), to_files.get(key));
+        let status = match (before, after) {
+            (Some(b), Some(a)) if b.sha256 == a.sha256 && b.link_target == a.link_target => {
+                continue;
+            }
+            (Some(_), Some(_)) => "modified",
+            (None, Some(_)) => "added",
+            (Some(_), None) => "removed",
+            (None, None) => continue,
+        };
+
+        let before_bytes = match before {
+            Some(f) => recorded_content(home, &snapshots, f)?,
+            None => Some(Vec::new()),
+        };
+        let after_bytes = match after {
+            Some(f) => recorded_content(home, &snapshots, f)?,
+            None => Some(Vec::new()),
+        };
+        let diff = match (before_bytes, after_bytes) {
+            (Some(b), Some(a)) => match (String::from_utf8(b), String::from_utf8(a)) {
+                (Ok(b), Ok(a)) => Some(unified_diff(
+                    &b,
+                    &a,
+                    &format!("{}: {}", from.id, key.1),
+                    &format!("{}: {}", to.id, key.1),
+                )),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        out.push(SnapshotFileDiff {
+            target: key.0.clone(),
+            path: key.1.clone(),
+            path_posix: crate::paths::path_to_posix_string(&PathBuf::from(&key.1)),
+            status,
+            from_sha256: before.map(|f| f.sha256.clone()),
+            to_sha256: after.map(|f| f.sha256.clone()),
+            diff,
+        });
+    }
+
+    Ok(out)
+}
+
+/// Finds bytes hashing to `file.sha256` for this path in any snapshot's state or backups.
+fn recorded_content(
+    home: &AgentpackHome,
+    snapshots: &[DeploymentSnapshot],
+    file: &ManagedFile,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let abs = PathBuf::from(&file.path);
+    let matches = |p: &std::path::Path| {
+        std::fs::read(p)
+            .ok()
+            .filter(|b| sha256_hex(b) == file.sha256)
+    };
+
+    for s in snapshots.iter().rev() {
+        let state_root = DeploymentSnapshot::state_root(home, &s.id);
+        let state_path = crate::apply::snapshot_state_path(&state_root, &file.target, &abs)?;
+        if let Some(bytes) = matches(&state_path) {
+            return Ok(Some(bytes));
+        }
+        for c in &s.changes {
+            if c.path != file.path || c.before_sha256.as_deref() != Some(file.sha256.as_str()) {
+                continue;
+            }
+            if let Some(bytes) = c.backup_path.as_deref().and_then(|b| matches(b.as_ref())) {
+                return Ok(Some(bytes));
+            }
+        }
+    }
+    Ok(None)
+}
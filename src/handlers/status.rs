@@ -29,6 +29,7 @@ pub(crate) struct DriftSummary {
     pub(crate) modified: u64,
     pub(crate) missing: u64,
     pub(crate) extra: u64,
+    pub(crate) permissions: u64,
 }
 
 pub(crate) struct StatusDriftReport {
@@ -223,9 +224,8 @@ pub(crate) fn status_drift_report(
         }
 
         for tp in &managed_paths {
-            let expected = desired
-                .get(tp)
-                .map(|f| format!("sha256:{}", sha256_hex(&f.bytes)));
+            let desired_file = desired.get(tp);
+            let expected = desired_file.map(|f| format!("sha256:{}", sha256_hex(&f.bytes)));
             match std::fs::read(&tp.path) {
                 Ok(actual_bytes) => {
                     let actual = format!("sha256:{}", sha256_hex(&actual_bytes));
@@ -242,6 +242,20 @@ pub(crate) fn status_drift_report(
                                 actual: Some(actual),
                                 kind: "modified".to_string(),
                             });
+                        } else if let Some((expected, actual)) =
+                            desired_file.and_then(|f| permission_drift(tp, f))
+                        {
+                            summary.permissions += 1;
+                            drift.push(DriftItem {
+                                target: tp.target.clone(),
+                                root: Some(root.root.to_string_lossy().to_string()),
+                                root_posix: Some(crate::paths::path_to_posix_string(&root.root)),
+                                path: tp.path.to_string_lossy().to_string(),
+                                path_posix: crate::paths::path_to_posix_string(&tp.path),
+                                expected: Some(expected),
+                                actual: Some(actual),
+                                kind: "permissions".to_string(),
+                            });
                         }
                     } else {
                         summary.extra += 1;
@@ -328,3 +342,23 @@ pub(crate) fn status_drift_report(
         needs_deploy_apply,
     })
 }
+
+/// Expected/actual `mode:` strings when a deployed file's content matches but its executable bit
+/// does not.
+fn permission_drift(
+    tp: &TargetPath,
+    desired_file: &crate::deploy::DesiredFile,
+) -> Option<(String, String)> {
+    if desired_file.link_target.is_some()
+        || crate::fs::is_symlink(&tp.path)
+        || !crate::fs::executable_bit_differs(&tp.path, desired_file.mode)
+    {
+        return None;
+    }
+    let actual = crate::fs::file_mode(&tp.path)?;
+    let expected = match desired_file.mode {
+        Some(mode) => format!("mode:{mode:04o}"),
+        None => "mode:non-executable".to_string(),
+    };
+    Some((expected, format!("mode:{actual:04o}")))
+}
@@ -0,0 +1,257 @@
+//! Manifest hooks: commands run before and after `deploy --apply` and after `rollback`.
+//!
+//! Each hook gets one JSON payload on stdin (`protocol = "agentpack.hook"`, the hook name,
+//! snapshot id, targets and changes) and runs with the config repo as cwd. Only the exit code
+//! matters; stdout is ignored. Results are recorded in the snapshot (`hooks[]`) and appended to
+//! `logs/events.jsonl`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{HookConfig, Hooks};
+use crate::paths::AgentpackHome;
+use crate::state::DeploymentSnapshot;
+use crate::subprocess::{resolve_program, stderr_tail, wait_with_timeout};
+use crate::user_error::UserError;
+
+pub const HOOK_PROTOCOL: &str = "agentpack.hook";
+pub const HOOK_PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_HOOK_TIMEOUT_MS: u64 = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    PreDeploy,
+    PostDeploy,
+    PostRollback,
+}
+
+impl HookStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookStage::PreDeploy => "pre_deploy",
+            HookStage::PostDeploy => "post_deploy",
+            HookStage::PostRollback => "post_rollback",
+        }
+    }
+
+    fn configured(self, hooks: &Hooks) -> &[HookConfig] {
+        match self {
+            HookStage::PreDeploy => &hooks.pre_deploy,
+            HookStage::PostDeploy => &hooks.post_deploy,
+            HookStage::PostRollback => &hooks.post_rollback,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookResult {
+    pub hook: HookStage,
+    pub command: Vec<String>,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Why the hook could not run or was stopped (spawn failure, timeout).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Tail of the hook's stderr.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+}
+
+/// What hooks receive on stdin.
+pub fn hook_payload(
+    stage: HookStage,
+    snapshot_id: Option<&str>,
+    targets: &[String],
+    changes: serde_json::Value,
+    extra: serde_json::Value,
+) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "protocol": HOOK_PROTOCOL,
+        "protocol_version": HOOK_PROTOCOL_VERSION,
+        "hook": stage.as_str(),
+        "snapshot_id": snapshot_id,
+        "targets": targets,
+        "changes": changes,
+    });
+    if let (Some(obj), serde_json::Value::Object(extra)) = (payload.as_object_mut(), extra) {
+        obj.extend(extra);
+    }
+    payload
+}
+
+/// Runs the hooks configured for `stage` in order. `pre_deploy` stops at the first failure;
+/// the other stages run every hook.
+pub fn run_hooks(
+    repo_dir: &Path,
+    hooks: &Hooks,
+    stage: HookStage,
+    payload: &serde_json::Value,
+) -> anyhow::Result<Vec<HookResult>> {
+    let input = serde_json::to_vec(payload).context("serialize hook payload")?;
+    let mut out = Vec::new();
+    for hook in stage.configured(hooks) {
+        let result = run_hook(repo_dir, stage, hook, payload, &input);
+        let failed = !result.success;
+        out.push(result);
+        if failed && stage == HookStage::PreDeploy {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+fn run_hook(
+    repo_dir: &Path,
+    stage: HookStage,
+    hook: &HookConfig,
+    payload: &serde_json::Value,
+    input: &[u8],
+) -> HookResult {
+    let timeout = Duration::from_millis(hook.timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS));
+    let mut result = HookResult {
+        hook: stage,
+        command: hook.command.clone(),
+        success: false,
+        exit_code: None,
+        timed_out: false,
+        duration_ms: 0,
+        error: None,
+        stderr: String::new(),
+    };
+    let Some((program, args)) = hook.command.split_first() else {
+        result.error = Some("empty command".to_string());
+        return result;
+    };
+
+    let started = Instant::now();
+    let mut command = Command::new(resolve_program(repo_dir, program));
+    command
+        .args(args)
+        .current_dir(repo_dir)
+        .env("AGENTPACK_HOOK", stage.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(id) = payload.get("snapshot_id").and_then(|v| v.as_str()) {
+        command.env("AGENTPACK_SNAPSHOT_ID", id);
+    }
+
+    let waited = command
+        .spawn()
+        .map_err(anyhow::Error::from)
+        .and_then(|child| wait_with_timeout(child, input.to_vec(), timeout));
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    match waited {
+        Ok(Some(output)) => {
+            result.success = output.status.success();
+            result.exit_code = output.status.code();
+            result.stderr = stderr_tail(&output.stderr);
+        }
+        Ok(None) => {
+            result.timed_out = true;
+            result.error = Some(format!("timed out after {}ms", timeout.as_millis()));
+        }
+        Err(err) => result.error = Some(format!("failed to start: {err:#}")),
+    }
+    result
+}
+
+/// Appends one `logs/events.jsonl` record per hook run.
+pub fn record_hook_events(
+    home: &AgentpackHome,
+    machine_id: &str,
+    command_id: &str,
+    snapshot_id: Option<&str>,
+    targets: &[String],
+    results: &[HookResult],
+) -> anyhow::Result<()> {
+    for r in results {
+        let event = serde_json::json!({
+            "event": "hook",
+            "command_id": command_id,
+            "hook": r.hook,
+            "command": r.command,
+            "success": r.success,
+            "exit_code": r.exit_code,
+            "timed_out": r.timed_out,
+            "duration_ms": r.duration_ms,
+            "snapshot_id": snapshot_id,
+            "targets": targets,
+        });
+        let record = crate::events::new_record(machine_id.to_string(), event)?;
+        crate::events::append_event(home, &record)?;
+    }
+    Ok(())
+}
+
+/// Adds hook results to a saved snapshot.
+pub fn record_in_snapshot(
+    home: &AgentpackHome,
+    snapshot_id: &str,
+    results: &[HookResult],
+) -> anyhow::Result<()> {
+    if results.is_empty() {
+        return Ok(());
+    }
+    let path = DeploymentSnapshot::path(home, snapshot_id);
+    let mut snapshot = DeploymentSnapshot::load(&path)
+        .with_context(|| format!("load snapshot {}", path.display()))?;
+    snapshot.hooks.extend(results.iter().cloned());
+    snapshot.save(&path)
+}
+
+/// Warnings for hooks that failed after the change was already made.
+pub fn failure_warnings(results: &[HookResult]) -> Vec<String> {
+    results
+        .iter()
+        .filter(|r| !r.success)
+        .map(|r| {
+            let why = match (&r.error, r.exit_code) {
+                (Some(err), _) => err.clone(),
+                (None, Some(code)) => format!("exit code {code}"),
+                (None, None) => "terminated by signal".to_string(),
+            };
+            format!(
+                "{} hook `{}` failed: {why}",
+                r.hook.as_str(),
+                r.command.join(" ")
+            )
+        })
+        .collect()
+}
+
+/// A `pre_deploy` hook failed: nothing was written.
+pub fn hook_failed(result: &HookResult) -> anyhow::Error {
+    let reason_code = if result.timed_out {
+        "hook_timeout"
+    } else if result.error.is_some() {
+        "hook_spawn_failed"
+    } else {
+        "hook_exit_nonzero"
+    };
+    let message = failure_warnings(std::slice::from_ref(result))
+        .pop()
+        .unwrap_or_default();
+    anyhow::Error::new(
+        UserError::new("E_HOOK_FAILED", format!("{message}; nothing was deployed")).with_details(
+            serde_json::json!({
+                "hook": result.hook,
+                "command": result.command,
+                "exit_code": result.exit_code,
+                "timed_out": result.timed_out,
+                "stderr": result.stderr,
+                "reason_code": reason_code,
+                "next_actions": ["inspect_hook_stderr", "edit_manifest_hooks", "retry_command"],
+            }),
+        ),
+    )
+}
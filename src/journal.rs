@@ -0,0 +1,253 @@
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::write_symlink;
+use crate::paths::AgentpackHome;
+use crate::state::DeploymentSnapshot;
+
+pub const JOURNAL_FILENAME: &str = "journal.jsonl";
+
+/// One line of an apply journal (`snapshots/<id>/journal.jsonl`).
+///
+/// The journal is append-only: a `begin` record, then one `change` record per target path,
+/// each written (and synced) before that path is touched. It is removed once the snapshot
+/// is saved, so a journal without a matching `<id>.json` snapshot is an interrupted apply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum JournalRecord {
+    Begin {
+        snapshot_id: String,
+        kind: String,
+        created_at: String,
+    },
+    Change(JournalChange),
+}
+
+/// What a path looked like before the apply touched it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalChange {
+    pub target: String,
+    pub path: String,
+    /// Whether anything (file or symlink) existed at `path`.
+    pub existed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_link_target: Option<String>,
+}
+
+pub struct ApplyJournal {
+    pub snapshot_id: String,
+    pub kind: String,
+    pub created_at: String,
+    path: PathBuf,
+    file: std::fs::File,
+}
+
+impl ApplyJournal {
+    pub fn path(home: &AgentpackHome, snapshot_id: &str) -> PathBuf {
+        home.snapshots_dir.join(snapshot_id).join(JOURNAL_FILENAME)
+    }
+
+    pub fn begin(
+        home: &AgentpackHome,
+        snapshot_id: &str,
+        kind: &str,
+        created_at: &str,
+    ) -> anyhow::Result<Self> {
+        let path = Self::path(home, snapshot_id);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("create {}", parent.display()))?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("create journal {}", path.display()))?;
+        let mut journal = Self {
+            snapshot_id: snapshot_id.to_string(),
+            kind: kind.to_string(),
+            created_at: created_at.to_string(),
+            path,
+            file,
+        };
+        journal.append(&JournalRecord::Begin {
+            snapshot_id: journal.snapshot_id.clone(),
+            kind: journal.kind.clone(),
+            created_at: journal.created_at.clone(),
+        })?;
+        Ok(journal)
+    }
+
+    /// Records the pre-image of a path; call before mutating it.
+    pub fn record(&mut self, change: JournalChange) -> anyhow::Result<()> {
+        self.append(&JournalRecord::Change(change))
+    }
+
+    /// Marks the apply as complete; call after the snapshot has been saved.
+    pub fn commit(self) -> anyhow::Result<()> {
+        drop(self.file);
+        std::fs::remove_file(&self.path)
+            .with_context(|| format!("remove journal {}", self.path.display()))
+    }
+
+    fn append(&mut self, record: &JournalRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(record).context("serialize journal record")?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .with_context(|| format!("write journal {}", self.path.display()))?;
+        self.file
+            .sync_data()
+            .with_context(|| format!("sync journal {}", self.path.display()))
+    }
+}
+
+/// An apply that started but never saved its snapshot.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterruptedApply {
+    pub snapshot_id: String,
+    pub kind: String,
+    pub created_at: String,
+    pub journal: String,
+    pub changes: usize,
+}
+
+/// Scans the snapshots dir for journals of applies that never completed. Read-only.
+///
+/// Journals left behind by an apply that did save its snapshot are committed already and are
+/// ignored (see [`remove_committed_journals`]).
+pub fn find_interrupted(home: &AgentpackHome) -> anyhow::Result<Vec<InterruptedApply>> {
+    let mut out = Vec::new();
+    for (snapshot_id, journal_path) in journals(home)? {
+        if DeploymentSnapshot::path(home, &snapshot_id).exists() {
+            continue;
+        }
+
+        let (begin, changes) = read_journal(&journal_path)?;
+        let (kind, created_at) = match begin {
+            Some(JournalRecord::Begin {
+                kind, created_at, ..
+            }) => (kind, created_at),
+            _ => (String::new(), String::new()),
+        };
+        out.push(InterruptedApply {
+            snapshot_id,
+            kind,
+            created_at,
+            journal: journal_path.to_string_lossy().to_string(),
+            changes: changes.len(),
+        });
+    }
+
+    out.sort_by(|a, b| a.snapshot_id.cmp(&b.snapshot_id));
+    Ok(out)
+}
+
+/// Restores every path recorded in the journal of `snapshot_id` to its pre-apply state, then
+/// discards the incomplete snapshot directory. Safe to re-run if interrupted itself.
+pub fn restore_interrupted(home: &AgentpackHome, snapshot_id: &str) -> anyhow::Result<usize> {
+    let journal_path = ApplyJournal::path(home, snapshot_id);
+    let (_, changes) = read_journal(&journal_path)?;
+
+    for change in changes.iter().rev() {
+        let path = PathBuf::from(&change.path);
+        if let Some(link) = &change.before_link_target {
+            write_symlink(&path, Path::new(link))?;
+        } else if let Some(backup) = &change.backup_path {
+            let backup_path = PathBuf::from(backup);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("create {}", parent.display()))?;
+            }
+            if crate::fs::is_symlink(&path) {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("remove {}", path.display()))?;
+            }
+            std::fs::copy(&backup_path, &path).with_context(|| {
+                format!("restore {} -> {}", backup_path.display(), path.display())
+            })?;
+        } else if !change.existed && crate::fs::path_present(&path) {
+            std::fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+        }
+    }
+
+    let snapshot_dir = home.snapshots_dir.join(snapshot_id);
+    std::fs::remove_dir_all(&snapshot_dir)
+        .with_context(|| format!("remove {}", snapshot_dir.display()))?;
+    Ok(changes.len())
+}
+
+/// Removes journals whose apply saved its snapshot but crashed before committing the journal.
+///
+/// Call only while holding the [`crate::state_lock::StateLock`].
+pub fn remove_committed_journals(home: &AgentpackHome) -> anyhow::Result<()> {
+    for (snapshot_id, journal_path) in journals(home)? {
+        if DeploymentSnapshot::path(home, &snapshot_id).exists() {
+            std::fs::remove_file(&journal_path)
+                .with_context(|| format!("remove journal {}", journal_path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Restores all interrupted applies (newest first, so stacked applies unwind in order) and
+/// removes committed journals. Call only while holding the [`crate::state_lock::StateLock`].
+pub fn restore_all_interrupted(home: &AgentpackHome) -> anyhow::Result<Vec<InterruptedApply>> {
+    remove_committed_journals(home)?;
+    let interrupted = find_interrupted(home)?;
+    for apply in interrupted.iter().rev() {
+        restore_interrupted(home, &apply.snapshot_id)
+            .with_context(|| format!("restore interrupted apply {}", apply.snapshot_id))?;
+    }
+    Ok(interrupted)
+}
+
+/// `(snapshot_id, journal path)` for every snapshot directory that has a journal.
+fn journals(home: &AgentpackHome) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    if !home.snapshots_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut out = Vec::new();
+    for entry in std::fs::read_dir(&home.snapshots_dir)
+        .with_context(|| format!("read {}", home.snapshots_dir.display()))?
+    {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let journal_path = entry.path().join(JOURNAL_FILENAME);
+        if journal_path.exists() {
+            out.push((
+                entry.file_name().to_string_lossy().to_string(),
+                journal_path,
+            ));
+        }
+    }
+    Ok(out)
+}
+
+fn read_journal(path: &Path) -> anyhow::Result<(Option<JournalRecord>, Vec<JournalChange>)> {
+    let raw = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut begin = None;
+    let mut changes = Vec::new();
+    for line in raw.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // A crash can leave a torn final line; the path it describes was not touched yet.
+        let Ok(record) = serde_json::from_str::<JournalRecord>(line) else {
+            break;
+        };
+        match record {
+            JournalRecord::Begin { .. } => begin = Some(record),
+            JournalRecord::Change(change) => changes.push(change),
+        }
+    }
+    Ok((begin, changes))
+}
@@ -11,24 +11,38 @@ pub mod fs;
 pub mod git;
 pub(crate) mod handlers;
 pub mod hash;
+pub mod hooks;
 pub mod ids;
+pub mod journal;
+pub mod local_edits;
 pub mod lockfile;
 pub mod machine;
 pub mod markers;
 pub mod mcp;
+pub mod merge;
 pub mod output;
+pub mod output_spans;
 pub mod overlay;
 pub mod paths;
+pub mod plan_filter;
 pub mod policy;
 pub(crate) mod policy_allowlist;
 pub(crate) mod policy_pack;
 pub mod project;
+pub mod project_registry;
+pub mod rollback_plan;
 pub(crate) mod roots;
+pub mod saved_plan;
+pub mod snapshot_archive;
+pub mod snapshot_tags;
 pub mod source;
 pub mod state;
+pub mod state_lock;
 pub mod store;
+pub mod subprocess;
 pub mod target_adapters;
 pub mod target_manifest;
+pub mod target_plugin;
 pub mod target_registry;
 pub mod target_selection;
 pub mod targets;
@@ -36,5 +50,6 @@ pub mod tui_apply;
 pub mod tui_core;
 pub mod user_error;
 pub mod validate;
+pub mod watch;
 
 pub use cli::run;
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::deploy::{DesiredState, MergeStatus, Op, PlanResult, TargetPath, UpdateKind};
+use crate::hash::sha256_hex;
+use crate::paths::AgentpackHome;
+use crate::state::DeploymentSnapshot;
+use crate::target_manifest::{manifest_path_for_target, read_target_manifest_soft};
+use crate::targets::TargetRoot;
+
+/// Conflict marker labels: the file on disk, the last deployed upstream, the new upstream.
+const MERGE_LABELS: [&str; 3] = ["local", "deployed", "upstream"];
+
+/// Three-way merges managed files that were edited locally while their upstream also changed.
+///
+/// The merge base is the upstream content last deployed to the path: the target manifest names
+/// the snapshot, and the snapshot keeps the deployed bytes (checked against the manifest
+/// sha256), or, for a merged deploy, the upstream bytes next to them. A merged update keeps the
+/// upstream content in `bytes` (so it becomes the next base) and writes the merge result from
+/// `merged`; the plan change is marked `merge: clean|conflict`.
+///
+/// While the upstream of a merged file stays unchanged, the merge result is what gets deployed:
+/// the path is left out of the plan, or restored to the merge result if it was edited since.
+/// Other paths without a usable base, binary files, symlinks, and edits whose upstream did not
+/// change are left alone (a plain managed update, as before).
+pub fn merge_local_edits(
+    home: &AgentpackHome,
+    roots: &[TargetRoot],
+    plan: &mut PlanResult,
+    desired: &mut DesiredState,
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let candidates = plan.changes.iter().any(|c| {
+        matches!(c.op, Op::Update) && matches!(c.update_kind, Some(UpdateKind::ManagedUpdate))
+    });
+    if !candidates {
+        return Ok(());
+    }
+
+    let deployed = deployed_entries(roots);
+    let mut unchanged = Vec::new();
+    for change in &mut plan.changes {
+        if !matches!(change.op, Op::Update)
+            || !matches!(change.update_kind, Some(UpdateKind::ManagedUpdate))
+        {
+            continue;
+        }
+        let tp = TargetPath {
+            target: change.target.clone(),
+            path: change.path.clone().into(),
+        };
+        let Some(entry) = deployed.get(&tp).filter(|e| e.link_target.is_none()) else {
+            continue;
+        };
+        let Some(desired_file) = desired.get_mut(&tp) else {
+            continue;
+        };
+        if desired_file.link_target.is_some() || crate::fs::is_symlink(&tp.path) {
+            continue;
+        }
+
+        let Ok(local) = std::fs::read(&tp.path) else {
+            continue;
+        };
+        let Some(base) = merge_base_bytes(home, &tp, entry)? else {
+            continue;
+        };
+        if local == base {
+            continue;
+        }
+        if base == desired_file.bytes {
+            if entry.merge_base_sha256.is_some()
+                && let Some(last) = snapshot_bytes(home, &tp, entry)?
+            {
+                if local == last {
+                    unchanged.push(tp);
+                } else {
+                    change.after_sha256 = Some(sha256_hex(&last));
+                }
+                desired_file.merged = Some(last);
+            }
+            continue;
+        }
+        if ![&base, &local, &desired_file.bytes]
+            .iter()
+            .all(|b| is_text(b))
+        {
+            continue;
+        }
+
+        let outcome =
+            crate::merge::merge_three_way(&base, &local, &desired_file.bytes, Some(MERGE_LABELS));
+        if outcome.conflicted {
+            warnings.push(format!(
+                "{} {}: local edits conflict with upstream changes; deploy --apply refuses until resolved",
+                tp.target,
+                tp.path.display()
+            ));
+            change.merge = Some(MergeStatus::Conflict);
+            change.reason = "local edits conflict with upstream".to_string();
+        } else {
+            change.merge = Some(MergeStatus::Clean);
+            change.reason = "merge local edits with upstream".to_string();
+        }
+        change.after_sha256 = Some(sha256_hex(&outcome.merged));
+        desired_file.merged = Some(outcome.merged);
+    }
+
+    if !unchanged.is_empty() {
+        plan.changes.retain(|c| {
+            !unchanged
+                .iter()
+                .any(|tp| tp.target == c.target && tp.path == Path::new(&c.path))
+        });
+        plan.summary.update -= unchanged.len() as u64;
+    }
+    Ok(())
+}
+
+/// A file recorded in a target manifest, with the snapshot that deployed it.
+#[derive(Debug, Clone)]
+pub(crate) struct DeployedEntry {
+    pub(crate) sha256: String,
+    pub(crate) module_ids: Vec<String>,
+    pub(crate) link_target: Option<String>,
+    pub(crate) merge_base_sha256: Option<String>,
+    pub(crate) mode: Option<u32>,
+    pub(crate) snapshot_id: String,
+}
+
+/// Files recorded in the target manifests of `roots`, keyed by absolute path.
+pub(crate) fn deployed_entries(roots: &[TargetRoot]) -> BTreeMap<TargetPath, DeployedEntry> {
+    let mut out = BTreeMap::new();
+    for root in roots {
+        let path = manifest_path_for_target(&root.root, &root.target);
+        if !path.exists() {
+            continue;
+        }
+        // Unreadable manifests are already reported while planning.
+        let (Some(manifest), _) = read_target_manifest_soft(&path, &root.target) else {
+            continue;
+        };
+        let Some(snapshot_id) = manifest.snapshot_id else {
+            continue;
+        };
+        for f in manifest.managed_files {
+            out.insert(
+                TargetPath {
+                    target: root.target.clone(),
+                    path: root.root.join(&f.path),
+                },
+                DeployedEntry {
+                    sha256: f.sha256,
+                    module_ids: f.module_ids,
+                    link_target: f.link_target,
+                    merge_base_sha256: f.merge_base_sha256,
+                    mode: f.mode,
+                    snapshot_id: snapshot_id.clone(),
+                },
+            );
+        }
+    }
+    out
+}
+
+/// The bytes last deployed to `tp`, as kept by the snapshot named in its manifest entry.
+///
+/// `None` when the snapshot state is gone or no longer matches the recorded sha256.
+pub(crate) fn snapshot_bytes(
+    home: &AgentpackHome,
+    tp: &TargetPath,
+    entry: &DeployedEntry,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let state_path = crate::apply::snapshot_state_path(
+        &DeploymentSnapshot::state_root(home, &entry.snapshot_id),
+        &tp.target,
+        &tp.path,
+    )?;
+    Ok(std::fs::read(&state_path)
+        .ok()
+        .filter(|b| sha256_hex(b) == entry.sha256))
+}
+
+/// The upstream bytes last deployed to `tp`: the merge base kept next to a merged deploy, or
+/// else the deployed bytes themselves.
+pub(crate) fn merge_base_bytes(
+    home: &AgentpackHome,
+    tp: &TargetPath,
+    entry: &DeployedEntry,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(sha256) = &entry.merge_base_sha256 else {
+        return snapshot_bytes(home, tp, entry);
+    };
+    let base_path = crate::apply::snapshot_state_path(
+        &DeploymentSnapshot::merge_base_root(home, &entry.snapshot_id),
+        &tp.target,
+        &tp.path,
+    )?;
+    Ok(std::fs::read(&base_path)
+        .ok()
+        .filter(|b| &sha256_hex(b) == sha256))
+}
+
+fn is_text(bytes: &[u8]) -> bool {
+    !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok()
+}
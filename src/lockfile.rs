@@ -57,6 +57,9 @@ pub struct FileEntry {
     pub path: String,
     pub sha256: String,
     pub bytes: u64,
+    /// POSIX permission bits, recorded only for executable files (like git); not part of the module hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
 }
 
 impl Lockfile {
@@ -213,6 +216,7 @@ pub fn hash_tree(root: &Path) -> anyhow::Result<(Vec<FileEntry>, String)> {
             path: file_name,
             sha256: sha.clone(),
             bytes: bytes.len() as u64,
+            mode: crate::fs::executable_mode(root),
         };
         let module_hash =
             sha256_hex(format!("{}\n{}\n{}\n", entry.path, entry.sha256, entry.bytes).as_bytes());
@@ -244,6 +248,7 @@ pub fn hash_tree(root: &Path) -> anyhow::Result<(Vec<FileEntry>, String)> {
             path: rel,
             sha256: sha,
             bytes: bytes.len() as u64,
+            mode: crate::fs::executable_mode(e.path()),
         });
     }
 
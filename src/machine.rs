@@ -1,7 +1,22 @@
+use std::path::Path;
 use std::process::Command;
 
 use anyhow::Context as _;
 
+use crate::config::Manifest;
+use crate::user_error::UserError;
+
+/// The `--machine` override when it normalizes to a non-empty id, else the detected machine id.
+pub fn resolve_machine_id(machine_override: Option<&str>) -> anyhow::Result<String> {
+    if let Some(m) = machine_override {
+        let normalized = normalize_machine_id(m);
+        if !normalized.is_empty() {
+            return Ok(normalized);
+        }
+    }
+    detect_machine_id()
+}
+
 pub fn detect_machine_id() -> anyhow::Result<String> {
     if let Ok(val) = std::env::var("AGENTPACK_MACHINE_ID") {
         let id = normalize_machine_id(&val);
@@ -55,3 +70,64 @@ pub fn normalize_machine_id(s: &str) -> String {
     }
     out.trim_matches('-').to_string()
 }
+
+/// The `machines:` group `machine_id` belongs to, if any (a machine may match at most one group).
+pub fn machine_group<'a>(
+    manifest: &'a Manifest,
+    machine_id: &str,
+) -> anyhow::Result<Option<&'a str>> {
+    let groups: Vec<&str> = manifest
+        .machines
+        .iter()
+        .filter(|(_, group)| {
+            group
+                .patterns
+                .iter()
+                .any(|p| crate::paths::glob_segment_matches(p, machine_id))
+        })
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    match groups.as_slice() {
+        [] => Ok(None),
+        [group] => Ok(Some(group)),
+        _ => Err(anyhow::Error::new(
+            UserError::new(
+                "E_CONFIG_INVALID",
+                format!(
+                    "machine {machine_id} matches multiple machine groups: {}",
+                    groups.join(", ")
+                ),
+            )
+            .with_details(serde_json::json!({
+                "machine_id": machine_id,
+                "groups": groups,
+                "reason_code": "machine_group_ambiguous",
+                "next_actions": ["edit_manifest_machines", "retry_command"],
+            })),
+        )),
+    }
+}
+
+/// Profile used when none is given explicitly: the machine group's `default_profile`, else
+/// `default`.
+///
+/// Best-effort: a missing/invalid manifest or an ambiguous group falls back to `default`; the
+/// error surfaces again when the command loads the engine.
+pub fn implicit_profile(repo_override: Option<&Path>, machine_override: Option<&str>) -> String {
+    let resolve = || -> anyhow::Result<Option<String>> {
+        let home = crate::paths::AgentpackHome::resolve()?;
+        let repo = crate::paths::RepoPaths::resolve(&home, repo_override)?;
+        let manifest = Manifest::load(&repo.manifest_path)?;
+        if manifest.machines.is_empty() {
+            return Ok(None);
+        }
+        let machine_id = resolve_machine_id(machine_override)?;
+        Ok(machine_group(&manifest, &machine_id)?
+            .and_then(|group| manifest.machines[group].default_profile.clone()))
+    };
+    resolve()
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "default".to_string())
+}
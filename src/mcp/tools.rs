@@ -15,6 +15,7 @@ mod envelope;
 mod evolve_propose;
 mod evolve_restore;
 mod explain;
+mod overlay_list;
 mod preview;
 mod read_only;
 mod rollback;
@@ -25,7 +26,8 @@ mod tool_schema;
 
 pub(super) use args::{
     CommonArgs, DeployApplyArgs, DoctorArgs, EvolveProposeArgs, EvolveRestoreArgs, EvolveScopeArg,
-    ExplainArgs, ExplainKindArg, PreviewArgs, RollbackArgs, StatusArgs, StatusOnly,
+    ExplainArgs, ExplainKindArg, OverlayListArgs, PreviewArgs, RollbackArgs, StatusArgs,
+    StatusOnly,
 };
 
 use deploy_plan::deploy_plan_envelope_in_process;
@@ -35,7 +37,7 @@ use envelope::{
 };
 use tool_schema::{tool, tool_input_schema};
 
-pub(super) const TOOLS_INSTRUCTIONS: &str = "Agentpack MCP server (stdio). Tools: plan, diff, preview, status, doctor, deploy, deploy_apply, rollback, evolve_propose, evolve_restore, explain.";
+pub(super) const TOOLS_INSTRUCTIONS: &str = "Agentpack MCP server (stdio). Tools: plan, diff, preview, status, doctor, deploy, deploy_apply, rollback, evolve_propose, evolve_restore, explain, overlay_list.";
 
 pub(super) fn tools() -> Vec<Tool> {
     tool_registry::tools()
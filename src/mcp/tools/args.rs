@@ -13,6 +13,18 @@ pub(in crate::mcp) struct CommonArgs {
     pub dry_run: Option<bool>,
 }
 
+impl CommonArgs {
+    /// `profile`, else the machine group's default profile (else `default`).
+    pub fn resolved_profile(&self) -> String {
+        self.profile.clone().unwrap_or_else(|| {
+            crate::machine::implicit_profile(
+                self.repo.as_deref().map(std::path::Path::new),
+                self.machine.as_deref(),
+            )
+        })
+    }
+}
+
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub(in crate::mcp) struct StatusArgs {
@@ -28,6 +40,7 @@ pub(in crate::mcp) enum StatusOnly {
     Missing,
     Modified,
     Extra,
+    Permissions,
 }
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
@@ -39,6 +52,15 @@ pub(in crate::mcp) struct DoctorArgs {
     pub target: Option<String>,
 }
 
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(in crate::mcp) struct OverlayListArgs {
+    #[serde(default)]
+    pub repo: Option<String>,
+    #[serde(default)]
+    pub machine: Option<String>,
+}
+
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub(in crate::mcp) struct DeployApplyArgs {
@@ -50,6 +72,13 @@ pub(in crate::mcp) struct DeployApplyArgs {
     pub confirm_token: Option<String>,
     #[serde(default)]
     pub yes: bool,
+    /// Only apply changes to files of these modules (like `deploy --module`).
+    #[serde(default)]
+    pub modules: Option<Vec<String>>,
+    /// Only apply changes whose path relative to the target root matches one of these globs
+    /// (like `deploy --path`).
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
 }
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
@@ -60,6 +89,9 @@ pub(in crate::mcp) struct RollbackArgs {
     pub to: String,
     #[serde(default)]
     pub yes: bool,
+    /// Overwrite files that were edited after the current deployment.
+    #[serde(default)]
+    pub discard_local_edits: bool,
 }
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
@@ -75,6 +107,7 @@ pub(in crate::mcp) struct PreviewArgs {
 #[serde(rename_all = "snake_case")]
 pub(in crate::mcp) enum EvolveScopeArg {
     Global,
+    Profile,
     Machine,
     Project,
 }
@@ -91,6 +124,12 @@ pub(in crate::mcp) struct EvolveProposeArgs {
     #[serde(default)]
     pub branch: Option<String>,
     #[serde(default)]
+    pub prefer_target: Option<String>,
+    #[serde(default)]
+    pub merge: bool,
+    #[serde(default)]
+    pub upstream: bool,
+    #[serde(default)]
     pub yes: bool,
 }
 
@@ -111,7 +111,8 @@ async fn call_deploy_apply_in_process(
         };
 
         let repo_override = args.common.repo.as_ref().map(std::path::PathBuf::from);
-        let profile = args.common.profile.as_deref().unwrap_or("default");
+        let profile = args.common.resolved_profile();
+        let profile = profile.as_str();
         let target = args.common.target.as_deref().unwrap_or("all");
         let machine_override = args.common.machine.as_deref();
 
@@ -121,9 +122,16 @@ async fn call_deploy_apply_in_process(
                 targets,
                 desired,
                 plan,
-                warnings,
+                mut warnings,
                 roots,
-            } = crate::handlers::read_only::read_only_context_in(&engine, profile, target)?;
+            } = crate::handlers::read_only::read_only_context_in(&engine, profile, target)?
+                .filtered(
+                    &engine.home,
+                    &crate::plan_filter::PlanFilter {
+                        modules: args.modules.clone().unwrap_or_default(),
+                        paths: args.paths.clone().unwrap_or_default(),
+                    },
+                )?;
 
             let will_apply = !args.common.dry_run.unwrap_or(false);
             if !will_apply {
@@ -164,12 +172,14 @@ async fn call_deploy_apply_in_process(
                     let envelope = serde_json::to_value(&envelope)?;
                     Ok((text, envelope))
                 }
-                crate::handlers::deploy::DeployApplyOutcome::Applied { snapshot_id } => {
+                crate::handlers::deploy::DeployApplyOutcome::Applied { snapshot_id, hooks } => {
+                    warnings.extend(crate::hooks::failure_warnings(&hooks));
                     let data = crate::app::deploy_json::deploy_json_data_applied(
                         profile,
                         targets,
                         plan,
                         snapshot_id,
+                        hooks,
                     );
                     let mut envelope = crate::output::JsonEnvelope::ok(meta.command, data)
                         .with_command_meta(meta.command_id_string(), meta.command_path_vec());
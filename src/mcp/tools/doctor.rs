@@ -48,17 +48,29 @@ pub(super) async fn call_doctor_in_process(
         };
 
         let prefix = action_prefix(args.repo.as_deref(), target);
-        let next_actions =
-            doctor_next_actions(&report.roots, report.needs_gitignore_fix, false, &prefix);
+        let next_actions = doctor_next_actions(
+            &report.roots,
+            report.needs_gitignore_fix,
+            report.needs_restore,
+            false,
+            &prefix,
+        );
 
         let crate::handlers::doctor::DoctorReport {
             machine_id,
             roots,
             gitignore_fixes,
+            interrupted_applies,
             warnings,
             ..
         } = report;
-        let data = doctor_json_data(machine_id, roots, gitignore_fixes, &next_actions.json)?;
+        let data = doctor_json_data(
+            machine_id,
+            roots,
+            gitignore_fixes,
+            interrupted_applies,
+            &next_actions.json,
+        )?;
 
         let mut envelope = crate::output::JsonEnvelope::ok(meta.command, data)
             .with_command_meta(meta.command_id_string(), meta.command_path_vec());
@@ -14,7 +14,8 @@ pub(super) async fn call_evolve_propose_in_process(
         };
 
         let repo_override = args.common.repo.as_ref().map(std::path::PathBuf::from);
-        let profile = args.common.profile.as_deref().unwrap_or("default");
+        let profile = args.common.resolved_profile();
+        let profile = profile.as_str();
         let target = args.common.target.as_deref().unwrap_or("all");
         let machine_override = args.common.machine.as_deref();
         let dry_run = args.common.dry_run.unwrap_or(false);
@@ -38,10 +39,19 @@ pub(super) async fn call_evolve_propose_in_process(
 
         let scope = match args.scope.unwrap_or(super::EvolveScopeArg::Global) {
             super::EvolveScopeArg::Global => crate::handlers::evolve::EvolveScope::Global,
+            super::EvolveScopeArg::Profile => crate::handlers::evolve::EvolveScope::Profile,
             super::EvolveScopeArg::Machine => crate::handlers::evolve::EvolveScope::Machine,
             super::EvolveScopeArg::Project => crate::handlers::evolve::EvolveScope::Project,
         };
 
+        let divergent = match (args.prefer_target.as_deref(), args.merge) {
+            (Some(target), _) => {
+                crate::handlers::evolve::EvolveDivergentResolution::PreferTarget(target)
+            }
+            (None, true) => crate::handlers::evolve::EvolveDivergentResolution::Merge,
+            (None, false) => crate::handlers::evolve::EvolveDivergentResolution::Skip,
+        };
+
         let engine = match crate::engine::Engine::load(repo_override.as_deref(), machine_override) {
             Ok(v) => v,
             Err(err) => {
@@ -60,6 +70,8 @@ pub(super) async fn call_evolve_propose_in_process(
                 module_filter: args.module_id.as_deref(),
                 scope,
                 branch_override: args.branch.as_deref(),
+                divergent,
+                upstream: args.upstream,
                 dry_run,
                 confirmed: args.yes,
                 json: true,
@@ -107,6 +119,18 @@ pub(super) async fn call_evolve_propose_in_process(
                 let envelope = serde_json::to_value(&envelope)?;
                 (text, envelope)
             }
+            Ok(crate::handlers::evolve::EvolveProposeOutcome::Upstream(report)) => {
+                let data = crate::app::evolve_propose_json::evolve_propose_json_data_upstream(
+                    report.proposals,
+                    report.skipped,
+                );
+                let mut envelope = crate::output::JsonEnvelope::ok(meta.command, data)
+                    .with_command_meta(meta.command_id_string(), meta.command_path_vec());
+                envelope.warnings = report.warnings;
+                let text = serde_json::to_string_pretty(&envelope)?;
+                let envelope = serde_json::to_value(&envelope)?;
+                (text, envelope)
+            }
             Ok(crate::handlers::evolve::EvolveProposeOutcome::NeedsConfirmation) => {
                 let err = UserError::confirm_required("evolve propose");
                 let envelope = super::envelope_from_anyhow_error(meta, &err);
@@ -14,7 +14,8 @@ pub(super) async fn call_evolve_restore_in_process(
         };
 
         let repo_override = args.common.repo.as_ref().map(std::path::PathBuf::from);
-        let profile = args.common.profile.as_deref().unwrap_or("default");
+        let profile = args.common.resolved_profile();
+        let profile = profile.as_str();
         let target = args.common.target.as_deref().unwrap_or("all");
         let machine_override = args.common.machine.as_deref();
         let dry_run = args.common.dry_run.unwrap_or(false);
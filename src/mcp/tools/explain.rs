@@ -10,7 +10,8 @@ pub(super) async fn call_explain_in_process(
         };
 
         let repo_override = args.common.repo.as_ref().map(std::path::PathBuf::from);
-        let profile = args.common.profile.as_deref().unwrap_or("default");
+        let profile = args.common.resolved_profile();
+        let profile = profile.as_str();
         let target = args.common.target.as_deref().unwrap_or("all");
         let machine_override = args.common.machine.as_deref();
 
@@ -86,18 +87,24 @@ pub(super) async fn call_explain_in_process(
                                     m, &module_id, &tp, &roots,
                                 )
                             });
-                            let layer = match (module, module_path.as_deref()) {
+                            let layers = match (module, module_path.as_deref()) {
                                 (Some(m), Some(rel)) => Some(
-                                    crate::cli::util::source_layer_for_module_file(
-                                        &engine, m, rel,
+                                    crate::cli::util::source_layers_for_module_file(
+                                        &engine, m, profile, rel,
                                     )?,
                                 ),
                                 _ => None,
                             };
+                            let layer = layers.as_ref().map(|l| {
+                                l.last()
+                                    .cloned()
+                                    .unwrap_or_else(|| "missing".to_string())
+                            });
                             modules.push(ExplainedModule {
                                 module_id,
                                 module_type,
                                 layer,
+                                layers: layers.unwrap_or_default(),
                                 module_path,
                             });
                         }
@@ -111,7 +118,12 @@ pub(super) async fn call_explain_in_process(
                         });
                     }
 
-                    let data = explain_plan_json_data(profile, targets, explained);
+                    let data = explain_plan_json_data(
+                        profile,
+                        engine.machine_group()?,
+                        targets,
+                        explained,
+                    );
                     let mut envelope = crate::output::JsonEnvelope::ok(meta.command, data)
                         .with_command_meta(meta.command_id_string(), meta.command_path_vec());
                     envelope.warnings = warnings;
@@ -0,0 +1,42 @@
+use anyhow::Context as _;
+
+pub(super) async fn call_overlay_list_in_process(
+    args: super::OverlayListArgs,
+) -> anyhow::Result<(String, serde_json::Value)> {
+    tokio::task::spawn_blocking(move || {
+        let command_path = ["overlay", "list"];
+        let meta = super::CommandMeta {
+            command: "overlay.list",
+            command_id: "overlay list",
+            command_path: &command_path,
+        };
+
+        let repo_override = args.repo.as_ref().map(std::path::PathBuf::from);
+
+        let result = (|| -> anyhow::Result<(String, serde_json::Value)> {
+            let engine =
+                crate::engine::Engine::load(repo_override.as_deref(), args.machine.as_deref())?;
+            let report = crate::handlers::overlay::overlay_list_report(&engine)?;
+
+            let data = crate::handlers::overlay::overlay_list_json_data(&report);
+            let mut envelope = crate::output::JsonEnvelope::ok(meta.command, data)
+                .with_command_meta(meta.command_id_string(), meta.command_path_vec());
+            envelope.warnings = report.warnings;
+
+            let text = serde_json::to_string_pretty(&envelope)?;
+            let envelope = serde_json::to_value(&envelope)?;
+            Ok((text, envelope))
+        })();
+
+        match result {
+            Ok(v) => Ok(v),
+            Err(err) => {
+                let envelope = super::envelope_from_anyhow_error(meta, &err);
+                let text = serde_json::to_string_pretty(&envelope)?;
+                Ok((text, envelope))
+            }
+        }
+    })
+    .await
+    .context("mcp overlay list handler task join")?
+}
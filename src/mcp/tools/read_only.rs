@@ -13,7 +13,8 @@ pub(super) async fn call_read_only_in_process(
         };
 
         let repo_override = args.repo.as_ref().map(std::path::PathBuf::from);
-        let profile = args.profile.as_deref().unwrap_or("default");
+        let profile = args.resolved_profile();
+        let profile = profile.as_str();
         let target = args.target.as_deref().unwrap_or("all");
         let machine_override = args.machine.as_deref();
 
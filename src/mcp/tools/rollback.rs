@@ -14,17 +14,37 @@ pub(super) async fn call_rollback_in_process(
         let home = crate::paths::AgentpackHome::resolve().context("resolve agentpack home")?;
 
         let super::RollbackArgs {
-            repo: _repo_override,
+            repo: repo_override,
             to: snapshot_id,
             yes,
+            discard_local_edits,
         } = args;
-        let result = crate::handlers::rollback::rollback(&home, &snapshot_id, true, yes);
+        let repo = crate::paths::RepoPaths::resolve(
+            &home,
+            repo_override.as_deref().map(std::path::Path::new),
+        )
+        .context("resolve repo paths")?;
+        let result = crate::handlers::rollback::rollback(
+            &home,
+            &repo,
+            None,
+            &snapshot_id,
+            &crate::rollback_plan::RollbackSelection::default(),
+            discard_local_edits,
+            true,
+            yes,
+        );
 
         let (text, envelope) = match result {
-            Ok(event) => {
-                let data = crate::app::rollback_json::rollback_json_data(&snapshot_id, &event.id);
-                let envelope = crate::output::JsonEnvelope::ok(meta.command, data)
+            Ok((plan, event)) => {
+                let data = crate::app::rollback_json::rollback_json_data(
+                    &plan.snapshot_id,
+                    &event.id,
+                    &event.hooks,
+                );
+                let mut envelope = crate::output::JsonEnvelope::ok(meta.command, data)
                     .with_command_meta(meta.command_id_string(), meta.command_path_vec());
+                envelope.warnings = plan.warnings;
                 let text = serde_json::to_string_pretty(&envelope)?;
                 let envelope = serde_json::to_value(&envelope)?;
                 (text, envelope)
@@ -157,6 +157,19 @@ pub(super) async fn call_tool(
                 Err(err) => Ok(tool_result_unexpected(meta, &err)),
             }
         }
+        "overlay_list" => {
+            let command_path = ["overlay", "list"];
+            let meta = super::CommandMeta {
+                command: "overlay.list",
+                command_id: "overlay list",
+                command_path: &command_path,
+            };
+            let args = deserialize_args::<super::OverlayListArgs>(request.arguments)?;
+            match super::overlay_list::call_overlay_list_in_process(args).await {
+                Ok((text, envelope)) => Ok(tool_result_from_envelope(text, envelope)),
+                Err(err) => Ok(tool_result_unexpected(meta, &err)),
+            }
+        }
         other => Ok(CallToolResult {
             content: vec![Content::text(format!("unknown tool: {other}"))],
             structured_content: None,
@@ -57,7 +57,8 @@ pub(super) async fn call_status_in_process(
         }
 
         let repo_override = args.common.repo.as_ref().map(std::path::PathBuf::from);
-        let profile = args.common.profile.as_deref().unwrap_or("default");
+        let profile = args.common.resolved_profile();
+        let profile = profile.as_str();
         let target = args.common.target.as_deref().unwrap_or("all");
         let machine_override = args.common.machine.as_deref();
 
@@ -113,6 +114,7 @@ pub(super) async fn call_status_in_process(
                 summary.modified,
                 summary.missing,
                 summary.extra,
+                summary.permissions,
                 any_manifest,
                 report.needs_deploy_apply,
             ) {
@@ -129,6 +131,7 @@ pub(super) async fn call_status_in_process(
                     super::StatusOnly::Missing => "missing",
                     super::StatusOnly::Modified => "modified",
                     super::StatusOnly::Extra => "extra",
+                    super::StatusOnly::Permissions => "permissions",
                 })
                 .collect();
 
@@ -2,7 +2,7 @@ use rmcp::model::Tool;
 
 use super::{
     CommonArgs, DeployApplyArgs, DoctorArgs, EvolveProposeArgs, EvolveRestoreArgs, ExplainArgs,
-    PreviewArgs, RollbackArgs, StatusArgs, tool, tool_input_schema,
+    OverlayListArgs, PreviewArgs, RollbackArgs, StatusArgs, tool, tool_input_schema,
 };
 
 pub(super) fn tools() -> Vec<Tool> {
@@ -73,5 +73,11 @@ pub(super) fn tools() -> Vec<Tool> {
             tool_input_schema::<ExplainArgs>(),
             true,
         ),
+        tool(
+            "overlay_list",
+            "List overlays with kind, baseline and drift status (read-only; returns Agentpack JSON envelope).",
+            tool_input_schema::<OverlayListArgs>(),
+            true,
+        ),
     ]
 }
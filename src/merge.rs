@@ -0,0 +1,240 @@
+//! In-process line-based three-way merge (diff3), built on the same `similar` diffs as
+//! [`crate::diff`].
+//!
+//! `ours` and `theirs` are each diffed against `base`; regions where only one side changed take
+//! that side, regions where both made the same change take it once, and everything else becomes
+//! a conflict block in `git merge-file` style (`<<<<<<< ours` / `=======` / `>>>>>>> theirs`).
+
+use serde::Serialize;
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+pub const DEFAULT_LABELS: [&str; 3] = ["ours", "base", "theirs"];
+
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    /// Merged bytes; conflict blocks carry markers.
+    pub merged: Vec<u8>,
+    pub conflicted: bool,
+    pub hunks: Vec<ConflictHunk>,
+}
+
+/// A 1-based line range; `lines == 0` marks an empty side, positioned before line `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub lines: usize,
+}
+
+impl LineRange {
+    fn of(start_idx: usize, end_idx: usize) -> Self {
+        Self {
+            start: start_idx + 1,
+            lines: end_idx - start_idx,
+        }
+    }
+}
+
+/// One conflict block: where it sits in each input and in the merged output (markers
+/// included), plus the three competing snippets (lossy UTF-8).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConflictHunk {
+    pub base: LineRange,
+    pub ours: LineRange,
+    pub theirs: LineRange,
+    pub merged: LineRange,
+    pub base_text: String,
+    pub ours_text: String,
+    pub theirs_text: String,
+}
+
+/// Three-way merges `ours` and `theirs` against their common `base`. `labels` name the sides
+/// (`[ours, base, theirs]`) in conflict markers; the base label is currently unused because
+/// conflict blocks do not include the base section.
+pub fn merge_three_way(
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+    labels: Option<[&str; 3]>,
+) -> MergeOutcome {
+    let [ours_label, _, theirs_label] = labels.unwrap_or(DEFAULT_LABELS);
+    let base_lines = split_lines(base);
+    let ours_lines = split_lines(ours);
+    let theirs_lines = split_lines(theirs);
+
+    let ours_match = base_matches(&base_lines, &ours_lines);
+    let theirs_match = base_matches(&base_lines, &theirs_lines);
+
+    let mut out = MergeWriter::default();
+    let mut hunks = Vec::new();
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    loop {
+        // Stable run: the next base lines are kept, in lockstep, by both sides.
+        let mut k = 0;
+        while b + k < base_lines.len()
+            && ours_match[b + k] == Some(o + k)
+            && theirs_match[b + k] == Some(t + k)
+        {
+            k += 1;
+        }
+        if k > 0 {
+            out.push_lines(&base_lines[b..b + k]);
+            (b, o, t) = (b + k, o + k, t + k);
+            continue;
+        }
+
+        // Unstable region up to the next base line both sides kept (or the end).
+        let next = (b..base_lines.len())
+            .find_map(|j| Some((j, ours_match[j]?, theirs_match[j]?)))
+            .unwrap_or((base_lines.len(), ours_lines.len(), theirs_lines.len()));
+        if next == (b, o, t) {
+            break;
+        }
+        let (nb, no, nt) = next;
+        let base_chunk = &base_lines[b..nb];
+        let ours_chunk = &ours_lines[o..no];
+        let theirs_chunk = &theirs_lines[t..nt];
+
+        if ours_chunk == base_chunk || ours_chunk == theirs_chunk {
+            out.push_lines(theirs_chunk);
+        } else if theirs_chunk == base_chunk {
+            out.push_lines(ours_chunk);
+        } else {
+            let start = out.lines;
+            out.push_marker(&format!("<<<<<<< {ours_label}"));
+            out.push_lines(ours_chunk);
+            out.push_marker("=======");
+            out.push_lines(theirs_chunk);
+            out.push_marker(&format!(">>>>>>> {theirs_label}"));
+            hunks.push(ConflictHunk {
+                base: LineRange::of(b, nb),
+                ours: LineRange::of(o, no),
+                theirs: LineRange::of(t, nt),
+                merged: LineRange::of(start, out.lines),
+                base_text: join_lossy(base_chunk),
+                ours_text: join_lossy(ours_chunk),
+                theirs_text: join_lossy(theirs_chunk),
+            });
+        }
+        (b, o, t) = next;
+    }
+
+    MergeOutcome {
+        merged: out.bytes,
+        conflicted: !hunks.is_empty(),
+        hunks,
+    }
+}
+
+/// Splits into lines that keep their `\n` (the last line may lack one).
+fn split_lines(bytes: &[u8]) -> Vec<&[u8]> {
+    bytes.split_inclusive(|b| *b == b'\n').collect()
+}
+
+/// For every base line, the index of the line it is matched with on the other side.
+fn base_matches(base: &[&[u8]], other: &[&[u8]]) -> Vec<Option<usize>> {
+    let mut out = vec![None; base.len()];
+    for op in capture_diff_slices(Algorithm::Myers, base, other) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for i in 0..len {
+                out[old_index + i] = Some(new_index + i);
+            }
+        }
+    }
+    out
+}
+
+fn join_lossy(lines: &[&[u8]]) -> String {
+    String::from_utf8_lossy(&lines.concat()).into_owned()
+}
+
+#[derive(Default)]
+struct MergeWriter {
+    bytes: Vec<u8>,
+    lines: usize,
+}
+
+impl MergeWriter {
+    fn push_lines(&mut self, lines: &[&[u8]]) {
+        for line in lines {
+            self.bytes.extend_from_slice(line);
+        }
+        self.lines += lines.len();
+    }
+
+    /// Markers always start on a fresh line, even after a side that lacks a final newline.
+    fn push_marker(&mut self, marker: &str) {
+        if self.bytes.last().is_some_and(|b| *b != b'\n') {
+            self.bytes.push(b'\n');
+        }
+        self.bytes.extend_from_slice(marker.as_bytes());
+        self.bytes.push(b'\n');
+        self.lines += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(base: &str, ours: &str, theirs: &str) -> (String, MergeOutcome) {
+        let outcome = merge_three_way(base.as_bytes(), ours.as_bytes(), theirs.as_bytes(), None);
+        (String::from_utf8(outcome.merged.clone()).unwrap(), outcome)
+    }
+
+    #[test]
+    fn non_overlapping_edits_merge_cleanly() {
+        let (merged, outcome) = merge("a\nb\nc\nd\n", "A\nb\nc\nd\n", "a\nb\nc\nD\ne\n");
+        assert!(!outcome.conflicted);
+        assert_eq!(merged, "A\nb\nc\nD\ne\n");
+    }
+
+    #[test]
+    fn identical_edits_are_taken_once() {
+        let (merged, outcome) = merge("a\nb\n", "a\nB\n", "a\nB\n");
+        assert!(!outcome.conflicted);
+        assert_eq!(merged, "a\nB\n");
+    }
+
+    #[test]
+    fn overlapping_edits_conflict_with_hunks() {
+        let (merged, outcome) = merge("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert!(outcome.conflicted);
+        assert_eq!(
+            merged,
+            "a\n<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs\nc\n"
+        );
+        assert_eq!(
+            outcome.hunks,
+            vec![ConflictHunk {
+                base: LineRange { start: 2, lines: 1 },
+                ours: LineRange { start: 2, lines: 1 },
+                theirs: LineRange { start: 2, lines: 1 },
+                merged: LineRange { start: 2, lines: 5 },
+                base_text: "b\n".to_string(),
+                ours_text: "ours\n".to_string(),
+                theirs_text: "theirs\n".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn conflict_markers_start_on_a_new_line() {
+        let outcome = merge_three_way(b"a", b"b", b"c", Some(["local", "base", "upstream"]));
+        assert_eq!(
+            String::from_utf8(outcome.merged).unwrap(),
+            "<<<<<<< local\nb\n=======\nc\n>>>>>>> upstream\n"
+        );
+    }
+
+    #[test]
+    fn insertions_at_both_ends_merge_cleanly() {
+        let (merged, outcome) = merge("x\n", "top\nx\n", "x\nbottom\n");
+        assert!(!outcome.conflicted);
+        assert_eq!(merged, "top\nx\nbottom\n");
+    }
+}
@@ -77,3 +77,12 @@ where
     println!("{}", serde_json::to_string_pretty(envelope)?);
     Ok(())
 }
+
+/// One envelope per line, for commands that stream (`watch --json`).
+pub fn print_json_line<T>(envelope: &JsonEnvelope<T>) -> anyhow::Result<()>
+where
+    T: Serialize,
+{
+    println!("{}", serde_json::to_string(envelope)?);
+    Ok(())
+}
@@ -0,0 +1,288 @@
+//! Byte-range provenance of rendered outputs.
+//!
+//! Target adapters build outputs from module content plus text they synthesize themselves
+//! (Cursor/custom frontmatter, aggregation separators, module markers). [`SpannedOutput`] records
+//! which byte ranges came from which module, so `evolve propose` can map an edited output back to
+//! per-module content without relying on markers.
+
+use std::ops::Range;
+
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+/// A byte range of a rendered output. `module_id: None` marks text synthesized by the adapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputSpan {
+    pub module_id: Option<String>,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SpannedOutput {
+    bytes: Vec<u8>,
+    spans: Vec<OutputSpan>,
+}
+
+impl SpannedOutput {
+    pub fn push_synthesized(&mut self, text: &[u8]) {
+        self.push(None, text);
+    }
+
+    /// Appends module content; a newline the adapter adds to terminate it counts as content.
+    pub fn push_module(&mut self, module_id: &str, text: &[u8]) {
+        self.push(Some(module_id), text);
+    }
+
+    /// Appends another output, shifting its spans.
+    pub fn append(&mut self, other: SpannedOutput) {
+        for span in other.spans {
+            let text = &other.bytes[span.range.clone()];
+            self.push(span.module_id.as_deref(), text);
+        }
+    }
+
+    pub fn ends_with_newline(&self) -> bool {
+        self.bytes.ends_with(b"\n")
+    }
+
+    /// Appends a trailing newline when missing, extending the last span.
+    pub fn terminate_line(&mut self) {
+        if self.bytes.is_empty() || self.ends_with_newline() {
+            return;
+        }
+        self.bytes.push(b'\n');
+        if let Some(last) = self.spans.last_mut() {
+            last.range.end = self.bytes.len();
+        }
+    }
+
+    pub fn into_parts(self) -> (Vec<u8>, Vec<OutputSpan>) {
+        (self.bytes, self.spans)
+    }
+
+    fn push(&mut self, module_id: Option<&str>, text: &[u8]) {
+        if text.is_empty() {
+            return;
+        }
+        let start = self.bytes.len();
+        self.bytes.extend_from_slice(text);
+        let end = self.bytes.len();
+        if let Some(last) = self.spans.last_mut() {
+            if last.module_id.as_deref() == module_id {
+                last.range.end = end;
+                return;
+            }
+        }
+        self.spans.push(OutputSpan {
+            module_id: module_id.map(str::to_string),
+            range: start..end,
+        });
+    }
+}
+
+/// Joins instructions bodies the way aggregated outputs do: a `---` rule between modules, and
+/// per-module markers when there are several.
+pub fn aggregate_instructions<T: AsRef<[u8]>>(parts: &[(String, T)]) -> SpannedOutput {
+    let add_markers = parts.len() > 1;
+    let mut out = SpannedOutput::default();
+    for (i, (module_id, text)) in parts.iter().enumerate() {
+        let text = text.as_ref();
+        if i > 0 {
+            out.push_synthesized(b"\n\n---\n\n");
+        }
+        if add_markers {
+            let start = format!(
+                "{}{module_id} -->\n",
+                crate::markers::MODULE_SECTION_START_PREFIX
+            );
+            out.push_synthesized(start.as_bytes());
+            out.push_module(module_id, text);
+            out.terminate_line();
+            out.push_synthesized(crate::markers::MODULE_SECTION_END_MARKER.as_bytes());
+        } else {
+            out.push_module(module_id, text);
+        }
+    }
+    out
+}
+
+/// The part of an edited output that belongs to one module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleSlice {
+    pub module_id: String,
+    /// Range in the rendered (desired) bytes.
+    pub desired: Range<usize>,
+    /// Range in the edited (actual) bytes.
+    pub actual: Range<usize>,
+}
+
+/// Maps an edited output back to its module spans.
+///
+/// Synthesized spans anchor the mapping: each must survive unchanged (line-wise) in `actual`, and
+/// whatever lies between two anchors belongs to the module span between them. Returns `None` when
+/// synthesized text was edited or two module spans are not separated by synthesized text.
+pub fn split_module_edits(
+    desired: &[u8],
+    spans: &[OutputSpan],
+    actual: &[u8],
+) -> Option<Vec<ModuleSlice>> {
+    let desired_lines = split_lines(desired);
+    let actual_lines = split_lines(actual);
+    let desired_texts: Vec<&[u8]> = desired_lines.iter().map(|r| &desired[r.clone()]).collect();
+    let actual_texts: Vec<&[u8]> = actual_lines.iter().map(|r| &actual[r.clone()]).collect();
+
+    // For every unchanged desired line, the index of its counterpart in `actual`.
+    let mut equal_to: Vec<Option<usize>> = vec![None; desired_lines.len()];
+    for op in capture_diff_slices(Algorithm::Myers, &desired_texts, &actual_texts) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for i in 0..len {
+                equal_to[old_index + i] = Some(new_index + i);
+            }
+        }
+    }
+
+    let line_of = |offset: usize| desired_lines.partition_point(|r| r.end <= offset);
+    let map_synthesized = |range: &Range<usize>| -> Option<Range<usize>> {
+        let first = line_of(range.start);
+        let last = line_of(range.end - 1);
+        let mapped_first = equal_to.get(first).copied().flatten()?;
+        for (k, line) in (first..=last).enumerate() {
+            if equal_to.get(line).copied().flatten()? != mapped_first + k {
+                return None;
+            }
+        }
+        let start = actual_lines[mapped_first].start + (range.start - desired_lines[first].start);
+        let end = actual_lines[mapped_first + (last - first)].start
+            + (range.end - desired_lines[last].start);
+        Some(start..end)
+    };
+
+    let mut out = Vec::new();
+    let mut cursor = 0usize;
+    let mut pending: Option<&OutputSpan> = None;
+    for span in spans.iter().filter(|s| !s.range.is_empty()) {
+        match &span.module_id {
+            Some(_) => {
+                if pending.is_some() {
+                    return None;
+                }
+                pending = Some(span);
+            }
+            None => {
+                let mapped = map_synthesized(&span.range)?;
+                if mapped.start < cursor {
+                    return None;
+                }
+                if let Some(module_span) = pending.take() {
+                    out.push(module_slice(module_span, cursor..mapped.start));
+                } else if mapped.start != cursor {
+                    // Text inserted between two synthesized spans has no owner.
+                    return None;
+                }
+                cursor = mapped.end;
+            }
+        }
+    }
+    match pending {
+        Some(module_span) => out.push(module_slice(module_span, cursor..actual.len())),
+        None if cursor != actual.len() => return None,
+        None => {}
+    }
+    Some(out)
+}
+
+fn module_slice(span: &OutputSpan, actual: Range<usize>) -> ModuleSlice {
+    ModuleSlice {
+        module_id: span.module_id.clone().unwrap_or_default(),
+        desired: span.range.clone(),
+        actual,
+    }
+}
+
+fn split_lines(bytes: &[u8]) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        if *b == b'\n' {
+            out.push(start..i + 1);
+            start = i + 1;
+        }
+    }
+    if start < bytes.len() {
+        out.push(start..bytes.len());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(id, text)| (id.to_string(), text.to_string()))
+            .collect()
+    }
+
+    fn split(output: SpannedOutput, actual: &str) -> Option<Vec<(String, String)>> {
+        let (bytes, spans) = output.into_parts();
+        let slices = split_module_edits(&bytes, &spans, actual.as_bytes())?;
+        Some(
+            slices
+                .into_iter()
+                .map(|s| (s.module_id, actual[s.actual].to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn aggregated_bytes_match_marker_sections() {
+        let parts = parts(&[("a", "# a\n"), ("b", "# b")]);
+        let (bytes, _) = aggregate_instructions(&parts).into_parts();
+        let expected = [
+            crate::markers::format_module_section("a", "# a\n"),
+            crate::markers::format_module_section("b", "# b"),
+        ]
+        .join("\n\n---\n\n");
+        assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+    }
+
+    #[test]
+    fn edits_between_synthesized_text_map_to_their_module() {
+        let mut out = SpannedOutput::default();
+        out.push_synthesized(b"---\nglobs: []\n---\n\n");
+        out.push_module("a", b"one\ntwo");
+        out.terminate_line();
+
+        let edited = "---\nglobs: []\n---\n\none\ntwo (edited)\nthree\n";
+        assert_eq!(
+            split(out, edited),
+            Some(vec![(
+                "a".to_string(),
+                "one\ntwo (edited)\nthree\n".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn edited_synthesized_text_cannot_be_mapped() {
+        let mut out = SpannedOutput::default();
+        out.push_module("a", b"one\n");
+        out.push_synthesized(b"\n\n---\n\n");
+        out.push_module("b", b"two\n");
+
+        assert_eq!(
+            split(out.clone(), "one\nmore\n\n\n---\n\ntwo\n"),
+            Some(vec![
+                ("a".to_string(), "one\nmore\n".to_string()),
+                ("b".to_string(), "two\n".to_string()),
+            ])
+        );
+        assert_eq!(split(out, "one\n\n\n***\n\ntwo\n"), None);
+    }
+}
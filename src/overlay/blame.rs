@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::Serialize;
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+use super::{OverlayLayer, compose_module_tree};
+
+/// The layer that produced a line of a composed module file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LayerSource {
+    /// `upstream` or an overlay scope (`global`, `profile`, `group`, `machine`, `project`).
+    pub layer: String,
+    /// The layer changed the line through a patch (`.agentpack/patches/<relpath>.patch`).
+    pub patch: bool,
+}
+
+/// Replays the overlay composition one layer at a time and attributes each line of `rel_posix`
+/// in the final module tree to the layer that last changed it (`git blame` over the layers).
+///
+/// Returns `None` when the composed module has no such file. Lines exclude their newline.
+pub fn blame_module_file(
+    module_id: &str,
+    upstream_root: &Path,
+    overlays: &[OverlayLayer<'_>],
+    rel_posix: &str,
+) -> anyhow::Result<Option<Vec<(String, LayerSource)>>> {
+    let mut blamed: Option<Vec<(String, LayerSource)>> = None;
+    for depth in 0..=overlays.len() {
+        let source = match depth.checked_sub(1).map(|i| &overlays[i]) {
+            None => LayerSource {
+                layer: "upstream".to_string(),
+                patch: false,
+            },
+            Some(layer) => {
+                if !layer.dir.is_dir() {
+                    continue;
+                }
+                LayerSource {
+                    layer: layer.scope.to_string(),
+                    patch: layer
+                        .dir
+                        .join(".agentpack/patches")
+                        .join(format!("{rel_posix}.patch"))
+                        .is_file(),
+                }
+            }
+        };
+
+        let tmp = tempfile::tempdir().context("create tempdir")?;
+        let out_dir = tmp.path().join("module");
+        compose_module_tree(module_id, upstream_root, &overlays[..depth], &out_dir)?;
+        let path = out_dir.join(rel_posix);
+        blamed = if path.is_file() {
+            let bytes = std::fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+            let prev = blamed.take().unwrap_or_default();
+            Some(carry_line_origins(
+                &prev,
+                &String::from_utf8_lossy(&bytes),
+                &source,
+            ))
+        } else {
+            None
+        };
+    }
+    Ok(blamed)
+}
+
+/// Carries `prev` attributions over to the lines of `text`: lines the diff keeps retain their
+/// origin, inserted or changed lines get `fresh`.
+pub fn carry_line_origins<T: Clone>(
+    prev: &[(String, T)],
+    text: &str,
+    fresh: &T,
+) -> Vec<(String, T)> {
+    let old: Vec<&str> = prev.iter().map(|(line, _)| line.as_str()).collect();
+    let new: Vec<&str> = text.lines().collect();
+
+    let mut out: Vec<(String, T)> = new
+        .iter()
+        .map(|line| (line.to_string(), fresh.clone()))
+        .collect();
+    for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for i in 0..len {
+                out[new_index + i].1 = prev[old_index + i].1.clone();
+            }
+        }
+    }
+    out
+}
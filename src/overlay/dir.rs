@@ -8,7 +8,9 @@ use crate::hash::sha256_hex;
 use crate::user_error::UserError;
 
 use super::layout::delete_overlay_file;
-use super::rebase::{OverlayRebaseOptions, OverlayRebaseReport, merge_three_way_git};
+use crate::merge::merge_three_way;
+
+use super::rebase::{OverlayRebaseOptions, OverlayRebaseReport};
 
 pub(super) fn rebase_overlay_dir_files(
     overlay_dir: &Path,
@@ -98,10 +100,11 @@ pub(super) fn rebase_overlay_dir_files(
                     continue;
                 }
 
-                let merged = merge_three_way_git(&base, &ours, &upstream)?;
+                let merged = merge_three_way(&base, &ours, &upstream, None);
                 if merged.conflicted {
                     report.summary.conflict_files += 1;
                     report.conflicts.push(rel_posix.clone());
+                    report.record_conflict_hunks(&rel_posix, &rel_posix, merged.hunks);
                 }
 
                 if options.sparsify && !merged.conflicted && merged.merged == upstream {
@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::Serialize;
+
+use crate::fs::list_files;
+
+use super::layout::{BaselineUpstream, OverlayBaseline, OverlayKind, overlay_baseline_path};
+
+/// One existing overlay directory, as reported by `overlay list`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayInfo {
+    pub module_id: String,
+    pub scope: String,
+    /// Profile, group, machine or project id of the scope (absent for `global`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope_id: Option<String>,
+    pub overlay_dir: String,
+    pub overlay_dir_posix: String,
+    /// `dir` or `patch`.
+    pub kind: String,
+    /// Override files (excluding `.agentpack/`).
+    pub files: u64,
+    pub patches: u64,
+    pub deletes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<OverlayBaselineInfo>,
+    pub status: OverlayStatus,
+    /// Drift warnings (same text as `plan`/`deploy` warnings); empty unless `status=drifted`.
+    pub drift: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayBaselineInfo {
+    pub created_at: String,
+    pub upstream_sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<BaselineUpstream>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayStatus {
+    /// Upstream matches the overlay baseline.
+    Clean,
+    /// Upstream changed since the baseline (run `overlay rebase`).
+    Drifted,
+    /// No `.agentpack/baseline.json`, so drift cannot be computed.
+    NoBaseline,
+    /// The upstream module root could not be resolved.
+    UpstreamUnavailable,
+}
+
+impl OverlayStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Clean => "clean",
+            Self::Drifted => "drifted",
+            Self::NoBaseline => "no_baseline",
+            Self::UpstreamUnavailable => "upstream_unavailable",
+        }
+    }
+}
+
+/// Describes the overlay at `overlay_dir`, or returns `None` when it does not exist.
+/// `upstream_root=None` means the upstream could not be resolved (drift is not computed).
+pub fn inspect_overlay(
+    module_id: &str,
+    scope: &str,
+    upstream_root: Option<&Path>,
+    overlay_dir: &Path,
+) -> anyhow::Result<Option<OverlayInfo>> {
+    if !overlay_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let meta = super::layout::read_overlay_meta(overlay_dir)?;
+    let files = list_files(overlay_dir)
+        .with_context(|| format!("list overlay files {}", overlay_dir.display()))?;
+    let patches = super::patch::list_patch_files(overlay_dir)
+        .with_context(|| format!("list patch files {}", overlay_dir.display()))?;
+    let deletes = super::whiteout::read_overlay_deletes(overlay_dir)?;
+
+    let baseline_path = overlay_baseline_path(overlay_dir);
+    let baseline = if baseline_path.exists() {
+        let raw = std::fs::read_to_string(&baseline_path)
+            .with_context(|| format!("read {}", baseline_path.display()))?;
+        let baseline: OverlayBaseline =
+            serde_json::from_str(&raw).context("parse overlay baseline")?;
+        Some(OverlayBaselineInfo {
+            created_at: baseline.created_at,
+            upstream_sha256: baseline.upstream_sha256,
+            upstream: baseline.upstream,
+        })
+    } else {
+        None
+    };
+
+    let (status, drift) = match (&baseline, upstream_root) {
+        (None, _) => (OverlayStatus::NoBaseline, Vec::new()),
+        (Some(_), None) => (OverlayStatus::UpstreamUnavailable, Vec::new()),
+        (Some(_), Some(upstream_root)) => {
+            let drift =
+                super::overlay_drift_warnings(module_id, scope, upstream_root, overlay_dir)?;
+            if drift.is_empty() {
+                (OverlayStatus::Clean, drift)
+            } else {
+                (OverlayStatus::Drifted, drift)
+            }
+        }
+    };
+
+    Ok(Some(OverlayInfo {
+        module_id: module_id.to_string(),
+        scope: scope.to_string(),
+        scope_id: None,
+        overlay_dir: overlay_dir.to_string_lossy().to_string(),
+        overlay_dir_posix: crate::paths::path_to_posix_string(overlay_dir),
+        kind: match meta.overlay_kind {
+            OverlayKind::Dir => "dir",
+            OverlayKind::Patch => "patch",
+        }
+        .to_string(),
+        files: files.len() as u64,
+        patches: patches.len() as u64,
+        deletes: deletes.len() as u64,
+        baseline,
+        status,
+        drift,
+    }))
+}
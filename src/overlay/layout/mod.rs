@@ -44,7 +44,7 @@ pub(super) struct OverlayBaseline {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
-pub(super) enum BaselineUpstream {
+pub enum BaselineUpstream {
     Git {
         url: String,
         commit: String,
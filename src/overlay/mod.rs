@@ -1,7 +1,10 @@
+mod blame;
 mod dir;
+mod inventory;
 mod layout;
 mod patch;
 mod rebase;
+mod whiteout;
 
 use std::path::{Path, PathBuf};
 
@@ -10,14 +13,19 @@ use anyhow::Context as _;
 use crate::fs::{copy_tree, list_files};
 use crate::user_error::UserError;
 
+pub use blame::{LayerSource, blame_module_file, carry_line_origins};
+pub use inventory::{OverlayBaselineInfo, OverlayInfo, OverlayStatus, inspect_overlay};
 pub use layout::{
-    ensure_overlay_skeleton, ensure_overlay_skeleton_sparse, materialize_overlay_from_upstream,
-    resolve_upstream_module_root,
+    BaselineUpstream, ensure_overlay_skeleton, ensure_overlay_skeleton_sparse,
+    materialize_overlay_from_upstream, resolve_upstream_module_root,
+};
+pub use patch::{
+    OverlayCaptureReport, OverlayCaptureSkip, OverlayCaptureSource, capture_patch_overlay,
+    ensure_patch_overlay_layout,
 };
-pub use patch::ensure_patch_overlay_layout;
 pub use rebase::{
-    OverlayRebaseOptions, OverlayRebaseReport, OverlayRebaseSummary, overlay_drift_warnings,
-    rebase_overlay,
+    OverlayConflictHunk, OverlayRebaseOptions, OverlayRebaseReport, OverlayRebaseSummary,
+    overlay_drift_warnings, rebase_overlay,
 };
 
 #[derive(Debug, Clone)]
@@ -74,6 +82,10 @@ pub fn compose_module_tree(
             ));
         }
 
+        // Whiteouts apply before the layer's own files, so a layer can replace a directory.
+        let deletes = whiteout::read_overlay_deletes(overlay.dir)?;
+        whiteout::apply_overlay_deletes(out_dir, &deletes)?;
+
         match meta.overlay_kind {
             layout::OverlayKind::Dir => {
                 if has_patches {
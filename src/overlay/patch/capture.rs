@@ -0,0 +1,376 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::Serialize;
+
+use crate::config::Manifest;
+use crate::fs::{copy_tree, list_files, write_atomic};
+use crate::lockfile::hash_tree;
+use crate::paths::{AgentpackHome, RepoPaths};
+use crate::user_error::UserError;
+
+use super::super::layout::{
+    OverlayBaseline, OverlayKind, delete_overlay_file, ensure_overlay_skeleton_sparse, join_posix,
+    overlay_baseline_path, path_relative_posix, read_overlay_meta, resolve_upstream_module_root,
+    validate_posix_relpath,
+};
+
+/// Where `overlay capture` reads the edited module content from.
+#[derive(Debug, Clone, Copy)]
+pub enum OverlayCaptureSource<'a> {
+    /// The directory overlay being converted (typically created by `overlay edit`).
+    Overlay,
+    /// An edited copy of the whole module tree (e.g. a deployed skill directory).
+    Dir(&'a Path),
+    /// A single edited file; `relpath` defaults to the only upstream file with the same name.
+    File {
+        path: &'a Path,
+        relpath: Option<&'a str>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayCaptureSkip {
+    pub path: String,
+    /// `not_in_upstream` (patch overlays cannot create files) or `not_utf8`.
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct OverlayCaptureReport {
+    /// Module paths with a (re)written `.agentpack/patches/<relpath>.patch`.
+    pub patched: Vec<String>,
+    /// Module paths whose existing patch was dropped because the edit now matches upstream.
+    pub removed: Vec<String>,
+    /// Captured files identical to upstream.
+    pub unchanged: u64,
+    pub skipped: Vec<OverlayCaptureSkip>,
+}
+
+struct CapturedPatch {
+    relpath: String,
+    patch: Vec<u8>,
+    edited: Vec<u8>,
+}
+
+/// Turns edited module files into minimal per-file patches (diffed against the upstream module
+/// root) in a patch overlay at `overlay_dir`, after checking that applying the resulting patch
+/// set to upstream reproduces every edited file byte for byte.
+///
+/// With [`OverlayCaptureSource::Overlay`], the directory overlay's override files are replaced
+/// by the patches (the overlay becomes `overlay_kind=patch`).
+pub fn capture_patch_overlay(
+    home: &AgentpackHome,
+    repo: &RepoPaths,
+    manifest: &Manifest,
+    module_id: &str,
+    overlay_dir: &Path,
+    source: OverlayCaptureSource<'_>,
+    dry_run: bool,
+) -> anyhow::Result<OverlayCaptureReport> {
+    let module = manifest
+        .modules
+        .iter()
+        .find(|m| m.id == module_id)
+        .with_context(|| format!("module not found: {module_id}"))?;
+    let upstream_root = resolve_upstream_module_root(home, repo, module)?;
+
+    let override_files = if overlay_dir.exists() {
+        list_files(overlay_dir)
+            .with_context(|| format!("list overlay files {}", overlay_dir.display()))?
+    } else {
+        Vec::new()
+    };
+
+    let edited: Vec<(String, PathBuf)> = match source {
+        OverlayCaptureSource::Overlay => {
+            let is_dir_overlay = overlay_dir.exists()
+                && read_overlay_meta(overlay_dir)?.overlay_kind == OverlayKind::Dir;
+            if !is_dir_overlay || override_files.is_empty() {
+                return Err(capture_error(
+                    format!("no directory overlay files to capture for module {module_id}"),
+                    module_id,
+                    overlay_dir,
+                    "overlay_capture_no_source",
+                    &["overlay_edit", "pass_from"],
+                    "edit files with `overlay edit` first, or pass --from <edited dir|file>",
+                ));
+            }
+            ensure_overlay_baseline_current(module_id, overlay_dir, &upstream_root)?;
+            override_files
+                .iter()
+                .map(|p| (path_relative_posix(overlay_dir, p), p.clone()))
+                .collect()
+        }
+        OverlayCaptureSource::Dir(_) | OverlayCaptureSource::File { .. }
+            if !override_files.is_empty() =>
+        {
+            return Err(capture_error(
+                format!(
+                    "overlay for module {module_id} has directory override files; capture it without --from instead"
+                ),
+                module_id,
+                overlay_dir,
+                "overlay_capture_kind_conflict",
+                &["capture_overlay_without_from"],
+                "patch overlays cannot mix override files and patches",
+            ));
+        }
+        OverlayCaptureSource::Dir(dir) => list_files(dir)
+            .with_context(|| format!("list files {}", dir.display()))?
+            .into_iter()
+            .map(|p| (path_relative_posix(dir, &p), p))
+            .collect(),
+        OverlayCaptureSource::File { path, relpath } => {
+            let relpath = match relpath {
+                Some(relpath) => relpath.trim_start_matches("./").to_string(),
+                None => infer_relpath(module_id, overlay_dir, &upstream_root, path)?,
+            };
+            vec![(relpath, path.to_path_buf())]
+        }
+    };
+
+    let patches_root = overlay_dir.join(".agentpack").join("patches");
+    let mut report = OverlayCaptureReport::default();
+    let mut captured = Vec::new();
+    for (relpath, path) in edited {
+        if !validate_posix_relpath(&relpath) {
+            return Err(capture_error(
+                format!("invalid module path {relpath:?} for module {module_id}"),
+                module_id,
+                overlay_dir,
+                "overlay_capture_invalid_path",
+                &["fix_capture_path"],
+                "module paths must be relative POSIX paths without `..`",
+            ));
+        }
+
+        let upstream_path = join_posix(&upstream_root, &relpath);
+        if !upstream_path.is_file() {
+            report.skipped.push(OverlayCaptureSkip {
+                path: relpath,
+                reason: "not_in_upstream".to_string(),
+            });
+            continue;
+        }
+        let upstream = std::fs::read(&upstream_path)
+            .with_context(|| format!("read {}", upstream_path.display()))?;
+        let edited = std::fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+
+        if edited == upstream {
+            report.unchanged += 1;
+            if join_posix(&patches_root, &format!("{relpath}.patch")).exists() {
+                report.removed.push(relpath);
+            }
+            continue;
+        }
+        if std::str::from_utf8(&edited).is_err() || std::str::from_utf8(&upstream).is_err() {
+            report.skipped.push(OverlayCaptureSkip {
+                path: relpath,
+                reason: "not_utf8".to_string(),
+            });
+            continue;
+        }
+
+        let patch = super::rebase::compute_patch_from_upstream(&relpath, &upstream, &edited)?
+            .context("changed file produced an empty patch")?;
+        captured.push(CapturedPatch {
+            relpath,
+            patch,
+            edited,
+        });
+    }
+
+    // Converting a directory overlay must not silently drop files a patch cannot express.
+    if matches!(source, OverlayCaptureSource::Overlay) && !report.skipped.is_empty() {
+        return Err(anyhow::Error::new(
+            UserError::new(
+                "E_CONFIG_INVALID",
+                format!(
+                    "cannot convert overlay for module {module_id} to patches: {} file(s) are new or not UTF-8",
+                    report.skipped.len()
+                ),
+            )
+            .with_details(serde_json::json!({
+                "module_id": module_id,
+                "overlay_dir": overlay_dir.to_string_lossy(),
+                "skipped": report.skipped,
+                "reason_code": "overlay_capture_unsupported",
+                "next_actions": ["keep_dir_overlay"],
+                "hint": "patch overlays only modify existing upstream UTF-8 text files",
+            })),
+        ));
+    }
+
+    verify_captured_patches(module_id, overlay_dir, &upstream_root, &captured, &report)?;
+
+    report.patched = captured.iter().map(|c| c.relpath.clone()).collect();
+    if dry_run {
+        return Ok(report);
+    }
+
+    ensure_overlay_skeleton_sparse(home, repo, manifest, module_id, overlay_dir)?;
+    if matches!(source, OverlayCaptureSource::Overlay) {
+        for file in &override_files {
+            delete_overlay_file(overlay_dir, file, false)?;
+        }
+    }
+    let patches_dir = super::ensure_patch_overlay_layout(module_id, overlay_dir)?;
+    for c in &captured {
+        let path = join_posix(&patches_dir, &format!("{}.patch", c.relpath));
+        write_atomic(&path, &c.patch).with_context(|| format!("write {}", path.display()))?;
+    }
+    for relpath in &report.removed {
+        let path = join_posix(&patches_dir, &format!("{relpath}.patch"));
+        delete_overlay_file(&patches_dir, &path, false)?;
+    }
+
+    Ok(report)
+}
+
+/// Applies the resulting patch set (existing patches plus the captured ones) to a scratch copy
+/// of upstream and checks that every captured file comes out exactly as edited.
+fn verify_captured_patches(
+    module_id: &str,
+    overlay_dir: &Path,
+    upstream_root: &Path,
+    captured: &[CapturedPatch],
+    report: &OverlayCaptureReport,
+) -> anyhow::Result<()> {
+    let td = tempfile::tempdir().context("create tempdir")?;
+    let stage_dir = td.path().join("overlay");
+    let out_dir = td.path().join("out");
+
+    let stage_patches = stage_dir.join(".agentpack").join("patches");
+    let patches_root = overlay_dir.join(".agentpack").join("patches");
+    for existing in super::list_patch_files(overlay_dir)? {
+        let rel = path_relative_posix(&patches_root, &existing);
+        let target = rel.strip_suffix(".patch").unwrap_or(&rel);
+        if report.removed.iter().any(|r| r == target) {
+            continue;
+        }
+        let bytes =
+            std::fs::read(&existing).with_context(|| format!("read {}", existing.display()))?;
+        write_atomic(&join_posix(&stage_patches, &rel), &bytes)?;
+    }
+    for c in captured {
+        write_atomic(
+            &join_posix(&stage_patches, &format!("{}.patch", c.relpath)),
+            &c.patch,
+        )?;
+    }
+
+    std::fs::create_dir_all(&out_dir).context("create verify dir")?;
+    copy_tree(upstream_root, &out_dir).context("copy upstream")?;
+    let patch_files = super::list_patch_files(&stage_dir)?;
+    super::apply_patch_overlays(module_id, "capture", &stage_dir, &out_dir, &patch_files)?;
+
+    for c in captured {
+        let got = std::fs::read(join_posix(&out_dir, &c.relpath)).ok();
+        if got.as_deref() != Some(c.edited.as_slice()) {
+            return Err(anyhow::Error::new(
+                UserError::new(
+                    "E_OVERLAY_PATCH_APPLY_FAILED",
+                    format!(
+                        "captured patch for module {module_id} does not reproduce the edited file: {}",
+                        c.relpath
+                    ),
+                )
+                .with_details(serde_json::json!({
+                    "module_id": module_id,
+                    "overlay_dir": overlay_dir.to_string_lossy(),
+                    "relpath": c.relpath,
+                    "reason_code": "overlay_capture_mismatch",
+                    "next_actions": ["switch_to_dir_overlay"],
+                    "hint": "keep this edit in a directory overlay",
+                })),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Copied-but-unedited files in a stale directory overlay would turn into patches that revert
+/// upstream changes, so capturing requires an up-to-date baseline.
+fn ensure_overlay_baseline_current(
+    module_id: &str,
+    overlay_dir: &Path,
+    upstream_root: &Path,
+) -> anyhow::Result<()> {
+    let baseline_path = overlay_baseline_path(overlay_dir);
+    if !baseline_path.exists() {
+        return Ok(());
+    }
+    let raw = std::fs::read_to_string(&baseline_path)
+        .with_context(|| format!("read {}", baseline_path.display()))?;
+    let baseline: OverlayBaseline = serde_json::from_str(&raw).context("parse overlay baseline")?;
+    let (_, current_hash) = hash_tree(upstream_root)
+        .with_context(|| format!("hash upstream {}", upstream_root.display()))?;
+    if baseline.upstream_sha256 == current_hash {
+        return Ok(());
+    }
+
+    Err(capture_error(
+        format!("upstream changed since the overlay baseline for module {module_id}"),
+        module_id,
+        overlay_dir,
+        "overlay_capture_drifted",
+        &["overlay_rebase", "retry_overlay_capture"],
+        "run `agentpack overlay rebase` first so unedited copies match the current upstream",
+    ))
+}
+
+fn infer_relpath(
+    module_id: &str,
+    overlay_dir: &Path,
+    upstream_root: &Path,
+    path: &Path,
+) -> anyhow::Result<String> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+    let candidates: Vec<String> = list_files(upstream_root)?
+        .into_iter()
+        .filter(|p| p.file_name().map(|n| n.to_string_lossy().to_string()) == name)
+        .map(|p| path_relative_posix(upstream_root, &p))
+        .collect();
+
+    match candidates.as_slice() {
+        [only] => Ok(only.clone()),
+        _ => Err(anyhow::Error::new(
+            UserError::new(
+                "E_CONFIG_INVALID",
+                format!(
+                    "cannot tell which file of module {module_id} {} is; pass its module path",
+                    path.display()
+                ),
+            )
+            .with_details(serde_json::json!({
+                "module_id": module_id,
+                "overlay_dir": overlay_dir.to_string_lossy(),
+                "from": path.to_string_lossy(),
+                "candidates": candidates,
+                "reason_code": "overlay_capture_ambiguous",
+                "next_actions": ["pass_module_path"],
+                "hint": "use --rel <module-relative path>",
+            })),
+        )),
+    }
+}
+
+fn capture_error(
+    message: String,
+    module_id: &str,
+    overlay_dir: &Path,
+    reason_code: &str,
+    next_actions: &[&str],
+    hint: &str,
+) -> anyhow::Error {
+    anyhow::Error::new(UserError::new("E_CONFIG_INVALID", message).with_details(
+        serde_json::json!({
+            "module_id": module_id,
+            "overlay_dir": overlay_dir.to_string_lossy(),
+            "reason_code": reason_code,
+            "next_actions": next_actions,
+            "hint": hint,
+        }),
+    ))
+}
@@ -1,10 +1,9 @@
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 use anyhow::Context as _;
 use walkdir::WalkDir;
 
-use crate::fs::list_files;
+use crate::fs::{list_files, write_atomic};
 use crate::user_error::UserError;
 
 use super::layout::{
@@ -12,7 +11,13 @@ use super::layout::{
     write_overlay_meta,
 };
 
+mod capture;
 mod rebase;
+mod unified;
+
+pub use capture::{
+    OverlayCaptureReport, OverlayCaptureSkip, OverlayCaptureSource, capture_patch_overlay,
+};
 
 pub fn ensure_patch_overlay_layout(module_id: &str, overlay_dir: &Path) -> anyhow::Result<PathBuf> {
     let override_files = list_files(overlay_dir)?;
@@ -127,7 +132,7 @@ pub(super) fn apply_patch_overlays(
             )
         })?;
 
-        if std::str::from_utf8(&target_bytes).is_err() {
+        let Ok(target_text) = std::str::from_utf8(&target_bytes) else {
             return Err(anyhow::Error::new(
                 UserError::new(
                     "E_CONFIG_INVALID",
@@ -143,7 +148,7 @@ pub(super) fn apply_patch_overlays(
                     "hint": "use a directory overlay for binary/non-UTF8 files",
                 })),
             ));
-        }
+        };
 
         let patch_bytes = std::fs::read(patch_file)
             .with_context(|| format!("read patch {}", patch_file.display()))?;
@@ -175,18 +180,8 @@ pub(super) fn apply_patch_overlays(
             rel_target,
         )?;
 
-        let out = Command::new("git")
-            .arg("-c")
-            .arg("core.autocrlf=false")
-            .arg("apply")
-            .arg("--whitespace=nowarn")
-            .arg(patch_file)
-            .current_dir(out_dir)
-            .output()
-            .context("git apply")?;
-
-        if !out.status.success() {
-            return Err(anyhow::Error::new(
+        let patched = unified::apply_unified_diff(target_text, patch_text).map_err(|err| {
+            anyhow::Error::new(
                 UserError::new(
                     "E_OVERLAY_PATCH_APPLY_FAILED",
                     format!(
@@ -199,13 +194,15 @@ pub(super) fn apply_patch_overlays(
                     "overlay_dir": overlay_dir.to_string_lossy(),
                     "patch_file": patch_file.to_string_lossy(),
                     "relpath": rel_target,
-                    "stderr": String::from_utf8_lossy(&out.stderr),
+                    "error": format!("{err:#}"),
                     "reason_code": "overlay_patch_apply_failed",
                     "next_actions": ["regenerate_patch", "switch_to_dir_overlay", "retry_command"],
                     "hint": "regenerate the patch against the current upstream (or lower overlays) content",
                 })),
-            ));
-        }
+            )
+        })?;
+        write_atomic(&target_path, patched.as_bytes())
+            .with_context(|| format!("write {}", target_path.display()))?;
     }
 
     Ok(())
@@ -240,7 +237,11 @@ fn validate_patch_text_matches_file(
 
     let mut old_lines = Vec::new();
     let mut new_lines = Vec::new();
-    for line in patch_text.lines() {
+    // Only the file header before the first hunk names paths; hunk lines may start with `---`.
+    for line in patch_text
+        .lines()
+        .take_while(|line| !line.starts_with("@@ "))
+    {
         if let Some(rest) = line.strip_prefix("--- ") {
             old_lines.push(rest);
         } else if let Some(rest) = line.strip_prefix("+++ ") {
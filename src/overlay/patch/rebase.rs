@@ -1,6 +1,5 @@
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 use anyhow::Context as _;
 
@@ -9,7 +8,10 @@ use crate::hash::sha256_hex;
 use crate::user_error::UserError;
 
 use super::super::layout::{delete_overlay_file, join_posix, validate_posix_relpath};
-use super::super::rebase::{OverlayRebaseOptions, OverlayRebaseReport, merge_three_way_git};
+use super::unified::{apply_unified_diff, unified_diff};
+use crate::merge::merge_three_way;
+
+use super::super::rebase::{OverlayRebaseOptions, OverlayRebaseReport};
 
 pub(super) fn rebase_overlay_patch_files(
     module_id: &str,
@@ -115,10 +117,15 @@ pub(super) fn rebase_overlay_patch_files(
             ));
         }
 
-        let merged = merge_three_way_git(&base, &ours, &upstream)?;
+        let merged = merge_three_way(&base, &ours, &upstream, None);
         if merged.conflicted {
             report.summary.conflict_files += 1;
             report.conflicts.push(rel_target.to_string());
+            report.record_conflict_hunks(
+                rel_target,
+                &format!(".agentpack/conflicts/{rel_target}"),
+                merged.hunks,
+            );
             if !options.dry_run {
                 write_patch_conflict_artifact(overlay_dir, rel_target, &merged.merged)?;
                 // Keep the patch overlay consistent with the updated upstream by rewriting the
@@ -163,7 +170,7 @@ fn apply_patch_to_base_for_rebase(
     rel_target: &str,
     base: &[u8],
 ) -> anyhow::Result<Vec<u8>> {
-    if std::str::from_utf8(base).is_err() {
+    let Ok(base_text) = std::str::from_utf8(base) else {
         return Err(anyhow::Error::new(
             UserError::new(
                 "E_CONFIG_INVALID",
@@ -177,7 +184,7 @@ fn apply_patch_to_base_for_rebase(
                 "hint": "use a directory overlay for binary/non-UTF8 files",
             })),
         ));
-    }
+    };
 
     let patch_bytes =
         std::fs::read(patch_file).with_context(|| format!("read {}", patch_file.display()))?;
@@ -207,22 +214,8 @@ fn apply_patch_to_base_for_rebase(
         rel_target,
     )?;
 
-    let td = tempfile::tempdir().context("create tempdir")?;
-    let target_path = join_posix(td.path(), rel_target);
-    write_atomic(&target_path, base).with_context(|| format!("write {}", target_path.display()))?;
-
-    let out = Command::new("git")
-        .arg("-c")
-        .arg("core.autocrlf=false")
-        .arg("apply")
-        .arg("--whitespace=nowarn")
-        .arg(patch_file)
-        .current_dir(td.path())
-        .output()
-        .context("git apply")?;
-
-    if !out.status.success() {
-        return Err(anyhow::Error::new(
+    let ours = apply_unified_diff(base_text, patch_text).map_err(|err| {
+        anyhow::Error::new(
             UserError::new(
                 "E_CONFIG_INVALID",
                 format!("patch does not apply to baseline for {rel_target} (module {module_id})"),
@@ -232,55 +225,24 @@ fn apply_patch_to_base_for_rebase(
                 "overlay_dir": overlay_dir.to_string_lossy(),
                 "patch_file": patch_file.to_string_lossy(),
                 "relpath": rel_target,
-                "stderr": String::from_utf8_lossy(&out.stderr),
+                "error": format!("{err:#}"),
                 "hint": "regenerate the patch against the baseline content (or recreate the overlay baseline)",
             })),
-        ));
-    }
-
-    std::fs::read(&target_path).with_context(|| format!("read {}", target_path.display()))
+        )
+    })?;
+    Ok(ours.into_bytes())
 }
 
-fn compute_patch_from_upstream(
+/// Returns the patch turning `upstream` into `merged`, or `None` when they are identical.
+/// Both sides must be UTF-8 (callers check).
+pub(super) fn compute_patch_from_upstream(
     rel_target: &str,
     upstream: &[u8],
     merged: &[u8],
 ) -> anyhow::Result<Option<Vec<u8>>> {
-    let td = tempfile::tempdir().context("create tempdir")?;
-
-    let a_root = td.path().join("a");
-    let b_root = td.path().join("b");
-    let a_path = join_posix(&a_root, rel_target);
-    let b_path = join_posix(&b_root, rel_target);
-
-    write_atomic(&a_path, upstream).with_context(|| format!("write {}", a_path.display()))?;
-    write_atomic(&b_path, merged).with_context(|| format!("write {}", b_path.display()))?;
-
-    let a_rel = format!("a/{rel_target}");
-    let b_rel = format!("b/{rel_target}");
-
-    let out = Command::new("git")
-        .current_dir(td.path())
-        .arg("-c")
-        .arg("core.autocrlf=false")
-        .arg("diff")
-        .arg("--no-index")
-        .arg("--src-prefix=")
-        .arg("--dst-prefix=")
-        .arg("--")
-        .arg(a_rel)
-        .arg(b_rel)
-        .output()
-        .context("git diff --no-index")?;
-
-    match out.status.code() {
-        Some(0) => Ok(None),
-        Some(1) => Ok(Some(out.stdout)),
-        _ => anyhow::bail!(
-            "git diff --no-index failed: {}",
-            String::from_utf8_lossy(&out.stderr)
-        ),
-    }
+    let upstream = std::str::from_utf8(upstream).context("upstream content must be UTF-8")?;
+    let merged = std::str::from_utf8(merged).context("patched content must be UTF-8")?;
+    Ok(unified_diff(rel_target, upstream, merged).map(String::into_bytes))
 }
 
 fn write_patch_conflict_artifact(
@@ -0,0 +1,324 @@
+//! In-process single-file unified diffs for patch overlays: generating them (on the same
+//! `similar` line diffs as [`crate::merge`]) and applying them, so patch overlays work without
+//! a `git` executable.
+//!
+//! The applier follows `git apply` defaults: every context and removed line must match exactly,
+//! and a hunk that no longer sits at its recorded line is searched for nearby (before later
+//! hunks, after earlier ones). `\ No newline at end of file` markers are honoured on both sides.
+
+use similar::{Algorithm, DiffTag, capture_diff_slices, group_diff_ops};
+
+const CONTEXT_LINES: usize = 3;
+const NO_NEWLINE_MARKER: &str = "\\ No newline at end of file";
+
+/// Returns a `--- a/<relpath>` / `+++ b/<relpath>` unified diff turning `old` into `new`, or
+/// `None` when they are identical.
+pub(super) fn unified_diff(rel_target: &str, old: &str, new: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = capture_diff_slices(Algorithm::Myers, &old_lines, &new_lines);
+
+    let mut out = format!(
+        "diff --git a/{rel_target} b/{rel_target}\n--- a/{rel_target}\n+++ b/{rel_target}\n"
+    );
+    for group in group_diff_ops(ops, CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_range.start, old_range.len()),
+            hunk_range(new_range.start, new_range.len())
+        ));
+
+        for op in &group {
+            let (tag, old_op, new_op) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => push_lines(&mut out, ' ', &old_lines[old_op]),
+                DiffTag::Delete => push_lines(&mut out, '-', &old_lines[old_op]),
+                DiffTag::Insert => push_lines(&mut out, '+', &new_lines[new_op]),
+                DiffTag::Replace => {
+                    push_lines(&mut out, '-', &old_lines[old_op]);
+                    push_lines(&mut out, '+', &new_lines[new_op]);
+                }
+            }
+        }
+    }
+    Some(out)
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        // Empty sides are positioned after the line before them.
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+fn push_lines(out: &mut String, tag: char, lines: &[&str]) {
+    for line in lines {
+        out.push(tag);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push('\n');
+            out.push_str(NO_NEWLINE_MARKER);
+            out.push('\n');
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Hunk {
+    header: String,
+    old_start: usize,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+/// Applies a single-file unified diff to `base`.
+pub(super) fn apply_unified_diff(base: &str, patch: &str) -> anyhow::Result<String> {
+    let hunks = parse_hunks(patch)?;
+    if hunks.is_empty() {
+        anyhow::bail!("patch contains no hunks");
+    }
+
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(base.len());
+    let mut cursor = 0usize;
+    let mut offset = 0isize;
+    for (idx, hunk) in hunks.iter().enumerate() {
+        // An empty old side is positioned after line `old_start`; otherwise it starts there.
+        let recorded = if hunk.old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = recorded.saturating_add_signed(offset);
+        let Some(pos) = find_hunk(&base_lines, &hunk.old, cursor, expected) else {
+            anyhow::bail!(
+                "hunk #{} ({}) does not apply: context or removed lines not found",
+                idx + 1,
+                hunk.header
+            );
+        };
+
+        out.extend(base_lines[cursor..pos].iter().copied());
+        out.extend(hunk.new.iter().map(String::as_str));
+        cursor = pos + hunk.old.len();
+        offset = pos as isize - recorded as isize;
+    }
+    out.extend(base_lines[cursor..].iter().copied());
+    Ok(out)
+}
+
+/// Finds where `old` matches `lines` at or after `min`, preferring the position closest to
+/// `expected`.
+fn find_hunk(lines: &[&str], old: &[String], min: usize, expected: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(old.len())?;
+    if min > last {
+        return None;
+    }
+    let expected = expected.clamp(min, last);
+    let matches = |pos: usize| {
+        lines[pos..pos + old.len()]
+            .iter()
+            .zip(old)
+            .all(|(got, want)| *got == want)
+    };
+
+    for distance in 0..=(last - min) {
+        if let Some(pos) = expected.checked_add(distance).filter(|p| *p <= last)
+            && matches(pos)
+        {
+            return Some(pos);
+        }
+        if let Some(pos) = expected.checked_sub(distance).filter(|p| *p >= min)
+            && matches(pos)
+        {
+            return Some(pos);
+        }
+    }
+    None
+}
+
+fn parse_hunks(patch: &str) -> anyhow::Result<Vec<Hunk>> {
+    let mut lines = patch.split_inclusive('\n').peekable();
+    let mut hunks = Vec::new();
+
+    while let Some(line) = lines.next() {
+        if !line.starts_with("@@ ") {
+            // File headers (`diff --git`, `index`, `---`, `+++`) precede the first hunk; a second
+            // file section after it is not supported.
+            if !hunks.is_empty() && !line.trim().is_empty() {
+                anyhow::bail!(
+                    "unexpected line after hunk #{}: {}",
+                    hunks.len(),
+                    line.trim_end()
+                );
+            }
+            continue;
+        }
+
+        let header = line.trim_end().to_string();
+        let (old_start, mut old_left, mut new_left) = parse_hunk_header(&header)?;
+        let mut hunk = Hunk {
+            header,
+            old_start,
+            old: Vec::new(),
+            new: Vec::new(),
+        };
+
+        // Which sides the previous line went to, for `\ No newline at end of file`.
+        let mut last = (false, false);
+        while old_left > 0 || new_left > 0 || lines.peek().is_some_and(|l| l.starts_with('\\')) {
+            let Some(line) = lines.next() else {
+                anyhow::bail!("hunk {} is truncated", hunk.header);
+            };
+            // Some editors strip the single space of empty context lines.
+            let (tag, content) = match line {
+                "\n" | "\r\n" => (' ', line),
+                _ => {
+                    let mut chars = line.chars();
+                    let tag = chars.next().unwrap_or(' ');
+                    (tag, chars.as_str())
+                }
+            };
+            match tag {
+                ' ' if old_left > 0 && new_left > 0 => {
+                    hunk.old.push(content.to_string());
+                    hunk.new.push(content.to_string());
+                    (old_left, new_left) = (old_left - 1, new_left - 1);
+                    last = (true, true);
+                }
+                '-' if old_left > 0 => {
+                    hunk.old.push(content.to_string());
+                    old_left -= 1;
+                    last = (true, false);
+                }
+                '+' if new_left > 0 => {
+                    hunk.new.push(content.to_string());
+                    new_left -= 1;
+                    last = (false, true);
+                }
+                '\\' => {
+                    if last.0 {
+                        strip_newline(hunk.old.last_mut());
+                    }
+                    if last.1 {
+                        strip_newline(hunk.new.last_mut());
+                    }
+                }
+                _ => anyhow::bail!(
+                    "malformed line in hunk {}: {}",
+                    hunk.header,
+                    line.trim_end()
+                ),
+            }
+        }
+        hunks.push(hunk);
+    }
+
+    Ok(hunks)
+}
+
+fn strip_newline(line: Option<&mut String>) {
+    if let Some(line) = line
+        && line.ends_with('\n')
+    {
+        line.pop();
+    }
+}
+
+/// Parses `@@ -old_start[,old_len] +new_start[,new_len] @@`.
+fn parse_hunk_header(header: &str) -> anyhow::Result<(usize, usize, usize)> {
+    let malformed = || anyhow::anyhow!("malformed hunk header: {header}");
+    let ranges = header
+        .strip_prefix("@@ ")
+        .and_then(|rest| rest.split_once(" @@"))
+        .map(|(ranges, _)| ranges)
+        .ok_or_else(malformed)?;
+    let (old, new) = ranges.split_once(' ').ok_or_else(malformed)?;
+    let parse = |range: &str, sign: char| -> anyhow::Result<(usize, usize)> {
+        let range = range.strip_prefix(sign).ok_or_else(malformed)?;
+        let (start, len) = range.split_once(',').unwrap_or((range, "1"));
+        Ok((
+            start.parse().map_err(|_| malformed())?,
+            len.parse().map_err(|_| malformed())?,
+        ))
+    };
+    let (old_start, old_len) = parse(old, '-')?;
+    let (_, new_len) = parse(new, '+')?;
+    Ok((old_start, old_len, new_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(old: &str, new: &str) {
+        let patch = unified_diff("a.md", old, new).expect("files differ");
+        assert_eq!(
+            apply_unified_diff(old, &patch).expect("apply"),
+            new,
+            "{patch}"
+        );
+    }
+
+    #[test]
+    fn identical_content_has_no_diff() {
+        assert!(unified_diff("a.md", "a\n", "a\n").is_none());
+    }
+
+    #[test]
+    fn generated_diffs_roundtrip() {
+        roundtrip("a\nb\nc\n", "a\nB\nc\n");
+        roundtrip("", "new\nfile\n");
+        roundtrip("gone\n", "");
+        roundtrip("a\nb", "a\nb\n");
+        roundtrip("a\nb\n", "a\nb");
+        roundtrip("only", "changed");
+        roundtrip("a\r\nb\r\n", "a\r\nB\r\n");
+        let long: String = (0..40).map(|i| format!("line {i}\n")).collect();
+        roundtrip(
+            &long,
+            &long.replace("line 3\n", "x\n").replace("line 30\n", "y\n"),
+        );
+    }
+
+    #[test]
+    fn diff_uses_git_style_headers() {
+        let patch = unified_diff("dir/a.md", "a\nb\n", "a\nc\n").expect("diff");
+        assert_eq!(
+            patch,
+            "diff --git a/dir/a.md b/dir/a.md\n--- a/dir/a.md\n+++ b/dir/a.md\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n"
+        );
+    }
+
+    #[test]
+    fn hunks_apply_at_shifted_positions() {
+        let patch = unified_diff("a.md", "a\nb\nc\n", "a\nB\nc\n").expect("diff");
+        let applied = apply_unified_diff("new top\nmore\na\nb\nc\n", &patch).expect("apply");
+        assert_eq!(applied, "new top\nmore\na\nB\nc\n");
+    }
+
+    #[test]
+    fn mismatched_context_is_rejected() {
+        let patch = unified_diff("a.md", "a\nb\nc\n", "a\nB\nc\n").expect("diff");
+        let err = apply_unified_diff("a\nx\nc\n", &patch).unwrap_err();
+        assert!(err.to_string().contains("does not apply"), "{err}");
+    }
+
+    #[test]
+    fn second_file_sections_are_rejected() {
+        let mut patch = unified_diff("a.md", "a\n", "b\n").expect("diff");
+        patch.push_str("--- a/other.md\n+++ b/other.md\n@@ -1 +1 @@\n-a\n+b\n");
+        assert!(apply_unified_diff("a\n", &patch).is_err());
+    }
+}
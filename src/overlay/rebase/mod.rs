@@ -1,16 +1,15 @@
 use std::collections::BTreeMap;
-use std::io::Write as _;
 use std::path::Path;
-use std::process::Command;
 
 use anyhow::Context as _;
 use serde::Serialize;
-use tempfile::NamedTempFile;
 
 use crate::config::Manifest;
 use crate::fs::list_files;
 use crate::lockfile::hash_tree;
+use crate::merge::ConflictHunk;
 use crate::paths::{AgentpackHome, RepoPaths};
+use crate::state_lock::StateLock;
 use crate::user_error::UserError;
 
 use super::layout::{
@@ -85,6 +84,14 @@ pub fn overlay_drift_warnings(
         ));
     }
 
+    warnings.extend(super::whiteout::whiteout_warnings(
+        module_id,
+        overlay_kind,
+        &super::whiteout::read_overlay_deletes(overlay_dir)?,
+        &baseline_map,
+        &current_map,
+    ));
+
     Ok(warnings)
 }
 
@@ -109,7 +116,40 @@ pub struct OverlayRebaseReport {
     pub deleted: Vec<String>,
     pub skipped: Vec<String>,
     pub conflicts: Vec<String>,
+    /// Structured conflict blocks for `conflicts` (line-level three-way merge conflicts only).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflict_hunks: Vec<OverlayConflictHunk>,
     pub summary: OverlayRebaseSummary,
+    /// Whiteouts whose upstream path disappeared or changed (reported as envelope warnings).
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayConflictHunk {
+    /// Module-relative path of the conflicted file.
+    pub path: String,
+    /// Overlay-relative path of the conflict-marked file (the override file itself for dir
+    /// overlays, `.agentpack/conflicts/<path>` for patch overlays).
+    pub conflict_file: String,
+    #[serde(flatten)]
+    pub hunk: ConflictHunk,
+}
+
+impl OverlayRebaseReport {
+    pub(super) fn record_conflict_hunks(
+        &mut self,
+        path: &str,
+        conflict_file: &str,
+        hunks: Vec<ConflictHunk>,
+    ) {
+        self.conflict_hunks
+            .extend(hunks.into_iter().map(|hunk| OverlayConflictHunk {
+                path: path.to_string(),
+                conflict_file: conflict_file.to_string(),
+                hunk,
+            }));
+    }
 }
 
 pub fn rebase_overlay(
@@ -120,6 +160,11 @@ pub fn rebase_overlay(
     overlay_dir: &Path,
     options: OverlayRebaseOptions,
 ) -> anyhow::Result<OverlayRebaseReport> {
+    let _lock = if options.dry_run {
+        None
+    } else {
+        Some(StateLock::acquire(home, "overlay rebase")?)
+    };
     if !overlay_dir.exists() {
         return Err(anyhow::Error::new(
             UserError::new(
@@ -299,6 +344,20 @@ pub fn rebase_overlay(
         }
     };
 
+    let (current_manifest, _) = hash_tree(&upstream_root)
+        .with_context(|| format!("hash upstream {}", upstream_root.display()))?;
+    let current_map: BTreeMap<String, String> = current_manifest
+        .into_iter()
+        .map(|f| (f.path, f.sha256))
+        .collect();
+    report.warnings = super::whiteout::whiteout_warnings(
+        module_id,
+        "rebase",
+        &super::whiteout::read_overlay_deletes(overlay_dir)?,
+        &baseline_map,
+        &current_map,
+    );
+
     // Only rewrite the baseline when we could fully reason about base for all baseline-known files.
     if !options.dry_run {
         write_overlay_baseline(home, repo, module, &upstream_root, overlay_dir)?;
@@ -311,48 +370,3 @@ pub fn rebase_overlay(
 
     Ok(report)
 }
-
-#[derive(Debug)]
-pub(super) struct MergeOutcome {
-    pub(super) merged: Vec<u8>,
-    pub(super) conflicted: bool,
-}
-
-pub(super) fn merge_three_way_git(
-    base: &[u8],
-    ours: &[u8],
-    theirs: &[u8],
-) -> anyhow::Result<MergeOutcome> {
-    let mut ours_file = NamedTempFile::new().context("create temp ours")?;
-    ours_file.write_all(ours).context("write ours")?;
-
-    let mut base_file = NamedTempFile::new().context("create temp base")?;
-    base_file.write_all(base).context("write base")?;
-
-    let mut theirs_file = NamedTempFile::new().context("create temp theirs")?;
-    theirs_file.write_all(theirs).context("write theirs")?;
-
-    let out = Command::new("git")
-        .arg("merge-file")
-        .arg("-p")
-        .arg(ours_file.path())
-        .arg(base_file.path())
-        .arg(theirs_file.path())
-        .output()
-        .context("git merge-file")?;
-
-    match out.status.code() {
-        Some(0) => Ok(MergeOutcome {
-            merged: out.stdout,
-            conflicted: false,
-        }),
-        Some(1) => Ok(MergeOutcome {
-            merged: out.stdout,
-            conflicted: true,
-        }),
-        _ => anyhow::bail!(
-            "git merge-file failed: {}",
-            String::from_utf8_lossy(&out.stderr)
-        ),
-    }
-}
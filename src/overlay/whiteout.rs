@@ -0,0 +1,107 @@
+//! Whiteouts: `<overlay_dir>/.agentpack/deletes` lists module paths (POSIX, relative to the
+//! module root, one per line; `#` starts a comment) that the overlay removes from the upstream
+//! copy. A path naming a directory removes everything below it.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::user_error::UserError;
+
+use super::layout::{join_posix, validate_posix_relpath};
+
+pub(super) fn overlay_deletes_path(overlay_dir: &Path) -> PathBuf {
+    overlay_dir.join(".agentpack").join("deletes")
+}
+
+pub(super) fn read_overlay_deletes(overlay_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let path = overlay_deletes_path(overlay_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let raw = std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+    let mut out = Vec::new();
+    for (idx, line) in raw.lines().enumerate() {
+        let entry = line.trim().trim_end_matches('/');
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        if !validate_posix_relpath(entry) || entry.split('/').next() == Some(".agentpack") {
+            return Err(anyhow::Error::new(
+                UserError::new(
+                    "E_CONFIG_INVALID",
+                    format!(
+                        "invalid overlay whiteout {entry:?} at {}:{}",
+                        path.display(),
+                        idx + 1
+                    ),
+                )
+                .with_details(serde_json::json!({
+                    "overlay_dir": overlay_dir.to_string_lossy(),
+                    "deletes_path": path.to_string_lossy(),
+                    "line": idx + 1,
+                    "entry": entry,
+                    "reason_code": "overlay_whiteout_invalid",
+                    "next_actions": ["edit_overlay_deletes"],
+                    "hint": "list module-relative POSIX paths without `..`, a leading `/` or `.agentpack/`",
+                })),
+            ));
+        }
+        if !out.iter().any(|e| e == entry) {
+            out.push(entry.to_string());
+        }
+    }
+    Ok(out)
+}
+
+/// Removes whited-out paths from a composed module tree. Paths that are already gone are
+/// ignored here (`whiteout_warnings` reports them).
+pub(super) fn apply_overlay_deletes(out_dir: &Path, deletes: &[String]) -> anyhow::Result<()> {
+    for entry in deletes {
+        let path = join_posix(out_dir, entry);
+        let Ok(meta) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_dir() {
+            std::fs::remove_dir_all(&path).with_context(|| format!("remove {}", path.display()))?;
+        } else {
+            std::fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Compares whited-out paths between the overlay baseline and the current upstream
+/// (`path -> sha256` maps of module files).
+pub(super) fn whiteout_warnings(
+    module_id: &str,
+    overlay_kind: &str,
+    deletes: &[String],
+    baseline: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for entry in deletes {
+        let prefix = format!("{entry}/");
+        let select = |map: &BTreeMap<String, String>| -> Vec<(String, String)> {
+            map.iter()
+                .filter(|(p, _)| *p == entry || p.starts_with(&prefix))
+                .map(|(p, s)| (p.clone(), s.clone()))
+                .collect()
+        };
+        let was = select(baseline);
+        let now = select(current);
+        if now.is_empty() {
+            warnings.push(format!(
+                "overlay whiteout ({overlay_kind}) module {module_id}: {entry} no longer exists upstream; remove it from .agentpack/deletes"
+            ));
+        } else if was != now {
+            warnings.push(format!(
+                "overlay whiteout ({overlay_kind}) module {module_id}: upstream changed {entry} since the overlay baseline; it is still deleted"
+            ));
+        }
+    }
+    warnings
+}
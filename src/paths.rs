@@ -95,6 +95,52 @@ pub fn path_to_posix_string(path: &Path) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
 
+/// Matches a `/`-separated relative path against a glob: `*` and `?` match within one
+/// segment, `**` matches any number of segments.
+pub(crate) fn glob_path_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_matches('/').split('/').collect();
+    glob_segments_match(&pattern, &path)
+}
+
+fn glob_segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| glob_segments_match(rest, &path[i..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                glob_segment_matches(segment, name) && glob_segments_match(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches one path segment against a pattern supporting `*` and `?`.
+pub(crate) fn glob_segment_matches(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            mark = ni;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ni = mark;
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
 fn expand_tilde(s: &str) -> anyhow::Result<PathBuf> {
     if let Some(rest) = s.strip_prefix("~/") {
         let home = dirs::home_dir().context("resolve home dir")?;
@@ -0,0 +1,198 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use crate::deploy::{
+    DesiredFile, DesiredState, Op, PlanResult, PlanSummary, TargetPath, UpdateKind,
+};
+use crate::local_edits::{DeployedEntry, deployed_entries, merge_base_bytes, snapshot_bytes};
+use crate::paths::{AgentpackHome, glob_path_matches, path_to_posix_string};
+use crate::targets::{TargetRoot, best_root_for};
+
+/// `--module` / `--path` filters for `preview`, `plan`, `deploy` and `rollback`.
+///
+/// A change is kept when it belongs to one of `modules` (if any are given) and its path matches
+/// one of `paths` (if any are given). Path globs match relative to the target root (e.g.
+/// `AGENTS.md`, `my-skill/**`); a glob starting with `/` matches the absolute path.
+#[derive(Debug, Clone, Default)]
+pub struct PlanFilter {
+    pub modules: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+impl PlanFilter {
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty() && self.paths.is_empty()
+    }
+
+    /// The filter values that select a path owned by `module_ids`, or `None` when the path is
+    /// filtered out. `rel` is relative to the target root, `abs_posix` is the absolute path.
+    pub(crate) fn select(
+        &self,
+        module_ids: &[String],
+        rel: &str,
+        abs_posix: &str,
+    ) -> Option<(Vec<String>, Vec<String>)> {
+        let module_hit = self
+            .modules
+            .iter()
+            .filter(|m| module_ids.contains(m))
+            .cloned()
+            .collect::<Vec<_>>();
+        let path_hit = self
+            .paths
+            .iter()
+            .filter(|p| {
+                if p.starts_with('/') {
+                    path_matches(p, abs_posix)
+                } else {
+                    path_matches(p, rel)
+                }
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let keep = (self.modules.is_empty() || !module_hit.is_empty())
+            && (self.paths.is_empty() || !path_hit.is_empty());
+        keep.then_some((module_hit, path_hit))
+    }
+
+    /// Warns about `--module` / `--path` values that selected nothing.
+    pub(crate) fn warn_unused(
+        &self,
+        used_modules: &BTreeSet<String>,
+        used_paths: &BTreeSet<String>,
+        warnings: &mut Vec<String>,
+    ) {
+        for m in self.modules.iter().filter(|m| !used_modules.contains(*m)) {
+            warnings.push(format!(
+                "--module {m}: no planned change belongs to this module"
+            ));
+        }
+        for p in self.paths.iter().filter(|p| !used_paths.contains(*p)) {
+            warnings.push(format!("--path {p}: no planned change matches this path"));
+        }
+    }
+}
+
+/// Restricts `plan.changes` to `filter` and rewrites `desired` so that it describes the targets
+/// after applying only the kept changes.
+///
+/// Target manifests and snapshots are written from `desired`, so a filtered-out change must not
+/// show up there: a skipped create or adopt stays unmanaged, and a skipped managed update or
+/// delete stays owned with the content it was last deployed with.
+pub fn apply_plan_filter(
+    home: &AgentpackHome,
+    roots: &[TargetRoot],
+    filter: &PlanFilter,
+    plan: &mut PlanResult,
+    desired: &mut DesiredState,
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    if filter.is_empty() {
+        return Ok(());
+    }
+
+    let deployed = deployed_entries(roots);
+    let mut used_modules = BTreeSet::new();
+    let mut used_paths = BTreeSet::new();
+    let mut kept = Vec::new();
+    for change in std::mem::take(&mut plan.changes) {
+        let tp = TargetPath {
+            target: change.target.clone(),
+            path: PathBuf::from(&change.path),
+        };
+        let module_ids: &[String] = match (desired.get(&tp), deployed.get(&tp)) {
+            (Some(f), _) if !matches!(change.op, Op::Delete) => &f.module_ids,
+            (_, Some(entry)) => &entry.module_ids,
+            _ => &[],
+        };
+        let rel = best_root_for(roots, &tp.target, &tp.path)
+            .and_then(|r| tp.path.strip_prefix(&r.root).ok())
+            .map(path_to_posix_string)
+            .unwrap_or_else(|| change.path_posix.clone());
+
+        if let Some((module_hit, path_hit)) = filter.select(module_ids, &rel, &change.path_posix) {
+            used_modules.extend(module_hit);
+            used_paths.extend(path_hit);
+            kept.push(change);
+            continue;
+        }
+
+        let managed = matches!(change.op, Op::Delete)
+            || matches!(change.update_kind, Some(UpdateKind::ManagedUpdate));
+        match managed.then(|| last_deployed(home, &tp, deployed.get(&tp), desired.get(&tp))) {
+            Some(Some(file)) => {
+                desired.insert(tp, file);
+            }
+            _ => {
+                desired.remove(&tp);
+            }
+        }
+    }
+
+    filter.warn_unused(&used_modules, &used_paths, warnings);
+
+    plan.summary = PlanSummary::default();
+    for change in &kept {
+        match change.op {
+            Op::Create => plan.summary.create += 1,
+            Op::Update => plan.summary.update += 1,
+            Op::Delete => plan.summary.delete += 1,
+        }
+    }
+    plan.changes = kept;
+    Ok(())
+}
+
+/// A glob also selects everything below a directory it matches.
+fn path_matches(pattern: &str, rel: &str) -> bool {
+    let mut prefix = rel;
+    loop {
+        if glob_path_matches(pattern, prefix) {
+            return true;
+        }
+        match prefix.rsplit_once('/') {
+            Some((parent, _)) => prefix = parent,
+            None => return false,
+        }
+    }
+}
+
+/// What a skipped managed path keeps recording: the bytes it was last deployed with (and the
+/// merge base of a merged deploy), or (when the snapshot is gone) whatever is on disk now, since
+/// the path is left untouched.
+fn last_deployed(
+    home: &AgentpackHome,
+    tp: &TargetPath,
+    entry: Option<&DeployedEntry>,
+    desired: Option<&DesiredFile>,
+) -> Option<DesiredFile> {
+    let bytes = match entry {
+        Some(entry) => snapshot_bytes(home, tp, entry).ok().flatten(),
+        None => None,
+    };
+    let bytes = bytes.or_else(|| std::fs::read(&tp.path).ok())?;
+    let merge_base = entry
+        .filter(|e| e.merge_base_sha256.is_some())
+        .and_then(|e| merge_base_bytes(home, tp, e).ok().flatten());
+    let (bytes, merged) = match merge_base {
+        Some(base) => (base, Some(bytes)),
+        None => (bytes, None),
+    };
+    Some(DesiredFile {
+        bytes,
+        module_ids: entry
+            .map(|e| e.module_ids.clone())
+            .or_else(|| desired.map(|d| d.module_ids.clone()))
+            .unwrap_or_default(),
+        link_target: match entry {
+            Some(e) => e.link_target.as_ref().map(PathBuf::from),
+            None => std::fs::read_link(&tp.path).ok(),
+        },
+        mode: match entry {
+            Some(e) => e.mode,
+            None => crate::fs::executable_mode(&tp.path),
+        },
+        merged,
+        spans: Vec::new(),
+    })
+}
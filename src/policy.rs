@@ -609,7 +609,9 @@ fn lint_distribution_policy(
     if !required_targets.is_empty() {
         let mut missing: Vec<String> = required_targets
             .iter()
-            .filter(|t| !manifest.targets.contains_key(t.as_str()))
+            .filter(|t| {
+                !manifest.targets.contains_key(t.as_str()) && !manifest.is_declared_target(t)
+            })
             .cloned()
             .collect();
         missing.sort();
@@ -1375,6 +1377,13 @@ fn agentpack_command_id(argv: &[String]) -> Option<String> {
                 "deploy".to_string()
             }
         }
+        "watch" => {
+            if rest.contains(&"--apply") {
+                "watch --apply".to_string()
+            } else {
+                "watch".to_string()
+            }
+        }
         "doctor" => {
             if rest.contains(&"--fix") {
                 "doctor --fix".to_string()
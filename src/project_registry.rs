@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::write_atomic;
+use crate::paths::AgentpackHome;
+use crate::project::ProjectContext;
+use crate::user_error::UserError;
+
+pub const PROJECTS_FILENAME: &str = "projects.json";
+const PROJECTS_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisteredProject {
+    pub project_id: String,
+    pub project_root: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_url: Option<String>,
+    pub added_at: String,
+}
+
+impl RegisteredProject {
+    pub fn root(&self) -> PathBuf {
+        PathBuf::from(&self.project_root)
+    }
+}
+
+/// Project roots that `deploy --all-projects` renders and applies (`state/projects.json`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRegistry {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub projects: Vec<RegisteredProject>,
+}
+
+impl Default for ProjectRegistry {
+    fn default() -> Self {
+        Self {
+            schema_version: PROJECTS_SCHEMA_VERSION,
+            projects: Vec::new(),
+        }
+    }
+}
+
+impl ProjectRegistry {
+    pub fn path(home: &AgentpackHome) -> PathBuf {
+        home.state_dir.join(PROJECTS_FILENAME)
+    }
+
+    pub fn load(home: &AgentpackHome) -> anyhow::Result<Self> {
+        let path = Self::path(home);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw =
+            std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        let registry: Self = serde_json::from_str(&raw)
+            .with_context(|| format!("parse project registry {}", path.display()))?;
+        if registry.schema_version != PROJECTS_SCHEMA_VERSION {
+            anyhow::bail!(
+                "unsupported project registry schema_version {} in {}",
+                registry.schema_version,
+                path.display()
+            );
+        }
+        Ok(registry)
+    }
+
+    pub fn save(&self, home: &AgentpackHome) -> anyhow::Result<()> {
+        let path = Self::path(home);
+        let mut out = serde_json::to_string_pretty(self).context("serialize project registry")?;
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        write_atomic(&path, out.as_bytes()).with_context(|| format!("write {}", path.display()))
+    }
+
+    /// Registers the project containing `path` (its git root when inside a git repo).
+    ///
+    /// Returns the entry and whether it was newly added; re-adding a known project refreshes
+    /// its root and origin.
+    pub fn add(&mut self, path: &Path) -> anyhow::Result<(RegisteredProject, bool)> {
+        let dir = path
+            .canonicalize()
+            .map_err(|_| project_path_not_found(path))?;
+        if !dir.is_dir() {
+            return Err(project_path_not_found(path));
+        }
+        let project = ProjectContext::detect(&dir).context("detect project")?;
+        let project_root = project.project_root.to_string_lossy().to_string();
+
+        if let Some(existing) = self
+            .projects
+            .iter_mut()
+            .find(|p| p.project_id == project.project_id || p.project_root == project_root)
+        {
+            existing.project_id = project.project_id;
+            existing.project_root = project_root;
+            existing.origin_url = project.origin_url;
+            return Ok((existing.clone(), false));
+        }
+
+        let added_at = time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .context("format timestamp")?;
+        let entry = RegisteredProject {
+            project_id: project.project_id,
+            project_root,
+            origin_url: project.origin_url,
+            added_at,
+        };
+        self.projects.push(entry.clone());
+        self.projects
+            .sort_by(|a, b| a.project_root.cmp(&b.project_root));
+        Ok((entry, true))
+    }
+
+    /// Removes a project by id or root path.
+    pub fn remove(&mut self, selector: &str) -> anyhow::Result<RegisteredProject> {
+        let canonical = Path::new(selector)
+            .canonicalize()
+            .ok()
+            .map(|p| p.to_string_lossy().to_string());
+        let idx = self
+            .projects
+            .iter()
+            .position(|p| {
+                p.project_id == selector
+                    || p.project_root == selector
+                    || canonical.as_deref() == Some(p.project_root.as_str())
+            })
+            .ok_or_else(|| project_not_registered(selector))?;
+        Ok(self.projects.remove(idx))
+    }
+}
+
+fn project_path_not_found(path: &Path) -> anyhow::Error {
+    anyhow::Error::new(
+        UserError::new(
+            "E_PROJECT_NOT_FOUND",
+            format!("project directory not found: {}", path.display()),
+        )
+        .with_details(serde_json::json!({
+            "path": path.display().to_string(),
+            "path_posix": crate::paths::path_to_posix_string(path),
+            "reason_code": "project_path_not_found",
+            "next_actions": ["fix_project_path", "retry_command"],
+        })),
+    )
+}
+
+fn project_not_registered(selector: &str) -> anyhow::Error {
+    anyhow::Error::new(
+        UserError::new(
+            "E_PROJECT_NOT_FOUND",
+            format!("project is not registered: {selector}"),
+        )
+        .with_details(serde_json::json!({
+            "project": selector,
+            "reason_code": "project_not_registered",
+            "next_actions": ["run_project_list", "retry_command"],
+        })),
+    )
+}
@@ -0,0 +1,521 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::Serialize;
+
+use crate::apply::snapshot_state_path;
+use crate::deploy::{
+    DesiredFile, DesiredState, Op, PlanChange, PlanResult, PlanSummary, TargetPath,
+};
+use crate::hash::sha256_hex;
+use crate::paths::{AgentpackHome, path_to_posix_string};
+use crate::plan_filter::PlanFilter;
+use crate::state::{DeploymentSnapshot, ManagedFile, list_snapshots};
+use crate::target_manifest::{TargetManifest, is_target_manifest_path};
+use crate::targets::{TargetRoot, dedup_roots};
+use crate::user_error::UserError;
+
+/// Which part of a snapshot `rollback` restores (`--target`, `--module`, `--path`).
+#[derive(Debug, Clone, Default)]
+pub struct RollbackSelection {
+    /// `None` selects every target recorded by the snapshot.
+    pub target: Option<String>,
+    pub filter: PlanFilter,
+}
+
+/// A managed path whose current content differs from what the current deployment wrote, so
+/// rolling it back would discard a hand edit.
+#[derive(Debug, Clone, Serialize)]
+pub struct RollbackLocalEdit {
+    pub target: String,
+    pub path: String,
+    pub path_posix: String,
+    pub deployed_sha256: String,
+    pub current_sha256: Option<String>,
+}
+
+/// A target manifest restored (or removed, when `bytes` is `None`) by a full rollback.
+#[derive(Debug, Clone)]
+pub struct ManifestRestore {
+    pub target: String,
+    pub path: PathBuf,
+    pub bytes: Option<Vec<u8>>,
+    pub source: Option<String>,
+}
+
+/// What `rollback` would do, computed without writing anything.
+///
+/// `plan` and `desired` have the same meaning as for `preview`: create/update changes write
+/// `desired` content, delete changes remove the path. No-op restores are left out.
+#[derive(Debug, Clone)]
+pub struct RollbackPlan {
+    pub snapshot_id: String,
+    pub current_head: String,
+    pub targets: Vec<String>,
+    pub partial: bool,
+    pub plan: PlanResult,
+    pub desired: DesiredState,
+    /// The snapshot state file or backup each restore reads from.
+    pub sources: BTreeMap<TargetPath, String>,
+    /// Root and module ids of each planned path, from the target manifests.
+    pub owners: BTreeMap<TargetPath, Owner>,
+    pub roots: Vec<TargetRoot>,
+    /// Target manifests to restore; only used by a full rollback (a partial one patches them).
+    pub manifests: Vec<ManifestRestore>,
+    pub local_edits: Vec<RollbackLocalEdit>,
+    /// Managed files recorded by the current deployment.
+    pub current_files: Vec<ManagedFile>,
+    pub lockfile_sha256: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Owner {
+    pub root: PathBuf,
+    pub module_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Action {
+    Restore {
+        bytes: Vec<u8>,
+        link_target: Option<String>,
+        mode: Option<u32>,
+        source: String,
+    },
+    Delete,
+}
+
+/// Works out how `rollback --to <snapshot_ref>` changes the target files.
+///
+/// With snapshot state files, the snapshot's managed files are restored and files deployed
+/// since are deleted; older snapshots without state files are reached by undoing every later
+/// deploy from its backups.
+pub fn plan_rollback(
+    home: &AgentpackHome,
+    snapshot_ref: &str,
+    selection: &RollbackSelection,
+) -> anyhow::Result<RollbackPlan> {
+    let snapshot_id = crate::snapshot_tags::resolve_snapshot(home, snapshot_ref)?;
+    let target_path = DeploymentSnapshot::path(home, &snapshot_id);
+    let target_snapshot = DeploymentSnapshot::load(&target_path)
+        .with_context(|| format!("load snapshot {}", target_path.display()))?;
+    if target_snapshot.kind == "rollback" {
+        if let Some(to) = &target_snapshot.rolled_back_to {
+            anyhow::bail!("snapshot {snapshot_id} is a rollback event; use --to {to} instead");
+        }
+        anyhow::bail!(
+            "snapshot {snapshot_id} is a rollback event and cannot be used as a rollback target"
+        );
+    }
+
+    let snapshots = list_snapshots(home)?;
+    let mut parents: HashMap<String, Option<String>> = HashMap::new();
+    let mut head: Option<String> = None;
+    for s in &snapshots {
+        match s.kind.as_str() {
+            "deploy" | "bootstrap" => {
+                parents.insert(s.id.clone(), head.clone());
+                head = Some(s.id.clone());
+            }
+            "rollback" => {
+                if let Some(to) = &s.rolled_back_to {
+                    head = Some(to.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some(current_head) = head else {
+        anyhow::bail!("no deployment snapshots found");
+    };
+    let current_path = DeploymentSnapshot::path(home, &current_head);
+    let current_snapshot = DeploymentSnapshot::load(&current_path)
+        .with_context(|| format!("load current snapshot {}", current_path.display()))?;
+
+    let mut files: BTreeMap<TargetPath, Action> = BTreeMap::new();
+    let mut manifests: BTreeMap<TargetPath, Action> = BTreeMap::new();
+    let mut manifest_paths: BTreeSet<TargetPath> = BTreeSet::new();
+    for c in &current_snapshot.changes {
+        let tp = target_path_of(&c.target, &c.path);
+        if is_target_manifest_path(&tp.path) {
+            manifest_paths.insert(tp);
+        }
+    }
+
+    let target_state_root = DeploymentSnapshot::state_root(home, &snapshot_id);
+    if target_state_root.exists() {
+        for f in &target_snapshot.managed_files {
+            let tp = target_path_of(&f.target, &f.path);
+            let state_path = snapshot_state_path(&target_state_root, &f.target, &tp.path)?;
+            let bytes = read_state(&state_path, &tp.path)?;
+            let actual_sha = sha256_hex(&bytes);
+            if actual_sha != f.sha256 {
+                anyhow::bail!(
+                    "snapshot state hash mismatch for {}: expected {}, got {}",
+                    tp.path.display(),
+                    f.sha256,
+                    actual_sha
+                );
+            }
+            files.insert(
+                tp,
+                Action::Restore {
+                    bytes,
+                    link_target: f.link_target.clone(),
+                    mode: f.mode,
+                    source: state_path.to_string_lossy().to_string(),
+                },
+            );
+        }
+
+        for c in &target_snapshot.changes {
+            let tp = target_path_of(&c.target, &c.path);
+            if !is_target_manifest_path(&tp.path) || (c.op != "create" && c.op != "update") {
+                continue;
+            }
+            let state_path = snapshot_state_path(&target_state_root, &c.target, &tp.path)?;
+            let bytes = read_state(&state_path, &tp.path)?;
+            manifest_paths.insert(tp.clone());
+            manifests.insert(
+                tp,
+                Action::Restore {
+                    bytes,
+                    link_target: None,
+                    mode: None,
+                    source: state_path.to_string_lossy().to_string(),
+                },
+            );
+        }
+
+        for f in &current_snapshot.managed_files {
+            let tp = target_path_of(&f.target, &f.path);
+            files.entry(tp).or_insert(Action::Delete);
+        }
+    } else if current_head != snapshot_id {
+        // Undo newer deploys first; an older undo of the same path wins.
+        let mut cursor = current_head.clone();
+        while cursor != snapshot_id {
+            let snapshot_path = DeploymentSnapshot::path(home, &cursor);
+            let snapshot = DeploymentSnapshot::load(&snapshot_path)
+                .with_context(|| format!("load snapshot {}", snapshot_path.display()))?;
+
+            for c in &snapshot.changes {
+                let tp = target_path_of(&c.target, &c.path);
+                let action = match (&c.op[..], &c.backup_path) {
+                    ("create", None) => Action::Delete,
+                    ("update" | "delete", Some(backup)) => {
+                        let backup_path = PathBuf::from(backup);
+                        let bytes = std::fs::read(&backup_path).with_context(|| {
+                            format!(
+                                "read backup {} for {}",
+                                backup_path.display(),
+                                tp.path.display()
+                            )
+                        })?;
+                        Action::Restore {
+                            bytes,
+                            link_target: c.before_link_target.clone(),
+                            mode: crate::fs::file_mode(&backup_path),
+                            source: backup.clone(),
+                        }
+                    }
+                    _ => continue,
+                };
+                if is_target_manifest_path(&tp.path) {
+                    manifest_paths.insert(tp.clone());
+                    manifests.insert(tp, action);
+                } else {
+                    files.insert(tp, action);
+                }
+            }
+
+            cursor = parents.get(&cursor).cloned().flatten().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "snapshot {snapshot_id} is not reachable from current deployment state {current_head}"
+                )
+            })?;
+        }
+    }
+
+    // Ownership comes from the manifests on disk, overridden by the ones being restored.
+    let mut owners: BTreeMap<TargetPath, Owner> = BTreeMap::new();
+    for tp in &manifest_paths {
+        if let Ok(manifest) = TargetManifest::load(&tp.path) {
+            index_manifest(&mut owners, tp, &manifest);
+        }
+    }
+    for (tp, action) in &manifests {
+        if let Action::Restore { bytes, .. } = action
+            && let Ok(manifest) = serde_json::from_slice::<TargetManifest>(bytes)
+        {
+            index_manifest(&mut owners, tp, &manifest);
+        }
+    }
+
+    let current_files: BTreeMap<TargetPath, &ManagedFile> = current_snapshot
+        .managed_files
+        .iter()
+        .map(|f| (target_path_of(&f.target, &f.path), f))
+        .collect();
+
+    let mut out = RollbackPlan {
+        snapshot_id: snapshot_id.clone(),
+        current_head: current_head.clone(),
+        targets: match &selection.target {
+            Some(target) => vec![target.clone()],
+            None => target_snapshot.targets.clone(),
+        },
+        // `--target` only makes a rollback partial when the deployment covers other targets.
+        partial: !selection.filter.is_empty()
+            || selection.target.as_ref().is_some_and(|t| {
+                target_snapshot
+                    .targets
+                    .iter()
+                    .chain(&current_snapshot.targets)
+                    .any(|other| other != t)
+            }),
+        plan: PlanResult {
+            changes: Vec::new(),
+            summary: PlanSummary::default(),
+        },
+        desired: DesiredState::new(),
+        sources: BTreeMap::new(),
+        owners: BTreeMap::new(),
+        roots: Vec::new(),
+        manifests: Vec::new(),
+        local_edits: Vec::new(),
+        current_files: current_snapshot.managed_files.clone(),
+        lockfile_sha256: target_snapshot.lockfile_sha256.clone(),
+        warnings: Vec::new(),
+    };
+
+    let mut used_modules = BTreeSet::new();
+    let mut used_paths = BTreeSet::new();
+    for (tp, action) in files {
+        if selection.target.as_ref().is_some_and(|t| *t != tp.target) {
+            continue;
+        }
+        let owner = owners.get(&tp);
+        let path_posix = path_to_posix_string(&tp.path);
+        let rel = owner
+            .and_then(|o| tp.path.strip_prefix(&o.root).ok())
+            .map(path_to_posix_string)
+            .unwrap_or_else(|| path_posix.clone());
+        let module_ids = owner.map(|o| o.module_ids.as_slice()).unwrap_or_default();
+        let Some((module_hit, path_hit)) = selection.filter.select(module_ids, &rel, &path_posix)
+        else {
+            continue;
+        };
+
+        let present = crate::fs::path_present(&tp.path);
+        let current_bytes = std::fs::read(&tp.path).ok();
+        let before_sha256 = current_bytes.as_deref().map(sha256_hex);
+        let change = match &action {
+            Action::Restore {
+                bytes, link_target, ..
+            } => {
+                let unchanged = match link_target {
+                    Some(link) => {
+                        std::fs::read_link(&tp.path).is_ok_and(|current| current == Path::new(link))
+                    }
+                    None => {
+                        !crate::fs::is_symlink(&tp.path)
+                            && current_bytes.as_deref() == Some(bytes.as_slice())
+                    }
+                };
+                if unchanged {
+                    continue;
+                }
+                plan_change(
+                    &tp,
+                    if present { Op::Update } else { Op::Create },
+                    before_sha256.clone(),
+                    Some(sha256_hex(bytes)),
+                    link_target.clone(),
+                )
+            }
+            Action::Delete => {
+                if !present {
+                    continue;
+                }
+                plan_change(&tp, Op::Delete, before_sha256.clone(), None, None)
+            }
+        };
+
+        if present && let Some(deployed) = current_files.get(&tp) {
+            let edited = match &deployed.link_target {
+                Some(link) => {
+                    std::fs::read_link(&tp.path).map_or(true, |current| current != Path::new(link))
+                }
+                None => before_sha256.as_deref() != Some(deployed.sha256.as_str()),
+            };
+            if edited {
+                out.local_edits.push(RollbackLocalEdit {
+                    target: tp.target.clone(),
+                    path: change.path.clone(),
+                    path_posix: change.path_posix.clone(),
+                    deployed_sha256: deployed.sha256.clone(),
+                    current_sha256: before_sha256,
+                });
+            }
+        }
+
+        used_modules.extend(module_hit);
+        used_paths.extend(path_hit);
+        match change.op {
+            Op::Create => out.plan.summary.create += 1,
+            Op::Update => out.plan.summary.update += 1,
+            Op::Delete => out.plan.summary.delete += 1,
+        }
+        out.plan.changes.push(change);
+        if let Action::Restore {
+            bytes,
+            link_target,
+            mode,
+            source,
+        } = action
+        {
+            out.sources.insert(tp.clone(), source);
+            out.desired.insert(
+                tp.clone(),
+                DesiredFile {
+                    bytes,
+                    module_ids: module_ids.to_vec(),
+                    link_target: link_target.map(PathBuf::from),
+                    mode,
+                    merged: None,
+                    spans: Vec::new(),
+                },
+            );
+        }
+        if let Some(owner) = owner {
+            out.owners.insert(tp, owner.clone());
+        }
+    }
+    selection
+        .filter
+        .warn_unused(&used_modules, &used_paths, &mut out.warnings);
+
+    out.roots = dedup_roots(
+        out.owners
+            .iter()
+            .map(|(tp, o)| TargetRoot {
+                target: tp.target.clone(),
+                root: o.root.clone(),
+                scan_extras: false,
+            })
+            .collect(),
+    );
+    if !out.partial {
+        out.manifests = manifests
+            .into_iter()
+            .map(|(tp, action)| match action {
+                Action::Restore { bytes, source, .. } => ManifestRestore {
+                    target: tp.target,
+                    path: tp.path,
+                    bytes: Some(bytes),
+                    source: Some(source),
+                },
+                Action::Delete => ManifestRestore {
+                    target: tp.target,
+                    path: tp.path,
+                    bytes: None,
+                    source: None,
+                },
+            })
+            .collect();
+    }
+
+    Ok(out)
+}
+
+/// Refuses a rollback that would overwrite or delete hand-edited files.
+pub fn local_edits_error(plan: &RollbackPlan) -> anyhow::Error {
+    let mut sample_paths: Vec<String> = plan
+        .local_edits
+        .iter()
+        .map(|e| e.path_posix.clone())
+        .collect();
+    sample_paths.truncate(20);
+
+    anyhow::Error::new(
+        UserError::new(
+            "E_ROLLBACK_LOCAL_EDITS",
+            format!(
+                "refusing to rollback: {} file(s) were edited after the current deployment; pass --discard-local-edits to overwrite them",
+                plan.local_edits.len()
+            ),
+        )
+        .with_details(serde_json::json!({
+            "snapshot_id": plan.snapshot_id,
+            "current_snapshot_id": plan.current_head,
+            "flag": "--discard-local-edits",
+            "local_edits": plan.local_edits.len(),
+            "sample_paths": sample_paths,
+            "reason_code": "rollback_local_edits",
+            "next_actions": ["run_rollback_dry_run", "retry_with_discard_local_edits"],
+        })),
+    )
+}
+
+fn target_path_of(target: &str, path: &str) -> TargetPath {
+    TargetPath {
+        target: target.to_string(),
+        path: PathBuf::from(path),
+    }
+}
+
+fn read_state(state_path: &Path, path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(state_path).with_context(|| {
+        format!(
+            "read snapshot state {} for {}",
+            state_path.display(),
+            path.display()
+        )
+    })
+}
+
+fn index_manifest(
+    owners: &mut BTreeMap<TargetPath, Owner>,
+    manifest_path: &TargetPath,
+    manifest: &TargetManifest,
+) {
+    let Some(root) = manifest_path.path.parent() else {
+        return;
+    };
+    for f in &manifest.managed_files {
+        owners.insert(
+            TargetPath {
+                target: manifest_path.target.clone(),
+                path: root.join(&f.path),
+            },
+            Owner {
+                root: root.to_path_buf(),
+                module_ids: f.module_ids.clone(),
+            },
+        );
+    }
+}
+
+fn plan_change(
+    tp: &TargetPath,
+    op: Op,
+    before_sha256: Option<String>,
+    after_sha256: Option<String>,
+    link_target: Option<String>,
+) -> PlanChange {
+    PlanChange {
+        target: tp.target.clone(),
+        op,
+        path: tp.path.to_string_lossy().to_string(),
+        path_posix: path_to_posix_string(&tp.path),
+        before_sha256,
+        after_sha256,
+        update_kind: None,
+        reason: "rollback".to_string(),
+        link_target,
+        merge: None,
+    }
+}
@@ -0,0 +1,290 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+
+use crate::deploy::{DesiredFile, DesiredState, PlanResult, TargetPath};
+use crate::hash::sha256_hex;
+use crate::targets::TargetRoot;
+use crate::user_error::UserError;
+
+pub const SAVED_PLAN_SCHEMA_VERSION: u32 = 1;
+
+/// A plan written by `plan --out` and applied later by `deploy --apply --plan <file>`.
+///
+/// Carries everything `apply` needs (the plan, the full desired state and the target roots),
+/// so the applying process never re-renders and applies exactly what was reviewed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPlan {
+    pub schema_version: u32,
+    pub created_at: String,
+    pub profile: String,
+    pub target: String,
+    pub machine_id: String,
+    pub project_root: String,
+    /// sha256 over `plan`, `roots` and `desired`; guards against edits and truncation.
+    pub plan_hash: String,
+    pub plan: PlanResult,
+    pub roots: Vec<SavedRoot>,
+    pub desired: Vec<SavedDesiredFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRoot {
+    pub target: String,
+    pub root: String,
+    pub scan_extras: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedDesiredFile {
+    pub target: String,
+    pub path: String,
+    pub sha256: String,
+    pub module_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// UTF-8 content; binary content is stored in `content_hex` instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hex: Option<String>,
+    /// What apply writes when local edits were merged in (see `DesiredFile::merged`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged_content: Option<String>,
+}
+
+/// A planned change whose path no longer looks the way it did when the plan was saved.
+#[derive(Debug, Clone, Serialize)]
+pub struct StalePath {
+    pub target: String,
+    pub path: String,
+    pub path_posix: String,
+    pub expected_sha256: Option<String>,
+    pub actual_sha256: Option<String>,
+}
+
+impl SavedPlan {
+    pub fn new(
+        profile: &str,
+        target: &str,
+        machine_id: &str,
+        project_root: &Path,
+        plan: &PlanResult,
+        desired: &DesiredState,
+        roots: &[TargetRoot],
+    ) -> anyhow::Result<Self> {
+        let roots: Vec<SavedRoot> = roots
+            .iter()
+            .map(|r| SavedRoot {
+                target: r.target.clone(),
+                root: r.root.to_string_lossy().to_string(),
+                scan_extras: r.scan_extras,
+            })
+            .collect();
+        let desired: Vec<SavedDesiredFile> = desired
+            .iter()
+            .map(|(tp, f)| {
+                let (content, content_hex) = match std::str::from_utf8(&f.bytes) {
+                    Ok(text) => (Some(text.to_string()), None),
+                    Err(_) => (None, Some(hex::encode(&f.bytes))),
+                };
+                SavedDesiredFile {
+                    target: tp.target.clone(),
+                    path: tp.path.to_string_lossy().to_string(),
+                    sha256: sha256_hex(&f.bytes),
+                    module_ids: f.module_ids.clone(),
+                    link_target: f
+                        .link_target
+                        .as_ref()
+                        .map(|p| p.to_string_lossy().to_string()),
+                    mode: f.mode,
+                    content,
+                    content_hex,
+                    merged_content: f
+                        .merged
+                        .as_ref()
+                        .map(|b| String::from_utf8_lossy(b).to_string()),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            schema_version: SAVED_PLAN_SCHEMA_VERSION,
+            created_at: time::OffsetDateTime::now_utc()
+                .format(&time::format_description::well_known::Rfc3339)
+                .context("format timestamp")?,
+            profile: profile.to_string(),
+            target: target.to_string(),
+            machine_id: machine_id.to_string(),
+            project_root: project_root.to_string_lossy().to_string(),
+            plan_hash: plan_hash(plan, &roots, &desired)?,
+            plan: plan.clone(),
+            roots,
+            desired,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut out = serde_json::to_string_pretty(self).context("serialize saved plan")?;
+        out.push('\n');
+        crate::fs::write_atomic(path, out.as_bytes())
+            .with_context(|| format!("write {}", path.display()))
+    }
+
+    /// Loads a saved plan and checks that it is intact; does not look at the filesystem.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|err| {
+            invalid(
+                path,
+                format!("failed to read plan file {}: {err}", path.display()),
+                "plan_unreadable",
+            )
+        })?;
+        let plan: Self = serde_json::from_str(&raw).map_err(|err| {
+            invalid(
+                path,
+                format!("plan file is not a valid agentpack plan: {err}"),
+                "plan_invalid",
+            )
+        })?;
+        if plan.schema_version != SAVED_PLAN_SCHEMA_VERSION {
+            return Err(invalid(
+                path,
+                format!(
+                    "unsupported plan schema_version {} (expected {SAVED_PLAN_SCHEMA_VERSION})",
+                    plan.schema_version
+                ),
+                "plan_unsupported_version",
+            ));
+        }
+        let ok = plan_hash(&plan.plan, &plan.roots, &plan.desired)? == plan.plan_hash
+            && plan
+                .desired
+                .iter()
+                .all(|f| f.bytes().is_ok_and(|b| sha256_hex(&b) == f.sha256));
+        if !ok {
+            return Err(invalid(
+                path,
+                "plan file was modified after it was saved (plan_hash mismatch)".to_string(),
+                "plan_hash_mismatch",
+            ));
+        }
+        Ok(plan)
+    }
+
+    pub fn desired_state(&self) -> anyhow::Result<DesiredState> {
+        let mut out = DesiredState::new();
+        for f in &self.desired {
+            out.insert(
+                TargetPath {
+                    target: f.target.clone(),
+                    path: PathBuf::from(&f.path),
+                },
+                DesiredFile {
+                    bytes: f.bytes()?,
+                    module_ids: f.module_ids.clone(),
+                    link_target: f.link_target.as_ref().map(PathBuf::from),
+                    mode: f.mode,
+                    merged: f.merged_content.as_ref().map(|m| m.as_bytes().to_vec()),
+                    spans: Vec::new(),
+                },
+            );
+        }
+        Ok(out)
+    }
+
+    pub fn target_roots(&self) -> Vec<TargetRoot> {
+        self.roots
+            .iter()
+            .map(|r| TargetRoot {
+                target: r.target.clone(),
+                root: PathBuf::from(&r.root),
+                scan_extras: r.scan_extras,
+            })
+            .collect()
+    }
+
+    /// Changes whose path no longer matches the plan's `before_sha256` (a missing path
+    /// matches only a `create`).
+    pub fn stale_paths(&self) -> Vec<StalePath> {
+        self.plan
+            .changes
+            .iter()
+            .filter_map(|c| {
+                let actual = std::fs::read(&c.path).ok().map(|b| sha256_hex(&b));
+                (actual != c.before_sha256).then(|| StalePath {
+                    target: c.target.clone(),
+                    path: c.path.clone(),
+                    path_posix: c.path_posix.clone(),
+                    expected_sha256: c.before_sha256.clone(),
+                    actual_sha256: actual,
+                })
+            })
+            .collect()
+    }
+
+    /// Fails with `E_PLAN_STALE` unless every planned path is still in its planned state.
+    pub fn ensure_fresh(&self, path: &Path) -> anyhow::Result<()> {
+        let stale = self.stale_paths();
+        if stale.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow::Error::new(
+            UserError::new(
+                "E_PLAN_STALE",
+                format!(
+                    "{} planned path(s) changed on disk since the plan was saved; re-run `agentpack plan --out`",
+                    stale.len()
+                ),
+            )
+            .with_details(serde_json::json!({
+                "plan_file": path.display().to_string(),
+                "plan_file_posix": crate::paths::path_to_posix_string(path),
+                "plan_hash": self.plan_hash,
+                "stale": stale,
+                "reason_code": "plan_stale",
+                "next_actions": ["rerun_plan", "retry_command"],
+            })),
+        ))
+    }
+}
+
+impl SavedDesiredFile {
+    fn bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match (&self.content, &self.content_hex) {
+            (Some(text), _) => Ok(text.as_bytes().to_vec()),
+            (None, Some(encoded)) => hex::decode(encoded)
+                .with_context(|| format!("decode content_hex for {}", self.path)),
+            (None, None) => anyhow::bail!("missing content for {}", self.path),
+        }
+    }
+}
+
+fn plan_hash(
+    plan: &PlanResult,
+    roots: &[SavedRoot],
+    desired: &[SavedDesiredFile],
+) -> anyhow::Result<String> {
+    let hash_input = serde_json::json!({
+        "plan": plan,
+        "roots": roots,
+        "desired": desired,
+    });
+    let bytes = serde_json::to_vec(&hash_input).context("serialize plan_hash input")?;
+    Ok(hex::encode(sha2::Sha256::digest(bytes)))
+}
+
+fn invalid(path: &Path, message: String, reason_code: &str) -> anyhow::Error {
+    anyhow::Error::new(
+        UserError::new("E_PLAN_INVALID", message).with_details(serde_json::json!({
+            "plan_file": path.display().to_string(),
+            "plan_file_posix": crate::paths::path_to_posix_string(path),
+            "reason_code": reason_code,
+            "next_actions": ["rerun_plan", "retry_command"],
+        })),
+    )
+}
@@ -0,0 +1,286 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+
+use crate::hash::sha256_hex;
+use crate::paths::AgentpackHome;
+use crate::snapshot_tags::SnapshotTags;
+use crate::state::DeploymentSnapshot;
+use crate::state_lock::StateLock;
+use crate::user_error::UserError;
+
+pub const SNAPSHOT_ARCHIVE_SCHEMA_VERSION: u32 = 1;
+
+/// A deployment snapshot with its backups and state files, written by `snapshot export` and
+/// read by `snapshot import`.
+///
+/// Files are stored relative to the snapshot directory; absolute backup paths recorded in the
+/// snapshot are rewritten to the importing home. Target paths are kept as recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotArchive {
+    pub schema_version: u32,
+    pub exported_at: String,
+    /// sha256 over `snapshot`, `source_dir`, `tags` and `files`; guards against edits and truncation.
+    pub archive_hash: String,
+    pub snapshot: DeploymentSnapshot,
+    /// The snapshot directory on the exporting machine (`state/snapshots/<id>`).
+    pub source_dir: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub files: Vec<ArchiveFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    /// `/`-separated path relative to the snapshot directory (e.g. `backup/codex/<key>`).
+    pub path: String,
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// UTF-8 content; binary content is stored in `content_hex` instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hex: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportOutcome {
+    pub snapshot_id: String,
+    /// `false` when a snapshot with the same id already existed (nothing was written).
+    pub imported: bool,
+    pub files: usize,
+    pub tags_added: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl SnapshotArchive {
+    /// Collects snapshot `id` (already resolved) with its backup and state files.
+    pub fn export(home: &AgentpackHome, id: &str) -> anyhow::Result<Self> {
+        let snapshot_path = DeploymentSnapshot::path(home, id);
+        let snapshot = DeploymentSnapshot::load(&snapshot_path)
+            .with_context(|| format!("load snapshot {}", snapshot_path.display()))?;
+        let source_dir = home.snapshots_dir.join(id);
+
+        let mut files = Vec::new();
+        if source_dir.is_dir() {
+            for entry in walkdir::WalkDir::new(&source_dir).sort_by_file_name() {
+                let entry = entry.with_context(|| format!("walk {}", source_dir.display()))?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let rel = entry
+                    .path()
+                    .strip_prefix(&source_dir)
+                    .with_context(|| format!("relpath for {}", entry.path().display()))?;
+                let bytes = std::fs::read(entry.path())
+                    .with_context(|| format!("read {}", entry.path().display()))?;
+                let (content, content_hex) = match std::str::from_utf8(&bytes) {
+                    Ok(text) => (Some(text.to_string()), None),
+                    Err(_) => (None, Some(hex::encode(&bytes))),
+                };
+                files.push(ArchiveFile {
+                    path: crate::paths::path_to_posix_string(rel),
+                    sha256: sha256_hex(&bytes),
+                    mode: crate::fs::file_mode(entry.path()),
+                    content,
+                    content_hex,
+                });
+            }
+        }
+
+        let source_dir = source_dir.to_string_lossy().to_string();
+        let tags = SnapshotTags::load(home)?.names_for(id);
+        Ok(Self {
+            schema_version: SNAPSHOT_ARCHIVE_SCHEMA_VERSION,
+            exported_at: time::OffsetDateTime::now_utc()
+                .format(&time::format_description::well_known::Rfc3339)
+                .context("format timestamp")?,
+            archive_hash: archive_hash(&snapshot, &source_dir, &tags, &files)?,
+            snapshot,
+            source_dir,
+            tags,
+            files,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut out = serde_json::to_string_pretty(self).context("serialize snapshot archive")?;
+        out.push('\n');
+        crate::fs::write_atomic(path, out.as_bytes())
+            .with_context(|| format!("write {}", path.display()))
+    }
+
+    /// Loads an archive and checks that it is intact.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|err| {
+            invalid(
+                path,
+                format!("failed to read snapshot archive {}: {err}", path.display()),
+                "archive_unreadable",
+            )
+        })?;
+        let archive: Self = serde_json::from_str(&raw).map_err(|err| {
+            invalid(
+                path,
+                format!("file is not a valid agentpack snapshot archive: {err}"),
+                "archive_invalid",
+            )
+        })?;
+        if archive.schema_version != SNAPSHOT_ARCHIVE_SCHEMA_VERSION {
+            return Err(invalid(
+                path,
+                format!(
+                    "unsupported snapshot archive schema_version {} (expected {SNAPSHOT_ARCHIVE_SCHEMA_VERSION})",
+                    archive.schema_version
+                ),
+                "archive_unsupported_version",
+            ));
+        }
+        let id_ok = !archive.snapshot.id.is_empty()
+            && archive.snapshot.id.chars().all(|c| c.is_ascii_digit());
+        let paths_ok = archive.files.iter().all(|f| is_safe_relative(&f.path));
+        if !id_ok || !paths_ok {
+            return Err(invalid(
+                path,
+                "snapshot archive contains an invalid snapshot id or file path".to_string(),
+                "archive_invalid",
+            ));
+        }
+        let ok = archive_hash(
+            &archive.snapshot,
+            &archive.source_dir,
+            &archive.tags,
+            &archive.files,
+        )? == archive.archive_hash
+            && archive
+                .files
+                .iter()
+                .all(|f| f.bytes().is_ok_and(|b| sha256_hex(&b) == f.sha256));
+        if !ok {
+            return Err(invalid(
+                path,
+                "snapshot archive was modified after it was exported (archive_hash mismatch)"
+                    .to_string(),
+                "archive_hash_mismatch",
+            ));
+        }
+        Ok(archive)
+    }
+
+    /// Writes the snapshot into `home` (files first, snapshot metadata last) and adds its tags
+    /// unless a tag of the same name already exists.
+    pub fn import(&self, home: &AgentpackHome) -> anyhow::Result<ImportOutcome> {
+        let _lock = StateLock::acquire(home, "snapshot import")?;
+        let id = self.snapshot.id.clone();
+        let mut outcome = ImportOutcome {
+            snapshot_id: id.clone(),
+            imported: false,
+            files: self.files.len(),
+            tags_added: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        let snapshot_path = DeploymentSnapshot::path(home, &id);
+        if snapshot_path.exists() {
+            outcome
+                .warnings
+                .push(format!("snapshot {id} already exists; nothing imported"));
+        } else {
+            let dir = home.snapshots_dir.join(&id);
+            for f in &self.files {
+                let dst = dir.join(&f.path);
+                crate::fs::write_atomic_with_mode(&dst, &f.bytes()?, f.mode)?;
+            }
+
+            let mut snapshot = self.snapshot.clone();
+            if !snapshot.backup_root.is_empty() {
+                snapshot.backup_root = self.rebase_path(&snapshot.backup_root, &dir);
+            }
+            for c in &mut snapshot.changes {
+                if let Some(backup) = &c.backup_path {
+                    c.backup_path = Some(self.rebase_path(backup, &dir));
+                }
+            }
+            snapshot.save(&snapshot_path)?;
+            outcome.imported = true;
+        }
+
+        let mut tags = SnapshotTags::load(home)?;
+        for name in &self.tags {
+            match tags.tags.get(name) {
+                Some(existing) if existing.snapshot_id != id => outcome.warnings.push(format!(
+                    "tag {name} already points at snapshot {}; kept it",
+                    existing.snapshot_id
+                )),
+                Some(_) => {}
+                None => {
+                    tags.set(name, &id)?;
+                    outcome.tags_added.push(name.clone());
+                }
+            }
+        }
+        if !outcome.tags_added.is_empty() {
+            tags.save(home)?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// Maps a path under the exporting snapshot directory to the same place under `dir`.
+    fn rebase_path(&self, recorded: &str, dir: &Path) -> String {
+        match Path::new(recorded).strip_prefix(&self.source_dir) {
+            Ok(rel) => dir.join(rel).to_string_lossy().to_string(),
+            Err(_) => recorded.to_string(),
+        }
+    }
+}
+
+impl ArchiveFile {
+    fn bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match (&self.content, &self.content_hex) {
+            (Some(text), _) => Ok(text.as_bytes().to_vec()),
+            (None, Some(encoded)) => hex::decode(encoded)
+                .with_context(|| format!("decode content_hex for {}", self.path)),
+            (None, None) => anyhow::bail!("missing content for {}", self.path),
+        }
+    }
+}
+
+fn is_safe_relative(p: &str) -> bool {
+    let path = PathBuf::from(p);
+    !p.is_empty()
+        && !path.is_absolute()
+        && path
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+fn archive_hash(
+    snapshot: &DeploymentSnapshot,
+    source_dir: &str,
+    tags: &[String],
+    files: &[ArchiveFile],
+) -> anyhow::Result<String> {
+    let hash_input = serde_json::json!({
+        "snapshot": snapshot,
+        "source_dir": source_dir,
+        "tags": tags,
+        "files": files,
+    });
+    let bytes = serde_json::to_vec(&hash_input).context("serialize archive_hash input")?;
+    Ok(hex::encode(sha2::Sha256::digest(bytes)))
+}
+
+fn invalid(path: &Path, message: String, reason_code: &str) -> anyhow::Error {
+    anyhow::Error::new(
+        UserError::new("E_SNAPSHOT_ARCHIVE_INVALID", message).with_details(serde_json::json!({
+            "archive_file": path.display().to_string(),
+            "archive_file_posix": crate::paths::path_to_posix_string(path),
+            "reason_code": reason_code,
+            "next_actions": ["rerun_snapshot_export", "retry_command"],
+        })),
+    )
+}
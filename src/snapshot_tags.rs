@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::write_atomic;
+use crate::paths::AgentpackHome;
+use crate::state::DeploymentSnapshot;
+use crate::user_error::UserError;
+
+pub const SNAPSHOT_TAGS_FILENAME: &str = "snapshot_tags.json";
+const SNAPSHOT_TAGS_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotTag {
+    pub snapshot_id: String,
+    pub tagged_at: String,
+}
+
+/// Names for deployment snapshots (`state/snapshot_tags.json`), set by `snapshot tag`.
+///
+/// Anywhere a snapshot id is accepted (`rollback --to`, `snapshot show|diff|export`), a tag
+/// name works too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotTags {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub tags: BTreeMap<String, SnapshotTag>,
+}
+
+impl Default for SnapshotTags {
+    fn default() -> Self {
+        Self {
+            schema_version: SNAPSHOT_TAGS_SCHEMA_VERSION,
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl SnapshotTags {
+    pub fn path(home: &AgentpackHome) -> PathBuf {
+        home.state_dir.join(SNAPSHOT_TAGS_FILENAME)
+    }
+
+    pub fn load(home: &AgentpackHome) -> anyhow::Result<Self> {
+        let path = Self::path(home);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw =
+            std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        let tags: Self = serde_json::from_str(&raw)
+            .with_context(|| format!("parse snapshot tags {}", path.display()))?;
+        if tags.schema_version != SNAPSHOT_TAGS_SCHEMA_VERSION {
+            anyhow::bail!(
+                "unsupported snapshot tags schema_version {} in {}",
+                tags.schema_version,
+                path.display()
+            );
+        }
+        Ok(tags)
+    }
+
+    pub fn save(&self, home: &AgentpackHome) -> anyhow::Result<()> {
+        let path = Self::path(home);
+        let mut out = serde_json::to_string_pretty(self).context("serialize snapshot tags")?;
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        write_atomic(&path, out.as_bytes()).with_context(|| format!("write {}", path.display()))
+    }
+
+    /// Points `name` at `snapshot_id`; returns the snapshot it pointed at before, if any.
+    pub fn set(&mut self, name: &str, snapshot_id: &str) -> anyhow::Result<Option<String>> {
+        validate_tag_name(name)?;
+        let tagged_at = time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .context("format timestamp")?;
+        let previous = self.tags.insert(
+            name.to_string(),
+            SnapshotTag {
+                snapshot_id: snapshot_id.to_string(),
+                tagged_at,
+            },
+        );
+        Ok(previous
+            .map(|t| t.snapshot_id)
+            .filter(|id| id != snapshot_id))
+    }
+
+    pub fn names_for(&self, snapshot_id: &str) -> Vec<String> {
+        self.tags
+            .iter()
+            .filter(|(_, t)| t.snapshot_id == snapshot_id)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// Resolves a snapshot id or tag name to a snapshot id that exists on disk.
+pub fn resolve_snapshot(home: &AgentpackHome, reference: &str) -> anyhow::Result<String> {
+    let is_id = !reference.is_empty() && reference.chars().all(|c| c.is_ascii_digit());
+    if is_id && DeploymentSnapshot::path(home, reference).exists() {
+        return Ok(reference.to_string());
+    }
+
+    let tags = SnapshotTags::load(home)?;
+    if let Some(tag) = tags.tags.get(reference) {
+        if DeploymentSnapshot::path(home, &tag.snapshot_id).exists() {
+            return Ok(tag.snapshot_id.clone());
+        }
+        return Err(not_found(
+            reference,
+            format!(
+                "tag {reference} points at snapshot {} which no longer exists",
+                tag.snapshot_id
+            ),
+            "snapshot_tag_dangling",
+        ));
+    }
+
+    Err(not_found(
+        reference,
+        format!("no snapshot with id or tag {reference}"),
+        "snapshot_not_found",
+    ))
+}
+
+/// Tag names must not look like snapshot ids (all digits), so lookups stay unambiguous.
+fn validate_tag_name(name: &str) -> anyhow::Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if name.is_empty() || !valid_chars || name.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow::Error::new(
+            UserError::new(
+                "E_SNAPSHOT_TAG_INVALID",
+                format!(
+                    "invalid snapshot tag {name:?}: use letters, digits, '.', '_' or '-', and at least one non-digit"
+                ),
+            )
+            .with_details(serde_json::json!({
+                "tag": name,
+                "reason_code": "snapshot_tag_invalid",
+                "next_actions": ["choose_tag_name", "retry_command"],
+            })),
+        ));
+    }
+    Ok(())
+}
+
+fn not_found(reference: &str, message: String, reason_code: &str) -> anyhow::Error {
+    anyhow::Error::new(
+        UserError::new("E_SNAPSHOT_NOT_FOUND", message).with_details(serde_json::json!({
+            "snapshot": reference,
+            "reason_code": reason_code,
+            "next_actions": ["run_snapshot_list", "retry_command"],
+        })),
+    )
+}
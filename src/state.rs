@@ -11,6 +11,11 @@ pub struct ManagedFile {
     pub target: String,
     pub path: String,
     pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
+    /// POSIX permission bits deployed with the file (restored on rollback).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +29,9 @@ pub struct AppliedChange {
     pub before_sha256: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub after_sha256: Option<String>,
+    /// Set when the path was a symlink before the change (restored as a link on rollback).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_link_target: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +48,9 @@ pub struct DeploymentSnapshot {
     pub rolled_back_to: Option<String>,
     pub lockfile_sha256: Option<String>,
     pub backup_root: String,
+    /// Manifest hooks that ran with this deploy or rollback.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<crate::hooks::HookResult>,
 }
 
 fn default_snapshot_kind() -> String {
@@ -59,6 +70,12 @@ impl DeploymentSnapshot {
         home.snapshots_dir.join(id).join("state")
     }
 
+    /// Upstream content of files deployed with merged local edits (the next merge base), laid
+    /// out like [`Self::state_root`].
+    pub fn merge_base_root(home: &AgentpackHome, id: &str) -> PathBuf {
+        home.snapshots_dir.join(id).join("merge_base")
+    }
+
     pub fn load(path: &Path) -> anyhow::Result<Self> {
         let raw =
             std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
@@ -0,0 +1,177 @@
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::AgentpackHome;
+use crate::user_error::UserError;
+
+pub const STATE_LOCK_FILENAME: &str = "state.lock";
+pub const STATE_LOCK_HOLDER_FILENAME: &str = "state.lock.json";
+/// Seconds to wait for a busy lock before failing with `E_LOCKED` (default: 0, fail fast).
+pub const LOCK_TIMEOUT_ENV: &str = "AGENTPACK_LOCK_TIMEOUT";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+thread_local! {
+    static HELD_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Who holds the state lock (`state/state.lock.json`, informational only).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub command: String,
+    pub acquired_at: String,
+}
+
+/// Advisory, cross-process lock over deployment state (target writes, snapshots, overlays).
+///
+/// Backed by an OS file lock on `state/state.lock`, so it is released even if the holder
+/// crashes. Re-acquiring on a thread that already holds it is a no-op, so nested mutating
+/// paths (e.g. `init --bootstrap` applying a plan) do not deadlock.
+pub struct StateLock {
+    file: Option<std::fs::File>,
+    holder_path: PathBuf,
+    // The re-entrancy depth is per thread, so the guard must be dropped where it was acquired.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl StateLock {
+    pub fn path(home: &AgentpackHome) -> PathBuf {
+        home.state_dir.join(STATE_LOCK_FILENAME)
+    }
+
+    pub fn holder_path(home: &AgentpackHome) -> PathBuf {
+        home.state_dir.join(STATE_LOCK_HOLDER_FILENAME)
+    }
+
+    /// Acquires the lock for `command`, waiting up to `AGENTPACK_LOCK_TIMEOUT` seconds.
+    pub fn acquire(home: &AgentpackHome, command: &str) -> anyhow::Result<Self> {
+        let holder_path = Self::holder_path(home);
+        if HELD_DEPTH.with(|d| d.get()) > 0 {
+            HELD_DEPTH.with(|d| d.set(d.get() + 1));
+            return Ok(Self {
+                file: None,
+                holder_path,
+                _not_send: PhantomData,
+            });
+        }
+
+        let path = Self::path(home);
+        std::fs::create_dir_all(&home.state_dir)
+            .with_context(|| format!("create {}", home.state_dir.display()))?;
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("open lock {}", path.display()))?;
+
+        let timeout = lock_timeout();
+        let started = Instant::now();
+        loop {
+            match fs4::FileExt::try_lock(&file) {
+                Ok(()) => break,
+                Err(fs4::TryLockError::WouldBlock) => {
+                    if started.elapsed() >= timeout {
+                        return Err(locked_error(&path, read_holder(&holder_path), timeout));
+                    }
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                Err(fs4::TryLockError::Error(err)) => {
+                    return Err(err).with_context(|| format!("lock {}", path.display()));
+                }
+            }
+        }
+
+        let holder = LockHolder {
+            pid: std::process::id(),
+            command: command.to_string(),
+            acquired_at: time::OffsetDateTime::now_utc()
+                .format(&time::format_description::well_known::Rfc3339)
+                .context("format timestamp")?,
+        };
+        let mut out = serde_json::to_string_pretty(&holder).context("serialize lock holder")?;
+        out.push('\n');
+        crate::fs::write_atomic(&holder_path, out.as_bytes())
+            .with_context(|| format!("write {}", holder_path.display()))?;
+
+        HELD_DEPTH.with(|d| d.set(1));
+        Ok(Self {
+            file: Some(file),
+            holder_path,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Returns the current holder if another process (or thread) holds the lock right now.
+    ///
+    /// Reads the holder file without touching the OS lock: even a shared lock would make a
+    /// concurrent `acquire` fail, so read-only callers (`doctor`) must not lock at all. Holders
+    /// whose process is gone (a crash left the file behind) are ignored.
+    pub fn probe(home: &AgentpackHome) -> anyhow::Result<Option<LockHolder>> {
+        if HELD_DEPTH.with(|d| d.get()) > 0 {
+            return Ok(None);
+        }
+        Ok(read_holder(&Self::holder_path(home)).filter(|h| process_may_be_alive(h.pid)))
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        HELD_DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+        if let Some(file) = self.file.take() {
+            // Clear the holder first so waiters never report a stale pid.
+            let _ = std::fs::remove_file(&self.holder_path);
+            let _ = fs4::FileExt::unlock(&file);
+        }
+    }
+}
+
+fn lock_timeout() -> Duration {
+    std::env::var(LOCK_TIMEOUT_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map(Duration::from_secs_f64)
+        .unwrap_or(Duration::ZERO)
+}
+
+/// Best-effort liveness check via `/proc`; where it is unavailable every holder counts as alive.
+fn process_may_be_alive(pid: u32) -> bool {
+    let proc_dir = Path::new("/proc");
+    !proc_dir.join("self").exists() || proc_dir.join(pid.to_string()).exists()
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn locked_error(path: &Path, holder: Option<LockHolder>, waited: Duration) -> anyhow::Error {
+    let message = match &holder {
+        Some(h) => format!(
+            "deployment state is locked by another agentpack process (pid {}: {})",
+            h.pid, h.command
+        ),
+        None => "deployment state is locked by another agentpack process".to_string(),
+    };
+    anyhow::Error::new(
+        UserError::new("E_LOCKED", message).with_details(serde_json::json!({
+            "lock_path": path.display().to_string(),
+            "lock_path_posix": crate::paths::path_to_posix_string(path),
+            "holder_pid": holder.as_ref().map(|h| h.pid),
+            "holder_command": holder.as_ref().map(|h| h.command.clone()),
+            "holder_acquired_at": holder.as_ref().map(|h| h.acquired_at.clone()),
+            "waited_secs": waited.as_secs_f64(),
+            "wait_env": LOCK_TIMEOUT_ENV,
+            "reason_code": "state_locked",
+            "next_actions": ["wait_and_retry", "retry_command"],
+        })),
+    )
+}
@@ -0,0 +1,78 @@
+//! Running external commands that take a JSON request on stdin (target plugins, hooks).
+
+use std::io::{Read as _, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus};
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+
+pub(crate) struct ChildOutput {
+    pub(crate) status: ExitStatus,
+    pub(crate) stdout: Vec<u8>,
+    pub(crate) stderr: Vec<u8>,
+}
+
+/// A relative program path containing a separator is resolved against the config repo;
+/// bare names are looked up on `PATH`.
+pub(crate) fn resolve_program(repo_dir: &Path, program: &str) -> PathBuf {
+    let p = PathBuf::from(program);
+    if p.is_relative() && (program.contains('/') || program.contains('\\')) {
+        return repo_dir.join(p);
+    }
+    p
+}
+
+/// The last 20 lines of a child's stderr, for error details.
+pub(crate) fn stderr_tail(stderr: &[u8]) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.len().saturating_sub(20);
+    lines[start..].join("\n")
+}
+
+/// Writes `input` to the child's stdin and collects its output; returns `None` (after killing
+/// the child) when it runs longer than `timeout`. The child must be spawned with piped stdio.
+pub(crate) fn wait_with_timeout(
+    mut child: Child,
+    input: Vec<u8>,
+    timeout: Duration,
+) -> anyhow::Result<Option<ChildOutput>> {
+    let mut stdin = child.stdin.take().context("open child stdin")?;
+    let mut stdout = child.stdout.take().context("open child stdout")?;
+    let mut stderr = child.stderr.take().context("open child stderr")?;
+
+    // Write from a separate thread so a child that does not drain stdin cannot deadlock us.
+    std::thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().context("wait for child process")? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    Ok(Some(ChildOutput {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    }))
+}
@@ -1,14 +1,16 @@
+use crate::config::{CustomTargetConfig, Manifest, PluginTargetConfig};
 use crate::deploy::DesiredState;
 use crate::engine::Engine;
 use crate::targets::TargetRoot;
 
 pub trait TargetAdapter {
-    fn id(&self) -> &'static str;
+    fn id(&self) -> &str;
 
     fn render(
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
@@ -40,11 +42,12 @@ impl TargetAdapter for CodexAdapter {
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
     ) -> anyhow::Result<()> {
-        crate::targets::codex::render(engine, modules, desired, warnings, roots)
+        crate::targets::codex::render(engine, modules, profile, desired, warnings, roots)
     }
 }
 
@@ -58,11 +61,12 @@ impl TargetAdapter for ClaudeCodeAdapter {
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
     ) -> anyhow::Result<()> {
-        crate::targets::claude_code::render(engine, modules, desired, warnings, roots)
+        crate::targets::claude_code::render(engine, modules, profile, desired, warnings, roots)
     }
 }
 
@@ -76,11 +80,12 @@ impl TargetAdapter for CursorAdapter {
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
     ) -> anyhow::Result<()> {
-        crate::targets::cursor::render(engine, modules, desired, warnings, roots)
+        crate::targets::cursor::render(engine, modules, profile, desired, warnings, roots)
     }
 }
 
@@ -94,11 +99,12 @@ impl TargetAdapter for VscodeAdapter {
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
     ) -> anyhow::Result<()> {
-        crate::targets::vscode::render(engine, modules, desired, warnings, roots)
+        crate::targets::vscode::render(engine, modules, profile, desired, warnings, roots)
     }
 }
 
@@ -112,11 +118,12 @@ impl TargetAdapter for JetbrainsAdapter {
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
     ) -> anyhow::Result<()> {
-        crate::targets::jetbrains::render(engine, modules, desired, warnings, roots)
+        crate::targets::jetbrains::render(engine, modules, profile, desired, warnings, roots)
     }
 }
 
@@ -130,11 +137,12 @@ impl TargetAdapter for ZedAdapter {
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
     ) -> anyhow::Result<()> {
-        crate::targets::zed::render(engine, modules, desired, warnings, roots)
+        crate::targets::zed::render(engine, modules, profile, desired, warnings, roots)
     }
 }
 
@@ -148,14 +156,107 @@ impl TargetAdapter for ExportDirAdapter {
         &self,
         engine: &Engine,
         modules: &[&crate::config::Module],
+        profile: &str,
         desired: &mut DesiredState,
         warnings: &mut Vec<String>,
         roots: &mut Vec<TargetRoot>,
     ) -> anyhow::Result<()> {
-        crate::targets::export_dir::render(engine, modules, desired, warnings, roots)
+        crate::targets::export_dir::render(engine, modules, profile, desired, warnings, roots)
     }
 }
 
+/// Generic adapter for a declarative `custom_targets:` entry in the manifest.
+pub struct CustomTargetAdapter<'a> {
+    name: &'a str,
+    config: &'a CustomTargetConfig,
+}
+
+impl TargetAdapter for CustomTargetAdapter<'_> {
+    fn id(&self) -> &str {
+        self.name
+    }
+
+    fn render(
+        &self,
+        engine: &Engine,
+        modules: &[&crate::config::Module],
+        profile: &str,
+        desired: &mut DesiredState,
+        warnings: &mut Vec<String>,
+        roots: &mut Vec<TargetRoot>,
+    ) -> anyhow::Result<()> {
+        crate::targets::custom::render(
+            engine,
+            self.name,
+            self.config,
+            modules,
+            profile,
+            desired,
+            warnings,
+            roots,
+        )
+    }
+}
+
+pub fn custom_adapter_for<'a>(
+    manifest: &'a Manifest,
+    target: &str,
+) -> Option<CustomTargetAdapter<'a>> {
+    manifest
+        .custom_targets
+        .get_key_value(target)
+        .map(|(name, config)| CustomTargetAdapter {
+            name: name.as_str(),
+            config,
+        })
+}
+
+/// Adapter for a `plugin_targets:` entry, rendered out of process.
+pub struct PluginTargetAdapter<'a> {
+    name: &'a str,
+    config: &'a PluginTargetConfig,
+}
+
+impl TargetAdapter for PluginTargetAdapter<'_> {
+    fn id(&self) -> &str {
+        self.name
+    }
+
+    fn render(
+        &self,
+        engine: &Engine,
+        modules: &[&crate::config::Module],
+        profile: &str,
+        desired: &mut DesiredState,
+        warnings: &mut Vec<String>,
+        roots: &mut Vec<TargetRoot>,
+    ) -> anyhow::Result<()> {
+        crate::target_plugin::render(
+            engine,
+            self.name,
+            self.config,
+            modules,
+            profile,
+            desired,
+            warnings,
+            roots,
+        )
+    }
+}
+
+pub fn plugin_adapter_for<'a>(
+    manifest: &'a Manifest,
+    target: &str,
+) -> Option<PluginTargetAdapter<'a>> {
+    manifest
+        .plugin_targets
+        .get_key_value(target)
+        .map(|(name, config)| PluginTargetAdapter {
+            name: name.as_str(),
+            config,
+        })
+}
+
 pub fn adapter_for(target: &str) -> Option<&'static dyn TargetAdapter> {
     #[cfg(feature = "target-codex")]
     static CODEX: CodexAdapter = CodexAdapter;
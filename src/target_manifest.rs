@@ -11,12 +11,22 @@ pub const LEGACY_TARGET_MANIFEST_FILENAME: &str = ".agentpack.manifest.json";
 pub const TARGET_MANIFEST_GITIGNORE_LINE: &str = ".agentpack.manifest*.json";
 const TARGET_MANIFEST_SCHEMA_VERSION: u32 = 1;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ManagedManifestFile {
     pub path: String,
     pub sha256: String,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub module_ids: Vec<String>,
+    /// Symlink target owned by agentpack (symlink mode); `sha256` is the content at deploy time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
+    /// Set when local edits were merged in: the sha256 of the upstream content, kept by the
+    /// snapshot as the next merge base (`sha256` is then the merged content on disk).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_base_sha256: Option<String>,
+    /// Mode of executable files (see [`crate::fs::executable_mode`]); absent otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
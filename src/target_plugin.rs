@@ -0,0 +1,581 @@
+//! Out-of-process target adapters ("target plugins").
+//!
+//! Agentpack runs the configured command, writes one JSON request to its stdin and
+//! reads one JSON response from its stdout. The protocol is versioned via
+//! `protocol_version`; responses are validated strictly (unknown fields, paths
+//! outside the declared roots, and unknown module ids are rejected).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Module, ModuleType, PluginTargetConfig, TargetScope};
+use crate::deploy::DesiredState;
+use crate::engine::Engine;
+use crate::subprocess::{resolve_program, stderr_tail, wait_with_timeout};
+use crate::targets::TargetRoot;
+use crate::user_error::UserError;
+
+pub const PROTOCOL_NAME: &str = "agentpack.target_plugin";
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginRequest {
+    pub protocol: &'static str,
+    pub protocol_version: u32,
+    pub target: String,
+    pub scope: TargetScope,
+    pub options: BTreeMap<String, serde_yaml::Value>,
+    pub project: PluginProject,
+    pub modules: Vec<PluginModule>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginProject {
+    pub project_root: String,
+    pub project_id: String,
+    pub origin_url: Option<String>,
+    pub machine_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginModule {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub module_type: ModuleType,
+    pub tags: Vec<String>,
+    pub metadata: BTreeMap<String, serde_yaml::Value>,
+    /// Materialized module directory (upstream + overlays), valid for the duration of the call.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginResponse {
+    pub protocol_version: u32,
+    pub roots: Vec<PluginRoot>,
+    #[serde(default)]
+    pub files: Vec<PluginFile>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginRoot {
+    pub root: String,
+    #[serde(default)]
+    pub scan_extras: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginFile {
+    pub path: String,
+    pub module_ids: Vec<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub content_hex: Option<String>,
+}
+
+fn plugin_failed(message: String, details: serde_json::Value) -> anyhow::Error {
+    anyhow::Error::new(UserError::new("E_TARGET_PLUGIN_FAILED", message).with_details(details))
+}
+
+fn invalid_output(
+    target: &str,
+    message: impl Into<String>,
+    extra: serde_json::Value,
+) -> anyhow::Error {
+    let mut details = serde_json::json!({
+        "target": target,
+        "reason_code": "plugin_output_invalid",
+        "next_actions": ["fix_plugin_output", "retry_command"],
+    });
+    if let (Some(obj), serde_json::Value::Object(extra)) = (details.as_object_mut(), extra) {
+        obj.extend(extra);
+    }
+    anyhow::Error::new(
+        UserError::new("E_TARGET_PLUGIN_INVALID_OUTPUT", message.into()).with_details(details),
+    )
+}
+
+/// Runs the plugin with `request` on stdin and returns its raw stdout.
+fn run_plugin(
+    engine: &Engine,
+    target: &str,
+    cfg: &PluginTargetConfig,
+    request: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let Some((program, args)) = cfg.command.split_first() else {
+        return Err(crate::config::plugin_target_invalid(
+            target,
+            format!("plugin target {target} requires a non-empty command"),
+            serde_json::json!({ "field": "command" }),
+        ));
+    };
+    let timeout = Duration::from_millis(cfg.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+
+    let child = Command::new(resolve_program(&engine.repo.repo_dir, program))
+        .args(args)
+        .current_dir(&engine.repo.repo_dir)
+        .env("AGENTPACK_TARGET", target)
+        .env(
+            "AGENTPACK_TARGET_PLUGIN_PROTOCOL",
+            PROTOCOL_VERSION.to_string(),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            plugin_failed(
+                format!("failed to start plugin for target {target}: {err}"),
+                serde_json::json!({
+                    "target": target,
+                    "command": cfg.command,
+                    "error": err.to_string(),
+                    "reason_code": "plugin_spawn_failed",
+                    "next_actions": ["edit_manifest_plugin_targets", "retry_command"],
+                }),
+            )
+        })?;
+
+    let Some(output) = wait_with_timeout(child, request.to_vec(), timeout)? else {
+        return Err(plugin_failed(
+            format!(
+                "plugin for target {target} timed out after {}ms",
+                timeout.as_millis()
+            ),
+            serde_json::json!({
+                "target": target,
+                "command": cfg.command,
+                "timeout_ms": timeout.as_millis() as u64,
+                "reason_code": "plugin_timeout",
+                "next_actions": ["increase_plugin_timeout", "retry_command"],
+            }),
+        ));
+    };
+
+    if !output.status.success() {
+        return Err(plugin_failed(
+            format!("plugin for target {target} failed ({})", output.status),
+            serde_json::json!({
+                "target": target,
+                "command": cfg.command,
+                "exit_code": output.status.code(),
+                "stderr": stderr_tail(&output.stderr),
+                "reason_code": "plugin_exit_nonzero",
+                "next_actions": ["inspect_plugin_stderr", "retry_command"],
+            }),
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+fn absolute_clean_path(raw: &str) -> Option<PathBuf> {
+    let p = PathBuf::from(raw);
+    if !p.is_absolute() || p.components().any(|c| matches!(c, Component::ParentDir)) {
+        return None;
+    }
+    Some(p)
+}
+
+struct ValidatedResponse {
+    roots: Vec<TargetRoot>,
+    files: Vec<(PathBuf, Vec<u8>, Vec<String>)>,
+    warnings: Vec<String>,
+}
+
+/// Project-scope roots must lie inside the project root and user-scope roots outside it.
+fn root_matches_scope(root: &Path, scope: &TargetScope, project_root: &Path) -> bool {
+    match scope {
+        TargetScope::Project => root.starts_with(project_root),
+        TargetScope::User => !root.starts_with(project_root),
+        TargetScope::Both => true,
+    }
+}
+
+fn validate_response(
+    target: &str,
+    response: PluginResponse,
+    module_ids: &BTreeSet<String>,
+    scope: &TargetScope,
+    project_root: &Path,
+) -> anyhow::Result<ValidatedResponse> {
+    if response.protocol_version != PROTOCOL_VERSION {
+        return Err(invalid_output(
+            target,
+            format!(
+                "plugin for target {target} speaks protocol_version {} (expected {PROTOCOL_VERSION})",
+                response.protocol_version
+            ),
+            serde_json::json!({
+                "protocol_version": response.protocol_version,
+                "supported": [PROTOCOL_VERSION],
+            }),
+        ));
+    }
+
+    let mut roots = Vec::new();
+    for r in &response.roots {
+        let Some(root) = absolute_clean_path(&r.root) else {
+            return Err(invalid_output(
+                target,
+                format!(
+                    "plugin root must be an absolute path without `..`: {}",
+                    r.root
+                ),
+                serde_json::json!({ "root": r.root }),
+            ));
+        };
+        if !root_matches_scope(&root, scope, project_root) {
+            let scope_name = match scope {
+                TargetScope::Project => "project",
+                _ => "user",
+            };
+            return Err(invalid_output(
+                target,
+                format!(
+                    "plugin root {} does not match the target's {scope_name} scope (project root: {})",
+                    r.root,
+                    project_root.display()
+                ),
+                serde_json::json!({
+                    "root": r.root,
+                    "scope": scope_name,
+                    "project_root": project_root.to_string_lossy(),
+                }),
+            ));
+        }
+        roots.push(TargetRoot {
+            target: target.to_string(),
+            root,
+            scan_extras: r.scan_extras,
+        });
+    }
+
+    let mut files = Vec::new();
+    for f in response.files {
+        let Some(path) = absolute_clean_path(&f.path) else {
+            return Err(invalid_output(
+                target,
+                format!(
+                    "plugin file path must be an absolute path without `..`: {}",
+                    f.path
+                ),
+                serde_json::json!({ "path": f.path }),
+            ));
+        };
+        if !roots
+            .iter()
+            .any(|r| path != r.root && path.starts_with(&r.root))
+        {
+            return Err(invalid_output(
+                target,
+                format!("plugin file is not under any declared root: {}", f.path),
+                serde_json::json!({ "path": f.path }),
+            ));
+        }
+        if f.module_ids.is_empty() {
+            return Err(invalid_output(
+                target,
+                format!("plugin file has no module_ids: {}", f.path),
+                serde_json::json!({ "path": f.path }),
+            ));
+        }
+        if let Some(unknown) = f.module_ids.iter().find(|id| !module_ids.contains(*id)) {
+            return Err(invalid_output(
+                target,
+                format!(
+                    "plugin file references unknown module id {unknown}: {}",
+                    f.path
+                ),
+                serde_json::json!({ "path": f.path, "module_id": unknown }),
+            ));
+        }
+        let bytes = match (f.content, f.content_hex) {
+            (Some(text), None) => text.into_bytes(),
+            (None, Some(h)) => hex::decode(h.trim()).map_err(|err| {
+                invalid_output(
+                    target,
+                    format!("plugin file has invalid content_hex: {}", f.path),
+                    serde_json::json!({ "path": f.path, "error": err.to_string() }),
+                )
+            })?,
+            _ => {
+                return Err(invalid_output(
+                    target,
+                    format!(
+                        "plugin file must set exactly one of content/content_hex: {}",
+                        f.path
+                    ),
+                    serde_json::json!({ "path": f.path }),
+                ));
+            }
+        };
+        files.push((path, bytes, f.module_ids));
+    }
+
+    let warnings = response
+        .warnings
+        .into_iter()
+        .map(|w| format!("target {target}: {w}"))
+        .collect();
+    Ok(ValidatedResponse {
+        roots,
+        files,
+        warnings,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render(
+    engine: &Engine,
+    target: &str,
+    cfg: &PluginTargetConfig,
+    modules: &[&Module],
+    profile: &str,
+    desired: &mut DesiredState,
+    warnings: &mut Vec<String>,
+    roots: &mut Vec<TargetRoot>,
+) -> anyhow::Result<()> {
+    let mut materialized = Vec::new();
+    let mut request_modules = Vec::new();
+    for m in modules
+        .iter()
+        .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == target))
+    {
+        let (tmp, dir) = engine.materialize_module(m, profile, warnings)?;
+        request_modules.push(PluginModule {
+            id: m.id.clone(),
+            module_type: m.module_type.clone(),
+            tags: m.tags.clone(),
+            metadata: m.metadata.clone(),
+            path: dir.to_string_lossy().to_string(),
+        });
+        materialized.push(tmp);
+    }
+
+    let module_ids: BTreeSet<String> = request_modules.iter().map(|m| m.id.clone()).collect();
+    let request = PluginRequest {
+        protocol: PROTOCOL_NAME,
+        protocol_version: PROTOCOL_VERSION,
+        target: target.to_string(),
+        scope: cfg.scope.clone(),
+        options: cfg.options.clone(),
+        project: PluginProject {
+            project_root: engine.project.project_root.to_string_lossy().to_string(),
+            project_id: engine.project.project_id.clone(),
+            origin_url: engine.project.origin_url.clone(),
+            machine_id: engine.machine_id.clone(),
+        },
+        modules: request_modules,
+    };
+    let request = serde_json::to_vec(&request).context("serialize plugin request")?;
+
+    let stdout = run_plugin(engine, target, cfg, &request)?;
+    drop(materialized);
+
+    let response: PluginResponse = serde_json::from_slice(&stdout).map_err(|err| {
+        invalid_output(
+            target,
+            format!("plugin for target {target} returned invalid JSON: {err}"),
+            serde_json::json!({ "error": err.to_string() }),
+        )
+    })?;
+
+    let validated = validate_response(
+        target,
+        response,
+        &module_ids,
+        &cfg.scope,
+        &engine.project.project_root,
+    )?;
+    roots.extend(validated.roots);
+    warnings.extend(validated.warnings);
+    for (path, bytes, ids) in validated.files {
+        crate::deploy::insert_desired_file(desired, target, path, bytes, ids)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn ids(list: &[&str]) -> BTreeSet<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn root() -> String {
+        if cfg!(windows) {
+            "C:\\out".to_string()
+        } else {
+            "/out".to_string()
+        }
+    }
+
+    fn file_under_root(name: &str) -> String {
+        Path::new(&root()).join(name).to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn response_rejects_unknown_fields() {
+        let raw = r#"{"protocol_version":1,"roots":[],"files":[],"extra":true}"#;
+        assert!(serde_json::from_str::<PluginResponse>(raw).is_err());
+    }
+
+    #[test]
+    fn response_rejects_files_outside_roots_and_unknown_modules() {
+        let outside = PluginResponse {
+            protocol_version: 1,
+            roots: vec![PluginRoot {
+                root: root(),
+                scan_extras: false,
+            }],
+            files: vec![PluginFile {
+                path: file_under_root("../escape.md"),
+                module_ids: vec!["a".to_string()],
+                content: Some("x".to_string()),
+                content_hex: None,
+            }],
+            warnings: Vec::new(),
+        };
+        assert!(
+            validate_response(
+                "t",
+                outside,
+                &ids(&["a"]),
+                &TargetScope::Both,
+                Path::new("/project")
+            )
+            .is_err()
+        );
+
+        let unknown = PluginResponse {
+            protocol_version: 1,
+            roots: vec![PluginRoot {
+                root: root(),
+                scan_extras: false,
+            }],
+            files: vec![PluginFile {
+                path: file_under_root("a.md"),
+                module_ids: vec!["b".to_string()],
+                content: Some("x".to_string()),
+                content_hex: None,
+            }],
+            warnings: Vec::new(),
+        };
+        assert!(
+            validate_response(
+                "t",
+                unknown,
+                &ids(&["a"]),
+                &TargetScope::Both,
+                Path::new("/project")
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn response_accepts_hex_content_and_rejects_version_mismatch() {
+        let ok = PluginResponse {
+            protocol_version: 1,
+            roots: vec![PluginRoot {
+                root: root(),
+                scan_extras: true,
+            }],
+            files: vec![PluginFile {
+                path: file_under_root("a.bin"),
+                module_ids: vec!["a".to_string()],
+                content: None,
+                content_hex: Some("00ff".to_string()),
+            }],
+            warnings: vec!["note".to_string()],
+        };
+        let validated = validate_response(
+            "t",
+            ok,
+            &ids(&["a"]),
+            &TargetScope::Both,
+            Path::new("/project"),
+        )
+        .expect("valid");
+        assert!(validated.roots[0].scan_extras);
+        assert_eq!(validated.files[0].1, vec![0x00, 0xff]);
+        assert_eq!(validated.warnings, vec!["target t: note".to_string()]);
+
+        let v2 = PluginResponse {
+            protocol_version: 2,
+            roots: Vec::new(),
+            files: Vec::new(),
+            warnings: Vec::new(),
+        };
+        assert!(
+            validate_response(
+                "t",
+                v2,
+                &ids(&[]),
+                &TargetScope::Both,
+                Path::new("/project")
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn response_roots_must_match_the_target_scope() {
+        let response = || PluginResponse {
+            protocol_version: 1,
+            roots: vec![PluginRoot {
+                root: root(),
+                scan_extras: false,
+            }],
+            files: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let project = PathBuf::from(root()).join("project");
+        let outside = Path::new(&root()).join("elsewhere");
+
+        assert!(
+            validate_response("t", response(), &ids(&[]), &TargetScope::Project, &project).is_err()
+        );
+        assert!(
+            validate_response("t", response(), &ids(&[]), &TargetScope::User, &project).is_ok()
+        );
+        assert!(
+            validate_response(
+                "t",
+                response(),
+                &ids(&[]),
+                &TargetScope::User,
+                Path::new(&root())
+            )
+            .is_err()
+        );
+        assert!(
+            validate_response(
+                "t",
+                response(),
+                &ids(&[]),
+                &TargetScope::Project,
+                Path::new(&root())
+            )
+            .is_ok()
+        );
+        assert!(
+            validate_response("t", response(), &ids(&[]), &TargetScope::Both, &outside).is_ok()
+        );
+    }
+}
@@ -15,6 +15,21 @@ pub const COMPILED_TARGETS: &[&str] = &[
     "export_dir",
 ];
 
+/// All built-in target ids, regardless of enabled cargo features.
+pub const BUILTIN_TARGETS: &[&str] = &[
+    "codex",
+    "claude_code",
+    "cursor",
+    "vscode",
+    "jetbrains",
+    "zed",
+    "export_dir",
+];
+
+pub fn is_builtin_target(target: &str) -> bool {
+    BUILTIN_TARGETS.iter().any(|t| t == &target)
+}
+
 pub fn is_compiled_target(target: &str) -> bool {
     COMPILED_TARGETS.iter().any(|t| t == &target)
 }
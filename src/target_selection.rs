@@ -2,14 +2,22 @@ use crate::config::Manifest;
 use crate::user_error::UserError;
 
 pub fn selected_targets(manifest: &Manifest, target_filter: &str) -> anyhow::Result<Vec<String>> {
-    let mut known: Vec<String> = manifest.targets.keys().cloned().collect();
+    let mut known: Vec<String> = manifest
+        .targets
+        .keys()
+        .chain(manifest.declared_target_names())
+        .cloned()
+        .collect();
     known.sort();
 
     match target_filter {
         "all" => {
             let missing: Vec<String> = known
                 .iter()
-                .filter(|t| !crate::target_registry::is_compiled_target(t))
+                .filter(|t| {
+                    !crate::target_registry::is_compiled_target(t)
+                        && !manifest.is_declared_target(t)
+                })
                 .cloned()
                 .collect();
             if !missing.is_empty() {
@@ -46,6 +54,7 @@ pub fn selected_targets(manifest: &Manifest, target_filter: &str) -> anyhow::Res
             }
             Ok(vec![t.to_string()])
         }
+        t if manifest.is_declared_target(t) => Ok(vec![t.to_string()]),
         other => Err(anyhow::Error::new(
             UserError::new(
                 "E_TARGET_UNSUPPORTED",
@@ -53,7 +62,11 @@ pub fn selected_targets(manifest: &Manifest, target_filter: &str) -> anyhow::Res
             )
             .with_details(serde_json::json!({
                 "target": other,
-                "allowed": crate::target_registry::allowed_target_filters(),
+                "allowed": crate::target_registry::allowed_target_filters()
+                    .into_iter()
+                    .map(str::to_string)
+                    .chain(manifest.declared_target_names().cloned())
+                    .collect::<Vec<_>>(),
                 "reason_code": "target_filter_unsupported",
                 "next_actions": ["inspect_help_json", "retry_with_supported_target"],
             })),
@@ -8,12 +8,13 @@ use crate::store::sanitize_module_id;
 
 use super::TargetRoot;
 use super::util::{
-    expand_tilde, first_file, get_bool, insert_file, module_name_from_id, scope_flags,
+    expand_tilde, first_file, get_bool, insert_file_with_mode, module_name_from_id, scope_flags,
 };
 
 pub(crate) fn render(
     engine: &Engine,
     modules: &[&Module],
+    profile: &str,
     desired: &mut DesiredState,
     warnings: &mut Vec<String>,
     roots: &mut Vec<TargetRoot>,
@@ -68,25 +69,27 @@ pub(crate) fn render(
         .filter(|m| matches!(m.module_type, ModuleType::Command))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "claude_code"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let cmd_file = first_file(&materialized)?;
         let name = cmd_file
             .file_name()
             .and_then(|s| s.to_str())
             .unwrap_or("command.md");
         let bytes = std::fs::read(&cmd_file)?;
+        let mode = crate::fs::executable_mode(&cmd_file);
 
         if write_user_commands {
-            insert_file(
+            insert_file_with_mode(
                 desired,
                 "claude_code",
                 user_commands_dir.join(name),
                 bytes.clone(),
+                mode,
                 vec![m.id.clone()],
             )?;
         }
         if write_repo_commands {
-            insert_file(
+            insert_file_with_mode(
                 desired,
                 "claude_code",
                 engine
@@ -95,6 +98,7 @@ pub(crate) fn render(
                     .join(".claude/commands")
                     .join(name),
                 bytes,
+                mode,
                 vec![m.id.clone()],
             )?;
         }
@@ -108,7 +112,7 @@ pub(crate) fn render(
         if !write_user_skills && !write_repo_skills {
             continue;
         }
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let skill_name = module_name_from_id(&m.id).unwrap_or_else(|| sanitize_module_id(&m.id));
 
         let files = list_files(&materialized)?;
@@ -119,14 +123,16 @@ pub(crate) fn render(
                 .to_string_lossy()
                 .replace('\\', "/");
             let bytes = std::fs::read(&f)?;
+            let mode = crate::fs::executable_mode(&f);
 
             if write_user_skills {
                 let dst = user_skills_dir.join(&skill_name).join(&rel);
-                insert_file(
+                insert_file_with_mode(
                     desired,
                     "claude_code",
                     dst,
                     bytes.clone(),
+                    mode,
                     vec![m.id.clone()],
                 )?;
             }
@@ -137,7 +143,14 @@ pub(crate) fn render(
                     .join(".claude/skills")
                     .join(&skill_name)
                     .join(&rel);
-                insert_file(desired, "claude_code", dst, bytes, vec![m.id.clone()])?;
+                insert_file_with_mode(
+                    desired,
+                    "claude_code",
+                    dst,
+                    bytes,
+                    mode,
+                    vec![m.id.clone()],
+                )?;
             }
         }
     }
@@ -1,19 +1,24 @@
+use std::collections::BTreeMap;
+
 use anyhow::Context as _;
 
 use crate::config::{Module, ModuleType};
 use crate::deploy::DesiredState;
 use crate::engine::Engine;
 use crate::fs::list_files;
+use crate::output_spans::SpannedOutput;
 use crate::store::sanitize_module_id;
 
 use super::TargetRoot;
 use super::util::{
-    codex_home_from_options, first_file, get_bool, insert_file, module_name_from_id, scope_flags,
+    codex_home_from_options, first_file, get_bool, insert_file_with_mode, insert_output,
+    instructions_subdirs, module_name_from_id, scope_flags,
 };
 
 pub(crate) fn render(
     engine: &Engine,
     modules: &[&Module],
+    profile: &str,
     desired: &mut DesiredState,
     warnings: &mut Vec<String>,
     roots: &mut Vec<TargetRoot>,
@@ -32,6 +37,7 @@ pub(crate) fn render(
     let write_user_prompts = allow_user && get_bool(opts, "write_user_prompts", true);
     let write_agents_global = allow_user && get_bool(opts, "write_agents_global", true);
     let write_agents_repo_root = allow_project && get_bool(opts, "write_agents_repo_root", true);
+    let write_agents_nested = allow_project && get_bool(opts, "write_agents_nested", true);
 
     if write_agents_global {
         roots.push(TargetRoot {
@@ -70,58 +76,74 @@ pub(crate) fn render(
     }
 
     let mut instructions_parts: Vec<(String, String)> = Vec::new();
+    let mut nested_parts: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
     for m in modules
         .iter()
         .filter(|m| matches!(m.module_type, ModuleType::Instructions))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "codex"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let agents_path = materialized.join("AGENTS.md");
         if agents_path.exists() {
-            instructions_parts.push((
-                m.id.clone(),
-                std::fs::read_to_string(&agents_path)
-                    .with_context(|| format!("read {}", agents_path.display()))?,
-            ));
+            let text = std::fs::read_to_string(&agents_path)
+                .with_context(|| format!("read {}", agents_path.display()))?;
+            if m.instructions_subpaths().is_empty() {
+                instructions_parts.push((m.id.clone(), text));
+                continue;
+            }
+            if !write_agents_nested {
+                warnings.push(format!(
+                    "codex: nested AGENTS.md files are disabled (write_agents_nested or project scope); module {} is aggregated at the root instead of its instructions subpaths",
+                    m.id
+                ));
+                instructions_parts.push((m.id.clone(), text));
+                continue;
+            }
+            for subdir in instructions_subdirs(&engine.project.project_root, m, warnings)? {
+                nested_parts
+                    .entry(subdir)
+                    .or_default()
+                    .push((m.id.clone(), text.clone()));
+            }
         }
     }
 
+    if !nested_parts.is_empty() {
+        roots.push(TargetRoot {
+            target: "codex".to_string(),
+            root: engine.project.project_root.clone(),
+            scan_extras: false,
+        });
+    }
+    for (subdir, parts) in nested_parts {
+        let (module_ids, output) = combine_instructions(parts);
+        insert_output(
+            desired,
+            "codex",
+            engine.project.project_root.join(subdir).join("AGENTS.md"),
+            output,
+            module_ids,
+        )?;
+    }
+
     if !instructions_parts.is_empty() {
-        let module_ids: Vec<String> = instructions_parts
-            .iter()
-            .map(|(id, _)| id.clone())
-            .collect();
-        let add_markers = instructions_parts.len() > 1;
-        let combined = if add_markers {
-            instructions_parts
-                .into_iter()
-                .map(|(module_id, text)| crate::markers::format_module_section(&module_id, &text))
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        } else {
-            instructions_parts
-                .into_iter()
-                .map(|(_, text)| text)
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        };
-        let bytes = combined.into_bytes();
+        let (module_ids, output) = combine_instructions(instructions_parts);
 
         if write_agents_global {
-            insert_file(
+            insert_output(
                 desired,
                 "codex",
                 codex_home.join("AGENTS.md"),
-                bytes.clone(),
+                output.clone(),
                 module_ids.clone(),
             )?;
         }
         if write_agents_repo_root {
-            insert_file(
+            insert_output(
                 desired,
                 "codex",
                 engine.project.project_root.join("AGENTS.md"),
-                bytes,
+                output,
                 module_ids,
             )?;
         }
@@ -135,18 +157,20 @@ pub(crate) fn render(
         if !write_user_prompts {
             continue;
         }
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let prompt_file = first_file(&materialized)?;
         let name = prompt_file
             .file_name()
             .and_then(|s| s.to_str())
             .unwrap_or("prompt.md");
         let bytes = std::fs::read(&prompt_file)?;
-        insert_file(
+        let mode = crate::fs::executable_mode(&prompt_file);
+        insert_file_with_mode(
             desired,
             "codex",
             codex_home.join("prompts").join(name),
             bytes,
+            mode,
             vec![m.id.clone()],
         )?;
     }
@@ -156,7 +180,7 @@ pub(crate) fn render(
         .filter(|m| matches!(m.module_type, ModuleType::Skill))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "codex"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let skill_name = module_name_from_id(&m.id).unwrap_or_else(|| sanitize_module_id(&m.id));
 
         let files = list_files(&materialized)?;
@@ -167,10 +191,18 @@ pub(crate) fn render(
                 .to_string_lossy()
                 .replace('\\', "/");
             let bytes = std::fs::read(&f)?;
+            let mode = crate::fs::executable_mode(&f);
 
             if write_user_skills {
                 let dst = codex_home.join("skills").join(&skill_name).join(&rel);
-                insert_file(desired, "codex", dst, bytes.clone(), vec![m.id.clone()])?;
+                insert_file_with_mode(
+                    desired,
+                    "codex",
+                    dst,
+                    bytes.clone(),
+                    mode,
+                    vec![m.id.clone()],
+                )?;
             }
             if write_repo_skills {
                 let dst = engine
@@ -179,10 +211,19 @@ pub(crate) fn render(
                     .join(".codex/skills")
                     .join(&skill_name)
                     .join(&rel);
-                insert_file(desired, "codex", dst, bytes, vec![m.id.clone()])?;
+                insert_file_with_mode(desired, "codex", dst, bytes, mode, vec![m.id.clone()])?;
             }
         }
     }
 
     Ok(())
 }
+
+/// Joins instructions into one `AGENTS.md` body (with per-module markers when there are several).
+fn combine_instructions(parts: Vec<(String, String)>) -> (Vec<String>, SpannedOutput) {
+    let module_ids: Vec<String> = parts.iter().map(|(id, _)| id.clone()).collect();
+    (
+        module_ids,
+        crate::output_spans::aggregate_instructions(&parts),
+    )
+}
@@ -3,13 +3,15 @@ use anyhow::Context as _;
 use crate::config::{Module, ModuleType};
 use crate::deploy::DesiredState;
 use crate::engine::Engine;
+use crate::output_spans::SpannedOutput;
 
 use super::TargetRoot;
-use super::util::{get_bool, insert_file, scope_flags};
+use super::util::{get_bool, insert_output, instructions_subdirs, scope_flags};
 
 pub(crate) fn render(
     engine: &Engine,
     modules: &[&Module],
+    profile: &str,
     desired: &mut DesiredState,
     warnings: &mut Vec<String>,
     roots: &mut Vec<TargetRoot>,
@@ -42,7 +44,7 @@ pub(crate) fn render(
             continue;
         }
 
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let agents_file = materialized.join("AGENTS.md");
         let body_bytes = std::fs::read(&agents_file)
             .with_context(|| format!("read {}", agents_file.display()))?;
@@ -50,17 +52,29 @@ pub(crate) fn render(
         let description = format!("agentpack: {}", m.id);
         let description_json =
             serde_json::to_string(&description).context("serialize cursor rule description")?;
-        let header =
-            format!("---\ndescription: {description_json}\nglobs: []\nalwaysApply: true\n---\n\n");
+        // Nested instructions become auto-attached rules scoped to their directories.
+        let header = if m.instructions_subpaths().is_empty() {
+            format!("---\ndescription: {description_json}\nglobs: []\nalwaysApply: true\n---\n\n")
+        } else {
+            let subdirs = instructions_subdirs(&engine.project.project_root, m, warnings)?;
+            if subdirs.is_empty() {
+                continue;
+            }
+            let globs: Vec<String> = subdirs.iter().map(|d| format!("{d}/**")).collect();
+            let globs_json =
+                serde_json::to_string(&globs).context("serialize cursor rule globs")?;
+            format!(
+                "---\ndescription: {description_json}\nglobs: {globs_json}\nalwaysApply: false\n---\n\n"
+            )
+        };
 
-        let mut out = header.into_bytes();
-        out.extend(body_bytes);
-        if !out.ends_with(b"\n") {
-            out.push(b'\n');
-        }
+        let mut out = SpannedOutput::default();
+        out.push_synthesized(header.as_bytes());
+        out.push_module(&m.id, &body_bytes);
+        out.terminate_line();
 
         let name = format!("{}.mdc", crate::ids::module_fs_key(&m.id));
-        insert_file(
+        insert_output(
             desired,
             "cursor",
             rules_dir.join(name),
@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::config::{
+    CustomAggregateMode, CustomFileOutput, CustomTargetConfig, Module, ModuleType,
+    TEMPLATE_MODULE_NAME, TEMPLATE_PROJECT_ROOT,
+};
+use crate::deploy::DesiredState;
+use crate::engine::Engine;
+use crate::fs::list_files;
+use crate::output_spans::SpannedOutput;
+use crate::store::sanitize_module_id;
+
+use super::TargetRoot;
+use super::util::{
+    expand_tilde, first_file, insert_file_with_mode, insert_output, module_name_from_id,
+    warn_instructions_subpaths_at_root,
+};
+
+/// Expands a custom target path template (`~`, `{project_root}`, `{module_name}`).
+pub(crate) fn expand_path_template(
+    template: &str,
+    project_root: &Path,
+    module_name: Option<&str>,
+) -> anyhow::Result<PathBuf> {
+    let mut t = template.trim().replace('\\', "/");
+    if let Some(name) = module_name {
+        t = t.replace(TEMPLATE_MODULE_NAME, name);
+    }
+
+    if let Some(rest) = t.strip_prefix(TEMPLATE_PROJECT_ROOT) {
+        let rest = rest.trim_start_matches('/');
+        if rest.is_empty() {
+            return Ok(project_root.to_path_buf());
+        }
+        return Ok(project_root.join(rest));
+    }
+    if t == "~" {
+        return dirs::home_dir().context("resolve home dir");
+    }
+    expand_tilde(&t)
+}
+
+fn module_name(module: &Module) -> String {
+    module_name_from_id(&module.id).unwrap_or_else(|| sanitize_module_id(&module.id))
+}
+
+fn applies_to(module: &Module, target: &str) -> bool {
+    module.targets.is_empty() || module.targets.iter().any(|t| t == target)
+}
+
+fn with_frontmatter(
+    frontmatter: Option<&str>,
+    module: Option<&Module>,
+    body: SpannedOutput,
+) -> SpannedOutput {
+    let Some(frontmatter) = frontmatter else {
+        return body;
+    };
+
+    let mut fm = frontmatter.trim_end_matches('\n').to_string();
+    if let Some(m) = module {
+        fm = fm
+            .replace("{module_id}", &m.id)
+            .replace(TEMPLATE_MODULE_NAME, &module_name(m));
+    }
+
+    let mut out = SpannedOutput::default();
+    out.push_synthesized(format!("---\n{fm}\n---\n\n").as_bytes());
+    out.append(body);
+    out.terminate_line();
+    out
+}
+
+fn output_file_name(source: &Path, output: &CustomFileOutput, fallback: &str) -> String {
+    let name = source
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(fallback);
+    let Some(ext) = output.extension.as_deref() else {
+        return name.to_string();
+    };
+    if name.ends_with(ext) {
+        name.to_string()
+    } else if let Some(stem) = name.strip_suffix(".md") {
+        format!("{stem}{ext}")
+    } else {
+        format!("{name}{ext}")
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render(
+    engine: &Engine,
+    target: &str,
+    cfg: &CustomTargetConfig,
+    modules: &[&Module],
+    profile: &str,
+    desired: &mut DesiredState,
+    warnings: &mut Vec<String>,
+    roots: &mut Vec<TargetRoot>,
+) -> anyhow::Result<()> {
+    let project_root = &engine.project.project_root;
+
+    let mut scan_extras_roots = Vec::new();
+    for extra in &cfg.scan_extras {
+        scan_extras_roots.push(expand_path_template(extra, project_root, None)?);
+    }
+    for (_kind, root_template) in cfg.root_templates() {
+        let root = expand_path_template(&root_template, project_root, None)?;
+        let scan_extras = scan_extras_roots.contains(&root);
+        roots.push(TargetRoot {
+            target: target.to_string(),
+            root,
+            scan_extras,
+        });
+    }
+
+    if let Some(out) = &cfg.instructions {
+        let mut instructions_parts: Vec<(&Module, Vec<u8>)> = Vec::new();
+        for m in modules
+            .iter()
+            .filter(|m| matches!(m.module_type, ModuleType::Instructions))
+            .filter(|m| applies_to(m, target))
+        {
+            warn_instructions_subpaths_at_root(target, m, warnings);
+            let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
+            let agents_path = materialized.join("AGENTS.md");
+            if agents_path.exists() {
+                instructions_parts.push((
+                    m,
+                    std::fs::read(&agents_path)
+                        .with_context(|| format!("read {}", agents_path.display()))?,
+                ));
+            }
+        }
+
+        match out.aggregate {
+            CustomAggregateMode::PerModule => {
+                for (m, body) in instructions_parts {
+                    let dst = expand_path_template(&out.path, project_root, Some(&module_name(m)))?;
+                    let mut spanned = SpannedOutput::default();
+                    spanned.push_module(&m.id, &body);
+                    let output = with_frontmatter(out.frontmatter.as_deref(), Some(m), spanned);
+                    insert_output(desired, target, dst, output, vec![m.id.clone()])?;
+                }
+            }
+            CustomAggregateMode::Concat if !instructions_parts.is_empty() => {
+                let parts: Vec<(String, Vec<u8>)> = instructions_parts
+                    .into_iter()
+                    .map(|(m, body)| (m.id.clone(), body))
+                    .collect();
+                let module_ids: Vec<String> = parts.iter().map(|(id, _)| id.clone()).collect();
+                let combined = crate::output_spans::aggregate_instructions(&parts);
+                let dst = expand_path_template(&out.path, project_root, None)?;
+                let output = with_frontmatter(out.frontmatter.as_deref(), None, combined);
+                insert_output(desired, target, dst, output, module_ids)?;
+            }
+            CustomAggregateMode::Concat => {}
+        }
+    }
+
+    if let Some(out) = &cfg.skill {
+        for m in modules
+            .iter()
+            .filter(|m| matches!(m.module_type, ModuleType::Skill))
+            .filter(|m| applies_to(m, target))
+        {
+            let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
+            let skill_root = expand_path_template(&out.path, project_root, Some(&module_name(m)))?;
+
+            for f in list_files(&materialized)? {
+                let rel = f
+                    .strip_prefix(&materialized)
+                    .with_context(|| format!("compute relpath for {}", f.display()))?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let bytes = std::fs::read(&f)?;
+                let mode = crate::fs::executable_mode(&f);
+                insert_file_with_mode(
+                    desired,
+                    target,
+                    skill_root.join(&rel),
+                    bytes,
+                    mode,
+                    vec![m.id.clone()],
+                )?;
+            }
+        }
+    }
+
+    for (module_type, output, fallback) in [
+        (ModuleType::Prompt, &cfg.prompt, "prompt.md"),
+        (ModuleType::Command, &cfg.command, "command.md"),
+    ] {
+        let Some(out) = output else {
+            continue;
+        };
+        let dir = expand_path_template(&out.dir, project_root, None)?;
+        for m in modules
+            .iter()
+            .filter(|m| m.module_type == module_type)
+            .filter(|m| applies_to(m, target))
+        {
+            let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
+            let file = first_file(&materialized)?;
+            let name = output_file_name(&file, out, fallback);
+            let bytes = std::fs::read(&file)?;
+            let mode = crate::fs::executable_mode(&file);
+            insert_file_with_mode(
+                desired,
+                target,
+                dir.join(name),
+                bytes,
+                mode,
+                vec![m.id.clone()],
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -11,7 +11,8 @@ use crate::user_error::UserError;
 
 use super::TargetRoot;
 use super::util::{
-    expand_tilde, first_file, get_bool, insert_file, module_name_from_id, scope_flags,
+    expand_tilde, first_file, get_bool, insert_file_with_mode, insert_output, module_name_from_id,
+    scope_flags, warn_instructions_subpaths_at_root,
 };
 
 fn export_root_from_options(
@@ -54,6 +55,7 @@ fn export_root_from_options(
 pub(crate) fn render(
     engine: &Engine,
     modules: &[&Module],
+    profile: &str,
     desired: &mut DesiredState,
     warnings: &mut Vec<String>,
     roots: &mut Vec<TargetRoot>,
@@ -101,7 +103,8 @@ pub(crate) fn render(
         .filter(|m| matches!(m.module_type, ModuleType::Instructions))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "export_dir"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        warn_instructions_subpaths_at_root("export_dir", m, warnings);
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let agents_path = materialized.join("AGENTS.md");
         if agents_path.exists() {
             instructions_parts.push((
@@ -117,37 +120,23 @@ pub(crate) fn render(
             .iter()
             .map(|(id, _)| id.clone())
             .collect();
-        let add_markers = instructions_parts.len() > 1;
-        let combined = if add_markers {
-            instructions_parts
-                .into_iter()
-                .map(|(module_id, text)| crate::markers::format_module_section(&module_id, &text))
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        } else {
-            instructions_parts
-                .into_iter()
-                .map(|(_, text)| text)
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        };
-        let bytes = combined.into_bytes();
+        let combined = crate::output_spans::aggregate_instructions(&instructions_parts);
 
         if allow_user {
-            insert_file(
+            insert_output(
                 desired,
                 "export_dir",
                 user_root.join("AGENTS.md"),
-                bytes.clone(),
+                combined.clone(),
                 module_ids.clone(),
             )?;
         }
         if allow_project {
-            insert_file(
+            insert_output(
                 desired,
                 "export_dir",
                 project_root.join("AGENTS.md"),
-                bytes,
+                combined,
                 module_ids,
             )?;
         }
@@ -158,29 +147,32 @@ pub(crate) fn render(
         .filter(|m| matches!(m.module_type, ModuleType::Prompt))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "export_dir"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let prompt_file = first_file(&materialized)?;
         let name = prompt_file
             .file_name()
             .and_then(|s| s.to_str())
             .unwrap_or("prompt.md");
         let bytes = std::fs::read(&prompt_file)?;
+        let mode = crate::fs::executable_mode(&prompt_file);
 
         if allow_user {
-            insert_file(
+            insert_file_with_mode(
                 desired,
                 "export_dir",
                 user_root.join("prompts").join(name),
                 bytes.clone(),
+                mode,
                 vec![m.id.clone()],
             )?;
         }
         if allow_project {
-            insert_file(
+            insert_file_with_mode(
                 desired,
                 "export_dir",
                 project_root.join("prompts").join(name),
                 bytes,
+                mode,
                 vec![m.id.clone()],
             )?;
         }
@@ -191,7 +183,7 @@ pub(crate) fn render(
         .filter(|m| matches!(m.module_type, ModuleType::Skill))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "export_dir"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let skill_name = module_name_from_id(&m.id).unwrap_or_else(|| sanitize_module_id(&m.id));
 
         let files = list_files(&materialized)?;
@@ -202,20 +194,22 @@ pub(crate) fn render(
                 .to_string_lossy()
                 .replace('\\', "/");
             let bytes = std::fs::read(&f)?;
+            let mode = crate::fs::executable_mode(&f);
 
             if allow_user {
                 let dst = user_root.join("skills").join(&skill_name).join(&rel);
-                insert_file(
+                insert_file_with_mode(
                     desired,
                     "export_dir",
                     dst,
                     bytes.clone(),
+                    mode,
                     vec![m.id.clone()],
                 )?;
             }
             if allow_project {
                 let dst = project_root.join("skills").join(&skill_name).join(&rel);
-                insert_file(desired, "export_dir", dst, bytes, vec![m.id.clone()])?;
+                insert_file_with_mode(desired, "export_dir", dst, bytes, mode, vec![m.id.clone()])?;
             }
         }
     }
@@ -225,29 +219,32 @@ pub(crate) fn render(
         .filter(|m| matches!(m.module_type, ModuleType::Command))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "export_dir"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let command_file = first_file(&materialized)?;
         let name = command_file
             .file_name()
             .and_then(|s| s.to_str())
             .unwrap_or("command.md");
         let bytes = std::fs::read(&command_file)?;
+        let mode = crate::fs::executable_mode(&command_file);
 
         if allow_user {
-            insert_file(
+            insert_file_with_mode(
                 desired,
                 "export_dir",
                 user_root.join("commands").join(name),
                 bytes.clone(),
+                mode,
                 vec![m.id.clone()],
             )?;
         }
         if allow_project {
-            insert_file(
+            insert_file_with_mode(
                 desired,
                 "export_dir",
                 project_root.join("commands").join(name),
                 bytes,
+                mode,
                 vec![m.id.clone()],
             )?;
         }
@@ -5,11 +5,12 @@ use crate::deploy::DesiredState;
 use crate::engine::Engine;
 
 use super::TargetRoot;
-use super::util::{get_bool, insert_file, scope_flags};
+use super::util::{get_bool, insert_output, scope_flags, warn_instructions_subpaths_at_root};
 
 pub(crate) fn render(
     engine: &Engine,
     modules: &[&Module],
+    profile: &str,
     desired: &mut DesiredState,
     warnings: &mut Vec<String>,
     roots: &mut Vec<TargetRoot>,
@@ -42,8 +43,9 @@ pub(crate) fn render(
         if !write_guidelines {
             continue;
         }
+        warn_instructions_subpaths_at_root("jetbrains", m, warnings);
 
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let agents_path = materialized.join("AGENTS.md");
         if agents_path.exists() {
             instructions_parts.push((
@@ -59,26 +61,13 @@ pub(crate) fn render(
             .iter()
             .map(|(id, _)| id.clone())
             .collect();
-        let add_markers = instructions_parts.len() > 1;
-        let combined = if add_markers {
-            instructions_parts
-                .into_iter()
-                .map(|(module_id, text)| crate::markers::format_module_section(&module_id, &text))
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        } else {
-            instructions_parts
-                .into_iter()
-                .map(|(_, text)| text)
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        };
+        let combined = crate::output_spans::aggregate_instructions(&instructions_parts);
 
-        insert_file(
+        insert_output(
             desired,
             "jetbrains",
             junie_dir.join("guidelines.md"),
-            combined.into_bytes(),
+            combined,
             module_ids,
         )?;
     }
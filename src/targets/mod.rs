@@ -6,6 +6,7 @@ pub(crate) mod claude_code;
 pub(crate) mod codex;
 #[cfg(feature = "target-cursor")]
 pub(crate) mod cursor;
+pub(crate) mod custom;
 #[cfg(feature = "target-export-dir")]
 pub(crate) mod export_dir;
 #[cfg(feature = "target-jetbrains")]
@@ -6,15 +6,115 @@ use anyhow::Context as _;
 use crate::config::TargetScope;
 use crate::deploy::DesiredState;
 use crate::fs::list_files;
+use crate::output_spans::SpannedOutput;
+#[cfg(any(
+    feature = "target-codex",
+    feature = "target-cursor",
+    feature = "target-vscode"
+))]
+use crate::paths::glob_segment_matches;
 
-pub(crate) fn insert_file(
+pub(crate) fn insert_output(
+    desired: &mut DesiredState,
+    target: &str,
+    path: PathBuf,
+    output: SpannedOutput,
+    module_ids: Vec<String>,
+) -> anyhow::Result<()> {
+    crate::deploy::insert_desired_output(desired, target, path, output, module_ids)
+}
+
+pub(crate) fn insert_file_with_mode(
     desired: &mut DesiredState,
     target: &str,
     path: PathBuf,
     bytes: Vec<u8>,
+    mode: Option<u32>,
     module_ids: Vec<String>,
 ) -> anyhow::Result<()> {
-    crate::deploy::insert_desired_file(desired, target, path, bytes, module_ids)
+    crate::deploy::insert_desired_file_with_mode(desired, target, path, bytes, mode, module_ids)
+}
+
+/// Warns that a target without per-directory instructions keeps a nested module in its root file.
+pub(crate) fn warn_instructions_subpaths_at_root(
+    target: &str,
+    module: &crate::config::Module,
+    warnings: &mut Vec<String>,
+) {
+    if !module.instructions_subpaths().is_empty() {
+        warnings.push(format!(
+            "{target}: no per-directory instructions files; module {} is aggregated at the root instead of its instructions subpaths",
+            module.id
+        ));
+    }
+}
+
+/// Resolves an instructions module's nested subpaths (dirs or `*`/`?` globs) to existing
+/// project-relative directories, sorted and deduplicated.
+#[cfg(any(
+    feature = "target-codex",
+    feature = "target-cursor",
+    feature = "target-vscode"
+))]
+pub(crate) fn instructions_subdirs(
+    project_root: &Path,
+    module: &crate::config::Module,
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<Vec<String>> {
+    let mut out = std::collections::BTreeSet::new();
+    for pattern in module.instructions_subpaths() {
+        let mut candidates = vec![String::new()];
+        for segment in pattern.trim_end_matches('/').split('/') {
+            let mut next = Vec::new();
+            for base in &candidates {
+                let dir = project_root.join(base);
+                if !segment.contains(['*', '?']) {
+                    if dir.join(segment).is_dir() {
+                        next.push(join_rel(base, segment));
+                    }
+                    continue;
+                }
+                let Ok(entries) = std::fs::read_dir(&dir) else {
+                    continue;
+                };
+                for entry in entries {
+                    let entry = entry.with_context(|| format!("read dir {}", dir.display()))?;
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if name.starts_with('.') && !segment.starts_with('.') {
+                        continue;
+                    }
+                    if entry.path().is_dir() && glob_segment_matches(segment, &name) {
+                        next.push(join_rel(base, &name));
+                    }
+                }
+            }
+            candidates = next;
+        }
+
+        if candidates.is_empty() {
+            warnings.push(format!(
+                "module {}: instructions subpath {:?} matched no directories under {}",
+                module.id,
+                pattern,
+                project_root.display()
+            ));
+        }
+        out.extend(candidates);
+    }
+    Ok(out.into_iter().collect())
+}
+
+#[cfg(any(
+    feature = "target-codex",
+    feature = "target-cursor",
+    feature = "target-vscode"
+))]
+fn join_rel(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else {
+        format!("{base}/{name}")
+    }
 }
 
 pub(crate) fn module_name_from_id(id: &str) -> Option<String> {
@@ -3,13 +3,17 @@ use anyhow::Context as _;
 use crate::config::{Module, ModuleType};
 use crate::deploy::DesiredState;
 use crate::engine::Engine;
+use crate::output_spans::SpannedOutput;
 
 use super::TargetRoot;
-use super::util::{first_file, get_bool, insert_file, scope_flags};
+use super::util::{
+    first_file, get_bool, insert_file_with_mode, insert_output, instructions_subdirs, scope_flags,
+};
 
 pub(crate) fn render(
     engine: &Engine,
     modules: &[&Module],
+    profile: &str,
     desired: &mut DesiredState,
     warnings: &mut Vec<String>,
     roots: &mut Vec<TargetRoot>,
@@ -49,15 +53,42 @@ pub(crate) fn render(
         .filter(|m| matches!(m.module_type, ModuleType::Instructions))
         .filter(|m| m.targets.is_empty() || m.targets.iter().any(|t| t == "vscode"))
     {
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let agents_path = materialized.join("AGENTS.md");
-        if agents_path.exists() {
-            instructions_parts.push((
-                m.id.clone(),
-                std::fs::read_to_string(&agents_path)
-                    .with_context(|| format!("read {}", agents_path.display()))?,
-            ));
+        if !agents_path.exists() {
+            continue;
+        }
+        let text = std::fs::read_to_string(&agents_path)
+            .with_context(|| format!("read {}", agents_path.display()))?;
+        if m.instructions_subpaths().is_empty() {
+            instructions_parts.push((m.id.clone(), text));
+            continue;
+        }
+        if !write_instructions {
+            continue;
+        }
+
+        // Nested instructions become path-specific instructions files scoped with `applyTo`.
+        let subdirs = instructions_subdirs(&engine.project.project_root, m, warnings)?;
+        if subdirs.is_empty() {
+            continue;
         }
+        let apply_to: Vec<String> = subdirs.iter().map(|d| format!("{d}/**")).collect();
+        let apply_to_json =
+            serde_json::to_string(&apply_to.join(",")).context("serialize vscode applyTo")?;
+        let mut out = SpannedOutput::default();
+        out.push_synthesized(format!("---\napplyTo: {apply_to_json}\n---\n\n").as_bytes());
+        out.push_module(&m.id, text.as_bytes());
+        out.terminate_line();
+
+        let name = format!("{}.instructions.md", crate::ids::module_fs_key(&m.id));
+        insert_output(
+            desired,
+            "vscode",
+            github_dir.join("instructions").join(name),
+            out,
+            vec![m.id.clone()],
+        )?;
     }
 
     if write_instructions && !instructions_parts.is_empty() {
@@ -65,26 +96,13 @@ pub(crate) fn render(
             .iter()
             .map(|(id, _)| id.clone())
             .collect();
-        let add_markers = instructions_parts.len() > 1;
-        let combined = if add_markers {
-            instructions_parts
-                .into_iter()
-                .map(|(module_id, text)| crate::markers::format_module_section(&module_id, &text))
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        } else {
-            instructions_parts
-                .into_iter()
-                .map(|(_, text)| text)
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        };
+        let combined = crate::output_spans::aggregate_instructions(&instructions_parts);
 
-        insert_file(
+        insert_output(
             desired,
             "vscode",
             github_dir.join("copilot-instructions.md"),
-            combined.into_bytes(),
+            combined,
             module_ids,
         )?;
     }
@@ -97,7 +115,7 @@ pub(crate) fn render(
         if !write_prompts {
             continue;
         }
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let prompt_file = first_file(&materialized)?;
         let name = prompt_file
             .file_name()
@@ -113,11 +131,13 @@ pub(crate) fn render(
         };
 
         let bytes = std::fs::read(&prompt_file)?;
-        insert_file(
+        let mode = crate::fs::executable_mode(&prompt_file);
+        insert_file_with_mode(
             desired,
             "vscode",
             prompts_dir.join(name),
             bytes,
+            mode,
             vec![m.id.clone()],
         )?;
     }
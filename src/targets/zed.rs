@@ -5,11 +5,12 @@ use crate::deploy::DesiredState;
 use crate::engine::Engine;
 
 use super::TargetRoot;
-use super::util::{get_bool, insert_file, scope_flags};
+use super::util::{get_bool, insert_output, scope_flags, warn_instructions_subpaths_at_root};
 
 pub(crate) fn render(
     engine: &Engine,
     modules: &[&Module],
+    profile: &str,
     desired: &mut DesiredState,
     warnings: &mut Vec<String>,
     roots: &mut Vec<TargetRoot>,
@@ -41,8 +42,9 @@ pub(crate) fn render(
         if !write_rules {
             continue;
         }
+        warn_instructions_subpaths_at_root("zed", m, warnings);
 
-        let (_tmp, materialized) = engine.materialize_module(m, warnings)?;
+        let (_tmp, materialized) = engine.materialize_module(m, profile, warnings)?;
         let agents_path = materialized.join("AGENTS.md");
         if agents_path.exists() {
             instructions_parts.push((
@@ -58,26 +60,13 @@ pub(crate) fn render(
             .iter()
             .map(|(id, _)| id.clone())
             .collect();
-        let add_markers = instructions_parts.len() > 1;
-        let combined = if add_markers {
-            instructions_parts
-                .into_iter()
-                .map(|(module_id, text)| crate::markers::format_module_section(&module_id, &text))
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        } else {
-            instructions_parts
-                .into_iter()
-                .map(|(_, text)| text)
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        };
+        let combined = crate::output_spans::aggregate_instructions(&instructions_parts);
 
-        insert_file(
+        insert_output(
             desired,
             "zed",
             engine.project.project_root.join(".rules"),
-            combined.into_bytes(),
+            combined,
             module_ids,
         )?;
     }
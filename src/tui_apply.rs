@@ -48,7 +48,9 @@ pub fn apply_from_tui_in(
         ConfirmationStyle::Explicit,
     )? {
         DeployApplyOutcome::NoChanges => Ok(ApplyOutcome::NoChanges),
-        DeployApplyOutcome::Applied { snapshot_id } => Ok(ApplyOutcome::Applied { snapshot_id }),
+        DeployApplyOutcome::Applied { snapshot_id, .. } => {
+            Ok(ApplyOutcome::Applied { snapshot_id })
+        }
         DeployApplyOutcome::NeedsConfirmation => anyhow::bail!(
             "tui apply requires explicit confirmation, but confirmation was not provided"
         ),
@@ -101,7 +101,7 @@ fn render_diff_text(
         } else {
             desired
                 .get(&desired_key)
-                .and_then(|f| String::from_utf8(f.bytes.clone()).ok())
+                .and_then(|f| String::from_utf8(f.deployed_bytes().to_vec()).ok())
         };
 
         writeln!(out).context("write diff spacer")?;
@@ -162,8 +162,8 @@ fn render_status_text(
     writeln!(out, "Drift ({}):", drift.len()).context("write drift header")?;
     writeln!(
         out,
-        "Summary: modified={} missing={} extra={}",
-        summary.modified, summary.missing, summary.extra
+        "Summary: modified={} missing={} extra={} permissions={}",
+        summary.modified, summary.missing, summary.extra, summary.permissions
     )
     .context("write drift summary")?;
 
@@ -0,0 +1,226 @@
+//! Polling support for `agentpack watch`.
+//!
+//! Watching is stat-based: a [`Fingerprint`] records the size and mtime of every watched file,
+//! so comparing two of them never reads file contents. Only when a change has settled does the
+//! caller reload the engine and re-render.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::config::SourceKind;
+use crate::deploy::{DesiredState, TargetPath};
+use crate::engine::Engine;
+use crate::hash::sha256_hex;
+use crate::local_edits::deployed_entries;
+use crate::paths::path_to_posix_string;
+use crate::target_manifest::manifest_path_for_target;
+use crate::targets::TargetRoot;
+
+pub const DEFAULT_INTERVAL_MS: u64 = 500;
+pub const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// What one watch cycle polls until the next re-render.
+#[derive(Debug, Default, Clone)]
+pub struct WatchSet {
+    /// Directory trees that feed rendering: the config repo (manifest, overlays, in-repo
+    /// modules) and `local_path` sources that live outside it.
+    pub inputs: Vec<PathBuf>,
+    /// Subtrees of `inputs` that only feed file content: overlay dirs and `local_path` sources.
+    /// Changes anywhere else (manifest, lockfile, ...) never trigger an auto-apply.
+    pub local: Vec<PathBuf>,
+    /// Individual deployed files: target manifests, managed files and desired outputs.
+    pub deployed: Vec<PathBuf>,
+}
+
+impl WatchSet {
+    /// Input trees only depend on the manifest, so they can be fingerprinted before rendering.
+    pub fn input_paths(engine: &Engine) -> Vec<PathBuf> {
+        let repo_dir = engine.repo.repo_dir.as_path();
+        let mut inputs = vec![repo_dir.to_path_buf()];
+        for module in engine.manifest.modules.iter().filter(|m| m.enabled) {
+            if module.source.kind() != SourceKind::LocalPath {
+                continue;
+            }
+            let Ok(root) =
+                crate::overlay::resolve_upstream_module_root(&engine.home, &engine.repo, module)
+            else {
+                continue;
+            };
+            if !root.starts_with(repo_dir) && !inputs.iter().any(|p| root.starts_with(p)) {
+                inputs.push(root);
+            }
+        }
+        inputs
+    }
+
+    pub fn local_input_paths(engine: &Engine) -> Vec<PathBuf> {
+        let repo_dir = engine.repo.repo_dir.as_path();
+        // Project overlays are the only thing under `projects/`.
+        let mut local = vec![repo_dir.join("overlays"), repo_dir.join("projects")];
+        for module in engine.manifest.modules.iter().filter(|m| m.enabled) {
+            if module.source.kind() != SourceKind::LocalPath {
+                continue;
+            }
+            if let Ok(root) =
+                crate::overlay::resolve_upstream_module_root(&engine.home, &engine.repo, module)
+            {
+                local.push(root);
+            }
+        }
+        local
+    }
+
+    /// Changed inputs that are outside every local subtree.
+    pub fn non_local_changes(&self, now: &Fingerprint, last: &Fingerprint) -> Vec<PathBuf> {
+        now.changed_since(last)
+            .filter(|p| !self.local.iter().any(|root| p.starts_with(root)))
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    pub fn deployed_paths(roots: &[TargetRoot], desired: &DesiredState) -> Vec<PathBuf> {
+        let mut deployed: Vec<PathBuf> = roots
+            .iter()
+            .map(|r| manifest_path_for_target(&r.root, &r.target))
+            .collect();
+        deployed.extend(deployed_entries(roots).into_keys().map(|tp| tp.path));
+        deployed.extend(desired.keys().map(|tp| tp.path.clone()));
+        deployed.sort();
+        deployed.dedup();
+        deployed
+    }
+
+    pub fn fingerprint(&self) -> (Fingerprint, Fingerprint) {
+        (
+            Fingerprint::of_trees(&self.inputs),
+            Fingerprint::of_files(&self.deployed),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    symlink: bool,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::symlink_metadata(path).ok()?;
+        Some(Self {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+            symlink: meta.file_type().is_symlink(),
+        })
+    }
+}
+
+/// Size and mtime of every file under a set of paths; missing paths are simply absent.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Fingerprint(BTreeMap<PathBuf, FileStamp>);
+
+impl Fingerprint {
+    /// Walks directory trees (skipping `.git`); a path that is a file is stamped as is.
+    pub fn of_trees(paths: &[PathBuf]) -> Self {
+        let mut out = BTreeMap::new();
+        for path in paths {
+            let walker = walkdir::WalkDir::new(path)
+                .follow_links(false)
+                .into_iter()
+                .filter_entry(|e| e.file_name() != ".git");
+            for entry in walker.filter_map(Result::ok) {
+                if entry.file_type().is_dir() {
+                    continue;
+                }
+                if let Some(stamp) = FileStamp::of(entry.path()) {
+                    out.insert(entry.into_path(), stamp);
+                }
+            }
+        }
+        Self(out)
+    }
+
+    /// Paths that were added, removed or restamped since `earlier`.
+    pub fn changed_since<'a>(&'a self, earlier: &'a Fingerprint) -> impl Iterator<Item = &'a Path> {
+        let touched = self
+            .0
+            .iter()
+            .filter(|(p, stamp)| earlier.0.get(*p) != Some(*stamp))
+            .map(|(p, _)| p);
+        let removed = earlier.0.keys().filter(|p| !self.0.contains_key(*p));
+        touched.chain(removed).map(PathBuf::as_path)
+    }
+
+    pub fn of_files(paths: &[PathBuf]) -> Self {
+        Self(
+            paths
+                .iter()
+                .filter_map(|p| FileStamp::of(p).map(|s| (p.clone(), s)))
+                .collect(),
+        )
+    }
+}
+
+/// A managed file whose content no longer matches what was deployed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManagedDrift {
+    pub target: String,
+    pub path: String,
+    pub path_posix: String,
+    /// `modified` or `missing`.
+    pub kind: String,
+    pub deployed_sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_sha256: Option<String>,
+}
+
+/// Compares managed files with the sha256 their target manifest recorded at deploy time
+/// (unlike `status`, which compares with the current desired outputs). Symlink-mode entries
+/// follow their source and are skipped.
+pub fn managed_drift(roots: &[TargetRoot]) -> Vec<ManagedDrift> {
+    let mut out = Vec::new();
+    for (tp, entry) in deployed_entries(roots) {
+        if entry.link_target.is_some() {
+            continue;
+        }
+        let current = match std::fs::read(&tp.path) {
+            Ok(bytes) => Some(sha256_hex(&bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(_) => continue,
+        };
+        if current.as_deref() == Some(entry.sha256.as_str()) {
+            continue;
+        }
+        out.push(drift_item(
+            &tp,
+            if current.is_some() {
+                "modified"
+            } else {
+                "missing"
+            },
+            entry.sha256,
+            current,
+        ));
+    }
+    out
+}
+
+fn drift_item(
+    tp: &TargetPath,
+    kind: &str,
+    deployed_sha256: String,
+    current_sha256: Option<String>,
+) -> ManagedDrift {
+    ManagedDrift {
+        target: tp.target.clone(),
+        path: tp.path.to_string_lossy().to_string(),
+        path_posix: path_to_posix_string(&tp.path),
+        kind: kind.to_string(),
+        deployed_sha256,
+        current_sha256,
+    }
+}
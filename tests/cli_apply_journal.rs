@@ -0,0 +1,324 @@
+#![cfg(all(unix, feature = "target-claude-code", feature = "target-codex"))]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn setup(home: &Path, targets: &str) -> std::path::PathBuf {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let skill_dir = repo_dir.join("modules/skills/my-skill");
+    std::fs::create_dir_all(&skill_dir).expect("create skill dir");
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# my-skill\n",
+    )
+    .expect("write SKILL.md");
+
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!(
+            r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: true
+
+modules:
+  - id: skill:my-skill
+    type: skill
+    tags: ["base"]
+    targets: {targets}
+    source:
+      local_path:
+        path: "modules/skills/my-skill"
+"#
+        ),
+    )
+    .expect("write manifest");
+
+    workspace
+}
+
+fn snapshot_files(home: &Path) -> Vec<String> {
+    let dir = home.join("state/snapshots");
+    if !dir.exists() {
+        return Vec::new();
+    }
+    let mut out: Vec<String> = std::fs::read_dir(&dir)
+        .expect("read snapshots dir")
+        .map(|e| e.expect("entry").file_name().to_string_lossy().to_string())
+        .collect();
+    out.sort();
+    out
+}
+
+fn has_target_manifest(dir: &Path) -> bool {
+    std::fs::read_dir(dir).expect("read dir").any(|e| {
+        let e = e.expect("entry");
+        let path = e.path();
+        if path.is_dir() {
+            has_target_manifest(&path)
+        } else {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with(".agentpack.manifest")
+        }
+    })
+}
+
+#[test]
+fn failed_apply_restores_files_already_written() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home, r#"["claude_code", "codex"]"#);
+
+    // An unmanaged file the apply adopts (and overwrites) before it fails.
+    let claude_skill = workspace.join(".claude/skills/my-skill/SKILL.md");
+    std::fs::create_dir_all(claude_skill.parent().expect("parent")).expect("create dir");
+    std::fs::write(&claude_skill, "local content\n").expect("write local file");
+
+    // The codex output cannot be written: its root is a dangling symlink.
+    std::os::unix::fs::symlink(home.join("missing-dir"), workspace.join(".codex"))
+        .expect("create dangling symlink");
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &["deploy", "--apply", "--adopt", "--yes", "--json"],
+    );
+    assert!(!deploy.status.success(), "{deploy:?}");
+
+    assert_eq!(
+        std::fs::read_to_string(&claude_skill).expect("read restored file"),
+        "local content\n"
+    );
+    assert!(
+        !has_target_manifest(&workspace.join(".claude")),
+        "no target manifest is written by a failed apply"
+    );
+    assert!(
+        snapshot_files(home).is_empty(),
+        "no snapshot or journal is left behind: {:?}",
+        snapshot_files(home)
+    );
+}
+
+#[test]
+fn doctor_reports_and_restores_an_interrupted_apply() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home, r#"["claude_code"]"#);
+
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    let deployed_snapshot = parse_stdout_json(&deploy)["data"]["snapshot_id"]
+        .as_str()
+        .expect("snapshot_id")
+        .to_string();
+
+    let deployed = workspace.join(".claude/skills/my-skill/SKILL.md");
+    let original = std::fs::read_to_string(&deployed).expect("read deployed");
+
+    // A crash after saving the snapshot but before committing the journal leaves a committed
+    // journal behind: not an interrupted apply.
+    let committed_journal = home
+        .join("state/snapshots")
+        .join(&deployed_snapshot)
+        .join("journal.jsonl");
+    std::fs::create_dir_all(committed_journal.parent().expect("parent"))
+        .expect("create snapshot dir");
+    std::fs::write(
+        &committed_journal,
+        format!(
+            "{}\n",
+            serde_json::json!({
+                "record": "begin",
+                "snapshot_id": deployed_snapshot,
+                "kind": "deploy",
+                "created_at": "2026-01-01T00:00:00Z",
+            })
+        ),
+    )
+    .expect("write committed journal");
+
+    // Simulate a crash halfway through the next apply: one file rewritten from a backup the
+    // journal knows about, one new file created, and no snapshot saved.
+    let snapshot_id = "1999999999999999999";
+    let snapshot_dir = home.join("state/snapshots").join(snapshot_id);
+    let backup = snapshot_dir.join("backup/claude_code/0000000000000000");
+    std::fs::create_dir_all(backup.parent().expect("parent")).expect("create backup dir");
+    std::fs::copy(&deployed, &backup).expect("write backup");
+    let created = workspace.join(".claude/skills/my-skill/NEW.md");
+    let journal = [
+        serde_json::json!({
+            "record": "begin",
+            "snapshot_id": snapshot_id,
+            "kind": "deploy",
+            "created_at": "2026-01-01T00:00:00Z",
+        }),
+        serde_json::json!({
+            "record": "change",
+            "target": "claude_code",
+            "path": deployed.to_string_lossy(),
+            "existed": true,
+            "backup_path": backup.to_string_lossy(),
+        }),
+        serde_json::json!({
+            "record": "change",
+            "target": "claude_code",
+            "path": created.to_string_lossy(),
+            "existed": false,
+        }),
+    ]
+    .iter()
+    .map(|r| format!("{r}\n"))
+    .collect::<String>();
+    // A torn trailing record (crash mid-append) is ignored.
+    std::fs::write(
+        snapshot_dir.join("journal.jsonl"),
+        format!("{journal}{{\"record\":\"cha"),
+    )
+    .expect("write journal");
+    std::fs::write(&deployed, "half written\n").expect("overwrite deployed");
+    std::fs::write(&created, "new\n").expect("write created");
+
+    let doctor = agentpack_in(home, &workspace, &["doctor", "--json"]);
+    assert!(doctor.status.success(), "{doctor:?}");
+    let doctor = parse_stdout_json(&doctor);
+    let interrupted = doctor["data"]["interrupted_applies"]
+        .as_array()
+        .expect("interrupted_applies");
+    assert_eq!(interrupted.len(), 1);
+    assert_eq!(interrupted[0]["snapshot_id"], snapshot_id);
+    assert_eq!(interrupted[0]["changes"], 2);
+    assert_eq!(interrupted[0]["restored"], false);
+    // Read-only doctor leaves the state dir alone.
+    assert!(committed_journal.exists());
+    assert!(
+        doctor["data"]["next_actions"]
+            .as_array()
+            .expect("next_actions")
+            .iter()
+            .any(|a| a.as_str().is_some_and(|a| a.contains("doctor --fix")))
+    );
+
+    let fix = agentpack_in(home, &workspace, &["doctor", "--fix", "--yes", "--json"]);
+    assert!(fix.status.success(), "{fix:?}");
+    let fix = parse_stdout_json(&fix);
+    assert_eq!(fix["data"]["interrupted_applies"][0]["restored"], true);
+
+    assert_eq!(
+        std::fs::read_to_string(&deployed).expect("read restored"),
+        original
+    );
+    assert!(!created.exists());
+    assert!(!snapshot_dir.exists());
+    assert!(!committed_journal.exists());
+
+    let status = agentpack_in(home, &workspace, &["status", "--json"]);
+    assert!(status.status.success(), "{status:?}");
+    let status = parse_stdout_json(&status);
+    assert_eq!(status["data"]["summary"]["modified"], 0);
+}
+
+#[test]
+fn failed_rollback_restores_files_it_already_rewrote() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home, r#"["claude_code", "codex"]"#);
+
+    let first = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(first.status.success(), "{first:?}");
+    let first_id = parse_stdout_json(&first)["data"]["snapshot_id"]
+        .as_str()
+        .expect("snapshot_id")
+        .to_string();
+
+    let source = home.join("repo/modules/skills/my-skill/SKILL.md");
+    let updated = "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# updated\n";
+    std::fs::write(&source, updated).expect("update skill");
+    let second = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(second.status.success(), "{second:?}");
+
+    // The claude_code file is restored first; the codex one then cannot be written.
+    std::fs::remove_dir_all(workspace.join(".codex")).expect("remove codex dir");
+    std::os::unix::fs::symlink(home.join("missing-dir"), workspace.join(".codex"))
+        .expect("create dangling symlink");
+
+    let rollback = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "rollback",
+            "--to",
+            &first_id,
+            "--discard-local-edits",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(!rollback.status.success(), "{rollback:?}");
+
+    assert_eq!(
+        std::fs::read_to_string(workspace.join(".claude/skills/my-skill/SKILL.md"))
+            .expect("read claude skill"),
+        updated
+    );
+    let doctor = agentpack_in(home, &workspace, &["doctor", "--json"]);
+    assert!(doctor.status.success(), "{doctor:?}");
+    assert_eq!(
+        parse_stdout_json(&doctor)["data"]["interrupted_applies"],
+        serde_json::json!([])
+    );
+}
@@ -0,0 +1,363 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn git_init(dir: &Path) {
+    std::fs::create_dir_all(dir).expect("create project dir");
+    assert!(
+        Command::new("git")
+            .current_dir(dir)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+}
+
+fn setup(home: &Path, codex_target: &str) {
+    let init = agentpack_in(home, home, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let module_dir = repo_dir.join("modules/instructions/base");
+    std::fs::create_dir_all(&module_dir).expect("create module dir");
+    std::fs::write(module_dir.join("AGENTS.md"), "# Shared rules\n").expect("write AGENTS.md");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!(
+            r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+{codex_target}
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+"#
+        ),
+    )
+    .expect("write manifest");
+}
+
+const PROJECT_SCOPE: &str = r#"    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+"#;
+
+fn register(home: &Path, proj: &Path) {
+    git_init(proj);
+    let add = agentpack_in(
+        home,
+        home,
+        &[
+            "project",
+            "add",
+            proj.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(add.status.success(), "{add:?}");
+}
+
+#[test]
+fn deploy_all_projects_applies_each_registered_project() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    setup(home, PROJECT_SCOPE);
+
+    let proj_a = home.join("src/a");
+    let proj_b = home.join("src/b");
+    git_init(&proj_a);
+    git_init(&proj_b);
+
+    for proj in [&proj_a, &proj_b] {
+        let add = agentpack_in(
+            home,
+            home,
+            &[
+                "project",
+                "add",
+                proj.to_str().expect("utf8 path"),
+                "--yes",
+                "--json",
+            ],
+        );
+        assert!(add.status.success(), "{add:?}");
+        assert_eq!(parse_stdout_json(&add)["data"]["added"], true);
+    }
+
+    // Registering from a subdirectory resolves to the already-registered git root.
+    std::fs::create_dir_all(proj_a.join("nested")).expect("create nested dir");
+    let again = agentpack_in(
+        home,
+        &proj_a.join("nested"),
+        &["project", "add", "--yes", "--json"],
+    );
+    assert!(again.status.success(), "{again:?}");
+    assert_eq!(parse_stdout_json(&again)["data"]["added"], false);
+
+    let list = agentpack_in(home, home, &["project", "list", "--json"]);
+    assert!(list.status.success(), "{list:?}");
+    let list = parse_stdout_json(&list);
+    assert_eq!(
+        list["data"]["projects"].as_array().expect("projects").len(),
+        2
+    );
+
+    let preview = agentpack_in(home, home, &["deploy", "--all-projects", "--json"]);
+    assert!(preview.status.success(), "{preview:?}");
+    let preview = parse_stdout_json(&preview);
+    assert_eq!(preview["data"]["applied"], false);
+    assert_eq!(preview["data"]["summary"]["create"], 2);
+    assert_eq!(
+        preview["data"]["projects"]
+            .as_array()
+            .expect("projects")
+            .len(),
+        2
+    );
+
+    let refused = agentpack_in(
+        home,
+        home,
+        &["deploy", "--all-projects", "--apply", "--json"],
+    );
+    assert!(!refused.status.success());
+    assert_eq!(
+        parse_stdout_json(&refused)["errors"][0]["code"],
+        "E_CONFIRM_REQUIRED"
+    );
+
+    let deploy = agentpack_in(
+        home,
+        home,
+        &["deploy", "--all-projects", "--apply", "--yes", "--json"],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    assert_eq!(deploy["data"]["applied"], true);
+    let projects = deploy["data"]["projects"].as_array().expect("projects");
+    let snapshot_ids: std::collections::BTreeSet<&str> = projects
+        .iter()
+        .map(|p| p["snapshot_id"].as_str().expect("snapshot_id"))
+        .collect();
+    assert_eq!(snapshot_ids.len(), 2);
+
+    for proj in [&proj_a, &proj_b] {
+        assert_eq!(
+            std::fs::read_to_string(proj.join("AGENTS.md")).expect("read AGENTS.md"),
+            "# Shared rules\n"
+        );
+        assert!(proj.join(".agentpack.manifest.codex.json").exists());
+    }
+
+    // A project that disappeared is skipped, not fatal; the others report no changes.
+    std::fs::remove_dir_all(&proj_b).expect("remove project b");
+    let rerun = agentpack_in(
+        home,
+        home,
+        &["deploy", "--all-projects", "--apply", "--yes", "--json"],
+    );
+    assert!(rerun.status.success(), "{rerun:?}");
+    let rerun = parse_stdout_json(&rerun);
+    assert_eq!(rerun["data"]["applied"], false);
+    let projects = rerun["data"]["projects"].as_array().expect("projects");
+    assert_eq!(projects[0]["reason"], "no_changes");
+    assert_eq!(projects[1]["skipped"], true);
+    assert_eq!(projects[1]["reason"], "project_root_missing");
+    assert!(
+        rerun["warnings"]
+            .as_array()
+            .expect("warnings")
+            .iter()
+            .any(|w| w
+                .as_str()
+                .is_some_and(|w| w.contains("directory not found")))
+    );
+
+    let project_b_id = projects[1]["project_id"]
+        .as_str()
+        .expect("project_id")
+        .to_string();
+    let remove = agentpack_in(
+        home,
+        home,
+        &["project", "remove", &project_b_id, "--yes", "--json"],
+    );
+    assert!(remove.status.success(), "{remove:?}");
+
+    let missing = agentpack_in(
+        home,
+        home,
+        &["project", "remove", &project_b_id, "--yes", "--json"],
+    );
+    assert!(!missing.status.success());
+    let missing = parse_stdout_json(&missing);
+    assert_eq!(missing["errors"][0]["code"], "E_PROJECT_NOT_FOUND");
+    assert_eq!(
+        missing["errors"][0]["details"]["reason_code"],
+        "project_not_registered"
+    );
+}
+
+#[test]
+fn deploy_all_projects_plans_user_scope_outputs_once() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    setup(
+        home,
+        r#"    mode: files
+    scope: both
+    options:
+      codex_home: "~/.codex"
+      write_repo_skills: false
+      write_user_skills: false
+      write_user_prompts: false
+"#,
+    );
+    let proj_a = home.join("src/a");
+    let proj_b = home.join("src/b");
+    register(home, &proj_a);
+    register(home, &proj_b);
+
+    let preview = agentpack_in(home, home, &["deploy", "--all-projects", "--json"]);
+    assert!(preview.status.success(), "{preview:?}");
+    let preview = parse_stdout_json(&preview);
+    // Two project-root AGENTS.md files plus one shared ~/.codex/AGENTS.md.
+    assert_eq!(preview["data"]["summary"]["create"], 3);
+
+    let deploy = agentpack_in(
+        home,
+        home,
+        &["deploy", "--all-projects", "--apply", "--yes", "--json"],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    let projects = deploy["data"]["projects"].as_array().expect("projects");
+    let global_changes: Vec<usize> = projects
+        .iter()
+        .map(|p| {
+            p["changes"]
+                .as_array()
+                .expect("changes")
+                .iter()
+                .filter(|c| {
+                    c["path_posix"]
+                        .as_str()
+                        .is_some_and(|p| p.ends_with(".codex/AGENTS.md"))
+                })
+                .count()
+        })
+        .collect();
+    assert_eq!(global_changes, vec![1, 0]);
+    assert_eq!(
+        std::fs::read_to_string(home.join(".codex/AGENTS.md")).expect("read global AGENTS.md"),
+        "# Shared rules\n"
+    );
+    for proj in [&proj_a, &proj_b] {
+        assert!(proj.join("AGENTS.md").exists());
+    }
+
+    let rerun = agentpack_in(home, home, &["deploy", "--all-projects", "--json"]);
+    assert!(rerun.status.success(), "{rerun:?}");
+    let rerun = parse_stdout_json(&rerun);
+    assert_eq!(rerun["data"]["summary"]["create"], 0);
+    assert_eq!(rerun["data"]["summary"]["update"], 0);
+    assert_eq!(rerun["data"]["summary"]["delete"], 0);
+}
+
+#[test]
+fn deploy_all_projects_keeps_going_after_a_failed_project() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    setup(home, PROJECT_SCOPE);
+    let manifest_path = home.join("repo/agentpack.yaml");
+    let manifest = std::fs::read_to_string(&manifest_path).expect("read manifest");
+    std::fs::write(
+        &manifest_path,
+        format!(
+            "{manifest}\nhooks:\n  pre_deploy:\n    - command: [\"sh\", \"-c\", \"if grep -q 'src/a'; then exit 3; fi\"]\n"
+        ),
+    )
+    .expect("write hooks");
+
+    let proj_a = home.join("src/a");
+    let proj_b = home.join("src/b");
+    register(home, &proj_a);
+    register(home, &proj_b);
+
+    let deploy = agentpack_in(
+        home,
+        home,
+        &["deploy", "--all-projects", "--apply", "--yes", "--json"],
+    );
+    assert!(!deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    let err = &deploy["errors"][0];
+    assert_eq!(err["code"], "E_DEPLOY_PROJECTS_FAILED", "{deploy}");
+    assert_eq!(err["details"]["reason_code"], "deploy_projects_failed");
+    assert_eq!(err["details"]["applied"], true);
+
+    let projects = err["details"]["projects"].as_array().expect("projects");
+    let project = |suffix: &str| {
+        projects
+            .iter()
+            .find(|p| {
+                p["project_root_posix"]
+                    .as_str()
+                    .is_some_and(|r| r.ends_with(suffix))
+            })
+            .expect("project entry")
+    };
+    let failed = project("src/a");
+    assert_eq!(failed["failed"], true);
+    assert_eq!(failed["applied"], false);
+    assert_eq!(failed["error"]["code"], "E_HOOK_FAILED");
+    let applied = project("src/b");
+    assert_eq!(applied["applied"], true);
+    assert!(applied["snapshot_id"].is_string());
+    assert_eq!(
+        err["details"]["failed"].as_array().expect("failed").len(),
+        1
+    );
+
+    assert!(!proj_a.join("AGENTS.md").exists());
+    assert_eq!(
+        std::fs::read_to_string(proj_b.join("AGENTS.md")).expect("read AGENTS.md"),
+        "# Shared rules\n"
+    );
+}
@@ -0,0 +1,230 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn mode_of(path: &Path) -> u32 {
+    std::fs::metadata(path)
+        .expect("metadata")
+        .permissions()
+        .mode()
+        & 0o777
+}
+
+fn set_mode(path: &Path, mode: u32) {
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).expect("chmod");
+}
+
+#[test]
+fn deploy_preserves_executable_bits_and_reports_permission_drift() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    let skill_dir = repo_dir.join("modules/skills/my-skill");
+    std::fs::create_dir_all(skill_dir.join("scripts")).expect("create skill dir");
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# my-skill\n",
+    )
+    .expect("write SKILL.md");
+    let script = skill_dir.join("scripts/run.sh");
+    std::fs::write(&script, "#!/bin/sh\necho ok\n").expect("write script");
+    set_mode(&script, 0o755);
+
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:my-skill
+    type: skill
+    tags: ["base"]
+    targets: ["claude_code"]
+    source:
+      local_path:
+        path: "modules/skills/my-skill"
+"#,
+    )
+    .expect("write manifest");
+
+    let deploy_args = [
+        "--target",
+        "claude_code",
+        "deploy",
+        "--apply",
+        "--yes",
+        "--json",
+    ];
+    let deploy = agentpack_in(home, &workspace, &deploy_args);
+    assert!(deploy.status.success(), "{deploy:?}");
+
+    let deployed = workspace.join(".claude/skills/my-skill/scripts/run.sh");
+    assert_eq!(mode_of(&deployed), 0o755);
+
+    // Lockfiles record the mode of executable files only.
+    let lock = agentpack_in(home, &workspace, &["lock", "--yes", "--json"]);
+    assert!(lock.status.success(), "{lock:?}");
+    let lockfile: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(repo_dir.join("agentpack.lock.json")).expect("read lockfile"),
+    )
+    .expect("parse lockfile");
+    let files = lockfile["modules"][0]["file_manifest"]
+        .as_array()
+        .expect("file_manifest");
+    let script_entry = files
+        .iter()
+        .find(|f| f["path"] == "scripts/run.sh")
+        .expect("script entry");
+    assert_eq!(script_entry["mode"], 0o755);
+    let skill_entry = files
+        .iter()
+        .find(|f| f["path"] == "SKILL.md")
+        .expect("SKILL.md entry");
+    assert!(skill_entry.get("mode").is_none());
+
+    // A mode-only change is drift of its own kind and a plan update.
+    set_mode(&deployed, 0o644);
+
+    let status = agentpack_in(
+        home,
+        &workspace,
+        &["--target", "claude_code", "status", "--json"],
+    );
+    assert!(status.status.success(), "{status:?}");
+    let status = parse_stdout_json(&status);
+    assert_eq!(status["data"]["summary"]["modified"], 0);
+    assert_eq!(status["data"]["summary"]["permissions"], 1);
+    let drift = &status["data"]["drift"][0];
+    assert_eq!(drift["kind"], "permissions");
+    assert_eq!(drift["expected"], "mode:0755");
+    assert_eq!(drift["actual"], "mode:0644");
+
+    let plan = agentpack_in(
+        home,
+        &workspace,
+        &["--target", "claude_code", "plan", "--json"],
+    );
+    assert!(plan.status.success(), "{plan:?}");
+    let plan = parse_stdout_json(&plan);
+    assert_eq!(plan["data"]["summary"]["update"], 1);
+    assert_eq!(plan["data"]["changes"][0]["reason"], "mode differs");
+
+    let deploy = agentpack_in(home, &workspace, &deploy_args);
+    assert!(deploy.status.success(), "{deploy:?}");
+    assert_eq!(mode_of(&deployed), 0o755);
+
+    // Only the executable bit is tracked: files deployed before modes were tracked (0600) and
+    // other permission changes are not drift.
+    let skill_md = workspace.join(".claude/skills/my-skill/SKILL.md");
+    set_mode(&skill_md, 0o600);
+    set_mode(&deployed, 0o700);
+    let status = agentpack_in(
+        home,
+        &workspace,
+        &["--target", "claude_code", "status", "--json"],
+    );
+    assert!(status.status.success(), "{status:?}");
+    let status = parse_stdout_json(&status);
+    assert_eq!(status["data"]["summary"]["permissions"], 0);
+    assert_eq!(status["data"]["drift"].as_array().expect("drift").len(), 0);
+    let plan = agentpack_in(
+        home,
+        &workspace,
+        &["--target", "claude_code", "plan", "--json"],
+    );
+    assert!(plan.status.success(), "{plan:?}");
+    assert_eq!(parse_stdout_json(&plan)["data"]["summary"]["update"], 0);
+
+    // A filtered deploy keeps the recorded mode of the files it skips.
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\nname: my-skill\ndescription: Example Skill v2\n---\n\n# my-skill\n",
+    )
+    .expect("edit SKILL.md");
+    std::fs::write(&script, "#!/bin/sh\necho v2\n").expect("edit script");
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--target",
+            "claude_code",
+            "deploy",
+            "--apply",
+            "--path",
+            "my-skill/SKILL.md",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    let manifest: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            workspace.join(".claude/skills/.agentpack.manifest.claude_code.json"),
+        )
+        .expect("read target manifest"),
+    )
+    .expect("parse target manifest");
+    let files = manifest["managed_files"].as_array().expect("managed_files");
+    let script_entry = files
+        .iter()
+        .find(|f| f["path"] == "my-skill/scripts/run.sh")
+        .expect("script entry");
+    assert_eq!(script_entry["mode"], 0o755);
+    let skill_entry = files
+        .iter()
+        .find(|f| f["path"] == "my-skill/SKILL.md")
+        .expect("SKILL.md entry");
+    assert!(skill_entry.get("mode").is_none());
+}
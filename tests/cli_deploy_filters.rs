@@ -0,0 +1,191 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn write_skill(repo_dir: &Path, name: &str, body: &str) {
+    let dir = repo_dir.join("modules/skills").join(name);
+    std::fs::create_dir_all(&dir).expect("create skill dir");
+    std::fs::write(
+        dir.join("SKILL.md"),
+        format!("---\nname: {name}\ndescription: {name} skill\n---\n\n{body}\n"),
+    )
+    .expect("write SKILL.md");
+}
+
+fn manifest_sha(manifest: &Path, rel: &str) -> Option<String> {
+    let raw = std::fs::read_to_string(manifest).expect("read manifest");
+    let manifest: serde_json::Value = serde_json::from_str(&raw).expect("parse manifest");
+    manifest["managed_files"]
+        .as_array()
+        .expect("managed_files")
+        .iter()
+        .find(|f| f["path"] == rel)
+        .map(|f| f["sha256"].as_str().expect("sha256").to_string())
+}
+
+#[test]
+fn deploy_module_filter_applies_only_that_module_and_keeps_manifests_consistent() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let instructions = repo_dir.join("modules/instructions/base/AGENTS.md");
+    std::fs::create_dir_all(instructions.parent().expect("parent")).expect("create module dir");
+    std::fs::write(&instructions, "# Rules v1\n").expect("write AGENTS.md");
+    write_skill(&repo_dir, "fixer", "v1");
+    write_skill(&repo_dir, "helper", "v1");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+  - id: skill:fixer
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/skills/fixer
+"#,
+    )
+    .expect("write manifest");
+
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    let root_manifest = workspace.join(".agentpack.manifest.codex.json");
+    let skills_manifest = workspace.join(".codex/skills/.agentpack.manifest.codex.json");
+    let agents_sha = manifest_sha(&root_manifest, "AGENTS.md").expect("AGENTS.md managed");
+
+    // Upstream moves on for both modules, and a new module appears.
+    std::fs::write(&instructions, "# Rules v2\n").expect("edit AGENTS.md");
+    write_skill(&repo_dir, "fixer", "v2");
+    let manifest_yaml = std::fs::read_to_string(repo_dir.join("agentpack.yaml")).expect("read");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!(
+            "{manifest_yaml}  - id: skill:helper\n    type: skill\n    tags: [\"base\"]\n    source:\n      local_path:\n        path: modules/skills/helper\n"
+        ),
+    )
+    .expect("add module");
+
+    let preview = agentpack_in(
+        home,
+        &workspace,
+        &["preview", "--path", "AGENTS.md", "--json"],
+    );
+    assert!(preview.status.success(), "{preview:?}");
+    let preview = parse_stdout_json(&preview);
+    assert_eq!(preview["data"]["plan"]["summary"]["update"], 1);
+    assert_eq!(preview["data"]["plan"]["summary"]["create"], 0);
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "deploy",
+            "--apply",
+            "--module",
+            "skill:fixer",
+            "--module",
+            "skill:missing",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    assert_eq!(deploy["data"]["summary"]["update"], 1);
+    assert_eq!(deploy["data"]["summary"]["create"], 0);
+    assert!(
+        deploy["warnings"]
+            .as_array()
+            .expect("warnings")
+            .iter()
+            .any(|w| w.as_str().unwrap_or_default().contains("skill:missing"))
+    );
+
+    assert!(
+        std::fs::read_to_string(workspace.join(".codex/skills/fixer/SKILL.md"))
+            .expect("read skill")
+            .contains("v2")
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("AGENTS.md")).expect("read AGENTS.md"),
+        "# Rules v1\n"
+    );
+    assert!(!workspace.join(".codex/skills/helper").exists());
+
+    // Skipped paths keep their previous manifest state.
+    assert_eq!(
+        manifest_sha(&root_manifest, "AGENTS.md").as_deref(),
+        Some(agents_sha.as_str())
+    );
+    assert!(manifest_sha(&skills_manifest, "helper/SKILL.md").is_none());
+    assert!(manifest_sha(&skills_manifest, "fixer/SKILL.md").is_some());
+
+    let plan = agentpack_in(home, &workspace, &["plan", "--json"]);
+    assert!(plan.status.success(), "{plan:?}");
+    let plan = parse_stdout_json(&plan);
+    assert_eq!(plan["data"]["summary"]["update"], 1);
+    assert_eq!(plan["data"]["summary"]["create"], 1);
+    let update = plan["data"]["changes"]
+        .as_array()
+        .expect("changes")
+        .iter()
+        .find(|c| c["op"] == "update")
+        .expect("update change")
+        .clone();
+    assert_eq!(update["update_kind"], "managed_update");
+    assert!(update["merge"].is_null());
+}
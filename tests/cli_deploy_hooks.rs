@@ -0,0 +1,188 @@
+#![cfg(all(unix, feature = "target-codex"))]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn json_ok(home: &Path, cwd: &Path, args: &[&str]) -> serde_json::Value {
+    let output = agentpack_in(home, cwd, args);
+    assert!(output.status.success(), "{args:?}: {output:?}");
+    parse_stdout_json(&output)
+}
+
+const MANIFEST: &str = r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+"#;
+
+#[test]
+fn deploy_and_rollback_run_manifest_hooks() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let instructions = repo_dir.join("modules/instructions/base/AGENTS.md");
+    std::fs::create_dir_all(instructions.parent().expect("parent")).expect("create module dir");
+    std::fs::write(&instructions, "# Rules v1\n").expect("write AGENTS.md");
+
+    // A failing pre_deploy hook aborts before anything is written.
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!(
+            "{MANIFEST}\nhooks:\n  pre_deploy:\n    - command: [\"sh\", \"-c\", \"echo not today >&2; exit 3\"]\n"
+        ),
+    )
+    .expect("write manifest");
+    let refused = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(!refused.status.success(), "{refused:?}");
+    let err = &parse_stdout_json(&refused)["errors"][0];
+    assert_eq!(err["code"], "E_HOOK_FAILED");
+    assert_eq!(err["details"]["reason_code"], "hook_exit_nonzero");
+    assert_eq!(err["details"]["exit_code"], 3);
+    assert!(
+        err["details"]["stderr"]
+            .as_str()
+            .expect("stderr")
+            .contains("not today")
+    );
+    assert!(!workspace.join("AGENTS.md").exists());
+
+    // Post hooks get the payload on stdin; results land in the snapshot and the event log.
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!(
+            "{MANIFEST}\nhooks:\n  pre_deploy:\n    - command: [\"true\"]\n  post_deploy:\n    - command: [\"sh\", \"-c\", \"cat > post_deploy.json\"]\n  post_rollback:\n    - command: [\"sh\", \"-c\", \"cat > post_rollback.json; exit 1\"]\n"
+        ),
+    )
+    .expect("write manifest");
+    let first = json_ok(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    let first_id = first["data"]["snapshot_id"]
+        .as_str()
+        .expect("snapshot_id")
+        .to_string();
+    let hooks = first["data"]["hooks"].as_array().expect("hooks");
+    assert_eq!(hooks.len(), 2, "{hooks:?}");
+    assert_eq!(hooks[0]["hook"], "pre_deploy");
+    assert_eq!(hooks[1]["hook"], "post_deploy");
+    assert!(hooks.iter().all(|h| h["success"] == true));
+
+    let payload: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(repo_dir.join("post_deploy.json")).expect("read payload"),
+    )
+    .expect("payload json");
+    assert_eq!(payload["protocol"], "agentpack.hook");
+    assert_eq!(payload["hook"], "post_deploy");
+    assert_eq!(payload["snapshot_id"], first_id.as_str());
+    assert_eq!(payload["targets"], serde_json::json!(["codex"]));
+    assert_eq!(payload["changes"][0]["op"], "create");
+
+    let snapshot: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(home.join(format!("state/snapshots/{first_id}.json")))
+            .expect("read snapshot"),
+    )
+    .expect("snapshot json");
+    assert_eq!(snapshot["hooks"].as_array().expect("hooks").len(), 2);
+
+    std::fs::write(&instructions, "# Rules v2\n").expect("edit AGENTS.md");
+    json_ok(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+
+    // A failing post_rollback hook only warns: the rollback already happened.
+    let rollback = json_ok(
+        home,
+        &workspace,
+        &[
+            "--machine",
+            "rollback-box",
+            "rollback",
+            "--to",
+            &first_id,
+            "--yes",
+            "--json",
+        ],
+    );
+    assert_eq!(rollback["data"]["hooks"][0]["hook"], "post_rollback");
+    assert_eq!(rollback["data"]["hooks"][0]["success"], false);
+    let warnings = rollback["warnings"].as_array().expect("warnings");
+    assert!(
+        warnings.iter().any(|w| w
+            .as_str()
+            .unwrap_or_default()
+            .contains("post_rollback hook")),
+        "{warnings:?}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("AGENTS.md")).expect("read AGENTS.md"),
+        "# Rules v1\n"
+    );
+    let payload: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(repo_dir.join("post_rollback.json")).expect("read payload"),
+    )
+    .expect("payload json");
+    assert_eq!(payload["rolled_back_to"], first_id.as_str());
+    assert_eq!(payload["partial"], false);
+
+    let events = std::fs::read_to_string(home.join("state/logs/events.jsonl")).expect("events");
+    let hook_events: Vec<serde_json::Value> = events
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).expect("event json"))
+        .filter(|e| e["event"]["event"] == "hook")
+        .collect();
+    // Failed pre_deploy, then pre/post of two deploys, then post_rollback.
+    assert_eq!(hook_events.len(), 6, "{hook_events:?}");
+    assert_eq!(hook_events[0]["success"], false);
+    assert_eq!(hook_events[5]["event"]["hook"], "post_rollback");
+    assert_eq!(hook_events[5]["command_id"], "rollback");
+    // Rollback hook events carry the same machine id as deploy hooks (`--machine` included).
+    assert_eq!(hook_events[4]["machine_id"], "test-machine");
+    assert_eq!(hook_events[5]["machine_id"], "rollback-box");
+}
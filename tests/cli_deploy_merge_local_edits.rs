@@ -0,0 +1,246 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+const RULES: &str = "# Rules\n\nalpha\n\none\n\ntwo\n\nbeta\n\nthree\n\nfour\n\ngamma\n";
+
+/// Deploys `RULES` as the repo-root AGENTS.md and returns (workspace, module AGENTS.md).
+fn setup_deployed(home: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let module_dir = repo_dir.join("modules/instructions/base");
+    std::fs::create_dir_all(&module_dir).expect("create module dir");
+    let module_file = module_dir.join("AGENTS.md");
+    std::fs::write(&module_file, RULES).expect("write AGENTS.md");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+"#,
+    )
+    .expect("write manifest");
+
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    assert!(
+        std::fs::read_to_string(workspace.join("AGENTS.md"))
+            .expect("read deployed")
+            .contains("alpha\n")
+    );
+
+    (workspace, module_file)
+}
+
+fn edit(path: &Path, from: &str, to: &str) {
+    let raw = std::fs::read_to_string(path).expect("read file");
+    assert!(raw.contains(from), "{from:?} not in {}", path.display());
+    std::fs::write(path, raw.replacen(from, to, 1)).expect("write file");
+}
+
+#[test]
+fn deploy_merges_local_edits_with_upstream_changes() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let (workspace, module_file) = setup_deployed(home);
+    let deployed = workspace.join("AGENTS.md");
+
+    edit(&deployed, "alpha\n", "alpha (local note)\n");
+    edit(&module_file, "gamma\n", "gamma v2\n");
+
+    let preview = agentpack_in(home, &workspace, &["deploy", "--json"]);
+    assert!(preview.status.success(), "{preview:?}");
+    let preview = parse_stdout_json(&preview);
+    let change = &preview["data"]["changes"][0];
+    assert_eq!(change["merge"], "clean");
+    assert_eq!(change["update_kind"], "managed_update");
+
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    let merged = std::fs::read_to_string(&deployed).expect("read merged");
+    assert!(merged.contains("alpha (local note)\n"), "{merged}");
+    assert!(merged.contains("gamma v2\n"), "{merged}");
+
+    // The local edit is still drift against upstream, so evolve can propose it.
+    let propose = agentpack_in(
+        home,
+        &workspace,
+        &["evolve", "propose", "--dry-run", "--json"],
+    );
+    assert!(propose.status.success(), "{propose:?}");
+    let propose = parse_stdout_json(&propose);
+    assert!(
+        propose["data"]["candidates"]
+            .as_array()
+            .expect("candidates")
+            .iter()
+            .any(|c| c["module_id"] == "instructions:base")
+    );
+
+    // The next upstream change merges against the last deployed upstream, keeping the edit.
+    edit(&module_file, "four\n", "four v3\n");
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    let merged = std::fs::read_to_string(&deployed).expect("read merged");
+    assert!(merged.contains("alpha (local note)\n"), "{merged}");
+    assert!(merged.contains("four v3\n"), "{merged}");
+}
+
+#[test]
+fn redeploy_without_upstream_changes_keeps_merged_local_edits() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let (workspace, module_file) = setup_deployed(home);
+    let deployed = workspace.join("AGENTS.md");
+
+    edit(&deployed, "alpha\n", "alpha (local note)\n");
+    edit(&module_file, "gamma\n", "gamma v2\n");
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    let merged = std::fs::read_to_string(&deployed).expect("read merged");
+
+    // Nothing upstream changed: the merged file is what is deployed, so there is nothing to do.
+    let preview = agentpack_in(home, &workspace, &["deploy", "--json"]);
+    assert!(preview.status.success(), "{preview:?}");
+    let preview = parse_stdout_json(&preview);
+    assert_eq!(preview["data"]["summary"]["update"], 0, "{preview}");
+
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    assert_eq!(
+        std::fs::read_to_string(&deployed).expect("read deployed"),
+        merged
+    );
+
+    // Further local edits are restored to the merge result, not to bare upstream.
+    edit(&deployed, "two\n", "two (scratch)\n");
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    assert_eq!(
+        std::fs::read_to_string(&deployed).expect("read deployed"),
+        merged
+    );
+
+    // The merged file is what was deployed, so rolling back does not see it as a local edit.
+    let list = agentpack_in(home, &workspace, &["snapshot", "list", "--json"]);
+    assert!(list.status.success(), "{list:?}");
+    let first_id = parse_stdout_json(&list)["data"]["snapshots"]
+        .as_array()
+        .expect("snapshots")
+        .iter()
+        .filter_map(|s| s["id"].as_str())
+        .min()
+        .expect("first snapshot")
+        .to_string();
+    let rollback = agentpack_in(
+        home,
+        &workspace,
+        &["rollback", "--to", &first_id, "--yes", "--json"],
+    );
+    assert!(rollback.status.success(), "{rollback:?}");
+    assert_eq!(
+        std::fs::read_to_string(&deployed).expect("read rolled back"),
+        RULES
+    );
+}
+
+#[test]
+fn deploy_refuses_when_local_edits_conflict_with_upstream() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let (workspace, module_file) = setup_deployed(home);
+    let deployed = workspace.join("AGENTS.md");
+
+    edit(&deployed, "alpha\n", "alpha (local note)\n");
+    edit(&deployed, "beta\n", "beta (local)\n");
+    edit(&module_file, "beta\n", "beta (upstream)\n");
+    let local = std::fs::read_to_string(&deployed).expect("read local");
+
+    // The conflict-marked merge is visible in the plan.
+    let preview = agentpack_in(home, &workspace, &["deploy", "--json"]);
+    assert!(preview.status.success(), "{preview:?}");
+    assert_eq!(
+        parse_stdout_json(&preview)["data"]["changes"][0]["merge"],
+        "conflict"
+    );
+
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(!deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    let err = &deploy["errors"][0];
+    assert_eq!(err["code"], "E_MERGE_CONFLICT");
+    assert_eq!(err["details"]["reason_code"], "merge_conflict");
+    assert_eq!(
+        err["details"]["conflicts"][0]["path"],
+        deployed.to_string_lossy().as_ref()
+    );
+
+    // Nothing was written.
+    assert_eq!(
+        std::fs::read_to_string(&deployed).expect("read local"),
+        local
+    );
+
+    // Taking the upstream side of the conflict into the local file resolves it; the other
+    // local edit is merged as usual.
+    edit(&deployed, "beta (local)\n", "beta (upstream)\n");
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    let merged = std::fs::read_to_string(&deployed).expect("read merged");
+    assert!(merged.contains("alpha (local note)\n"), "{merged}");
+    assert!(merged.contains("beta (upstream)\n"), "{merged}");
+}
@@ -0,0 +1,216 @@
+#![cfg(unix)]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn setup(home: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    let skill_dir = repo_dir.join("modules/skills/my-skill");
+    std::fs::create_dir_all(&skill_dir).expect("create skill dir");
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# my-skill\n",
+    )
+    .expect("write SKILL.md");
+
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: symlink
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:my-skill
+    type: skill
+    tags: ["base"]
+    targets: ["claude_code"]
+    source:
+      local_path:
+        path: "modules/skills/my-skill"
+"#,
+    )
+    .expect("write manifest");
+
+    (workspace, skill_dir)
+}
+
+#[test]
+fn symlink_mode_links_local_skill_files_and_rolls_back() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let (workspace, skill_dir) = setup(home);
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--target",
+            "claude_code",
+            "deploy",
+            "--apply",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    let snapshot_id = parse_stdout_json(&deploy)["data"]["snapshot_id"]
+        .as_str()
+        .expect("snapshot_id")
+        .to_string();
+
+    let deployed = workspace.join(".claude/skills/my-skill/SKILL.md");
+    let meta = std::fs::symlink_metadata(&deployed).expect("deployed metadata");
+    assert!(meta.file_type().is_symlink());
+    assert_eq!(
+        std::fs::read_link(&deployed).expect("read link"),
+        std::fs::canonicalize(skill_dir.join("SKILL.md")).expect("canonicalize")
+    );
+
+    // Edits in the config repo show through without a redeploy and do not drift.
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# my-skill v2\n",
+    )
+    .expect("edit SKILL.md");
+    assert!(
+        std::fs::read_to_string(&deployed)
+            .expect("read deployed")
+            .contains("v2")
+    );
+
+    let plan = agentpack_in(
+        home,
+        &workspace,
+        &["--target", "claude_code", "plan", "--json"],
+    );
+    assert!(plan.status.success(), "{plan:?}");
+    let plan = parse_stdout_json(&plan);
+    assert_eq!(plan["data"]["summary"]["create"], 0);
+    assert_eq!(plan["data"]["summary"]["update"], 0);
+
+    let status = agentpack_in(
+        home,
+        &workspace,
+        &["--target", "claude_code", "status", "--json"],
+    );
+    assert!(status.status.success(), "{status:?}");
+    let status = parse_stdout_json(&status);
+    assert_eq!(status["data"]["summary"]["modified"], 0);
+
+    // Rolling back to before the deploy removes the link but never touches the source.
+    std::fs::remove_file(&deployed).expect("remove link");
+    let rollback = agentpack_in(
+        home,
+        &workspace,
+        &["rollback", "--to", &snapshot_id, "--yes", "--json"],
+    );
+    assert!(rollback.status.success(), "{rollback:?}");
+    assert!(
+        std::fs::symlink_metadata(&deployed)
+            .expect("restored metadata")
+            .file_type()
+            .is_symlink()
+    );
+    assert!(skill_dir.join("SKILL.md").exists());
+}
+
+#[test]
+fn symlink_mode_falls_back_to_copy_when_overlay_applies() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let (workspace, _skill_dir) = setup(home);
+
+    let edit = agentpack_in(
+        home,
+        &workspace,
+        &["overlay", "edit", "skill:my-skill", "--yes", "--json"],
+    );
+    assert!(edit.status.success(), "{edit:?}");
+    let overlay_dir = parse_stdout_json(&edit)["data"]["overlay_dir"]
+        .as_str()
+        .expect("overlay_dir")
+        .to_string();
+    std::fs::write(
+        Path::new(&overlay_dir).join("SKILL.md"),
+        "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# overlaid\n",
+    )
+    .expect("write overlay");
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--target",
+            "claude_code",
+            "deploy",
+            "--apply",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    let warnings = deploy["warnings"].as_array().expect("warnings");
+    assert!(warnings.iter().any(|w| {
+        w.as_str()
+            .is_some_and(|w| w.contains("deployed as a copy in symlink mode (overlays applied)"))
+    }));
+
+    let deployed = workspace.join(".claude/skills/my-skill/SKILL.md");
+    let meta = std::fs::symlink_metadata(&deployed).expect("deployed metadata");
+    assert!(meta.file_type().is_file());
+    assert!(
+        std::fs::read_to_string(&deployed)
+            .expect("read deployed")
+            .contains("# overlaid")
+    );
+}
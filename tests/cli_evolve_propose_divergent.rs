@@ -0,0 +1,158 @@
+mod journeys;
+
+use journeys::common::{TestEnv, git_ok, git_stdout, run_json_ok, write_file};
+
+const SKILL: &str = "---\nname: one\ndescription: Example Skill for tests\n---\n\n# one\n\nalpha\nbravo\ncharlie\ndelta\necho\n";
+
+fn setup(env: &TestEnv) -> (std::path::PathBuf, std::path::PathBuf) {
+    let init = env
+        .agentpack()
+        .args(["--json", "--yes", "init", "--git"])
+        .output()
+        .expect("run agentpack init --git");
+    assert!(init.status.success());
+
+    let repo_dir = env.repo_dir();
+    git_ok(&repo_dir, &["config", "user.email", "test@example.com"]);
+    git_ok(&repo_dir, &["config", "user.name", "Test User"]);
+
+    write_file(&repo_dir.join("modules/skills/one/SKILL.md"), SKILL);
+    write_file(
+        &repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: true
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_repo_skills: true
+
+modules:
+  - id: skill:one
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/skills/one
+"#,
+    );
+    git_ok(&repo_dir, &["add", "-A"]);
+    git_ok(&repo_dir, &["commit", "-m", "chore(test): seed repo"]);
+
+    run_json_ok(env, &["deploy", "--apply", "--yes", "--json"]);
+    let codex = env.workspace().join(".codex/skills/one/SKILL.md");
+    let claude = env.workspace().join(".claude/skills/one/SKILL.md");
+    assert!(codex.is_file() && claude.is_file());
+    (codex, claude)
+}
+
+#[test]
+fn evolve_propose_reports_divergent_edits_across_targets() {
+    let env = TestEnv::new();
+    let (codex, claude) = setup(&env);
+    write_file(&codex, &SKILL.replace("alpha", "alpha (codex)"));
+    write_file(&claude, &SKILL.replace("alpha", "alpha (claude)"));
+
+    let v = run_json_ok(&env, &["evolve", "propose", "--dry-run", "--json"]);
+    assert_eq!(v["data"]["reason"], "no_proposeable_drift");
+    assert_eq!(v["data"]["summary"]["skipped_divergent"], 2);
+    assert_eq!(v["data"]["summary"]["drifted_proposeable"], 0);
+
+    let skipped = &v["data"]["skipped"][0];
+    assert_eq!(skipped["reason_code"], "divergent_edits");
+    assert_eq!(skipped["module_id"], "skill:one");
+    let variants = skipped["variants"].as_array().expect("variants");
+    let targets: Vec<&str> = variants
+        .iter()
+        .filter_map(|v| v["target"].as_str())
+        .collect();
+    assert_eq!(targets, ["claude_code", "codex"]);
+    let diff = skipped["diff"].as_str().expect("diff");
+    assert!(diff.contains("-alpha (claude)"), "{diff}");
+    assert!(diff.contains("+alpha (codex)"), "{diff}");
+    let next_actions = skipped["next_actions"].as_array().expect("next_actions");
+    assert!(next_actions.iter().any(|a| {
+        a.as_str()
+            .is_some_and(|a| a.contains("--prefer-target codex"))
+    }));
+    assert!(
+        next_actions
+            .iter()
+            .any(|a| a.as_str().is_some_and(|a| a.contains("--merge")))
+    );
+
+    // Overlapping edits cannot be merged.
+    let v = run_json_ok(
+        &env,
+        &["evolve", "propose", "--merge", "--dry-run", "--json"],
+    );
+    assert_eq!(v["data"]["reason"], "no_proposeable_drift");
+    assert_eq!(v["data"]["skipped"][0]["reason_code"], "divergent_edits");
+
+    // Choosing a target proposes that variant.
+    let branch = "evolve/divergent-codex";
+    let v = run_json_ok(
+        &env,
+        &[
+            "evolve",
+            "propose",
+            "--prefer-target",
+            "codex",
+            "--branch",
+            branch,
+            "--yes",
+            "--json",
+        ],
+    );
+    assert_eq!(v["data"]["created"], true);
+    let files = v["data"]["files_posix"].as_array().expect("files_posix");
+    assert_eq!(files.len(), 1);
+    let spec = format!("{branch}:{}", files[0].as_str().expect("file"));
+    let proposed = git_stdout(&env.repo_dir(), &["show", spec.as_str()]);
+    assert!(proposed.contains("alpha (codex)"));
+}
+
+#[test]
+fn evolve_propose_merges_non_overlapping_divergent_edits() {
+    let env = TestEnv::new();
+    let (codex, claude) = setup(&env);
+    write_file(&codex, &SKILL.replace("alpha", "alpha (codex)"));
+    write_file(&claude, &SKILL.replace("echo", "echo (claude)"));
+
+    let v = run_json_ok(
+        &env,
+        &["evolve", "propose", "--merge", "--dry-run", "--json"],
+    );
+    assert_eq!(v["data"]["reason"], "dry_run");
+    let candidates = v["data"]["candidates"].as_array().expect("candidates");
+    assert_eq!(candidates.len(), 2);
+    assert!(candidates.iter().all(|c| c["resolution"] == "merged"));
+
+    let branch = "evolve/divergent-merge";
+    let v = run_json_ok(
+        &env,
+        &[
+            "evolve", "propose", "--merge", "--branch", branch, "--yes", "--json",
+        ],
+    );
+    assert_eq!(v["data"]["created"], true);
+    let file = v["data"]["files_posix"][0].as_str().expect("file");
+    let proposed = git_stdout(&env.repo_dir(), &["show", &format!("{branch}:{file}")]);
+    assert_eq!(
+        proposed,
+        SKILL
+            .replace("alpha", "alpha (codex)")
+            .replace("echo", "echo (claude)")
+    );
+}
@@ -0,0 +1,115 @@
+#![cfg(all(feature = "target-cursor", feature = "target-codex"))]
+
+mod journeys;
+
+use journeys::common::{TestEnv, git_ok, git_stdout, run_json_ok, write_file};
+
+const BODY: &str = "# one\n\nalpha\nbravo\n";
+
+fn setup(env: &TestEnv) -> (std::path::PathBuf, std::path::PathBuf) {
+    let init = env
+        .agentpack()
+        .args(["--json", "--yes", "init", "--git"])
+        .output()
+        .expect("run agentpack init --git");
+    assert!(init.status.success());
+
+    let repo_dir = env.repo_dir();
+    git_ok(&repo_dir, &["config", "user.email", "test@example.com"]);
+    git_ok(&repo_dir, &["config", "user.name", "Test User"]);
+
+    write_file(&repo_dir.join("modules/instructions/one/AGENTS.md"), BODY);
+    write_file(
+        &repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+  cursor:
+    mode: files
+    scope: project
+
+modules:
+  - id: instructions:one
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/one
+"#,
+    );
+    git_ok(&repo_dir, &["add", "-A"]);
+    git_ok(&repo_dir, &["commit", "-m", "chore(test): seed repo"]);
+
+    run_json_ok(env, &["deploy", "--apply", "--yes", "--json"]);
+    let rule = std::fs::read_dir(env.workspace().join(".cursor/rules"))
+        .expect("read cursor rules dir")
+        .map(|e| e.expect("dir entry").path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "mdc"))
+        .expect("cursor rule");
+    let agents = env.workspace().join("AGENTS.md");
+    assert!(rule.is_file() && agents.is_file());
+    (rule, agents)
+}
+
+#[test]
+fn evolve_propose_strips_generated_frontmatter_from_cursor_rules() {
+    let env = TestEnv::new();
+    let (rule, agents) = setup(&env);
+
+    let deployed = std::fs::read_to_string(&rule).expect("read rule");
+    assert!(deployed.starts_with("---\ndescription:"), "{deployed}");
+    write_file(&rule, &deployed.replace("bravo", "bravo (edited)"));
+    // Without the frontmatter, the rule carries the same edit as the codex output: not divergent.
+    write_file(&agents, &BODY.replace("bravo", "bravo (edited)"));
+
+    let v = run_json_ok(&env, &["evolve", "propose", "--dry-run", "--json"]);
+    assert_eq!(v["data"]["reason"], "dry_run");
+    assert_eq!(v["data"]["summary"]["skipped_divergent"], 0);
+    let candidates = v["data"]["candidates"].as_array().expect("candidates");
+    assert_eq!(candidates.len(), 2, "{candidates:?}");
+    assert!(
+        candidates
+            .iter()
+            .all(|c| c["module_id"] == "instructions:one")
+    );
+
+    let branch = "evolve/cursor-rule";
+    let v = run_json_ok(
+        &env,
+        &["evolve", "propose", "--branch", branch, "--yes", "--json"],
+    );
+    assert_eq!(v["data"]["created"], true);
+    let file = v["data"]["files_posix"][0].as_str().expect("file");
+    assert!(file.ends_with("AGENTS.md"), "{file}");
+    let proposed = git_stdout(&env.repo_dir(), &["show", &format!("{branch}:{file}")]);
+    assert_eq!(proposed, BODY.replace("bravo", "bravo (edited)"));
+}
+
+#[test]
+fn evolve_propose_skips_edits_to_generated_frontmatter() {
+    let env = TestEnv::new();
+    let (rule, _agents) = setup(&env);
+
+    let deployed = std::fs::read_to_string(&rule).expect("read rule");
+    write_file(
+        &rule,
+        &deployed.replace("alwaysApply: true", "alwaysApply: false"),
+    );
+
+    let v = run_json_ok(&env, &["evolve", "propose", "--dry-run", "--json"]);
+    assert_eq!(v["data"]["reason"], "no_proposeable_drift");
+    assert_eq!(v["data"]["summary"]["skipped_synthesized"], 1);
+    let skipped = &v["data"]["skipped"][0];
+    assert_eq!(skipped["reason_code"], "synthesized_text_edited");
+    assert_eq!(skipped["module_id"], "instructions:one");
+    assert_eq!(skipped["target"], "cursor");
+}
@@ -0,0 +1,300 @@
+mod journeys;
+
+use std::path::{Path, PathBuf};
+
+use journeys::common::{TestEnv, git_ok, git_stdout, run_json_ok, write_file};
+
+const SKILL: &str =
+    "---\nname: one\ndescription: Example Skill for tests\n---\n\n# one\n\nalpha\nbravo\ncharlie\n";
+
+fn commit_all(dir: &Path, message: &str) -> String {
+    git_ok(dir, &["add", "-A"]);
+    git_ok(dir, &["commit", "-m", message]);
+    git_stdout(dir, &["rev-parse", "HEAD"]).trim().to_string()
+}
+
+/// A shared skills repo with skill `one` in a subdirectory, pushed to a bare remote.
+/// Returns `(working clone, bare remote, commit)`.
+fn shared_skills_repo(env: &TestEnv) -> (PathBuf, PathBuf, String) {
+    let shared = env.home().join("shared-skills");
+    let remote = env.home().join("shared-skills.git");
+    std::fs::create_dir_all(&shared).expect("create shared repo");
+    git_ok(&shared, &["init", "-b", "main"]);
+    git_ok(&shared, &["config", "user.email", "test@example.com"]);
+    git_ok(&shared, &["config", "user.name", "Test User"]);
+    write_file(&shared.join("skills/one/SKILL.md"), SKILL);
+    let locked_commit = commit_all(&shared, "add skill one");
+    git_ok(
+        env.home(),
+        &["init", "--bare", remote.to_string_lossy().as_ref()],
+    );
+    git_ok(
+        &shared,
+        &["remote", "add", "origin", remote.to_string_lossy().as_ref()],
+    );
+    git_ok(&shared, &["push", "-u", "origin", "main"]);
+    (shared, remote, locked_commit)
+}
+
+#[test]
+fn evolve_propose_upstream_commits_edits_onto_the_locked_commit_and_writes_patches() {
+    let env = TestEnv::new();
+    let (shared, remote, locked_commit) = shared_skills_repo(&env);
+
+    env.init_repo();
+    let repo_dir = env.repo_dir();
+    write_file(
+        &repo_dir.join("modules/skills/local/SKILL.md"),
+        "---\nname: local\ndescription: Local skill\n---\n\n# local\n",
+    );
+    write_file(
+        &env.manifest_path(),
+        &format!(
+            r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_repo_skills: true
+
+modules:
+  - id: skill:one
+    type: skill
+    tags: ["base"]
+    source:
+      git:
+        url: "file://{}"
+        ref: main
+        subdir: skills/one
+  - id: skill:local
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/skills/local
+"#,
+            remote.to_string_lossy()
+        ),
+    );
+    run_json_ok(&env, &["lock", "--yes", "--json"]);
+    run_json_ok(&env, &["deploy", "--apply", "--yes", "--json"]);
+
+    // Upstream moves on after the lock; the proposal must still be based on the locked commit.
+    write_file(
+        &shared.join("skills/one/SKILL.md"),
+        &SKILL.replace("charlie", "charlie (upstream)"),
+    );
+    commit_all(&shared, "upstream change");
+    git_ok(&shared, &["push", "origin", "main"]);
+
+    let deployed = env.workspace().join(".claude/skills/one/SKILL.md");
+    write_file(&deployed, &SKILL.replace("alpha", "alpha (edited)"));
+    write_file(
+        &env.workspace().join(".claude/skills/local/SKILL.md"),
+        "---\nname: local\ndescription: Local skill\n---\n\n# local edited\n",
+    );
+
+    let out = env
+        .agentpack()
+        .args(["evolve", "propose", "--upstream", "--yes", "--json"])
+        .env("GIT_AUTHOR_NAME", "Test User")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test User")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .output()
+        .expect("run evolve propose --upstream");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json");
+    assert_eq!(v["data"]["created"], true);
+    assert_eq!(v["data"]["upstream"], true);
+
+    let proposals = v["data"]["proposals"].as_array().expect("proposals");
+    assert_eq!(proposals.len(), 1);
+    let p = &proposals[0];
+    assert_eq!(p["module_id"], "skill:one");
+    assert_eq!(p["base_commit"], locked_commit.as_str());
+    assert_eq!(p["subdir"], "skills/one");
+    assert_eq!(p["files"], serde_json::json!(["skills/one/SKILL.md"]));
+
+    let clone_dir = Path::new(p["clone_dir"].as_str().expect("clone_dir"));
+    let branch = p["branch"].as_str().expect("branch");
+    assert!(branch.starts_with("evolve/upstream-"));
+    assert_eq!(
+        git_stdout(clone_dir, &["rev-parse", "--abbrev-ref", "HEAD"]).trim(),
+        branch
+    );
+    assert_eq!(
+        git_stdout(clone_dir, &["rev-parse", "HEAD^"]).trim(),
+        locked_commit
+    );
+    assert_eq!(
+        git_stdout(clone_dir, &["remote", "get-url", "origin"]).trim(),
+        format!("file://{}", remote.to_string_lossy())
+    );
+    assert_eq!(
+        std::fs::read_to_string(clone_dir.join("skills/one/SKILL.md")).expect("read"),
+        SKILL.replace("alpha", "alpha (edited)")
+    );
+
+    let patches = p["patches"].as_array().expect("patches");
+    assert_eq!(patches.len(), 1);
+    let patch = std::fs::read_to_string(patches[0].as_str().expect("patch")).expect("read patch");
+    assert!(patch.contains("+alpha (edited)"), "{patch}");
+    assert!(patch.contains("skills/one/SKILL.md"), "{patch}");
+
+    // Local modules cannot be proposed upstream; nothing is pushed or written to overlays.
+    let skipped = v["data"]["skipped"].as_array().expect("skipped");
+    assert!(
+        skipped
+            .iter()
+            .any(|s| s["reason_code"] == "not_git_source" && s["module_id"] == "skill:local"),
+        "{skipped:?}"
+    );
+    assert!(
+        git_stdout(&remote, &["branch", "--list", "evolve/*"])
+            .trim()
+            .is_empty()
+    );
+    assert!(!repo_dir.join("overlays").exists());
+
+    // A local edit of a line an overlay changed does not apply to the upstream files, so
+    // nothing is proposed; the error still carries the skipped items.
+    write_file(&deployed, SKILL);
+    run_json_ok(
+        &env,
+        &[
+            "overlay",
+            "edit",
+            "skill:one",
+            "--sparse",
+            "--yes",
+            "--json",
+        ],
+    );
+    let overlay = run_json_ok(&env, &["overlay", "path", "skill:one", "--json"]);
+    let overlay_dir = Path::new(
+        overlay["data"]["overlay_dir"]
+            .as_str()
+            .expect("overlay_dir"),
+    );
+    write_file(
+        &overlay_dir.join("SKILL.md"),
+        &SKILL.replace("charlie", "charlie (overlay)"),
+    );
+    run_json_ok(&env, &["deploy", "--apply", "--yes", "--json"]);
+    write_file(&deployed, &SKILL.replace("charlie", "charlie (local)"));
+    let out = env
+        .agentpack()
+        .args(["evolve", "propose", "--upstream", "--yes", "--json"])
+        .output()
+        .expect("run evolve propose --upstream");
+    assert!(!out.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json");
+    let err = &v["errors"][0];
+    assert_eq!(err["code"], "E_EVOLVE_NO_UPSTREAM_PROPOSALS", "{v}");
+    assert_eq!(err["details"]["reason_code"], "no_upstream_proposals");
+    let skipped = err["details"]["skipped"].as_array().expect("skipped");
+    assert!(
+        skipped
+            .iter()
+            .any(|s| s["reason_code"] == "upstream_conflict" && s["module_id"] == "skill:one"),
+        "{skipped:?}"
+    );
+}
+
+#[test]
+fn evolve_propose_upstream_commits_without_a_git_identity() {
+    let env = TestEnv::new();
+    let (_shared, remote, locked_commit) = shared_skills_repo(&env);
+
+    env.init_repo();
+    write_file(
+        &env.manifest_path(),
+        &format!(
+            r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_repo_skills: true
+
+modules:
+  - id: skill:one
+    type: skill
+    tags: ["base"]
+    source:
+      git:
+        url: "file://{}"
+        ref: main
+        subdir: skills/one
+"#,
+            remote.to_string_lossy()
+        ),
+    );
+    run_json_ok(&env, &["lock", "--yes", "--json"]);
+    run_json_ok(&env, &["deploy", "--apply", "--yes", "--json"]);
+    write_file(
+        &env.workspace().join(".claude/skills/one/SKILL.md"),
+        &SKILL.replace("alpha", "alpha (edited)"),
+    );
+
+    // `user.useConfigOnly` stops git from guessing an identity from the host.
+    let out = env
+        .agentpack()
+        .args(["evolve", "propose", "--upstream", "--yes", "--json"])
+        .env_remove("GIT_AUTHOR_NAME")
+        .env_remove("GIT_AUTHOR_EMAIL")
+        .env_remove("GIT_COMMITTER_NAME")
+        .env_remove("GIT_COMMITTER_EMAIL")
+        .env_remove("EMAIL")
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_COUNT", "1")
+        .env("GIT_CONFIG_KEY_0", "user.useConfigOnly")
+        .env("GIT_CONFIG_VALUE_0", "true")
+        .output()
+        .expect("run evolve propose --upstream");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json");
+    let warnings = v["warnings"].as_array().expect("warnings");
+    assert!(
+        warnings.iter().any(|w| w
+            .as_str()
+            .is_some_and(|w| w.contains("no git identity configured"))),
+        "{warnings:?}"
+    );
+
+    let p = &v["data"]["proposals"][0];
+    assert_eq!(p["module_id"], "skill:one");
+    let clone_dir = Path::new(p["clone_dir"].as_str().expect("clone_dir"));
+    assert_eq!(
+        git_stdout(clone_dir, &["rev-parse", "HEAD^"]).trim(),
+        locked_commit
+    );
+    assert_eq!(
+        git_stdout(clone_dir, &["log", "-1", "--format=%an <%ae>"]).trim(),
+        "agentpack <agentpack@localhost>"
+    );
+    assert_eq!(p["patches"].as_array().expect("patches").len(), 1);
+}
@@ -0,0 +1,224 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .env("EDITOR", "")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn run_ok(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let out = agentpack_in(home, cwd, args);
+    assert!(
+        out.status.success(),
+        "{args:?}: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    out
+}
+
+const BASE: &str = "# Base\n\nalpha\nbravo\ncharlie\n";
+const EXTRA: &str = "# Extra\n\none\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+
+#[test]
+fn explain_file_attributes_lines_to_layers_patches_rendering_and_local_edits() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+    run_ok(home, &workspace, &["init"]);
+
+    let repo_dir = home.join("repo");
+    for (name, text) in [("base", BASE), ("extra", EXTRA)] {
+        let dir = repo_dir.join("modules/instructions").join(name);
+        std::fs::create_dir_all(&dir).expect("create module dir");
+        std::fs::write(dir.join("AGENTS.md"), text).expect("write AGENTS.md");
+    }
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+  - id: instructions:extra
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/extra
+"#,
+    )
+    .expect("write manifest");
+
+    // Global directory overlay on `base`, machine patch overlay on `extra`.
+    run_ok(
+        home,
+        &workspace,
+        &["overlay", "edit", "instructions:base", "--sparse"],
+    );
+    let out = run_ok(
+        home,
+        &workspace,
+        &["overlay", "path", "instructions:base", "--json"],
+    );
+    let base_overlay = parse_stdout_json(&out)["data"]["overlay_dir"]
+        .as_str()
+        .expect("overlay_dir")
+        .to_string();
+    std::fs::write(
+        Path::new(&base_overlay).join("AGENTS.md"),
+        BASE.replace("bravo", "bravo (global)"),
+    )
+    .expect("write global override");
+
+    let edited = home.join("extra-edited.md");
+    std::fs::write(&edited, EXTRA.replace("six", "six (machine)")).expect("write edited");
+    run_ok(
+        home,
+        &workspace,
+        &[
+            "overlay",
+            "capture",
+            "--module",
+            "instructions:extra",
+            "--scope",
+            "machine",
+            "--from",
+            edited.to_str().expect("utf-8 path"),
+            "--rel",
+            "AGENTS.md",
+        ],
+    );
+
+    run_ok(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    let deployed = workspace.join("AGENTS.md");
+    let raw = std::fs::read_to_string(&deployed).expect("read deployed");
+    std::fs::write(&deployed, raw.replace("three", "three (local)")).expect("edit deployed");
+
+    let out = run_ok(
+        home,
+        &workspace,
+        &["explain", "file", "AGENTS.md", "--json"],
+    );
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["command"], "explain.file");
+    let data = &v["data"];
+    assert_eq!(data["target"], "codex");
+    assert_eq!(data["deployed"], true);
+    assert_eq!(data["modified"], true);
+    assert_eq!(data["modules"][0]["module_id"], "instructions:base");
+    assert_eq!(data["modules"][1]["module_id"], "instructions:extra");
+
+    let origin_of = |needle: &str| -> serde_json::Value {
+        let hunks = data["hunks"].as_array().expect("hunks");
+        let hunk = hunks
+            .iter()
+            .find(|h| {
+                h["text"]
+                    .as_str()
+                    .is_some_and(|t| t.lines().any(|l| l == needle))
+            })
+            .unwrap_or_else(|| panic!("no hunk contains {needle:?}: {hunks:?}"));
+        hunk["origin"].clone()
+    };
+    assert_eq!(
+        origin_of("alpha"),
+        serde_json::json!({"layer": "upstream", "module_id": "instructions:base"})
+    );
+    assert_eq!(
+        origin_of("bravo (global)"),
+        serde_json::json!({"layer": "global", "module_id": "instructions:base"})
+    );
+    assert_eq!(
+        origin_of("six (machine)"),
+        serde_json::json!({"layer": "machine", "module_id": "instructions:extra", "patch": true})
+    );
+    assert_eq!(
+        origin_of("seven"),
+        serde_json::json!({"layer": "upstream", "module_id": "instructions:extra"})
+    );
+    assert_eq!(
+        origin_of("three (local)"),
+        serde_json::json!({"layer": "local"})
+    );
+    // Aggregation markers come from the target adapter, not from a module.
+    let render = data["summary"]["render"].as_u64().expect("render lines");
+    assert!(render > 0, "{data}");
+    assert_eq!(data["summary"]["global"], 1);
+    assert_eq!(data["summary"]["machine+patch"], 1);
+    assert_eq!(data["summary"]["local"], 1);
+
+    // Hunks cover every line of the on-disk file, in order.
+    let hunks = data["hunks"].as_array().expect("hunks");
+    let joined: String = hunks
+        .iter()
+        .map(|h| h["text"].as_str().unwrap_or_default())
+        .collect();
+    let on_disk = std::fs::read_to_string(&deployed).expect("read");
+    assert_eq!(
+        joined.lines().collect::<Vec<_>>(),
+        on_disk.lines().collect::<Vec<_>>()
+    );
+
+    let human = run_ok(home, &workspace, &["explain", "file", "AGENTS.md"]);
+    let stdout = String::from_utf8_lossy(&human.stdout);
+    assert!(
+        stdout.contains("machine+patch instructions:extra"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("| six (machine)"), "{stdout}");
+
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &["explain", "file", "README.md", "--json"],
+    );
+    assert!(!out.status.success());
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["errors"][0]["code"], "E_PATH_NOT_MANAGED");
+    assert_eq!(v["errors"][0]["details"]["reason_code"], "path_not_managed");
+}
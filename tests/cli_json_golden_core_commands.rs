@@ -371,6 +371,7 @@ fn json_core_command_data_matches_golden_snapshots() -> anyhow::Result<()> {
             "rollback",
             "--to",
             snapshot1.as_str(),
+            "--discard-local-edits",
             "--yes",
             "--json",
         ],
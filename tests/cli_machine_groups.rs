@@ -0,0 +1,305 @@
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .env("EDITOR", "")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).expect("stdout is valid json")
+}
+
+fn write_module(repo_dir: &Path, name: &str) {
+    let dir = repo_dir.join("modules/skills").join(name);
+    std::fs::create_dir_all(&dir).expect("create skill dir");
+    std::fs::write(
+        dir.join("SKILL.md"),
+        format!("---\nname: {name}\ndescription: Example Skill for tests\n---\n\n# {name}\n"),
+    )
+    .expect("write SKILL.md");
+}
+
+fn write_manifest(repo_dir: &Path, machines: &str) {
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!(
+            r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+  laptop:
+    include_tags: ["base", "laptop"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:one
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: "modules/skills/one"
+  - id: skill:two
+    type: skill
+    tags: ["laptop"]
+    source:
+      local_path:
+        path: "modules/skills/two"
+
+machines:
+{machines}"#
+        ),
+    )
+    .expect("write manifest");
+}
+
+fn setup(home: &Path, machines: &str) -> std::path::PathBuf {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    write_module(&repo_dir, "one");
+    write_module(&repo_dir, "two");
+    write_manifest(&repo_dir, machines);
+    workspace
+}
+
+const LAPTOPS: &str = r#"  laptops:
+    match: ["*-mbp", "studio"]
+    default_profile: laptop
+"#;
+
+#[test]
+fn group_overlay_and_default_profile_apply_to_member_machines() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home, LAPTOPS);
+
+    for args in [
+        &["overlay", "edit", "skill:one", "--sparse"][..],
+        &[
+            "--machine",
+            "alice-mbp",
+            "overlay",
+            "edit",
+            "skill:one",
+            "--sparse",
+            "--scope",
+            "group",
+        ][..],
+    ] {
+        let out = agentpack_in(home, &workspace, args);
+        assert!(
+            out.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+
+    let group_dir = home.join("repo/overlays/groups/laptops");
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--machine",
+            "alice-mbp",
+            "overlay",
+            "path",
+            "skill:one",
+            "--scope",
+            "group",
+            "--json",
+        ],
+    );
+    assert!(out.status.success());
+    let overlay_dir = parse_stdout_json(&out)["data"]["overlay_dir"]
+        .as_str()
+        .expect("overlay_dir")
+        .to_string();
+    assert!(Path::new(&overlay_dir).starts_with(&group_dir));
+
+    let global_out = agentpack_in(
+        home,
+        &workspace,
+        &["overlay", "path", "skill:one", "--json"],
+    );
+    let global_dir = parse_stdout_json(&global_out)["data"]["overlay_dir"]
+        .as_str()
+        .expect("overlay_dir")
+        .to_string();
+    let skill = "---\nname: one\ndescription: Example Skill for tests\n---\n\n";
+    std::fs::write(
+        Path::new(&global_dir).join("SKILL.md"),
+        format!("{skill}# global\n"),
+    )
+    .expect("write global override");
+    std::fs::write(
+        Path::new(&overlay_dir).join("SKILL.md"),
+        format!("{skill}# laptops\n"),
+    )
+    .expect("write group override");
+
+    // Member machine: default profile comes from the group; group overlay sits above global.
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &["--machine", "alice-mbp", "explain", "plan", "--json"],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["data"]["profile"], "laptop");
+    assert_eq!(v["data"]["machine_group"], "laptops");
+    let changes = v["data"]["changes"].as_array().expect("changes");
+    let one = changes
+        .iter()
+        .find(|c| {
+            c["path_posix"]
+                .as_str()
+                .is_some_and(|p| p.ends_with("skills/one/SKILL.md"))
+        })
+        .expect("change for skill one");
+    assert_eq!(one["modules"][0]["layer"], "group");
+    assert_eq!(
+        one["modules"][0]["layers"],
+        serde_json::json!(["upstream", "global", "group"])
+    );
+    assert!(changes.iter().any(|c| {
+        c["path_posix"]
+            .as_str()
+            .is_some_and(|p| p.ends_with("skills/two/SKILL.md"))
+    }));
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--machine",
+            "alice-mbp",
+            "deploy",
+            "--apply",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(
+        deploy.status.success(),
+        "{}",
+        String::from_utf8_lossy(&deploy.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join(".claude/skills/one/SKILL.md"))
+            .expect("read deployed"),
+        format!("{skill}# laptops\n")
+    );
+    assert!(workspace.join(".claude/skills/two/SKILL.md").exists());
+
+    // An explicit --profile wins over the group default.
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--machine",
+            "alice-mbp",
+            "--profile",
+            "default",
+            "plan",
+            "--json",
+        ],
+    );
+    assert_eq!(parse_stdout_json(&out)["data"]["profile"], "default");
+
+    // Non-member machine: no group layer, plain default profile.
+    let out = agentpack_in(home, &workspace, &["explain", "plan", "--json"]);
+    assert!(out.status.success());
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["data"]["profile"], "default");
+    assert_eq!(v["data"]["machine_group"], serde_json::Value::Null);
+
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &["overlay", "path", "skill:one", "--scope", "group", "--json"],
+    );
+    assert!(!out.status.success());
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "machine_group_missing"
+    );
+}
+
+#[test]
+fn machine_matching_several_groups_is_rejected() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(
+        home,
+        &format!("{LAPTOPS}  alice:\n    match: [\"alice-*\"]\n"),
+    );
+
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &["--machine", "alice-mbp", "plan", "--json"],
+    );
+    assert!(!out.status.success());
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "machine_group_ambiguous"
+    );
+    assert_eq!(
+        v["errors"][0]["details"]["groups"],
+        serde_json::json!(["alice", "laptops"])
+    );
+
+    let out = agentpack_in(home, &workspace, &["plan", "--json"]);
+    assert!(out.status.success());
+}
+
+#[test]
+fn invalid_machine_groups_are_config_errors() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(
+        home,
+        "  laptops:\n    match: [\"*-mbp\"]\n    default_profile: missing\n",
+    );
+
+    let out = agentpack_in(home, &workspace, &["plan", "--json"]);
+    assert!(!out.status.success());
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "machine_group_invalid"
+    );
+    assert_eq!(v["errors"][0]["details"]["field"], "default_profile");
+}
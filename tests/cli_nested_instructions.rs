@@ -0,0 +1,253 @@
+#![cfg(feature = "target-codex")]
+
+mod conformance_harness;
+
+use conformance_harness::ConformanceHarness;
+use std::path::Path;
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).expect("stdout is valid json")
+}
+
+fn write_instructions(repo_dir: &Path, name: &str, content: &str) {
+    let dir = repo_dir.join("modules/instructions").join(name);
+    std::fs::create_dir_all(&dir).expect("create module dir");
+    std::fs::write(dir.join("AGENTS.md"), content).expect("write AGENTS.md");
+}
+
+fn write_manifest(repo_dir: &Path, services_subpath: &str) {
+    let manifest = format!(
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+    instructions_subpaths:
+      instructions:infra: ["infra"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+  - id: instructions:services
+    type: instructions
+    tags: ["base"]
+    metadata:
+      subpath: {services_subpath}
+    source:
+      local_path:
+        path: modules/instructions/services
+  - id: instructions:shared
+    type: instructions
+    tags: ["base"]
+    metadata:
+      subpath: ["services/api", "web"]
+    source:
+      local_path:
+        path: modules/instructions/shared
+  - id: instructions:infra
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/infra
+"#
+    );
+    std::fs::write(repo_dir.join("agentpack.yaml"), manifest).expect("write manifest");
+}
+
+#[test]
+fn codex_writes_nested_agents_md_for_subpaths() {
+    let harness = ConformanceHarness::new();
+    let workspace = harness.workspace();
+    assert!(harness.agentpack(&["init"]).status.success());
+
+    let repo_dir = harness.home().join("repo");
+    write_instructions(&repo_dir, "base", "# Base rules\n");
+    write_instructions(&repo_dir, "services", "# Service rules\n");
+    write_instructions(&repo_dir, "shared", "# Shared rules\n");
+    write_instructions(&repo_dir, "infra", "# Infra rules\n");
+    write_manifest(&repo_dir, "\"services/*\"");
+
+    for dir in ["services/api", "services/worker", "web", "infra"] {
+        std::fs::create_dir_all(workspace.join(dir)).expect("create project dir");
+    }
+
+    let deploy = harness.agentpack(&["--target", "codex", "deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+
+    let root = std::fs::read_to_string(workspace.join("AGENTS.md")).expect("root AGENTS.md");
+    assert_eq!(root, "# Base rules\n");
+
+    let api = std::fs::read_to_string(workspace.join("services/api/AGENTS.md")).expect("api");
+    assert!(api.contains("# Service rules"));
+    assert!(api.contains("# Shared rules"));
+    assert!(api.contains("<!-- agentpack:module=instructions:services -->"));
+
+    let worker =
+        std::fs::read_to_string(workspace.join("services/worker/AGENTS.md")).expect("worker");
+    assert_eq!(worker, "# Service rules\n");
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("web/AGENTS.md")).expect("web"),
+        "# Shared rules\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("infra/AGENTS.md")).expect("infra"),
+        "# Infra rules\n"
+    );
+
+    // Nested outputs are tracked by the project-root manifest, so drift is detected.
+    let manifest: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(workspace.join(".agentpack.manifest.codex.json"))
+            .expect("read target manifest"),
+    )
+    .expect("parse target manifest");
+    let managed: Vec<&str> = manifest["managed_files"]
+        .as_array()
+        .expect("managed_files")
+        .iter()
+        .filter_map(|f| f["path"].as_str())
+        .collect();
+    assert!(managed.contains(&"services/worker/AGENTS.md"));
+
+    std::fs::write(workspace.join("web/AGENTS.md"), "local edit\n").expect("edit web");
+    let status = harness.agentpack(&["--target", "codex", "status", "--json"]);
+    assert!(status.status.success(), "{status:?}");
+    let status = parse_stdout_json(&status);
+    let drift = status["data"]["drift"].as_array().expect("drift");
+    assert!(drift.iter().any(|d| {
+        d["kind"] == "modified"
+            && d["path_posix"]
+                .as_str()
+                .is_some_and(|p| p.ends_with("web/AGENTS.md"))
+    }));
+}
+
+#[test]
+fn invalid_instructions_subpath_is_rejected() {
+    let harness = ConformanceHarness::new();
+    assert!(harness.agentpack(&["init"]).status.success());
+
+    let repo_dir = harness.home().join("repo");
+    write_instructions(&repo_dir, "base", "# Base rules\n");
+    write_instructions(&repo_dir, "services", "# Service rules\n");
+    write_instructions(&repo_dir, "shared", "# Shared rules\n");
+    write_instructions(&repo_dir, "infra", "# Infra rules\n");
+    write_manifest(&repo_dir, "\"../outside\"");
+
+    let plan = harness.agentpack(&["--target", "codex", "plan", "--json"]);
+    assert!(!plan.status.success());
+    let plan = parse_stdout_json(&plan);
+    assert_eq!(plan["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(
+        plan["errors"][0]["details"]["reason_code"],
+        "instructions_subpath_invalid"
+    );
+    assert_eq!(
+        plan["errors"][0]["details"]["module_id"],
+        "instructions:services"
+    );
+}
+
+#[test]
+#[cfg(all(feature = "target-vscode", feature = "target-zed"))]
+fn targets_without_nested_files_keep_subpath_modules_at_the_root() {
+    let harness = ConformanceHarness::new();
+    let workspace = harness.workspace();
+    assert!(harness.agentpack(&["init"]).status.success());
+
+    let repo_dir = harness.home().join("repo");
+    write_instructions(&repo_dir, "base", "# Base rules\n");
+    write_instructions(&repo_dir, "web", "# Web rules\n");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+      write_agents_nested: false
+  vscode:
+    mode: files
+    scope: project
+  zed:
+    mode: files
+    scope: project
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+  - id: instructions:web
+    type: instructions
+    tags: ["base"]
+    metadata:
+      subpath: ["web", "docs"]
+    source:
+      local_path:
+        path: modules/instructions/web
+"#,
+    )
+    .expect("write manifest");
+    for dir in ["web", "docs"] {
+        std::fs::create_dir_all(workspace.join(dir)).expect("create project dir");
+    }
+
+    let deploy = harness.agentpack(&["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    let warnings = deploy["warnings"].as_array().expect("warnings");
+    for target in ["codex", "zed"] {
+        assert!(
+            warnings.iter().any(|w| {
+                w.as_str().is_some_and(|w| {
+                    w.starts_with(&format!("{target}:")) && w.contains("instructions:web")
+                })
+            }),
+            "{target}: {warnings:?}"
+        );
+    }
+
+    // With nesting disabled, codex falls back to the root file instead of dropping the module.
+    let root = std::fs::read_to_string(workspace.join("AGENTS.md")).expect("root AGENTS.md");
+    assert!(root.contains("# Base rules") && root.contains("# Web rules"));
+    assert!(!workspace.join("web/AGENTS.md").exists());
+
+    let zed = std::fs::read_to_string(workspace.join(".rules")).expect("zed rules");
+    assert!(zed.contains("# Web rules"));
+
+    // VS Code gets a path-specific instructions file instead of the repo-wide one.
+    let copilot = std::fs::read_to_string(workspace.join(".github/copilot-instructions.md"))
+        .expect("copilot instructions");
+    assert_eq!(copilot, "# Base rules\n");
+    let scoped = std::fs::read_dir(workspace.join(".github/instructions"))
+        .expect("read instructions dir")
+        .map(|e| e.expect("dir entry").path())
+        .find(|p| p.to_string_lossy().ends_with(".instructions.md"))
+        .expect("path-specific instructions file");
+    assert_eq!(
+        std::fs::read_to_string(scoped).expect("read scoped"),
+        "---\napplyTo: \"docs/**,web/**\"\n---\n\n# Web rules\n"
+    );
+}
@@ -0,0 +1,222 @@
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .env("EDITOR", "")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).expect("stdout is valid json")
+}
+
+fn overlay_dir(home: &Path, cwd: &Path, scope: &str) -> std::path::PathBuf {
+    let out = agentpack_in(
+        home,
+        cwd,
+        &[
+            "overlay",
+            "path",
+            "skill:my-skill",
+            "--scope",
+            scope,
+            "--json",
+        ],
+    );
+    parse_stdout_json(&out)["data"]["overlay_dir"]
+        .as_str()
+        .expect("overlay_dir")
+        .into()
+}
+
+#[test]
+fn overlay_capture_converts_dir_overlays_and_deployed_files_into_patches() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .arg("init")
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    let skill_dir = repo_dir.join("modules/skills/my-skill");
+    std::fs::create_dir_all(skill_dir.join("scripts")).expect("create skill dir");
+    let skill_md = "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# my-skill\n";
+    std::fs::write(skill_dir.join("SKILL.md"), skill_md).expect("write SKILL.md");
+    std::fs::write(skill_dir.join("scripts/run.sh"), "echo one\necho two\n").expect("write run.sh");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:my-skill
+    type: skill
+    tags: ["base"]
+    targets: ["claude_code"]
+    source:
+      local_path:
+        path: "modules/skills/my-skill"
+"#,
+    )
+    .expect("write manifest");
+
+    // A full-copy directory overlay with one edited file.
+    let edit = agentpack_in(home, &workspace, &["overlay", "edit", "skill:my-skill"]);
+    assert!(edit.status.success());
+    let global = overlay_dir(home, &workspace, "global");
+    let edited_md = skill_md.replace("Example Skill", "Edited Skill");
+    std::fs::write(global.join("SKILL.md"), &edited_md).expect("edit overlay");
+
+    let capture_args = ["overlay", "capture", "--module", "skill:my-skill", "--json"];
+    let out = agentpack_in(home, &workspace, &capture_args);
+    assert!(!out.status.success());
+    assert_eq!(
+        parse_stdout_json(&out)["errors"][0]["code"],
+        "E_CONFIRM_REQUIRED"
+    );
+
+    let out = agentpack_in(home, &workspace, &[&capture_args[..], &["--yes"]].concat());
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["command"], "overlay.capture");
+    assert_eq!(
+        v["data"]["report"]["patched"],
+        serde_json::json!(["SKILL.md"])
+    );
+    assert_eq!(v["data"]["report"]["unchanged"], 1);
+    assert!(global.join(".agentpack/patches/SKILL.md.patch").is_file());
+    assert!(!global.join("SKILL.md").exists());
+    assert!(!global.join("scripts").exists());
+    let meta = std::fs::read_to_string(global.join(".agentpack/overlay.json")).expect("meta");
+    assert!(meta.contains("\"patch\""));
+
+    let deploy = [
+        "--target",
+        "claude_code",
+        "deploy",
+        "--apply",
+        "--yes",
+        "--json",
+    ];
+    assert!(agentpack_in(home, &workspace, &deploy).status.success());
+    let deployed = workspace.join(".claude/skills/my-skill");
+    assert_eq!(
+        std::fs::read_to_string(deployed.join("SKILL.md")).expect("read deployed"),
+        edited_md
+    );
+
+    // Capture a hand-edited deployed file; its module path is inferred from the file name.
+    let edited_sh = "echo one\necho 2\n";
+    std::fs::write(deployed.join("scripts/run.sh"), edited_sh).expect("edit deployed");
+    let from = deployed.join("scripts/run.sh");
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "overlay",
+            "capture",
+            "--module",
+            "skill:my-skill",
+            "--from",
+            from.to_str().expect("utf-8 path"),
+            "--json",
+            "--yes",
+        ],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+    let v = parse_stdout_json(&out);
+    assert_eq!(
+        v["data"]["report"]["patched"],
+        serde_json::json!(["scripts/run.sh"])
+    );
+    assert!(
+        global
+            .join(".agentpack/patches/scripts/run.sh.patch")
+            .is_file()
+    );
+
+    let out = agentpack_in(home, &workspace, &deploy);
+    assert!(out.status.success());
+    assert_eq!(
+        std::fs::read_to_string(deployed.join("SKILL.md")).expect("read deployed"),
+        edited_md
+    );
+    assert_eq!(
+        std::fs::read_to_string(deployed.join("scripts/run.sh")).expect("read deployed"),
+        edited_sh
+    );
+
+    // New files cannot be expressed as patches, so converting such an overlay is refused.
+    let edit = agentpack_in(
+        home,
+        &workspace,
+        &["overlay", "edit", "skill:my-skill", "--scope", "machine"],
+    );
+    assert!(edit.status.success());
+    let machine = overlay_dir(home, &workspace, "machine");
+    std::fs::write(machine.join("extra.md"), "new\n").expect("write extra");
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "overlay",
+            "capture",
+            "--module",
+            "skill:my-skill",
+            "--scope",
+            "machine",
+            "--json",
+            "--yes",
+        ],
+    );
+    assert!(!out.status.success());
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "overlay_capture_unsupported"
+    );
+    assert!(machine.join("extra.md").is_file());
+}
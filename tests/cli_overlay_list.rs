@@ -0,0 +1,253 @@
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .env("EDITOR", "")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).expect("stdout is valid json")
+}
+
+fn write_module(repo_dir: &Path, name: &str) {
+    let dir = repo_dir.join("modules/skills").join(name);
+    std::fs::create_dir_all(&dir).expect("create skill dir");
+    std::fs::write(
+        dir.join("SKILL.md"),
+        format!("---\nname: {name}\ndescription: Example Skill for tests\n---\n\n# {name}\n"),
+    )
+    .expect("write SKILL.md");
+}
+
+#[test]
+fn overlay_list_reports_kind_counts_and_drift() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    write_module(&repo_dir, "one");
+    write_module(&repo_dir, "two");
+    write_module(&repo_dir, "three");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:one
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: "modules/skills/one"
+  - id: skill:two
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: "modules/skills/two"
+  - id: skill:three
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: "modules/skills/three"
+"#,
+    )
+    .expect("write manifest");
+
+    let empty = agentpack_in(home, &workspace, &["overlay", "list", "--json"]);
+    assert!(empty.status.success());
+    let v = parse_stdout_json(&empty);
+    assert_eq!(v["command"], "overlay.list");
+    assert_eq!(v["data"]["overlays"], serde_json::json!([]));
+
+    for args in [
+        &["overlay", "edit", "skill:one", "--sparse"][..],
+        &[
+            "overlay",
+            "edit",
+            "skill:two",
+            "--kind",
+            "patch",
+            "--scope",
+            "machine",
+        ][..],
+    ] {
+        let out = agentpack_in(home, &workspace, args);
+        assert!(
+            out.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+
+    // One override file in the global overlay, then upstream moves on.
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &["overlay", "path", "skill:one", "--json"],
+    );
+    let one_dir = parse_stdout_json(&out)["data"]["overlay_dir"]
+        .as_str()
+        .expect("overlay_dir")
+        .to_string();
+    std::fs::write(Path::new(&one_dir).join("SKILL.md"), "overlay\n").expect("write override");
+    std::fs::write(
+        repo_dir.join("modules/skills/one/SKILL.md"),
+        "---\nname: one\ndescription: Changed upstream\n---\n",
+    )
+    .expect("update upstream");
+
+    let out = agentpack_in(home, &workspace, &["overlay", "list", "--json"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["ok"], true);
+    let overlays = v["data"]["overlays"].as_array().expect("overlays");
+    assert_eq!(overlays.len(), 2);
+
+    let one = &overlays[0];
+    assert_eq!(one["module_id"], "skill:one");
+    assert_eq!(one["scope"], "global");
+    assert_eq!(one["kind"], "dir");
+    assert_eq!(one["files"], 1);
+    assert_eq!(one["status"], "drifted");
+    assert_eq!(one["baseline"]["upstream"]["kind"], "local_path");
+    assert!(
+        one["drift"][0]
+            .as_str()
+            .unwrap_or_default()
+            .contains("upstream changed for SKILL.md")
+    );
+
+    let two = &overlays[1];
+    assert_eq!(two["module_id"], "skill:two");
+    assert_eq!(two["scope"], "machine");
+    assert_eq!(two["scope_id"], "test-machine");
+    assert_eq!(two["kind"], "patch");
+    assert_eq!(two["patches"], 0);
+    assert_eq!(two["status"], "clean");
+
+    assert_eq!(
+        v["data"]["summary"],
+        serde_json::json!({
+            "total": 2,
+            "clean": 1,
+            "drifted": 1,
+            "no_baseline": 0,
+            "upstream_unavailable": 0,
+        })
+    );
+
+    let human = agentpack_in(home, &workspace, &["overlay", "list"]);
+    assert!(human.status.success());
+    let stdout = String::from_utf8_lossy(&human.stdout);
+    assert!(stdout.contains("skill:one [global] dir (1 files): drifted"));
+    assert!(stdout.contains("skill:two [machine:test-machine] patch (0 patches): clean"));
+
+    // Overlays of other machines and projects are listed too, and an unreadable baseline is a
+    // warning rather than a failure of the whole list.
+    for args in [
+        &[
+            "--machine",
+            "other-box",
+            "overlay",
+            "edit",
+            "skill:three",
+            "--sparse",
+            "--scope",
+            "machine",
+        ][..],
+        &[
+            "overlay",
+            "edit",
+            "skill:three",
+            "--sparse",
+            "--scope",
+            "project",
+        ][..],
+    ] {
+        let out = agentpack_in(home, &workspace, args);
+        assert!(
+            out.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+    std::fs::write(
+        Path::new(&one_dir).join(".agentpack/baseline.json"),
+        "{not json",
+    )
+    .expect("corrupt baseline");
+
+    let out = agentpack_in(home, &workspace, &["overlay", "list", "--json"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let v = parse_stdout_json(&out);
+    let listed: Vec<(String, String)> = v["data"]["overlays"]
+        .as_array()
+        .expect("overlays")
+        .iter()
+        .map(|o| {
+            (
+                o["module_id"].as_str().expect("module_id").to_string(),
+                format!(
+                    "{}:{}",
+                    o["scope"].as_str().expect("scope"),
+                    o["scope_id"].as_str().unwrap_or_default()
+                ),
+            )
+        })
+        .collect();
+    assert_eq!(listed.len(), 3, "{listed:?}");
+    assert_eq!(
+        listed[0],
+        ("skill:two".to_string(), "machine:test-machine".to_string())
+    );
+    assert_eq!(
+        listed[1],
+        ("skill:three".to_string(), "machine:other-box".to_string())
+    );
+    assert_eq!(listed[2].0, "skill:three");
+    assert!(listed[2].1.starts_with("project:"), "{listed:?}");
+    assert!(v["warnings"].as_array().expect("warnings").iter().any(|w| {
+        w.as_str()
+            .is_some_and(|w| w.contains("failed to inspect global overlay"))
+    }));
+}
@@ -0,0 +1,230 @@
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .env("EDITOR", "")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).expect("stdout is valid json")
+}
+
+fn assert_ok(output: &std::process::Output, args: &[&str]) {
+    assert!(
+        output.status.success(),
+        "{args:?}: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+const SKILL: &str = "---\nname: one\ndescription: Example Skill for tests\n---\n\n";
+
+fn setup(home: &Path) -> std::path::PathBuf {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    let dir = repo_dir.join("modules/skills/one");
+    std::fs::create_dir_all(&dir).expect("create skill dir");
+    std::fs::write(dir.join("SKILL.md"), format!("{SKILL}# one\n")).expect("write SKILL.md");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+  work:
+    include_tags: ["base"]
+  personal:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:one
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: "modules/skills/one"
+"#,
+    )
+    .expect("write manifest");
+    workspace
+}
+
+fn overlay_path(home: &Path, workspace: &Path, global: &[&str], scope: &str) -> String {
+    let mut full = global.to_vec();
+    full.extend(["overlay", "path", "skill:one", "--scope", scope, "--json"]);
+    let out = agentpack_in(home, workspace, &full);
+    assert_ok(&out, &full);
+    parse_stdout_json(&out)["data"]["overlay_dir"]
+        .as_str()
+        .expect("overlay_dir")
+        .to_string()
+}
+
+fn explain_layers(home: &Path, workspace: &Path, profile: &str) -> serde_json::Value {
+    let args = ["--profile", profile, "explain", "plan", "--json"];
+    let out = agentpack_in(home, workspace, &args);
+    assert_ok(&out, &args);
+    let v = parse_stdout_json(&out);
+    let changes = v["data"]["changes"].as_array().expect("changes");
+    let one = changes
+        .iter()
+        .find(|c| {
+            c["path_posix"]
+                .as_str()
+                .is_some_and(|p| p.ends_with("skills/one/SKILL.md"))
+        })
+        .expect("change for skill one");
+    one["modules"][0]["layers"].clone()
+}
+
+#[test]
+fn profile_overlay_applies_only_to_its_profile_between_global_and_machine() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+
+    for args in [
+        &["overlay", "edit", "skill:one", "--sparse"][..],
+        &[
+            "--profile",
+            "work",
+            "overlay",
+            "edit",
+            "skill:one",
+            "--sparse",
+            "--scope",
+            "profile",
+        ][..],
+    ] {
+        let out = agentpack_in(home, &workspace, args);
+        assert_ok(&out, args);
+    }
+
+    let global_dir = overlay_path(home, &workspace, &[], "global");
+    let profile_dir = overlay_path(home, &workspace, &["--profile", "work"], "profile");
+    assert!(Path::new(&profile_dir).starts_with(home.join("repo/overlays/profiles/work")));
+    std::fs::write(
+        Path::new(&global_dir).join("SKILL.md"),
+        format!("{SKILL}# global\n"),
+    )
+    .expect("write global override");
+    std::fs::write(
+        Path::new(&profile_dir).join("SKILL.md"),
+        format!("{SKILL}# work\n"),
+    )
+    .expect("write profile override");
+
+    assert_eq!(
+        explain_layers(home, &workspace, "work"),
+        serde_json::json!(["upstream", "global", "profile"])
+    );
+    assert_eq!(
+        explain_layers(home, &workspace, "personal"),
+        serde_json::json!(["upstream", "global"])
+    );
+
+    let deployed = workspace.join(".claude/skills/one/SKILL.md");
+    for (profile, expected) in [("work", "# work\n"), ("personal", "# global\n")] {
+        let args = ["--profile", profile, "deploy", "--apply", "--yes", "--json"];
+        let out = agentpack_in(home, &workspace, &args);
+        assert_ok(&out, &args);
+        assert_eq!(
+            std::fs::read_to_string(&deployed).expect("read deployed"),
+            format!("{SKILL}{expected}")
+        );
+    }
+
+    // Machine overlays still win over the profile layer.
+    let args = [
+        "overlay",
+        "edit",
+        "skill:one",
+        "--sparse",
+        "--scope",
+        "machine",
+    ];
+    assert_ok(&agentpack_in(home, &workspace, &args), &args);
+    let machine_dir = overlay_path(home, &workspace, &[], "machine");
+    std::fs::write(
+        Path::new(&machine_dir).join("SKILL.md"),
+        format!("{SKILL}# machine\n"),
+    )
+    .expect("write machine override");
+    assert_eq!(
+        explain_layers(home, &workspace, "work"),
+        serde_json::json!(["upstream", "global", "profile", "machine"])
+    );
+
+    let args = ["--profile", "work", "overlay", "list", "--json"];
+    let out = agentpack_in(home, &workspace, &args);
+    assert_ok(&out, &args);
+    let scopes: Vec<_> = parse_stdout_json(&out)["data"]["overlays"]
+        .as_array()
+        .expect("overlays")
+        .iter()
+        .map(|o| o["scope"].clone())
+        .collect();
+    assert_eq!(
+        scopes,
+        vec![
+            serde_json::json!("global"),
+            serde_json::json!("profile"),
+            serde_json::json!("machine")
+        ]
+    );
+}
+
+#[test]
+fn profile_scope_requires_a_manifest_profile() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+
+    let out = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--profile",
+            "missing",
+            "overlay",
+            "path",
+            "skill:one",
+            "--scope",
+            "profile",
+            "--json",
+        ],
+    );
+    assert!(!out.status.success());
+    let v = parse_stdout_json(&out);
+    assert_eq!(v["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(v["errors"][0]["details"]["reason_code"], "profile_unknown");
+    assert_eq!(
+        v["errors"][0]["details"]["profiles"],
+        serde_json::json!(["default", "personal", "work"])
+    );
+}
@@ -118,5 +118,25 @@ fn overlay_rebase_conflicts_return_stable_json_error_code() -> anyhow::Result<()
         serde_json::json!(["resolve_overlay_conflicts", "retry_overlay_rebase"])
     );
 
+    // The conflict is reported structurally, matching the markers written to the overlay file.
+    assert_eq!(
+        v["errors"][0]["details"]["conflict_hunks"],
+        serde_json::json!([{
+            "path": "SKILL.md",
+            "conflict_file": "SKILL.md",
+            "base": {"start": 2, "lines": 1},
+            "ours": {"start": 2, "lines": 1},
+            "theirs": {"start": 2, "lines": 1},
+            "merged": {"start": 2, "lines": 5},
+            "base_text": "line2\n",
+            "ours_text": "line2-ours\n",
+            "theirs_text": "line2-theirs\n",
+        }])
+    );
+    assert_eq!(
+        std::fs::read_to_string(overlay_dir.join("SKILL.md"))?,
+        "line1\n<<<<<<< ours\nline2-ours\n=======\nline2-theirs\n>>>>>>> theirs\nline3\n"
+    );
+
     Ok(())
 }
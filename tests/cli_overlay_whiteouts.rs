@@ -0,0 +1,190 @@
+use std::path::Path;
+use std::process::Command;
+
+use agentpack::ids::module_fs_key;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("EDITOR", "")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).expect("stdout is valid json")
+}
+
+fn git(cwd: &Path, args: &[&str]) {
+    let out = Command::new("git")
+        .current_dir(cwd)
+        .args(args)
+        .output()
+        .expect("run git");
+    assert!(
+        out.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+fn commit_all(repo_dir: &Path, message: &str) {
+    git(repo_dir, &["add", "-A"]);
+    git(repo_dir, &["commit", "-m", message]);
+}
+
+#[test]
+fn overlay_whiteouts_remove_upstream_files_and_rebase_warns() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    git(&workspace, &["init"]);
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    git(&repo_dir, &["init"]);
+    git(&repo_dir, &["config", "user.email", "test@example.com"]);
+    git(&repo_dir, &["config", "user.name", "Test"]);
+
+    let skill_dir = repo_dir.join("modules/skills/my-skill");
+    std::fs::create_dir_all(skill_dir.join("scripts")).expect("create skill dir");
+    std::fs::create_dir_all(skill_dir.join("examples")).expect("create examples dir");
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# my-skill\n",
+    )
+    .expect("write SKILL.md");
+    std::fs::write(skill_dir.join("scripts/example.sh"), "echo v1\n").expect("write script");
+    std::fs::write(skill_dir.join("examples/a.md"), "a\n").expect("write example");
+
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:my-skill
+    type: skill
+    tags: ["base"]
+    targets: ["claude_code"]
+    source:
+      local_path:
+        path: "modules/skills/my-skill"
+"#,
+    )
+    .expect("write manifest");
+    commit_all(&repo_dir, "init");
+
+    let edit = agentpack_in(
+        home,
+        &workspace,
+        &["overlay", "edit", "skill:my-skill", "--sparse"],
+    );
+    assert!(edit.status.success());
+
+    let overlay_dir = repo_dir
+        .join("overlays")
+        .join(module_fs_key("skill:my-skill"));
+    std::fs::write(
+        overlay_dir.join(".agentpack/deletes"),
+        "# not wanted here\nscripts/example.sh\nexamples/\n",
+    )
+    .expect("write deletes");
+    commit_all(&repo_dir, "whiteouts");
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "--target",
+            "claude_code",
+            "deploy",
+            "--apply",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(deploy.status.success(), "deploy should succeed");
+
+    let deployed = workspace.join(".claude/skills/my-skill");
+    assert!(deployed.join("SKILL.md").is_file());
+    assert!(!deployed.join("scripts/example.sh").exists());
+    assert!(!deployed.join("examples").exists());
+
+    // Upstream changes one whited-out file and drops the other.
+    std::fs::write(skill_dir.join("scripts/example.sh"), "echo v2\n").expect("update script");
+    std::fs::remove_dir_all(skill_dir.join("examples")).expect("remove examples");
+    commit_all(&repo_dir, "upstream changes");
+
+    let rebase = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "overlay",
+            "rebase",
+            "skill:my-skill",
+            "--scope",
+            "global",
+            "--json",
+            "--yes",
+        ],
+    );
+    assert!(rebase.status.success(), "rebase should succeed");
+    let v = parse_stdout_json(&rebase);
+    assert_eq!(v["ok"], true);
+    let warnings: Vec<&str> = v["warnings"]
+        .as_array()
+        .expect("warnings array")
+        .iter()
+        .filter_map(|w| w.as_str())
+        .collect();
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.contains("upstream changed scripts/example.sh")),
+        "missing changed warning: {warnings:?}"
+    );
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.contains("examples no longer exists upstream")),
+        "missing removed warning: {warnings:?}"
+    );
+
+    // Invalid entries are configuration errors.
+    std::fs::write(overlay_dir.join(".agentpack/deletes"), "../outside\n").expect("write deletes");
+    let plan = agentpack_in(
+        home,
+        &workspace,
+        &["--target", "claude_code", "plan", "--json"],
+    );
+    assert!(!plan.status.success());
+    let v = parse_stdout_json(&plan);
+    assert_eq!(v["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "overlay_whiteout_invalid"
+    );
+}
@@ -206,3 +206,102 @@ modules:
         serde_json::json!(["regenerate_patch", "switch_to_dir_overlay", "retry_command"])
     );
 }
+
+#[test]
+fn patch_overlays_capture_and_apply_without_git() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    let empty_path = home.join("empty-path");
+    std::fs::create_dir_all(&empty_path).expect("create empty PATH dir");
+
+    let run = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_agentpack"))
+            .current_dir(&workspace)
+            .args(args)
+            .env("AGENTPACK_HOME", home)
+            .env("HOME", home)
+            .env("EDITOR", "")
+            .env("AGENTPACK_MACHINE_ID", "test-machine")
+            .env("PATH", &empty_path)
+            .output()
+            .expect("run agentpack");
+        assert!(
+            out.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&out.stdout)
+        );
+        parse_stdout_json(&out)
+    };
+    // `init` may initialize the config repo with git; everything after it runs without git.
+    assert!(agentpack_in(home, &workspace, &["init"]).status.success());
+
+    let repo_dir = home.join("repo");
+    let skill_dir = repo_dir.join("modules/skills/my-skill");
+    std::fs::create_dir_all(&skill_dir).expect("create skill dir");
+    let body: String = (1..=10).map(|i| format!("step {i}\n")).collect();
+    let upstream = format!(
+        "---\nname: my-skill\ndescription: Example Skill for tests\n---\n\n# my-skill\n\n{body}"
+    );
+    std::fs::write(skill_dir.join("SKILL.md"), &upstream).expect("write SKILL.md");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  claude_code:
+    mode: files
+    scope: project
+    options:
+      write_repo_commands: false
+      write_user_commands: false
+      write_repo_skills: true
+      write_user_skills: false
+
+modules:
+  - id: skill:my-skill
+    type: skill
+    tags: ["base"]
+    targets: ["claude_code"]
+    source:
+      local_path:
+        path: "modules/skills/my-skill"
+"#,
+    )
+    .expect("write manifest");
+
+    let edited_file = home.join("SKILL.md");
+    std::fs::write(&edited_file, upstream.replace("step 9", "patched step 9"))
+        .expect("write edited file");
+    let edited = edited_file.to_string_lossy().to_string();
+    run(&[
+        "overlay",
+        "capture",
+        "--module",
+        "skill:my-skill",
+        "--from",
+        &edited,
+        "--yes",
+        "--json",
+    ]);
+    run(&["deploy", "--apply", "--yes", "--json"]);
+    let deployed_path = workspace.join(".claude/skills/my-skill/SKILL.md");
+    assert_eq!(
+        std::fs::read_to_string(&deployed_path).expect("read deployed"),
+        upstream.replace("step 9", "patched step 9")
+    );
+
+    // The hunk still applies when upstream grows above it.
+    let upstream_v2 = upstream.replace("# my-skill\n", "# my-skill\n\nIntro.\n");
+    std::fs::write(skill_dir.join("SKILL.md"), &upstream_v2).expect("update upstream");
+    run(&["deploy", "--apply", "--yes", "--json"]);
+    assert_eq!(
+        std::fs::read_to_string(&deployed_path).expect("read deployed"),
+        upstream_v2.replace("step 9", "patched step 9")
+    );
+}
@@ -0,0 +1,217 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn write_skill(repo_dir: &Path, name: &str, body: &str) {
+    let dir = repo_dir.join("modules/skills").join(name);
+    std::fs::create_dir_all(&dir).expect("create skill dir");
+    std::fs::write(
+        dir.join("SKILL.md"),
+        format!("---\nname: {name}\ndescription: {name} skill\n---\n\n{body}\n"),
+    )
+    .expect("write SKILL.md");
+}
+
+fn json_ok(home: &Path, cwd: &Path, args: &[&str]) -> serde_json::Value {
+    let output = agentpack_in(home, cwd, args);
+    assert!(output.status.success(), "{args:?}: {output:?}");
+    parse_stdout_json(&output)
+}
+
+#[test]
+fn rollback_dry_run_filters_and_local_edit_guard() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let instructions = repo_dir.join("modules/instructions/base/AGENTS.md");
+    std::fs::create_dir_all(instructions.parent().expect("parent")).expect("create module dir");
+    std::fs::write(&instructions, "# Rules v1\n").expect("write AGENTS.md");
+    write_skill(&repo_dir, "fixer", "v1");
+    write_skill(&repo_dir, "helper", "v1");
+    let manifest_yaml = r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+  - id: skill:fixer
+    type: skill
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/skills/fixer
+"#;
+    std::fs::write(repo_dir.join("agentpack.yaml"), manifest_yaml).expect("write manifest");
+    let first = json_ok(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    let first = first["data"]["snapshot_id"]
+        .as_str()
+        .expect("snapshot_id")
+        .to_string();
+
+    std::fs::write(&instructions, "# Rules v2\n").expect("edit AGENTS.md");
+    write_skill(&repo_dir, "fixer", "v2");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!(
+            "{manifest_yaml}  - id: skill:helper\n    type: skill\n    tags: [\"base\"]\n    source:\n      local_path:\n        path: modules/skills/helper\n"
+        ),
+    )
+    .expect("add module");
+    json_ok(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+
+    let agents = workspace.join("AGENTS.md");
+    let fixer = workspace.join(".codex/skills/fixer/SKILL.md");
+    let helper = workspace.join(".codex/skills/helper/SKILL.md");
+
+    // Dry run: preview-shaped plan and diff, nothing written, no --yes needed.
+    let dry = json_ok(
+        home,
+        &workspace,
+        &["rollback", "--to", &first, "--dry-run", "--json"],
+    );
+    assert_eq!(dry["command"], "rollback");
+    assert_eq!(dry["data"]["snapshot_id"], first.as_str());
+    assert_eq!(dry["data"]["partial"], false);
+    assert_eq!(dry["data"]["plan"]["summary"]["update"], 2);
+    assert_eq!(dry["data"]["plan"]["summary"]["delete"], 1);
+    assert_eq!(dry["data"]["local_edits"], serde_json::json!([]));
+    let agents_diff = dry["data"]["diff"]["files"]
+        .as_array()
+        .expect("diff files")
+        .iter()
+        .find(|f| f["path"] == "AGENTS.md")
+        .expect("AGENTS.md diff")
+        .clone();
+    let unified = agents_diff["unified"].as_str().expect("unified diff");
+    assert!(unified.contains("-# Rules v2"), "{unified}");
+    assert!(unified.contains("+# Rules v1"), "{unified}");
+    assert_eq!(
+        std::fs::read_to_string(&agents).expect("read AGENTS.md"),
+        "# Rules v2\n"
+    );
+
+    // Only one module is rolled back; its manifest entry follows the restored content.
+    let partial = json_ok(
+        home,
+        &workspace,
+        &[
+            "rollback",
+            "--to",
+            &first,
+            "--module",
+            "skill:fixer",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert_eq!(partial["data"]["rolled_back_to"], first.as_str());
+    assert!(
+        std::fs::read_to_string(&fixer)
+            .expect("read fixer")
+            .contains("v1")
+    );
+    assert!(helper.exists());
+    assert_eq!(
+        std::fs::read_to_string(&agents).expect("read AGENTS.md"),
+        "# Rules v2\n"
+    );
+    let plan = json_ok(home, &workspace, &["plan", "--json"]);
+    let changes = plan["data"]["changes"].as_array().expect("changes");
+    assert_eq!(changes.len(), 1, "{changes:?}");
+    assert_eq!(changes[0]["update_kind"], "managed_update");
+    assert!(changes[0]["merge"].is_null());
+
+    // A hand edit after the deploy is not discarded silently.
+    std::fs::write(&agents, "# Rules v2\n\nlocal note\n").expect("hand edit");
+    let refused = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "rollback",
+            "--to",
+            &first,
+            "--path",
+            "AGENTS.md",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(!refused.status.success(), "{refused:?}");
+    let err = &parse_stdout_json(&refused)["errors"][0];
+    assert_eq!(err["code"], "E_ROLLBACK_LOCAL_EDITS");
+    assert_eq!(err["details"]["reason_code"], "rollback_local_edits");
+    assert_eq!(
+        std::fs::read_to_string(&agents).expect("read AGENTS.md"),
+        "# Rules v2\n\nlocal note\n"
+    );
+
+    json_ok(
+        home,
+        &workspace,
+        &[
+            "rollback",
+            "--to",
+            &first,
+            "--path",
+            "AGENTS.md",
+            "--discard-local-edits",
+            "--yes",
+            "--json",
+        ],
+    );
+    assert_eq!(
+        std::fs::read_to_string(&agents).expect("read AGENTS.md"),
+        "# Rules v1\n"
+    );
+    assert!(helper.exists());
+}
@@ -0,0 +1,222 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn setup(home: &Path) -> std::path::PathBuf {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let module_dir = repo_dir.join("modules/instructions/base");
+    std::fs::create_dir_all(&module_dir).expect("create module dir");
+    std::fs::write(module_dir.join("AGENTS.md"), "# Shared rules\n").expect("write AGENTS.md");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+"#,
+    )
+    .expect("write manifest");
+
+    workspace
+}
+
+#[test]
+fn saved_plan_applies_what_was_reviewed_even_if_the_repo_changed() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+    let plan_file = home.join("reviewed.plan.json");
+
+    let plan = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "plan",
+            "--out",
+            plan_file.to_str().expect("utf8 path"),
+            "--json",
+        ],
+    );
+    assert!(plan.status.success(), "{plan:?}");
+    let plan = parse_stdout_json(&plan);
+    assert_eq!(plan["data"]["summary"]["create"], 1);
+    let plan_hash = plan["data"]["saved_plan"]["plan_hash"]
+        .as_str()
+        .expect("plan_hash")
+        .to_string();
+    assert!(plan_file.exists());
+
+    // The config repo moves on after review; the saved plan still applies the reviewed bytes.
+    std::fs::write(
+        home.join("repo/modules/instructions/base/AGENTS.md"),
+        "# Edited after review\n",
+    )
+    .expect("edit module");
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "deploy",
+            "--apply",
+            "--plan",
+            plan_file.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    assert_eq!(deploy["data"]["applied"], true);
+    assert_eq!(deploy["data"]["saved_plan"]["plan_hash"], plan_hash);
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("AGENTS.md")).expect("read AGENTS.md"),
+        "# Shared rules\n"
+    );
+
+    // Re-applying the same plan is refused: AGENTS.md now exists, but the plan expected none.
+    let again = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "deploy",
+            "--apply",
+            "--plan",
+            plan_file.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(!again.status.success(), "{again:?}");
+    let again = parse_stdout_json(&again);
+    let err = &again["errors"][0];
+    assert_eq!(err["code"], "E_PLAN_STALE");
+    assert_eq!(err["details"]["reason_code"], "plan_stale");
+    assert!(err["details"]["stale"][0]["expected_sha256"].is_null());
+    assert!(err["details"]["stale"][0]["actual_sha256"].is_string());
+}
+
+#[test]
+fn saved_plan_is_refused_when_a_target_file_changed_or_the_file_was_edited() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+
+    let deploy = agentpack_in(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy.status.success(), "{deploy:?}");
+
+    std::fs::write(
+        home.join("repo/modules/instructions/base/AGENTS.md"),
+        "# Shared rules v2\n",
+    )
+    .expect("edit module");
+    let plan_file = home.join("update.plan.json");
+    let plan = agentpack_in(
+        home,
+        &workspace,
+        &["plan", "--out", plan_file.to_str().expect("utf8 path")],
+    );
+    assert!(plan.status.success(), "{plan:?}");
+
+    // Someone edits the deployed file between review and apply.
+    std::fs::write(workspace.join("AGENTS.md"), "# Local edit\n").expect("edit deployed");
+    let stale = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "deploy",
+            "--apply",
+            "--plan",
+            plan_file.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(!stale.status.success(), "{stale:?}");
+    assert_eq!(
+        parse_stdout_json(&stale)["errors"][0]["code"],
+        "E_PLAN_STALE"
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("AGENTS.md")).expect("read AGENTS.md"),
+        "# Local edit\n"
+    );
+
+    // A plan file edited after it was saved is rejected before anything is checked on disk.
+    let raw = std::fs::read_to_string(&plan_file).expect("read plan file");
+    std::fs::write(&plan_file, raw.replace("Shared rules v2", "Tampered rules"))
+        .expect("tamper plan file");
+    let tampered = agentpack_in(
+        home,
+        &workspace,
+        &[
+            "deploy",
+            "--apply",
+            "--plan",
+            plan_file.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert!(!tampered.status.success(), "{tampered:?}");
+    let tampered = parse_stdout_json(&tampered);
+    assert_eq!(tampered["errors"][0]["code"], "E_PLAN_INVALID");
+    assert_eq!(
+        tampered["errors"][0]["details"]["reason_code"],
+        "plan_hash_mismatch"
+    );
+}
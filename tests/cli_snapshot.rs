@@ -0,0 +1,264 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn json_ok(home: &Path, cwd: &Path, args: &[&str]) -> serde_json::Value {
+    let output = agentpack_in(home, cwd, args);
+    assert!(output.status.success(), "{args:?}: {output:?}");
+    parse_stdout_json(&output)
+}
+
+fn json_err(home: &Path, cwd: &Path, args: &[&str]) -> serde_json::Value {
+    let output = agentpack_in(home, cwd, args);
+    assert!(!output.status.success(), "{args:?}: {output:?}");
+    parse_stdout_json(&output)["errors"][0].clone()
+}
+
+/// Deploys two versions of AGENTS.md and returns (workspace, [first, second] snapshot ids).
+fn setup_two_deploys(home: &Path) -> (std::path::PathBuf, [String; 2]) {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let module_file = repo_dir.join("modules/instructions/base/AGENTS.md");
+    std::fs::create_dir_all(module_file.parent().expect("parent")).expect("create module dir");
+    std::fs::write(&module_file, "# Rules v1\n").expect("write AGENTS.md");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+"#,
+    )
+    .expect("write manifest");
+
+    let first = json_ok(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    std::fs::write(&module_file, "# Rules v2\n").expect("edit AGENTS.md");
+    let second = json_ok(home, &workspace, &["deploy", "--apply", "--yes", "--json"]);
+    let id = |v: &serde_json::Value| {
+        v["data"]["snapshot_id"]
+            .as_str()
+            .expect("snapshot_id")
+            .to_string()
+    };
+    (workspace, [id(&first), id(&second)])
+}
+
+#[test]
+fn snapshot_list_show_diff_and_tagged_rollback() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let (workspace, [first, second]) = setup_two_deploys(home);
+
+    let list = json_ok(home, &workspace, &["snapshot", "list", "--json"]);
+    assert_eq!(list["command"], "snapshot.list");
+    let snapshots = list["data"]["snapshots"].as_array().expect("snapshots");
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(snapshots[0]["id"], first.as_str());
+    assert_eq!(snapshots[1]["kind"], "deploy");
+    assert_eq!(snapshots[1]["targets"], serde_json::json!(["codex"]));
+    assert!(snapshots[1]["change_counts"]["by_op"]["update"].as_u64() >= Some(1));
+    assert!(snapshots[1].get("lockfile_sha256").is_some());
+
+    let show = json_ok(home, &workspace, &["snapshot", "show", &second, "--json"]);
+    let agents = workspace.join("AGENTS.md");
+    let change = show["data"]["changes"]
+        .as_array()
+        .expect("changes")
+        .iter()
+        .find(|c| c["path"] == agents.to_string_lossy().as_ref())
+        .expect("AGENTS.md change")
+        .clone();
+    assert_eq!(change["op"], "update");
+    assert!(change["backup_path"].is_string());
+
+    let diff = json_ok(
+        home,
+        &workspace,
+        &["snapshot", "diff", &first, &second, "--json"],
+    );
+    assert_eq!(diff["data"]["summary"]["modified"], 1);
+    let file = &diff["data"]["files"][0];
+    assert_eq!(file["status"], "modified");
+    let text = file["diff"].as_str().expect("diff text");
+    assert!(text.contains("-# Rules v1"), "{text}");
+    assert!(text.contains("+# Rules v2"), "{text}");
+
+    let tag = json_ok(
+        home,
+        &workspace,
+        &["snapshot", "tag", &first, "known-good", "--yes", "--json"],
+    );
+    assert_eq!(tag["data"]["snapshot_id"], first.as_str());
+    let tag_requires_yes = json_err(
+        home,
+        &workspace,
+        &["snapshot", "tag", &first, "other", "--json"],
+    );
+    assert_eq!(tag_requires_yes["code"], "E_CONFIRM_REQUIRED");
+    let bad_tag = json_err(
+        home,
+        &workspace,
+        &["snapshot", "tag", &first, "12345", "--yes", "--json"],
+    );
+    assert_eq!(bad_tag["code"], "E_SNAPSHOT_TAG_INVALID");
+
+    let rollback = json_ok(
+        home,
+        &workspace,
+        &["rollback", "--to", "known-good", "--yes", "--json"],
+    );
+    assert_eq!(rollback["data"]["rolled_back_to"], first.as_str());
+    assert_eq!(
+        std::fs::read_to_string(&agents).expect("read AGENTS.md"),
+        "# Rules v1\n"
+    );
+
+    let missing = json_err(
+        home,
+        &workspace,
+        &["rollback", "--to", "no-such-tag", "--yes", "--json"],
+    );
+    assert_eq!(missing["code"], "E_SNAPSHOT_NOT_FOUND");
+    assert_eq!(missing["details"]["reason_code"], "snapshot_not_found");
+}
+
+#[test]
+fn snapshot_export_import_round_trips_into_another_home() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path().join("a");
+    std::fs::create_dir_all(&home).expect("create home");
+    let (workspace, [_, second]) = setup_two_deploys(&home);
+
+    json_ok(
+        &home,
+        &workspace,
+        &["snapshot", "tag", &second, "release", "--yes", "--json"],
+    );
+    let archive = tmp.path().join("release.snapshot.json");
+    let export = json_ok(
+        &home,
+        &workspace,
+        &[
+            "snapshot",
+            "export",
+            "release",
+            "--out",
+            archive.to_str().expect("utf8 path"),
+            "--json",
+        ],
+    );
+    assert_eq!(export["data"]["snapshot_id"], second.as_str());
+    assert_eq!(export["data"]["tags"], serde_json::json!(["release"]));
+
+    let other = tmp.path().join("b");
+    std::fs::create_dir_all(&other).expect("create other home");
+    let import = json_ok(
+        &other,
+        &workspace,
+        &[
+            "snapshot",
+            "import",
+            archive.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert_eq!(import["data"]["imported"], true);
+    assert_eq!(import["data"]["tags_added"], serde_json::json!(["release"]));
+
+    let show = json_ok(
+        &other,
+        &workspace,
+        &["snapshot", "show", "release", "--json"],
+    );
+    assert_eq!(show["data"]["id"], second.as_str());
+    let backup = show["data"]["changes"]
+        .as_array()
+        .expect("changes")
+        .iter()
+        .find_map(|c| c["backup_path"].as_str())
+        .expect("a backup path");
+    assert!(Path::new(backup).starts_with(&other), "{backup}");
+    assert!(Path::new(backup).exists(), "{backup}");
+
+    let again = json_ok(
+        &other,
+        &workspace,
+        &[
+            "snapshot",
+            "import",
+            archive.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert_eq!(again["data"]["imported"], false);
+
+    let raw = std::fs::read_to_string(&archive).expect("read archive");
+    std::fs::write(&archive, raw.replace("Rules v2", "Rules v9")).expect("tamper archive");
+    let tampered = json_err(
+        &other,
+        &workspace,
+        &[
+            "snapshot",
+            "import",
+            archive.to_str().expect("utf8 path"),
+            "--yes",
+            "--json",
+        ],
+    );
+    assert_eq!(tampered["code"], "E_SNAPSHOT_ARCHIVE_INVALID");
+    assert_eq!(tampered["details"]["reason_code"], "archive_hash_mismatch");
+}
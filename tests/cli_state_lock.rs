@@ -0,0 +1,222 @@
+#![cfg(feature = "target-codex")]
+
+use std::path::Path;
+use std::process::Command;
+
+use agentpack::paths::AgentpackHome;
+use agentpack::state_lock::StateLock;
+
+fn agentpack_in(
+    home: &Path,
+    cwd: &Path,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .envs(envs.iter().copied())
+        .output()
+        .expect("run agentpack")
+}
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "stdout is not json: {err}\nstdout={}\nstderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn agentpack_home(root: &Path) -> AgentpackHome {
+    let state_dir = root.join("state");
+    AgentpackHome {
+        root: root.to_path_buf(),
+        repo_dir: root.join("repo"),
+        cache_dir: root.join("cache"),
+        snapshots_dir: state_dir.join("snapshots"),
+        logs_dir: state_dir.join("logs"),
+        state_dir,
+    }
+}
+
+fn setup(home: &Path) -> std::path::PathBuf {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"], &[]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let module_dir = repo_dir.join("modules/instructions/base");
+    std::fs::create_dir_all(&module_dir).expect("create module dir");
+    std::fs::write(module_dir.join("AGENTS.md"), "# Shared rules\n").expect("write AGENTS.md");
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+    options:
+      write_repo_skills: false
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+"#,
+    )
+    .expect("write manifest");
+
+    workspace
+}
+
+#[test]
+fn concurrent_mutation_fails_with_e_locked_naming_the_holder() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+
+    let lock = StateLock::acquire(&agentpack_home(home), "test holder").expect("acquire lock");
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &["deploy", "--apply", "--yes", "--json"],
+        &[],
+    );
+    assert!(!deploy.status.success(), "{deploy:?}");
+    let deploy = parse_stdout_json(&deploy);
+    let err = &deploy["errors"][0];
+    assert_eq!(err["code"], "E_LOCKED");
+    assert_eq!(err["details"]["holder_pid"], std::process::id());
+    assert_eq!(err["details"]["holder_command"], "test holder");
+    assert_eq!(err["details"]["reason_code"], "state_locked");
+    assert!(!workspace.join("AGENTS.md").exists());
+
+    let rollback = agentpack_in(
+        home,
+        &workspace,
+        &["rollback", "--to", "1", "--yes", "--json"],
+        &[],
+    );
+    assert!(!rollback.status.success(), "{rollback:?}");
+    assert_eq!(
+        parse_stdout_json(&rollback)["errors"][0]["code"],
+        "E_LOCKED"
+    );
+
+    // Read-only commands are not blocked, and report the holder.
+    let doctor = agentpack_in(home, &workspace, &["doctor", "--json"], &[]);
+    assert!(doctor.status.success(), "{doctor:?}");
+    assert!(
+        parse_stdout_json(&doctor)["warnings"]
+            .as_array()
+            .expect("warnings")
+            .iter()
+            .any(|w| w
+                .as_str()
+                .is_some_and(|w| w.contains("(pid ") && w.contains("test holder")))
+    );
+
+    drop(lock);
+    assert!(!home.join("state/state.lock.json").exists());
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &["deploy", "--apply", "--yes", "--json"],
+        &[],
+    );
+    assert!(deploy.status.success(), "{deploy:?}");
+    assert!(workspace.join("AGENTS.md").exists());
+}
+
+#[test]
+fn lock_timeout_waits_for_the_holder_to_finish() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+
+    let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+    let holder_home = agentpack_home(home);
+    let releaser = std::thread::spawn(move || {
+        let _lock = StateLock::acquire(&holder_home, "test holder").expect("acquire lock");
+        locked_tx.send(()).expect("signal locked");
+        std::thread::sleep(std::time::Duration::from_millis(300));
+    });
+    locked_rx.recv().expect("wait for lock");
+
+    let deploy = agentpack_in(
+        home,
+        &workspace,
+        &["deploy", "--apply", "--yes", "--json"],
+        &[("AGENTPACK_LOCK_TIMEOUT", "30")],
+    );
+    releaser.join().expect("join releaser");
+    assert!(deploy.status.success(), "{deploy:?}");
+    assert_eq!(parse_stdout_json(&deploy)["data"]["applied"], true);
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("AGENTS.md")).expect("read AGENTS.md"),
+        "# Shared rules\n"
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn doctor_ignores_a_holder_file_left_by_a_dead_process() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+
+    let mut child = Command::new("true").spawn().expect("spawn true");
+    let dead_pid = child.id();
+    child.wait().expect("wait for true");
+    std::fs::create_dir_all(home.join("state")).expect("create state dir");
+    std::fs::write(
+        home.join("state/state.lock.json"),
+        serde_json::json!({
+            "pid": dead_pid,
+            "command": "deploy --apply",
+            "acquired_at": "2026-01-01T00:00:00Z",
+        })
+        .to_string(),
+    )
+    .expect("write stale holder");
+
+    let doctor = agentpack_in(home, &workspace, &["doctor", "--json"], &[]);
+    assert!(doctor.status.success(), "{doctor:?}");
+    assert!(
+        !parse_stdout_json(&doctor)["warnings"]
+            .as_array()
+            .expect("warnings")
+            .iter()
+            .any(|w| w
+                .as_str()
+                .is_some_and(|w| w.contains("another agentpack process")))
+    );
+}
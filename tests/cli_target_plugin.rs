@@ -0,0 +1,183 @@
+#![cfg(unix)]
+
+mod conformance_harness;
+
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::Path;
+
+use conformance_harness::ConformanceHarness;
+
+fn parse_stdout_json(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).expect("stdout is valid json")
+}
+
+fn write_plugin(repo_dir: &Path, script: &str) {
+    let dir = repo_dir.join("plugins");
+    std::fs::create_dir_all(&dir).expect("create plugins dir");
+    let path = dir.join("acme.sh");
+    std::fs::write(&path, script).expect("write plugin");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).expect("chmod plugin");
+}
+
+fn write_manifest(repo_dir: &Path, timeout_ms: u64) {
+    let manifest = format!(
+        r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+plugin_targets:
+  acme:
+    command: ["./plugins/acme.sh"]
+    scope: project
+    timeout_ms: {timeout_ms}
+    options:
+      flavor: "spicy"
+
+modules:
+  - id: instructions:base
+    type: instructions
+    source:
+      local_path:
+        path: modules/instructions/base
+    enabled: true
+    tags: ["base"]
+"#
+    );
+    std::fs::write(repo_dir.join("agentpack.yaml"), manifest).expect("write manifest");
+    let module_dir = repo_dir.join("modules/instructions/base");
+    std::fs::create_dir_all(&module_dir).expect("create module dir");
+    std::fs::write(module_dir.join("AGENTS.md"), "# Base\n").expect("write module");
+}
+
+fn setup(timeout_ms: u64, script: &str) -> ConformanceHarness {
+    let harness = ConformanceHarness::new();
+    let init = harness.agentpack(&["init"]);
+    assert!(init.status.success());
+    let repo_dir = harness.home().join("repo");
+    write_manifest(&repo_dir, timeout_ms);
+    write_plugin(&repo_dir, script);
+    harness
+}
+
+#[test]
+fn plugin_target_deploys_and_rolls_back() {
+    let harness = ConformanceHarness::new();
+    let init = harness.agentpack(&["init"]);
+    assert!(init.status.success());
+    let repo_dir = harness.home().join("repo");
+    write_manifest(&repo_dir, 10_000);
+
+    let workspace = harness.workspace().canonicalize().expect("canonicalize");
+    let out_root = workspace.join(".acme");
+    let request_copy = harness.home().join("last_request.json");
+    let script = |body: &str| {
+        format!(
+            "#!/bin/sh\ncat > '{req}'\ncat <<'JSON'\n{{\"protocol_version\":1,\"roots\":[{{\"root\":\"{root}\",\"scan_extras\":true}}],\"files\":[{{\"path\":\"{root}/rules.md\",\"module_ids\":[\"instructions:base\"],\"content\":\"{body}\"}}],\"warnings\":[\"hello from plugin\"]}}\nJSON\n",
+            req = request_copy.display(),
+            root = out_root.display(),
+        )
+    };
+    write_plugin(&repo_dir, &script("v1\\n"));
+
+    let deploy1 = harness.agentpack(&["--target", "acme", "deploy", "--apply", "--yes", "--json"]);
+    assert!(
+        deploy1.status.success(),
+        "stdout={}\nstderr={}",
+        String::from_utf8_lossy(&deploy1.stdout),
+        String::from_utf8_lossy(&deploy1.stderr)
+    );
+    let v = parse_stdout_json(&deploy1);
+    let snapshot1 = v["data"]["snapshot_id"]
+        .as_str()
+        .expect("snapshot_id")
+        .to_string();
+    assert!(
+        v["warnings"]
+            .as_array()
+            .expect("warnings")
+            .iter()
+            .any(|w| w.as_str().is_some_and(|s| s.contains("hello from plugin")))
+    );
+    assert_eq!(
+        std::fs::read_to_string(out_root.join("rules.md")).expect("read output"),
+        "v1\n"
+    );
+    assert!(out_root.join(".agentpack.manifest.acme.json").exists());
+
+    let request: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&request_copy).expect("read request"))
+            .expect("request json");
+    assert_eq!(request["protocol"], "agentpack.target_plugin");
+    assert_eq!(request["protocol_version"], 1);
+    assert_eq!(request["target"], "acme");
+    assert_eq!(request["options"]["flavor"], "spicy");
+    assert_eq!(request["modules"][0]["id"], "instructions:base");
+    assert_eq!(request["modules"][0]["type"], "instructions");
+
+    write_plugin(&repo_dir, &script("v2\\n"));
+    let deploy2 = harness.agentpack(&["--target", "acme", "deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy2.status.success());
+    assert_eq!(
+        std::fs::read_to_string(out_root.join("rules.md")).expect("read output"),
+        "v2\n"
+    );
+
+    let rollback = harness.agentpack(&[
+        "--target",
+        "acme",
+        "rollback",
+        "--to",
+        snapshot1.as_str(),
+        "--yes",
+        "--json",
+    ]);
+    assert!(rollback.status.success());
+    assert_eq!(
+        std::fs::read_to_string(out_root.join("rules.md")).expect("read output"),
+        "v1\n"
+    );
+}
+
+#[test]
+fn plugin_target_timeout_is_reported() {
+    let harness = setup(200, "#!/bin/sh\nexec sleep 5\n");
+    let plan = harness.agentpack(&["--target", "acme", "plan", "--json"]);
+    assert!(!plan.status.success());
+    let v = parse_stdout_json(&plan);
+    assert_eq!(v["errors"][0]["code"], "E_TARGET_PLUGIN_FAILED");
+    assert_eq!(v["errors"][0]["details"]["reason_code"], "plugin_timeout");
+}
+
+#[test]
+fn plugin_target_nonzero_exit_is_reported() {
+    let harness = setup(10_000, "#!/bin/sh\necho boom >&2\nexit 3\n");
+    let plan = harness.agentpack(&["--target", "acme", "plan", "--json"]);
+    assert!(!plan.status.success());
+    let v = parse_stdout_json(&plan);
+    assert_eq!(v["errors"][0]["code"], "E_TARGET_PLUGIN_FAILED");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "plugin_exit_nonzero"
+    );
+    assert_eq!(v["errors"][0]["details"]["exit_code"], 3);
+    assert_eq!(v["errors"][0]["details"]["stderr"], "boom");
+}
+
+#[test]
+fn plugin_target_rejects_invalid_output() {
+    let harness = setup(
+        10_000,
+        "#!/bin/sh\ncat >/dev/null\necho '{\"protocol_version\":1,\"roots\":[],\"unexpected\":1}'\n",
+    );
+    let plan = harness.agentpack(&["--target", "acme", "plan", "--json"]);
+    assert!(!plan.status.success());
+    let v = parse_stdout_json(&plan);
+    assert_eq!(v["errors"][0]["code"], "E_TARGET_PLUGIN_INVALID_OUTPUT");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "plugin_output_invalid"
+    );
+}
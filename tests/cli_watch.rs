@@ -0,0 +1,234 @@
+#![cfg(feature = "target-codex")]
+
+use std::io::BufRead as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+fn agentpack_in(home: &Path, cwd: &Path, args: &[&str]) -> std::process::Output {
+    let bin = env!("CARGO_BIN_EXE_agentpack");
+    Command::new(bin)
+        .current_dir(cwd)
+        .args(args)
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .output()
+        .expect("run agentpack")
+}
+
+/// Kills the watcher even when an assertion fails.
+struct Watcher(std::process::Child);
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn wait_for(
+    rx: &mpsc::Receiver<serde_json::Value>,
+    what: &str,
+    pred: impl Fn(&serde_json::Value) -> bool,
+) -> serde_json::Value {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut seen = Vec::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(left) {
+            Ok(event) if pred(&event) => return event,
+            Ok(event) => seen.push(event),
+            Err(_) => break,
+        }
+    }
+    panic!("timed out waiting for {what}; saw {seen:#?}");
+}
+
+fn is_event(event: &serde_json::Value, name: &str) -> bool {
+    event["data"]["event"] == name
+}
+
+/// Starts `agentpack watch <args> --json` and streams its events.
+fn spawn_watch(
+    home: &Path,
+    cwd: &Path,
+    args: &[&str],
+) -> (Watcher, mpsc::Receiver<serde_json::Value>) {
+    let child = Command::new(env!("CARGO_BIN_EXE_agentpack"))
+        .current_dir(cwd)
+        .arg("watch")
+        .args(args)
+        .args(["--json", "--interval-ms", "50", "--debounce-ms", "100"])
+        .env("AGENTPACK_HOME", home)
+        .env("HOME", home)
+        .env("AGENTPACK_MACHINE_ID", "test-machine")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn watch");
+    let mut watcher = Watcher(child);
+    let stdout = watcher.0.stdout.take().expect("stdout");
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            let event: serde_json::Value = serde_json::from_str(&line).expect("event json line");
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    (watcher, rx)
+}
+
+const MANIFEST: &str = r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+targets:
+  codex:
+    mode: files
+    scope: project
+
+modules:
+  - id: instructions:base
+    type: instructions
+    tags: ["base"]
+    source:
+      local_path:
+        path: modules/instructions/base
+"#;
+
+/// A project workspace plus a config repo with one `local_path` instructions module.
+fn setup(home: &Path) -> std::path::PathBuf {
+    let workspace = home.join("workspace");
+    std::fs::create_dir_all(&workspace).expect("create workspace");
+    assert!(
+        Command::new("git")
+            .current_dir(&workspace)
+            .args(["init"])
+            .output()
+            .expect("git init")
+            .status
+            .success()
+    );
+
+    let init = agentpack_in(home, &workspace, &["init"]);
+    assert!(init.status.success(), "{init:?}");
+
+    let repo_dir = home.join("repo");
+    let instructions = repo_dir.join("modules/instructions/base/AGENTS.md");
+    std::fs::create_dir_all(instructions.parent().expect("parent")).expect("create module dir");
+    std::fs::write(&instructions, "# Rules v1\n").expect("write AGENTS.md");
+    std::fs::write(repo_dir.join("agentpack.yaml"), MANIFEST).expect("write manifest");
+    workspace
+}
+
+#[test]
+fn watch_applies_source_edits_and_reports_drift() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+    let repo_dir = home.join("repo");
+    let instructions = repo_dir.join("modules/instructions/base/AGENTS.md");
+
+    let refused = agentpack_in(home, &workspace, &["watch", "--apply", "--json"]);
+    assert!(!refused.status.success(), "{refused:?}");
+    let refused: serde_json::Value = serde_json::from_slice(&refused.stdout).expect("json");
+    assert_eq!(refused["errors"][0]["code"], "E_CONFIRM_REQUIRED");
+
+    let (_watcher, rx) = spawn_watch(home, &workspace, &["--apply", "--yes"]);
+
+    let started = wait_for(&rx, "started", |e| is_event(e, "started"));
+    assert_eq!(started["command_id"], "watch --apply");
+    let plan = wait_for(&rx, "initial plan", |e| is_event(e, "plan"));
+    assert_eq!(plan["data"]["summary"]["create"], 1);
+    // Startup only reports the plan.
+    std::thread::sleep(Duration::from_millis(500));
+    let agents = workspace.join("AGENTS.md");
+    assert!(!agents.exists());
+
+    // Config changes are re-planned but never auto-applied.
+    std::fs::write(
+        repo_dir.join("agentpack.yaml"),
+        format!("{MANIFEST}\n# touched\n"),
+    )
+    .expect("edit manifest");
+    let skipped = wait_for(&rx, "apply skipped after manifest edit", |e| {
+        is_event(e, "apply_skipped")
+    });
+    assert_eq!(skipped["data"]["reason_code"], "config_changed");
+    assert!(
+        skipped["data"]["paths"][0]
+            .as_str()
+            .expect("path")
+            .ends_with("/agentpack.yaml")
+    );
+    assert!(!agents.exists());
+
+    // Editing a local source applies the plan.
+    std::fs::write(&instructions, "# Rules v1.1\n").expect("edit source");
+    let applied = wait_for(&rx, "apply after first source edit", |e| {
+        is_event(e, "applied")
+    });
+    assert_eq!(applied["data"]["summary"]["create"], 1);
+    assert!(
+        std::fs::read_to_string(&agents)
+            .expect("read AGENTS.md")
+            .contains("# Rules v1.1")
+    );
+
+    // Later edits re-plan and apply only the changed file.
+    std::fs::write(&instructions, "# Rules v2\n").expect("edit source");
+    let plan = wait_for(&rx, "plan after source edit", |e| is_event(e, "plan"));
+    assert_eq!(plan["data"]["trigger"], serde_json::json!(["inputs"]));
+    assert_eq!(plan["data"]["changes"][0]["op"], "update");
+    let applied = wait_for(&rx, "apply after source edit", |e| is_event(e, "applied"));
+    assert_eq!(applied["data"]["summary"]["update"], 1);
+    assert!(
+        std::fs::read_to_string(&agents)
+            .expect("read AGENTS.md")
+            .contains("# Rules v2")
+    );
+
+    // Hand edits to a managed file are reported, not reverted.
+    std::fs::write(&agents, "# Rules v2\n\nlocal note\n").expect("hand edit");
+    let drift = wait_for(&rx, "drift", |e| is_event(e, "drift"));
+    assert_eq!(drift["data"]["drift"][0]["kind"], "modified");
+    assert!(
+        drift["data"]["drift"][0]["path_posix"]
+            .as_str()
+            .expect("path_posix")
+            .ends_with("/AGENTS.md")
+    );
+    std::thread::sleep(Duration::from_millis(500));
+    assert_eq!(
+        std::fs::read_to_string(&agents).expect("read AGENTS.md"),
+        "# Rules v2\n\nlocal note\n"
+    );
+}
+
+#[test]
+fn watch_reports_a_broken_config_at_startup_and_keeps_watching() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let home = tmp.path();
+    let workspace = setup(home);
+    let manifest = home.join("repo/agentpack.yaml");
+    std::fs::write(&manifest, "version: [\n").expect("break manifest");
+
+    let (_watcher, rx) = spawn_watch(home, &workspace, &["--apply", "--yes"]);
+    let error = wait_for(&rx, "startup error", |e| is_event(e, "error"));
+    assert_eq!(error["ok"], false);
+
+    std::fs::write(&manifest, MANIFEST).expect("fix manifest");
+    let plan = wait_for(&rx, "plan after fix", |e| is_event(e, "plan"));
+    assert_eq!(plan["data"]["summary"]["create"], 1);
+    let skipped = wait_for(&rx, "apply skipped after fix", |e| {
+        is_event(e, "apply_skipped")
+    });
+    assert_eq!(skipped["data"]["reason_code"], "config_changed");
+    assert!(!workspace.join("AGENTS.md").exists());
+}
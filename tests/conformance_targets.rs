@@ -1150,3 +1150,183 @@ fn conformance_export_dir_smoke() {
     );
     assert!(unmanaged.exists());
 }
+
+fn write_manifest_custom(repo_dir: &Path) {
+    let manifest = r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+custom_targets:
+  acme:
+    scope: project
+    instructions:
+      path: "{project_root}/.acme/rules/{module_name}.md"
+      aggregate: per_module
+      frontmatter: |
+        description: "agentpack: {module_id}"
+    prompt:
+      dir: "{project_root}/.acme/prompts"
+      extension: ".prompt.md"
+    scan_extras:
+      - "{project_root}/.acme/rules"
+
+modules:
+  - id: instructions:base
+    type: instructions
+    source:
+      local_path:
+        path: modules/instructions/base
+    enabled: true
+    tags: ["base"]
+    targets: ["acme"]
+  - id: prompt:hello
+    type: prompt
+    source:
+      local_path:
+        path: modules/prompts/hello
+    enabled: true
+    tags: ["base"]
+    targets: ["acme"]
+"#;
+    std::fs::write(repo_dir.join("agentpack.yaml"), manifest).expect("write manifest");
+}
+
+#[test]
+fn conformance_custom_target_smoke() {
+    let harness = ConformanceHarness::new();
+    let home = harness.home();
+    let workspace = harness.workspace();
+
+    let init = harness.agentpack(&["init"]);
+    assert!(init.status.success());
+
+    let repo_dir = home.join("repo");
+    write_manifest_custom(&repo_dir);
+
+    write_module(
+        &repo_dir,
+        "modules/instructions/base",
+        "AGENTS.md",
+        "# Base instructions\n",
+    );
+    write_module(&repo_dir, "modules/prompts/hello", "hello.md", "# Hello\n");
+
+    let deploy1 = harness.agentpack(&["--target", "acme", "deploy", "--apply", "--yes", "--json"]);
+    assert!(
+        deploy1.status.success(),
+        "deploy failed: status={:?}\nstdout={}\nstderr={}",
+        deploy1.status.code(),
+        String::from_utf8_lossy(&deploy1.stdout),
+        String::from_utf8_lossy(&deploy1.stderr)
+    );
+    let deploy1_json = parse_stdout_json(&deploy1);
+    assert_envelope_shape(&deploy1_json, "deploy", true);
+    let snapshot1 = deploy1_json["data"]["snapshot_id"]
+        .as_str()
+        .expect("snapshot_id")
+        .to_string();
+
+    let rules_dir = workspace.join(".acme/rules");
+    let prompts_dir = workspace.join(".acme/prompts");
+    assert!(rules_dir.join(".agentpack.manifest.acme.json").exists());
+    assert!(prompts_dir.join(".agentpack.manifest.acme.json").exists());
+    assert!(
+        prompts_dir.join("hello.prompt.md").exists(),
+        "files={:?}",
+        list_all_files(workspace)
+    );
+
+    let rule_path = rules_dir.join("base.md");
+    let v1 = std::fs::read_to_string(&rule_path).expect("read deployed rule");
+    assert!(v1.starts_with("---\ndescription: \"agentpack: instructions:base\"\n---\n\n"));
+    assert!(v1.contains("# Base instructions"));
+
+    let extra = rules_dir.join("unmanaged.md");
+    std::fs::write(&extra, "unmanaged\n").expect("write unmanaged");
+    std::fs::write(&rule_path, "local drift\n").expect("write drift");
+
+    let status = harness.agentpack(&["--target", "acme", "status", "--json"]);
+    assert!(status.status.success());
+    let status_json = parse_stdout_json(&status);
+    assert_envelope_shape(&status_json, "status", true);
+    let drift = status_json["data"]["drift"]
+        .as_array()
+        .expect("drift array");
+    assert!(drift.iter().any(|d| d["kind"] == "modified"));
+    assert!(drift.iter().any(|d| d["kind"] == "extra"));
+
+    let deploy_fix =
+        harness.agentpack(&["--target", "acme", "deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy_fix.status.success());
+    assert!(extra.exists());
+
+    write_module(
+        &repo_dir,
+        "modules/instructions/base",
+        "AGENTS.md",
+        "# Base instructions v2\n",
+    );
+    let deploy2 = harness.agentpack(&["--target", "acme", "deploy", "--apply", "--yes", "--json"]);
+    assert!(deploy2.status.success());
+    assert!(
+        std::fs::read_to_string(&rule_path)
+            .expect("read deployed rule")
+            .contains("Base instructions v2")
+    );
+
+    let rollback = harness.agentpack(&[
+        "--target",
+        "acme",
+        "rollback",
+        "--to",
+        snapshot1.as_str(),
+        "--yes",
+        "--json",
+    ]);
+    assert!(rollback.status.success());
+    let rollback_json = parse_stdout_json(&rollback);
+    assert_envelope_shape(&rollback_json, "rollback", true);
+    assert_eq!(
+        std::fs::read_to_string(&rule_path).expect("read deployed rule"),
+        v1
+    );
+    assert!(extra.exists());
+}
+
+#[test]
+fn custom_target_rejects_project_template_with_user_scope() {
+    let harness = ConformanceHarness::new();
+    let home = harness.home();
+
+    let init = harness.agentpack(&["init"]);
+    assert!(init.status.success());
+
+    let manifest = r#"version: 1
+
+profiles:
+  default:
+    include_tags: ["base"]
+
+custom_targets:
+  acme:
+    scope: user
+    instructions:
+      path: "{project_root}/.acme/RULES.md"
+
+modules: []
+"#;
+    std::fs::write(home.join("repo/agentpack.yaml"), manifest).expect("write manifest");
+
+    let plan = harness.agentpack(&["plan", "--json"]);
+    assert!(!plan.status.success());
+    let v = parse_stdout_json(&plan);
+    assert_envelope_shape(&v, "plan", false);
+    assert_eq!(v["errors"][0]["code"], "E_CONFIG_INVALID");
+    assert_eq!(
+        v["errors"][0]["details"]["reason_code"],
+        "custom_target_invalid"
+    );
+    assert_eq!(v["errors"][0]["details"]["field"], "instructions.path");
+}
@@ -241,12 +241,17 @@ fn ensure_overlay_skeleton_does_not_overwrite_existing_overlay() -> anyhow::Resu
             include_tags: Vec::new(),
             include_modules: Vec::new(),
             exclude_modules: Vec::new(),
+            instructions_subpaths: Default::default(),
         },
     );
     let manifest = agentpack::config::Manifest {
         version: 1,
         profiles,
         targets: Default::default(),
+        custom_targets: Default::default(),
+        plugin_targets: Default::default(),
+        hooks: Default::default(),
+        machines: Default::default(),
         modules: vec![module],
     };
 
@@ -321,12 +326,17 @@ fn ensure_overlay_skeleton_sparse_creates_metadata_without_copying() -> anyhow::
             include_tags: Vec::new(),
             include_modules: Vec::new(),
             exclude_modules: Vec::new(),
+            instructions_subpaths: Default::default(),
         },
     );
     let manifest = agentpack::config::Manifest {
         version: 1,
         profiles,
         targets: Default::default(),
+        custom_targets: Default::default(),
+        plugin_targets: Default::default(),
+        hooks: Default::default(),
+        machines: Default::default(),
         modules: vec![module],
     };
 
@@ -394,12 +404,17 @@ fn materialize_overlay_from_upstream_copies_missing_files_without_overwriting_ed
             include_tags: Vec::new(),
             include_modules: Vec::new(),
             exclude_modules: Vec::new(),
+            instructions_subpaths: Default::default(),
         },
     );
     let manifest = agentpack::config::Manifest {
         version: 1,
         profiles,
         targets: Default::default(),
+        custom_targets: Default::default(),
+        plugin_targets: Default::default(),
+        hooks: Default::default(),
+        machines: Default::default(),
         modules: vec![module],
     };
 
@@ -494,6 +509,7 @@ fn latest_snapshot_filters_kinds() -> anyhow::Result<()> {
         rolled_back_to: None,
         lockfile_sha256: None,
         backup_root: String::new(),
+        hooks: Vec::new(),
     };
     deploy.save(&DeploymentSnapshot::path(&home, &deploy.id))?;
 
@@ -507,6 +523,7 @@ fn latest_snapshot_filters_kinds() -> anyhow::Result<()> {
         rolled_back_to: None,
         lockfile_sha256: None,
         backup_root: String::new(),
+        hooks: Vec::new(),
     };
     bootstrap.save(&DeploymentSnapshot::path(&home, &bootstrap.id))?;
 
@@ -546,6 +563,7 @@ fn plan_orders_changes_stably() -> anyhow::Result<()> {
         DesiredFile {
             bytes: b"new-a".to_vec(),
             module_ids: Vec::new(),
+            ..Default::default()
         },
     );
     desired.insert(
@@ -556,6 +574,7 @@ fn plan_orders_changes_stably() -> anyhow::Result<()> {
         DesiredFile {
             bytes: b"new-b".to_vec(),
             module_ids: Vec::new(),
+            ..Default::default()
         },
     );
 
@@ -592,6 +611,7 @@ fn plan_matches_golden_snapshot() -> anyhow::Result<()> {
         DesiredFile {
             bytes: b"new-a".to_vec(),
             module_ids: Vec::new(),
+            ..Default::default()
         },
     );
     desired.insert(
@@ -602,6 +622,7 @@ fn plan_matches_golden_snapshot() -> anyhow::Result<()> {
         DesiredFile {
             bytes: b"new-b".to_vec(),
             module_ids: Vec::new(),
+            ..Default::default()
         },
     );
 
@@ -655,6 +676,7 @@ fn load_managed_paths_from_manifests_reads_rel_paths() -> anyhow::Result<()> {
         path: "a.txt".to_string(),
         sha256: "deadbeef".to_string(),
         module_ids: vec!["module:x".to_string()],
+        ..Default::default()
     });
     manifest.save(&manifest_path_for_target(&root, "codex"))?;
 
@@ -685,6 +707,7 @@ fn plan_deletes_only_manifest_managed_files() -> anyhow::Result<()> {
         path: "managed.txt".to_string(),
         sha256: agentpack::hash::sha256_hex(b"x"),
         module_ids: vec!["module:x".to_string()],
+        ..Default::default()
     });
     manifest.save(&manifest_path_for_target(root, "codex"))?;
 
@@ -739,6 +762,7 @@ fn apply_plan_writes_target_manifests() -> anyhow::Result<()> {
         DesiredFile {
             bytes: b"hello\n".to_vec(),
             module_ids: vec!["module:test".to_string()],
+            ..Default::default()
         },
     );
 
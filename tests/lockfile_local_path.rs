@@ -20,6 +20,7 @@ fn lockfile_stores_local_path_repo_relative() -> anyhow::Result<()> {
             include_tags: Vec::new(),
             include_modules: Vec::new(),
             exclude_modules: Vec::new(),
+            instructions_subpaths: Default::default(),
         },
     );
 
@@ -27,6 +28,10 @@ fn lockfile_stores_local_path_repo_relative() -> anyhow::Result<()> {
         version: 1,
         profiles,
         targets: BTreeMap::new(),
+        custom_targets: BTreeMap::new(),
+        plugin_targets: BTreeMap::new(),
+        hooks: Default::default(),
+        machines: Default::default(),
         modules: vec![Module {
             id: "prompt:test".to_string(),
             module_type: ModuleType::Prompt,
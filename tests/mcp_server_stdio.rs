@@ -105,6 +105,7 @@ modules: []
         "evolve_propose",
         "evolve_restore",
         "explain",
+        "overlay_list",
     ] {
         assert!(names.contains(&required), "missing tool: {required}");
     }
@@ -154,6 +155,13 @@ modules: []
             "explain diff",
             serde_json::json!(["explain", "diff"]),
         ),
+        (
+            "overlay_list",
+            "{}",
+            "overlay.list",
+            "overlay list",
+            serde_json::json!(["overlay", "list"]),
+        ),
     ]
     .iter()
     .enumerate()